@@ -6,10 +6,11 @@
 //! - Daemon subcommand: background daemon operations
 
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use anyhow::{Result, anyhow, Context};
+use crate::entitlements::native::root;
 use crate::constants::{DEFAULT_SCAN_PATHS, DEFAULT_POLLING_INTERVAL_STR, POLLING_INTERVAL_MIN, POLLING_INTERVAL_MAX};
-use crate::models::{ScanConfig, ScanFilters, PollingConfiguration, MonitorError};
+use crate::models::{ExtractorBackend, OsVersion, OutputFormat, Platform, ScanConfig, ScanFilters, SignerKind, PollingConfiguration, MonitorError};
 use std::time::Duration;
 
 /// Command line arguments for listent
@@ -18,6 +19,22 @@ use std::time::Duration;
 #[command(after_help = "Examples:
   listent                                      Scan default paths (/usr/bin, /usr/sbin)
   listent -e \"*network*\"                       Scan with entitlement filter
+  listent --signer developer-id,ad-hoc         Scan only third-party signed binaries
+  listent --unconstrained-private              Private entitlements without launch constraints
+  listent --sdk-before 13.0 /Applications      Binaries built against SDKs older than 13.0
+  listent --verify /Volumes/Copy/usr/bin       Detect binaries modified since signing
+  listent --verify-resources /Applications     Find bundles whose files changed since signing
+  listent --hardening /Applications            Report PIE, stack canaries, PAC and hardened runtime
+  listent --bundles /Applications/Xcode.app    Group helpers and extensions under their host app
+  listent build/App.ipa                        Scan the binaries inside an IPA without unpacking it
+  listent --json Vendor.pkg                    Review an installer's binaries, signer and scripts
+  listent --format ndjson / | jq -c .          Stream results as they are read, then a summary
+  listent --root /mnt/macos-image              Scan the default paths of a mounted macOS image
+  listent --one-file-system /                  Scan the boot volume without crossing into other mounts
+  listent --exclude node_modules,.git ~/src    Scan source trees without walking dependencies
+  listent --backend fixture:recorded.json      Replay signatures recorded on another machine
+  listent --cache /                            Only parse binaries changed since the last cached scan
+  listent --rebuild-cache /                    Full scan, refreshing the cache later scans reuse
   listent monitor                              Monitor all new processes
   listent monitor -e \"com.apple.security.*\"    Monitor with entitlement filter
   listent daemon install                       Install as background service")]
@@ -34,10 +51,95 @@ pub struct Args {
     #[arg(short, long, value_name = "PATTERN", value_delimiter = ',')]
     pub entitlement: Vec<String>,
 
-    /// Output in JSON format
-    #[arg(short, long)]
+    /// Filter by signer: apple-platform, app-store, developer-id, development, ad-hoc, unsigned, unverified, other
+    #[arg(long, value_name = "KIND", value_delimiter = ',')]
+    pub signer: Vec<SignerKind>,
+
+    /// Only show binaries holding private entitlements without any launch constraints
+    #[arg(long)]
+    pub unconstrained_private: bool,
+
+    /// Filter by platform: macos, ios, mac-catalyst, driverkit, ios-simulator, ...
+    #[arg(long, value_name = "PLATFORM", value_delimiter = ',')]
+    pub platform: Vec<Platform>,
+
+    /// Only show binaries linked against an SDK older than VERSION (e.g. 13.0)
+    #[arg(long, value_name = "VERSION")]
+    pub sdk_before: Option<OsVersion>,
+
+    /// Only show binaries whose minimum OS version is older than VERSION
+    #[arg(long, value_name = "VERSION")]
+    pub min_os_before: Option<OsVersion>,
+
+    /// Recompute code signature page and special-slot hashes to detect modified binaries
+    #[arg(long)]
+    pub verify: bool,
+
+    /// Check app, framework, appex and xpc bundles against their _CodeSignature/CodeResources seal
+    #[arg(long)]
+    pub verify_resources: bool,
+
+    /// Report exploit mitigations: PIE, __RESTRICT, stack canaries, ARC, PAC, hardened runtime, library validation
+    #[arg(long)]
+    pub hardening: bool,
+
+    /// Show results as a tree of app, appex, xpc, framework and system extension bundles
+    #[arg(long)]
+    pub bundles: bool,
+
+    /// Follow symlinks while walking directories (default)
+    #[arg(long, overrides_with = "no_follow_symlinks")]
+    pub follow_symlinks: bool,
+
+    /// Do not follow symlinks found while walking; scan paths themselves are still followed
+    #[arg(long, overrides_with = "follow_symlinks")]
+    pub no_follow_symlinks: bool,
+
+    /// Do not descend into directories on other filesystems than their scan path
+    #[arg(long)]
+    pub one_file_system: bool,
+
+    /// Also scan inside .zip, .ipa and .tar archives found while walking; archives given as paths always are
+    #[arg(long)]
+    pub expand_archives: bool,
+
+    /// Skip files and directories matching PATTERN (gitignore syntax); excluded directories are not walked
+    #[arg(long, value_name = "PATTERN", value_delimiter = ',')]
+    pub exclude: Vec<String>,
+
+    /// Only check files matching PATTERN (gitignore syntax)
+    #[arg(long, value_name = "PATTERN", value_delimiter = ',')]
+    pub include: Vec<String>,
+
+    /// Scan a mounted or extracted macOS filesystem: paths are resolved and reported inside DIR
+    #[arg(long, value_name = "DIR")]
+    pub root: Option<PathBuf>,
+
+    /// Extractor backend: auto, native, codesign or fixture:FILE
+    #[arg(long, value_name = "BACKEND", default_value = "auto")]
+    pub backend: ExtractorBackend,
+
+    /// Reuse reports cached in ~/.cache/listent by earlier --cache scans, and cache new ones
+    #[arg(long)]
+    pub cache: bool,
+
+    /// Discard the extraction cache and fill it again from this scan (implies --cache)
+    #[arg(long)]
+    pub rebuild_cache: bool,
+
+    /// Neither read nor write the extraction cache, overriding --cache and --rebuild-cache
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Output in JSON format (same as --format json)
+    #[arg(short, long, conflicts_with = "format")]
     pub json: bool,
 
+    /// Output format: human, json, or ndjson (one record per line as each binary is read, then aliases and a
+    /// summary; a single file gets no detailed view)
+    #[arg(long, value_name = "FORMAT", default_value = "human")]
+    pub format: OutputFormat,
+
     /// Suppress warnings about unreadable files
     #[arg(short, long)]
     pub quiet: bool,
@@ -49,7 +151,7 @@ pub enum Commands {
     /// Real-time process monitoring for entitlements
     #[command(about = "Monitor new processes for entitlements in real-time")]
     Monitor {
-        /// Directory or file paths to filter monitored processes
+        /// Absolute paths or patterns (gitignore syntax) to filter monitored processes
         #[arg(value_name = "PATH")]
         path: Vec<PathBuf>,
 
@@ -61,6 +163,10 @@ pub enum Commands {
         #[arg(short, long, default_value = DEFAULT_POLLING_INTERVAL_STR, value_name = "SECONDS")]
         interval: f64,
 
+        /// Extractor backend: auto, native, codesign or fixture:FILE
+        #[arg(long, value_name = "BACKEND", default_value = "auto")]
+        backend: ExtractorBackend,
+
         /// Output in JSON format
         #[arg(short, long)]
         json: bool,
@@ -142,18 +248,42 @@ pub fn parse_args_from(args: Args) -> Result<ScanConfig> {
         return Err(anyhow!("Internal error: parse_args called with subcommand"));
     }
 
-    // Validate paths if provided
+    if args.bundles && args.format == OutputFormat::Ndjson {
+        return Err(anyhow!("--bundles cannot be used with --format ndjson, which writes results as they are read"));
+    }
+
+    if let Some(root) = &args.root {
+        if !root.is_dir() {
+            return Err(anyhow!("Root is not a directory: {}", root.display()));
+        }
+    }
+
+    // Validate paths if provided; under a root they are paths inside the image
     let mut scan_paths = Vec::new();
     if !args.path.is_empty() {
         for path in &args.path {
-            if !path.exists() {
-                return Err(anyhow!("Path does not exist: {}", path.display()));
+            match &args.root {
+                Some(root) => {
+                    let host_path = root::host_path(root, path);
+                    if root::resolve(root, &host_path).is_err() {
+                        return Err(anyhow!("Path does not exist in {}: {}", root.display(), path.display()));
+                    }
+                    scan_paths.push(host_path.display().to_string());
+                }
+                None => {
+                    if !path.exists() {
+                        return Err(anyhow!("Path does not exist: {}", path.display()));
+                    }
+                    scan_paths.push(path.display().to_string());
+                }
             }
-            scan_paths.push(path.display().to_string());
         }
     } else {
         // Use default paths
-        scan_paths.extend(DEFAULT_SCAN_PATHS.iter().map(|s| s.to_string()));
+        scan_paths.extend(DEFAULT_SCAN_PATHS.iter().map(|s| match &args.root {
+            Some(root) => root::host_path(root, Path::new(s)).display().to_string(),
+            None => s.to_string(),
+        }));
     }
 
     // Validate entitlement filters if provided
@@ -162,15 +292,36 @@ pub fn parse_args_from(args: Args) -> Result<ScanConfig> {
             .context("Invalid entitlement filter")?;
     }
 
+    crate::scan::filter::validate_path_patterns(&args.exclude).context("Invalid --exclude pattern")?;
+    crate::scan::filter::validate_path_patterns(&args.include).context("Invalid --include pattern")?;
+
     let filters = ScanFilters {
         entitlements: args.entitlement,
+        signers: args.signer,
+        unconstrained_private: args.unconstrained_private,
+        platforms: args.platform,
+        sdk_before: args.sdk_before,
+        min_os_before: args.min_os_before,
     };
 
     Ok(ScanConfig {
         scan_paths,
         filters,
-        json_output: args.json,
+        output_format: if args.json { OutputFormat::Json } else { args.format },
         quiet_mode: args.quiet,
+        verify_signatures: args.verify,
+        report_hardening: args.hardening,
+        verify_resources: args.verify_resources,
+        group_bundles: args.bundles,
+        root: args.root,
+        follow_symlinks: !args.no_follow_symlinks,
+        one_file_system: args.one_file_system,
+        expand_archives: args.expand_archives,
+        exclude: args.exclude,
+        include: args.include,
+        use_cache: (args.cache || args.rebuild_cache) && !args.no_cache,
+        rebuild_cache: args.rebuild_cache,
+        backend: args.backend,
     })
 }
 
@@ -179,6 +330,7 @@ pub fn parse_monitor_config(
     path: Vec<PathBuf>,
     entitlement: Vec<String>,
     interval: f64,
+    backend: ExtractorBackend,
     json: bool,
     quiet: bool,
 ) -> Result<PollingConfiguration> {
@@ -193,14 +345,21 @@ pub fn parse_monitor_config(
             .context("Invalid entitlement filter")?;
     }
 
-    // Validate paths if provided
+    // Validate paths if provided; patterns need not exist
     let mut path_filters = Vec::new();
     for p in &path {
-        if !p.exists() {
-            return Err(anyhow!("Path does not exist: {}", p.display()));
+        let text = p.to_string_lossy().to_string();
+        if crate::scan::filter::is_plain_path(&text) {
+            if p.is_relative() {
+                return Err(anyhow!("Path filter must be absolute or a pattern (e.g. **/{}): {}", text, text));
+            }
+            if !p.exists() {
+                return Err(anyhow!("Path does not exist: {}", p.display()));
+            }
         }
-        path_filters.push(p.clone());
+        path_filters.push(crate::scan::filter::filter_pattern(&text));
     }
+    crate::scan::filter::validate_path_patterns(&path_filters).context("Invalid path filter")?;
 
     Ok(PollingConfiguration {
         interval: Duration::from_secs_f64(interval),
@@ -208,6 +367,7 @@ pub fn parse_monitor_config(
         entitlement_filters: entitlement,
         output_json: json,
         quiet_mode: quiet,
+        backend,
     })
 }
 
@@ -216,8 +376,8 @@ pub fn get_execution_mode() -> Result<ExecutionMode> {
     let args = Args::parse();
 
     match args.command {
-        Some(Commands::Monitor { path, entitlement, interval, json, quiet }) => {
-            Ok(ExecutionMode::Monitor { path, entitlement, interval, json, quiet })
+        Some(Commands::Monitor { path, entitlement, interval, backend, json, quiet }) => {
+            Ok(ExecutionMode::Monitor { path, entitlement, interval, backend, json, quiet })
         }
         Some(Commands::Daemon { action }) => {
             Ok(ExecutionMode::Daemon(action))
@@ -231,6 +391,7 @@ pub fn get_execution_mode() -> Result<ExecutionMode> {
 
 /// Execution modes for the application
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)] // Built once per run
 pub enum ExecutionMode {
     /// Scan mode with pre-parsed CLI args
     Scan(Args),
@@ -238,6 +399,7 @@ pub enum ExecutionMode {
         path: Vec<PathBuf>,
         entitlement: Vec<String>,
         interval: f64,
+        backend: ExtractorBackend,
         json: bool,
         quiet: bool,
     },
@@ -43,6 +43,11 @@ pub const CODESIGN_COMMAND: &str = "codesign";
 /// Arguments for extracting entitlements via codesign
 pub const CODESIGN_ENTITLEMENT_ARGS: &[&str] = &["-d", "--entitlements", "-", "--xml"];
 
+// --- Entitlement classification ---
+
+/// Key prefixes of private (Apple-internal) entitlements
+pub const PRIVATE_ENTITLEMENT_PREFIXES: &[&str] = &["com.apple.private."];
+
 // --- Daemon subcommand identifiers ---
 
 /// CLI subcommand name for daemon mode
@@ -16,11 +16,14 @@ use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Mutex;
 use tokio::signal;
+use crate::entitlements::extractor::EntitlementExtractor;
+use crate::entitlements::native::ReadOptions;
 use crate::models::{PollingConfiguration, ProcessSnapshot, MonitoredProcess};
 use crate::daemon::config::DaemonConfiguration;
 use crate::constants::{APP_SUBSYSTEM, DAEMON_CATEGORY, DAEMON_SUBCOMMAND, DAEMON_RUN_SUBCOMMAND};
 use crate::daemon::logging::{DaemonLogger, LogLevel};
 use crate::monitor::process_tracker::ProcessTracker;
+use crate::scan::filter::{filter_pattern, PathFilter};
 
 /// Check if a listent daemon process is already running
 /// Returns true if any listent process with 'daemon run' subcommand is running
@@ -257,9 +260,12 @@ async fn run_monitoring_loop(
     config: Arc<Mutex<DaemonConfiguration>>,
     logger: DaemonLogger,
 ) -> Result<()> {
-    let mut interval = {
+    let (mut interval, extractor, path_filter) = {
         let config = config.lock().await;
-        tokio::time::interval(config.polling_duration())
+        let extractor = crate::entitlements::extractor::create_extractor(&config.monitoring.backend, ReadOptions::default())?;
+        let patterns: Vec<String> = config.monitoring.path_filters.iter().map(|filter| filter_pattern(filter)).collect();
+        let path_filter = PathFilter::from_patterns(&patterns);
+        (tokio::time::interval(config.polling_duration()), extractor, path_filter)
     };
 
     loop {
@@ -273,11 +279,12 @@ async fn run_monitoring_loop(
             entitlement_filters: current_config.monitoring.entitlement_filters.clone(),
             output_json: false, // ULS logging instead
             quiet_mode: false,  // Log all detections
+            backend: current_config.monitoring.backend.clone(),
         };
         drop(current_config);
 
         // Create current snapshot using polling logic
-        let current_processes = match scan_current_processes(&polling_config).await {
+        let current_processes = match scan_current_processes(&polling_config, &path_filter, extractor.as_ref()).await {
             Ok(processes) => processes,
             Err(e) => {
                 logger.log_error(&format!("Failed to scan processes: {}", e), None)?;
@@ -321,7 +328,11 @@ async fn setup_signal_handlers() {
 }
 
 /// Scan current processes and their entitlements
-async fn scan_current_processes(config: &PollingConfiguration) -> Result<std::collections::HashMap<(u32, u64), MonitoredProcess>> {
+async fn scan_current_processes(
+    config: &PollingConfiguration,
+    path_filter: &PathFilter,
+    extractor: &dyn EntitlementExtractor,
+) -> Result<std::collections::HashMap<(u32, u64), MonitoredProcess>> {
     use sysinfo::{ProcessesToUpdate, System};
 
     let mut system = System::new_all();
@@ -341,20 +352,13 @@ async fn scan_current_processes(config: &PollingConfiguration) -> Result<std::co
         };
 
         // Apply path filters if specified
-        if !config.path_filters.is_empty() {
-            let matches_filter = config.path_filters.iter().any(|filter| {
-                executable_path.starts_with(filter)
-            });
-            if !matches_filter {
-                continue;
-            }
+        if !path_filter.is_empty() && !path_filter.selects(&executable_path) {
+            continue;
         }
 
         // Extract entitlements - keep full key-value pairs
-        let entitlements = match crate::entitlements::extract_entitlements(&executable_path) {
-            Ok(entitlements_map) => entitlements_map,
-            Err(_) => std::collections::HashMap::new(),
-        };
+        let report = extractor.extract(&executable_path).unwrap_or_default();
+        let entitlements = report.entitlements;
 
         // Apply entitlement filters if specified using consistent pattern matching
         let entitlement_keys: Vec<String> = entitlements.keys().cloned().collect();
@@ -370,6 +374,8 @@ async fn scan_current_processes(config: &PollingConfiguration) -> Result<std::co
             name: process_name,
             executable_path,
             entitlements,
+            code_directory: report.code_directory,
+            bundle_info: report.bundle_info,
             discovery_timestamp: std::time::SystemTime::now(),
         };
 
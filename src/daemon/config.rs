@@ -4,9 +4,11 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+use crate::models::ExtractorBackend;
+use crate::scan::filter::{is_plain_path, validate_path_patterns};
 use crate::constants::{DEFAULT_SCAN_PATHS, DEFAULT_POLLING_INTERVAL, POLLING_INTERVAL_MIN, POLLING_INTERVAL_MAX};
 
 /// Main daemon configuration structure
@@ -28,10 +30,14 @@ pub struct DaemonSettings {
 /// Process monitoring configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitoringSettings {
-    /// Filesystem paths to monitor for processes
-    pub path_filters: Vec<PathBuf>,
+    /// Absolute paths or patterns (gitignore syntax, as scan `--include`) of
+    /// processes to monitor; a pattern without a slash matches at any depth
+    pub path_filters: Vec<String>,
     /// Entitlements to filter for (empty = all)
     pub entitlement_filters: Vec<String>,
+    /// Extractor backend (auto, native, codesign or fixture:FILE)
+    #[serde(default)]
+    pub backend: ExtractorBackend,
 }
 
 impl Default for DaemonConfiguration {
@@ -43,11 +49,12 @@ impl Default for DaemonConfiguration {
             },
             monitoring: MonitoringSettings {
                 path_filters: {
-                    let mut paths = vec![PathBuf::from("/Applications")];
-                    paths.extend(DEFAULT_SCAN_PATHS.iter().map(PathBuf::from));
+                    let mut paths = vec!["/Applications".to_string()];
+                    paths.extend(DEFAULT_SCAN_PATHS.iter().map(|path| path.to_string()));
                     paths
                 },
                 entitlement_filters: vec![], // Monitor all entitlements by default
+                backend: ExtractorBackend::Auto,
             },
         }
     }
@@ -96,12 +103,20 @@ impl DaemonConfiguration {
             );
         }
 
-        // Validate paths exist and are readable
-        for path in &self.monitoring.path_filters {
+        // Validate paths exist and are readable; patterns need not match anything yet
+        for filter in &self.monitoring.path_filters {
+            if !is_plain_path(filter) {
+                continue;
+            }
+            let path = Path::new(filter);
+            if path.is_relative() {
+                anyhow::bail!("Monitoring path must be absolute or a pattern (e.g. **/{}): {}", filter, filter);
+            }
             if !path.exists() {
-                anyhow::bail!("Monitoring path does not exist: {}", path.display());
+                anyhow::bail!("Monitoring path does not exist: {}", filter);
             }
         }
+        validate_path_patterns(&self.monitoring.path_filters).context("Invalid monitoring path filter")?;
 
         Ok(())
     }
@@ -5,6 +5,7 @@
 
 use anyhow::{Context, Result};
 use serde_json::json;
+#[cfg(target_os = "macos")]
 use oslog::OsLogger;
 use log::{error, info};
 use std::process::Command;
@@ -32,10 +33,13 @@ impl DaemonLogger {
             anyhow::bail!("Subsystem must be in reverse DNS format (e.g., 'com.example.app')");
         }
 
-        // Initialize the oslog backend for the log crate
-        let logger = OsLogger::new(&subsystem);
-        log::set_boxed_logger(Box::new(logger))
-            .map_err(|e| anyhow::anyhow!("Failed to set logger: {}", e))?;
+        // Initialize the oslog backend for the log crate (ULS only exists on macOS)
+        #[cfg(target_os = "macos")]
+        {
+            let logger = OsLogger::new(&subsystem);
+            log::set_boxed_logger(Box::new(logger))
+                .map_err(|e| anyhow::anyhow!("Failed to set logger: {}", e))?;
+        }
         log::set_max_level(log::LevelFilter::Debug);
 
         Ok(Self {
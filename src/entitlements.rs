@@ -1,8 +1,9 @@
 //! Entitlement extraction module
 //!
 //! Handles:
-//! - Extracting entitlements from Mach-O binaries using optimized plist parsing
-//! - Fallback to manual XML parsing for compatibility
+//! - Extracting entitlements from Mach-O code signatures natively
+//! - Fallback to the codesign tool, parsing its plist with the plist crate
+//! - Selectable extractor backends (native, codesign, recorded fixtures)
 //! - Error handling for unsigned/malformed binaries
 //! - Performance optimization for batch operations
 //! - Pattern matching for entitlement filtering
@@ -16,24 +17,7 @@ use crate::constants::{CODESIGN_COMMAND, CODESIGN_ENTITLEMENT_ARGS};
 
 pub mod pattern_matcher;
 pub mod native;
-
-/// Extract entitlements from a binary file
-///
-/// Uses optimized plist parsing for better performance,
-/// with fallback to manual XML parsing if needed.
-pub fn extract_entitlements(binary_path: &Path) -> Result<HashMap<String, Value>> {
-    // Try optimized plist parsing first
-    match native::extract_entitlements_optimized(binary_path) {
-        Ok(entitlements) => return Ok(entitlements),
-        Err(_) => {
-            // Fall back to manual XML parsing if plist parsing fails
-            // This provides compatibility for edge cases
-        }
-    }
-
-    // Fallback to manual XML parsing (original implementation)
-    extract_entitlements_codesign(binary_path)
-}
+pub mod extractor;
 
 /// Extract entitlements using codesign command-line tool (fallback method)
 pub fn extract_entitlements_codesign(binary_path: &Path) -> Result<HashMap<String, Value>> {
@@ -48,10 +32,20 @@ pub fn extract_entitlements_codesign(binary_path: &Path) -> Result<HashMap<Strin
         return Ok(HashMap::new());
     }
 
-    let xml_content = String::from_utf8(output.stdout)?;
+    parse_codesign_output(&output.stdout)
+}
 
-    // Parse the XML plist to extract entitlements
-    parse_entitlements_plist(&xml_content)
+/// Parse the entitlements plist codesign printed, using the plist crate and
+/// falling back to manual XML parsing for output it rejects
+fn parse_codesign_output(stdout: &[u8]) -> Result<HashMap<String, Value>> {
+    if stdout.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    match plist::from_bytes::<plist::Value>(stdout) {
+        Ok(plist_value) => native::plist_to_json_map(plist_value),
+        Err(_) => parse_entitlements_plist(&String::from_utf8(stdout.to_vec())?),
+    }
 }
 
 /// Parse entitlements from XML plist format
@@ -169,10 +163,16 @@ fn parse_next_plist_value(content: &str) -> Result<Option<(Value, usize)>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::extractor::{AutoExtractor, EntitlementExtractor};
     use std::path::PathBuf;
     use tempfile::NamedTempFile;
     use std::io::Write;
 
+    /// Entitlements as the default (native, then codesign) backend reads them
+    fn extract_entitlements(binary_path: &Path) -> Result<HashMap<String, Value>> {
+        AutoExtractor::new(native::ReadOptions::default()).extract(binary_path).map(|report| report.entitlements)
+    }
+
     // ==================== parse_entitlements_plist tests ====================
 
     #[test]
@@ -276,7 +276,7 @@ mod tests {
         assert!(result.contains_key("some.integer.key"));
     }
 
-    // ==================== extract_entitlements tests ====================
+    // ==================== AutoExtractor tests ====================
 
     #[test]
     fn test_extract_entitlements_nonexistent_file() {
@@ -363,6 +363,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_codesign_output_keeps_nested_values() {
+        let plist = r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>com.apple.security.application-groups</key>
+    <array>
+        <string>group.com.example.one</string>
+        <string>group.com.example.two</string>
+    </array>
+    <key>com.apple.developer.icloud-container-environment</key>
+    <dict>
+        <key>production</key>
+        <true/>
+    </dict>
+</dict>
+</plist>"#;
+
+        let result = parse_codesign_output(plist.as_bytes()).unwrap();
+        assert_eq!(
+            result.get("com.apple.security.application-groups"),
+            Some(&serde_json::json!(["group.com.example.one", "group.com.example.two"]))
+        );
+        assert_eq!(
+            result.get("com.apple.developer.icloud-container-environment"),
+            Some(&serde_json::json!({ "production": true }))
+        );
+        assert!(parse_codesign_output(b"").unwrap().is_empty());
+    }
+
     // ==================== Plist parsing edge cases ====================
 
     #[test]
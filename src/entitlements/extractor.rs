@@ -0,0 +1,251 @@
+//! Entitlement extractor backends
+//!
+//! Scan, monitor and daemon modes read signatures through the
+//! `EntitlementExtractor` trait so the backend can be chosen with `--backend`:
+//! - `native`: the Mach-O parser in `native`, works on any host
+//! - `codesign`: the `codesign` subprocess, entitlements only
+//! - `fixture`: reports recorded in a JSON file, for running the pipeline off macOS
+//! - `auto`: native, falling back to codesign when it is installed
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use crate::constants::CODESIGN_COMMAND;
+use crate::models::{BinaryResult, EntitlementSource, ExtractorBackend, SignatureReport};
+use super::native::{self, ReadOptions};
+
+/// Reads the signature report of a binary
+pub trait EntitlementExtractor: Send + Sync {
+    fn extract(&self, binary_path: &Path) -> Result<SignatureReport>;
+
+    /// Read an archive member already in memory; `member_path` is the reported
+    /// `archive!/member` path. Only the native parser can read these.
+    fn extract_member(&self, member_path: &Path, _contents: &[u8]) -> Result<SignatureReport> {
+        Err(anyhow!("Archive members need the native or auto backend: {}", member_path.display()))
+    }
+}
+
+/// Build the extractor for a backend
+///
+/// Signature verification, hardening and resource seal checks need the native parser.
+pub fn create_extractor(backend: &ExtractorBackend, options: ReadOptions) -> Result<Box<dyn EntitlementExtractor>> {
+    if matches!(backend, ExtractorBackend::Codesign | ExtractorBackend::Fixture(_)) {
+        if options.verify {
+            return Err(anyhow!("--verify requires the native or auto backend, not {}", backend));
+        }
+        if options.hardening {
+            return Err(anyhow!("--hardening requires the native or auto backend, not {}", backend));
+        }
+        if options.verify_resources {
+            return Err(anyhow!("--verify-resources requires the native or auto backend, not {}", backend));
+        }
+    }
+
+    Ok(match backend {
+        ExtractorBackend::Auto => Box::new(AutoExtractor::new(options)),
+        ExtractorBackend::Native => Box::new(NativeExtractor::new(options)),
+        ExtractorBackend::Codesign => Box::new(CodesignExtractor),
+        ExtractorBackend::Fixture(path) => Box::new(FixtureExtractor::from_file(path)?),
+    })
+}
+
+/// Parses the embedded code signature directly
+#[derive(Debug, Default)]
+pub struct NativeExtractor {
+    options: ReadOptions,
+}
+
+impl NativeExtractor {
+    pub fn new(options: ReadOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl EntitlementExtractor for NativeExtractor {
+    fn extract(&self, binary_path: &Path) -> Result<SignatureReport> {
+        native::read_signature_report_with(binary_path, &self.options)
+    }
+
+    fn extract_member(&self, _member_path: &Path, contents: &[u8]) -> Result<SignatureReport> {
+        native::signature_report_from_reader_with(&mut Cursor::new(contents), &self.options)
+    }
+}
+
+/// Runs `codesign` per file; only recovers entitlements, never warnings or signing metadata
+#[derive(Debug, Default)]
+pub struct CodesignExtractor;
+
+impl EntitlementExtractor for CodesignExtractor {
+    fn extract(&self, binary_path: &Path) -> Result<SignatureReport> {
+        let entitlements = super::extract_entitlements_codesign(binary_path)?;
+        Ok(SignatureReport {
+            source: (!entitlements.is_empty()).then_some(EntitlementSource::Signature),
+            entitlements,
+            ..SignatureReport::default()
+        })
+    }
+}
+
+/// Native parsing with codesign as a fallback for signatures it cannot parse
+#[derive(Debug, Default)]
+pub struct AutoExtractor {
+    native: NativeExtractor,
+    /// Absent when codesign is not installed (e.g. on Linux)
+    codesign: Option<CodesignExtractor>,
+}
+
+impl AutoExtractor {
+    pub fn new(options: ReadOptions) -> Self {
+        Self {
+            native: NativeExtractor::new(options),
+            codesign: command_on_path(CODESIGN_COMMAND).then_some(CodesignExtractor),
+        }
+    }
+}
+
+impl EntitlementExtractor for AutoExtractor {
+    fn extract(&self, binary_path: &Path) -> Result<SignatureReport> {
+        match (self.native.extract(binary_path), &self.codesign) {
+            (Ok(report), _) => Ok(report),
+            // This provides compatibility for edge cases
+            (Err(_), Some(codesign)) => codesign.extract(binary_path),
+            (Err(e), None) => Err(e),
+        }
+    }
+
+    fn extract_member(&self, member_path: &Path, contents: &[u8]) -> Result<SignatureReport> {
+        self.native.extract_member(member_path, contents)
+    }
+}
+
+/// Serves reports recorded earlier instead of reading files
+#[derive(Debug, Default)]
+pub struct FixtureExtractor {
+    reports: HashMap<PathBuf, SignatureReport>,
+}
+
+/// Accepted fixture files: a map of path to report, or the output of `listent --json`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FixtureFile {
+    ScanOutput { results: Vec<BinaryResult> },
+    Reports(HashMap<PathBuf, SignatureReport>),
+}
+
+impl FixtureExtractor {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read fixture file: {}", path.display()))?;
+        Self::from_json(&content)
+            .with_context(|| format!("Failed to parse fixture file: {}", path.display()))
+    }
+
+    pub fn from_json(content: &str) -> Result<Self> {
+        let reports = match serde_json::from_str(content)? {
+            FixtureFile::Reports(reports) => reports,
+            FixtureFile::ScanOutput { results } => results.into_iter()
+                .map(|result| (PathBuf::from(&result.path), report_from_result(result)))
+                .collect(),
+        };
+        Ok(Self { reports })
+    }
+}
+
+impl EntitlementExtractor for FixtureExtractor {
+    fn extract(&self, binary_path: &Path) -> Result<SignatureReport> {
+        self.reports.get(binary_path)
+            .cloned()
+            .ok_or_else(|| anyhow!("No recorded signature for {}", binary_path.display()))
+    }
+
+    fn extract_member(&self, member_path: &Path, _contents: &[u8]) -> Result<SignatureReport> {
+        self.extract(member_path)
+    }
+}
+
+/// Rebuild the signature report behind a recorded scan result
+fn report_from_result(result: BinaryResult) -> SignatureReport {
+    SignatureReport {
+        entitlements: result.entitlements,
+        warnings: result.warnings,
+        slices: result.slices,
+        code_directory: result.code_directory,
+        signer: result.signer,
+        designated_requirement: result.designated_requirement,
+        constraints: result.constraints,
+        bundle_info: result.bundle_info,
+        provisioning_profile: result.provisioning_profile,
+        dylibs: result.dylibs,
+        rpaths: result.rpaths,
+        dylib_hijack_risks: result.dylib_hijack_risks,
+        hardening: result.hardening,
+        build_versions: result.build_versions,
+        source: result.source,
+        resource_seal: result.resource_seal,
+        ..SignatureReport::default()
+    }
+}
+
+/// Whether an executable of this name is found on PATH
+fn command_on_path(command: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(command).is_file()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixture_reports_by_path() {
+        let extractor = FixtureExtractor::from_json(r#"{
+            "/usr/bin/tool": {"entitlements": {"com.apple.security.app-sandbox": true}}
+        }"#).unwrap();
+
+        let report = extractor.extract(Path::new("/usr/bin/tool")).unwrap();
+        assert_eq!(report.entitlements.len(), 1);
+        assert!(extractor.extract(Path::new("/usr/bin/other")).is_err());
+    }
+
+    #[test]
+    fn test_fixture_accepts_recorded_scan_output() {
+        let extractor = FixtureExtractor::from_json(r#"{
+            "results": [{
+                "path": "/usr/bin/tool",
+                "entitlements": {"com.apple.private.tcc.allow": ["kTCCServiceCamera"]},
+                "entitlement_count": 1,
+                "designated_requirement": "identifier tool"
+            }],
+            "summary": {"scanned": 1, "matched": 1, "skipped_unreadable": 0, "duration_ms": 5}
+        }"#).unwrap();
+
+        let report = extractor.extract(Path::new("/usr/bin/tool")).unwrap();
+        assert!(report.entitlements.contains_key("com.apple.private.tcc.allow"));
+        assert_eq!(report.designated_requirement.as_deref(), Some("identifier tool"));
+    }
+
+    #[test]
+    fn test_malformed_fixture_is_error() {
+        assert!(FixtureExtractor::from_json("[1, 2]").is_err());
+        assert!(FixtureExtractor::from_file(Path::new("/nonexistent/fixtures.json")).is_err());
+    }
+
+    #[test]
+    fn test_verify_and_hardening_need_native_parsing() {
+        let verify = ReadOptions { verify: true, ..ReadOptions::default() };
+        let hardening = ReadOptions { hardening: true, ..ReadOptions::default() };
+        assert!(create_extractor(&ExtractorBackend::Codesign, verify.clone()).is_err());
+        assert!(create_extractor(&ExtractorBackend::Codesign, hardening.clone()).is_err());
+        assert!(create_extractor(&ExtractorBackend::Native, verify).is_ok());
+        assert!(create_extractor(&ExtractorBackend::Native, hardening).is_ok());
+        assert!(create_extractor(&ExtractorBackend::Auto, ReadOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn test_auto_without_codesign_reports_native_errors() {
+        let extractor = AutoExtractor { native: NativeExtractor::default(), codesign: None };
+        assert!(extractor.extract(Path::new("/nonexistent/binary")).is_err());
+    }
+}
@@ -1,43 +1,462 @@
-//! Optimized entitlement extraction using the plist crate
+//! Native entitlement extraction from Mach-O code signatures
 //!
-//! This module provides more reliable entitlement parsing than the fallback:
+//! Reads entitlements straight out of the embedded code signature instead of
+//! running `codesign` once per file:
+//! - Walks the Mach-O load commands to `LC_CODE_SIGNATURE`
+//! - Decodes the embedded SuperBlob and its `CSSLOT_ENTITLEMENTS` blob
+//! - Falls back to the linker's `__TEXT,__entitlements` section (simulator and
+//!   linker-signed builds) when the signature carries no entitlements
+//! - Reads the signing identifier, team, CDHash and flags from the CodeDirectory
+//! - Classifies the signer from the CMS certificate chain
+//! - Decompiles the designated requirement into the requirement language
+//! - Decodes launch and library constraints
+//! - Reads the identity and version keys of the embedded or bundle Info.plist
+//! - Checks entitlements against the bundle's embedded provisioning profile
+//! - Lists linked dylibs and rpaths, flagging hijackable loads
+//! - Reads the target platform, minimum OS and SDK versions
+//! - Optionally recomputes the page and special-slot hashes to detect tampering
+//! - Optionally reports exploit mitigations (PIE, stack canaries, PAC, ...)
+//! - Optionally checks the enclosing bundle's files against its resource seal
 //! - Uses the plist crate for proper binary/XML plist parsing
-//! - Better error handling for edge cases
+//!
+//! Works on any host, so extracted macOS filesystems can be scanned from Linux;
+//! see [`root`] for reading an image mounted under a foreign root.
 
-use std::collections::HashMap;
-use std::path::Path;
-use std::process::Command;
-use anyhow::{Result, anyhow};
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::{Path, PathBuf};
+use anyhow::{Result, anyhow, bail};
 use serde_json::Value;
-use crate::constants::{CODESIGN_COMMAND, CODESIGN_ENTITLEMENT_ARGS};
+use crate::models::{EntitlementSource, InfoPlistSource, ResourceSealVerification, SignatureReport, SignerInfo, SignerKind, SliceEntitlements};
+
+pub mod build_version;
+pub mod cms;
+pub mod code_directory;
+pub mod constraints;
+pub mod der;
+pub mod dylibs;
+pub mod hardening;
+pub mod info_plist;
+pub mod macho;
+pub mod profile;
+pub mod requirement;
+pub mod root;
+pub mod rsa;
+pub mod seal;
+pub mod superblob;
+pub mod verify;
+
+#[cfg(test)]
+pub(crate) mod fixtures;
+
+use macho::MachOHeader;
+use superblob::{
+    SuperBlob, CSMAGIC_EMBEDDED_DER_ENTITLEMENTS, CSMAGIC_EMBEDDED_ENTITLEMENTS,
+    CSSLOT_DER_ENTITLEMENTS, CSSLOT_ENTITLEMENTS,
+};
+
+/// Sections the linker embeds entitlements in (`ld -sectcreate`, simulator builds)
+const ENTITLEMENTS_SEGMENT: &str = "__TEXT";
+const ENTITLEMENTS_SECTION: &str = "__entitlements";
+const DER_ENTITLEMENTS_SECTION: &str = "__ents_der";
+
+/// Finds the main executable of the process loading a binary under an optional root
+pub type LoadingExecutable = fn(&Path, Option<&Path>) -> Option<PathBuf>;
+
+/// Options for reading a signature report
+#[derive(Debug, Clone, Default)]
+pub struct ReadOptions {
+    /// Recompute the code page and special-slot hashes
+    pub verify: bool,
+    /// Report exploit mitigations
+    pub hardening: bool,
+    /// Check the enclosing bundle's files against `_CodeSignature/CodeResources`
+    pub verify_resources: bool,
+    /// Contents of the enclosing bundle's Info.plist, reported in place of an
+    /// embedded one and checked against the info slot
+    pub info_plist: Option<Vec<u8>>,
+    /// Contents of the bundle's CodeResources, checked against the resources slot
+    pub code_resources: Option<Vec<u8>>,
+    /// Foreign filesystem root the binary was read from; absolute library
+    /// paths are looked up under it
+    pub root: Option<PathBuf>,
+    /// Finds the executable `@executable_path` names; without it, or when it
+    /// finds none, each binary is taken to be its own
+    pub loading_executable: Option<LoadingExecutable>,
+}
+
+/// Read the code signature report of a binary natively
+///
+/// Files that are not Mach-O, or are not signed, have no entitlements.
+#[allow(dead_code)] // Library API; the binary always passes read options
+pub fn read_signature_report(binary_path: &Path) -> Result<SignatureReport> {
+    read_signature_report_with(binary_path, &ReadOptions::default())
+}
+
+/// Read the code signature report of a binary natively with the given options
+///
+/// The Info.plist of the enclosing bundle is picked up from disk, as is its
+/// resource seal when `verify_resources` is set.
+pub fn read_signature_report_with(binary_path: &Path, options: &ReadOptions) -> Result<SignatureReport> {
+    let mut reader = BufReader::new(File::open(binary_path)?);
+    let mut options = options.clone();
+    let root = options.root.clone();
+    if options.info_plist.is_none() {
+        options.info_plist = bundle_file(binary_path, "Info.plist", root.as_deref());
+    }
+    let bundle_seal = if options.verify_resources { seal::find_seal(binary_path, root.as_deref()) } else { None };
+    let mut seal_warning = None;
+    if let Some(bundle_seal) = &bundle_seal {
+        match root::readable(root.as_deref(), &bundle_seal.code_resources_path()).and_then(std::fs::read) {
+            Ok(data) => options.code_resources = Some(data),
+            Err(e) => seal_warning = Some(format!("Could not read CodeResources: {}", e)),
+        }
+    }
+    let mut report = signature_report_from_reader_with(&mut reader, &options)?;
+    report.warnings.extend(seal_warning);
+
+    if let (Some(bundle_seal), Some(code_resources)) = (&bundle_seal, &options.code_resources) {
+        match seal::verify_seal(bundle_seal, code_resources, root.as_deref()) {
+            Ok((files, warnings)) => {
+                report.warnings.extend(warnings);
+                let verification = report.resource_seal.get_or_insert_with(Default::default);
+                verification.added = files.added;
+                verification.removed = files.removed;
+                verification.modified.extend(files.modified);
+            }
+            Err(e) => report.warnings.push(format!("Could not verify resource seal: {}", e)),
+        }
+    }
+
+    if let Some(data) = bundle_file(binary_path, profile::EMBEDDED_PROFILE_NAME, root.as_deref()) {
+        match profile::parse_profile(&data) {
+            Ok(mut profile) => {
+                profile.unauthorized_entitlements = profile::unauthorized_entitlements(&report.entitlements, &profile);
+                report.provisioning_profile = Some(profile);
+            }
+            Err(e) => report.warnings.push(format!("Could not read provisioning profile: {}", e)),
+        }
+    }
+    let executable = options
+        .loading_executable
+        .and_then(|find| find(binary_path, root.as_deref()))
+        .unwrap_or_else(|| binary_path.to_path_buf());
+    report.dylib_hijack_risks = dylibs::hijack_risks(binary_path, &executable, &report.entitlements, &report.dylibs, &report.rpaths, root.as_deref());
+    Ok(report)
+}
+
+/// Read the code signature report from any seekable Mach-O source (file, in-memory buffer, ...)
+#[allow(dead_code)] // Library API for in-memory images
+pub fn signature_report_from_reader<R: Read + Seek>(reader: &mut R) -> Result<SignatureReport> {
+    signature_report_from_reader_with(reader, &ReadOptions::default())
+}
+
+/// Read the code signature report from any seekable Mach-O source with the given options
+///
+/// Universal binaries get a per-slice breakdown; the top-level entitlements are
+/// those of the slice `codesign` would report on this host.
+pub fn signature_report_from_reader_with<R: Read + Seek>(reader: &mut R, options: &ReadOptions) -> Result<SignatureReport> {
+    let slices = macho::read_slices(reader)?;
+    let preferred = match macho::preferred_slice(&slices) {
+        Some(slice) => slice.clone(),
+        None => return Ok(SignatureReport::default()),
+    };
+
+    let mut report = if slices.len() == 1 {
+        slice_report(reader, &preferred, options)?
+    } else {
+        universal_report(reader, &slices, &preferred, options)?
+    };
+
+    if let Some(data) = &options.info_plist {
+        match info_plist::parse_info_plist(data, InfoPlistSource::Bundle) {
+            Ok(info) => report.bundle_info = Some(info),
+            Err(e) => report.warnings.push(format!("Could not read bundle Info.plist: {}", e)),
+        }
+    }
+    Ok(report)
+}
+
+/// Combine the reports of every slice of a universal binary
+fn universal_report<R: Read + Seek>(
+    reader: &mut R,
+    slices: &[macho::MachOSlice],
+    preferred: &macho::MachOSlice,
+    options: &ReadOptions,
+) -> Result<SignatureReport> {
+    let mut report = SignatureReport::default();
+    for slice in slices {
+        let slice_report = slice_report(reader, slice, options)?;
+        if slice == preferred {
+            report.entitlements = slice_report.entitlements.clone();
+            report.warnings = slice_report.warnings.clone();
+            report.code_directory = slice_report.code_directory.clone();
+            report.signer = slice_report.signer.clone();
+            report.designated_requirement = slice_report.designated_requirement.clone();
+            report.constraints = slice_report.constraints.clone();
+            report.verification = slice_report.verification.clone();
+            report.bundle_info = slice_report.bundle_info.clone();
+            report.dylibs = slice_report.dylibs.clone();
+            report.rpaths = slice_report.rpaths.clone();
+            report.hardening = slice_report.hardening;
+            report.source = slice_report.source;
+            report.resource_seal = slice_report.resource_seal.clone();
+            report.build_versions = slice_report.build_versions.clone();
+        }
+        report.slices.push(SliceEntitlements {
+            arch: slice.arch_name(),
+            entitlement_count: slice_report.entitlements.len(),
+            entitlements: slice_report.entitlements,
+            warnings: slice_report.warnings,
+            code_directory: slice_report.code_directory,
+            verification: slice_report.verification,
+        });
+    }
+
+    // Any tampered slice makes the whole file fail verification
+    if let Some(failed) = report.slices.iter().filter_map(|s| s.verification.as_ref()).find(|v| !v.valid) {
+        report.verification = Some(failed.clone());
+    }
+
+    Ok(report)
+}
+
+/// Read the signature, embedded Info.plist, linked libraries and build versions of a single architecture slice
+fn slice_report<R: Read + Seek>(reader: &mut R, slice: &macho::MachOSlice, options: &ReadOptions) -> Result<SignatureReport> {
+    let header = MachOHeader::read(reader, slice)?;
+    let mut report = match header.read_code_signature(reader)? {
+        Some(signature) => {
+            let mut report = report_from_signature(&signature)?;
+            if options.verify {
+                let superblob = SuperBlob::parse(&signature)?;
+                match verify::verify_signature(reader, header.slice_offset, slice.size, &superblob, options.info_plist.as_deref()) {
+                    Ok(verification) => report.verification = Some(verification),
+                    Err(e) => report.warnings.push(format!("Could not verify signature: {}", e)),
+                }
+            }
+            if let Some(code_resources) = &options.code_resources {
+                let superblob = SuperBlob::parse(&signature)?;
+                match verify::resources_match(&superblob, code_resources) {
+                    Ok(sealed) => {
+                        let modified = if sealed { Vec::new() } else { vec![seal::CODE_RESOURCES_PATH.to_string()] };
+                        report.resource_seal = Some(ResourceSealVerification { modified, ..Default::default() });
+                    }
+                    Err(e) => report.warnings.push(format!("Could not check the resources slot: {}", e)),
+                }
+            }
+            report
+        }
+        None => SignatureReport {
+            signer: Some(SignerInfo::without_certificate(SignerKind::Unsigned)),
+            ..SignatureReport::default()
+        },
+    };
+
+    if !report.entitlements.is_empty() {
+        report.source = Some(EntitlementSource::Signature);
+    } else {
+        match section_entitlements(reader, &header) {
+            Ok(Some(entitlements)) if !entitlements.is_empty() => {
+                report.entitlements = entitlements;
+                report.source = Some(EntitlementSource::Section);
+            }
+            Ok(_) => {}
+            Err(e) => report.warnings.push(format!("Could not read __entitlements section: {}", e)),
+        }
+    }
+
+    let embedded = info_plist::embedded_info_plist(reader, &header)
+        .and_then(|data| data.map(|data| info_plist::parse_info_plist(&data, InfoPlistSource::Embedded)).transpose());
+    match embedded {
+        Ok(info) => report.bundle_info = info,
+        Err(e) => report.warnings.push(format!("Could not read embedded Info.plist: {}", e)),
+    }
+
+    match dylibs::linked_dylibs(&header).and_then(|linked| Ok((linked, dylibs::rpaths(&header)?))) {
+        Ok((linked, rpaths)) => {
+            report.dylibs = linked;
+            report.rpaths = rpaths;
+        }
+        Err(e) => report.warnings.push(format!("Could not read linked libraries: {}", e)),
+    }
+
+    match build_version::build_versions(&header) {
+        Ok(versions) => report.build_versions = versions,
+        Err(e) => report.warnings.push(format!("Could not read build version: {}", e)),
+    }
 
-/// Extract entitlements using optimized codesign with proper plist parsing
-pub fn extract_entitlements_optimized(binary_path: &Path) -> Result<HashMap<String, Value>> {
-    // Call codesign to extract entitlements in plist format
-    let output = Command::new(CODESIGN_COMMAND)
-        .args(CODESIGN_ENTITLEMENT_ARGS)
-        .arg(binary_path)
-        .output()?;
+    if options.hardening {
+        match hardening::hardening_info(reader, slice, &header, &report.entitlements, report.code_directory.as_ref()) {
+            Ok(info) => report.hardening = Some(info),
+            Err(e) => report.warnings.push(format!("Could not assess hardening: {}", e)),
+        }
+    }
+    Ok(report)
+}
 
-    if !output.status.success() {
-        // Binary might not be signed or might not have entitlements
-        return Ok(HashMap::new());
+/// A file in the `Contents` directory of the bundle whose `Contents/MacOS`
+/// directory holds the binary (Info.plist, embedded.provisionprofile, ...),
+/// read inside the image under a foreign `root`
+fn bundle_file(binary_path: &Path, name: &str, root: Option<&Path>) -> Option<Vec<u8>> {
+    let macos = binary_path.parent()?;
+    let contents = macos.parent()?;
+    if macos.file_name()? != "MacOS" || contents.file_name()? != "Contents" {
+        return None;
     }
+    std::fs::read(root::readable(root, &contents.join(name)).ok()?).ok()
+}
 
-    if output.stdout.is_empty() {
-        return Ok(HashMap::new());
+/// Decode the entitlements of an embedded signature.
+///
+/// The XML plist is what `codesign` displays, so it wins when both encodings are
+/// present; the DER copy covers signers that only populate that one.
+fn report_from_signature(signature: &[u8]) -> Result<SignatureReport> {
+    let superblob = SuperBlob::parse(signature)?;
+    let xml = xml_entitlements(&superblob)?;
+    let der = der_entitlements(&superblob)?;
+
+    let mut warnings = Vec::new();
+    if let (Some(xml), Some(der)) = (&xml, &der) {
+        if let Some(mismatch) = describe_entitlement_mismatch(xml, der) {
+            warnings.push(mismatch);
+        }
+    }
+
+    // A damaged CodeDirectory should not hide the entitlements
+    let code_directory = match code_directory::best_code_directory(&superblob)
+        .and_then(|cd| cd.map(|cd| cd.info()).transpose())
+    {
+        Ok(info) => info,
+        Err(e) => {
+            warnings.push(format!("Could not read CodeDirectory: {}", e));
+            None
+        }
+    };
+
+    let signer = match cms::signature_signer(code_directory.as_ref(), &superblob) {
+        Ok(signer) => Some(signer),
+        Err(e) => {
+            warnings.push(format!("Could not read signer certificates: {}", e));
+            None
+        }
+    };
+
+    let designated_requirement = match requirement::designated_requirement(&superblob) {
+        Ok(requirement) => requirement,
+        Err(e) => {
+            warnings.push(format!("Could not decode designated requirement: {}", e));
+            None
+        }
+    };
+
+    let constraints = match constraints::signature_constraints(&superblob) {
+        Ok(constraints) => constraints,
+        Err(e) => {
+            warnings.push(format!("Could not decode launch constraints: {}", e));
+            None
+        }
+    };
+
+    Ok(SignatureReport {
+        entitlements: xml.or(der).unwrap_or_default(),
+        warnings,
+        code_directory,
+        signer,
+        designated_requirement,
+        constraints,
+        ..SignatureReport::default()
+    })
+}
+
+/// Decode the XML entitlements blob, if the signature has one
+fn xml_entitlements(superblob: &SuperBlob) -> Result<Option<HashMap<String, Value>>> {
+    let blob = match superblob.slot(CSSLOT_ENTITLEMENTS) {
+        Some(blob) => blob,
+        None => return Ok(None),
+    };
+    if blob.magic != CSMAGIC_EMBEDDED_ENTITLEMENTS {
+        bail!("Unexpected entitlements blob magic {:#x}", blob.magic);
+    }
+    if blob.payload().is_empty() {
+        return Ok(Some(HashMap::new()));
     }
 
     // Parse the plist XML using the plist crate for better performance and reliability
-    let plist_value: plist::Value = plist::from_bytes(&output.stdout)
+    let plist_value: plist::Value = plist::from_bytes(blob.payload())
         .map_err(|e| anyhow!("Failed to parse entitlements plist: {}", e))?;
 
     // Convert plist value to JSON-compatible HashMap
-    plist_to_json_map(plist_value)
+    plist_to_json_map(plist_value).map(Some)
+}
+
+/// Decode the DER entitlements blob, if the signature has one
+fn der_entitlements(superblob: &SuperBlob) -> Result<Option<HashMap<String, Value>>> {
+    let blob = match superblob.slot(CSSLOT_DER_ENTITLEMENTS) {
+        Some(blob) => blob,
+        None => return Ok(None),
+    };
+    if blob.magic != CSMAGIC_EMBEDDED_DER_ENTITLEMENTS {
+        bail!("Unexpected DER entitlements blob magic {:#x}", blob.magic);
+    }
+
+    der::decode_der_entitlements(blob.payload()).map(Some)
+}
+
+/// Decode the entitlements the linker embedded in `__TEXT,__entitlements`
+/// (XML plist) or `__TEXT,__ents_der`, if the slice has either
+fn section_entitlements<R: Read + Seek>(reader: &mut R, header: &MachOHeader) -> Result<Option<HashMap<String, Value>>> {
+    if let Some(data) = header.read_section(reader, ENTITLEMENTS_SEGMENT, ENTITLEMENTS_SECTION)? {
+        // Sections are NUL-padded to their alignment
+        let end = data.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+        if end == 0 {
+            return Ok(Some(HashMap::new()));
+        }
+        let plist_value: plist::Value = plist::from_bytes(&data[..end])
+            .map_err(|e| anyhow!("Failed to parse entitlements plist: {}", e))?;
+        return plist_to_json_map(plist_value).map(Some);
+    }
+    match header.read_section(reader, ENTITLEMENTS_SEGMENT, DER_ENTITLEMENTS_SECTION)? {
+        Some(data) => der::decode_der_entitlements(&data).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Describe how the XML and DER entitlement copies differ, if they do
+fn describe_entitlement_mismatch(xml: &HashMap<String, Value>, der: &HashMap<String, Value>) -> Option<String> {
+    let keys: BTreeSet<&String> = xml.keys().chain(der.keys()).collect();
+    let mut only_xml = Vec::new();
+    let mut only_der = Vec::new();
+    let mut differing = Vec::new();
+
+    for key in keys {
+        match (xml.get(key), der.get(key)) {
+            (Some(_), None) => only_xml.push(key.as_str()),
+            (None, Some(_)) => only_der.push(key.as_str()),
+            (Some(a), Some(b)) if a != b => differing.push(key.as_str()),
+            _ => {}
+        }
+    }
+
+    if only_xml.is_empty() && only_der.is_empty() && differing.is_empty() {
+        return None;
+    }
+
+    let mut parts = Vec::new();
+    if !only_xml.is_empty() {
+        parts.push(format!("only in XML: {}", only_xml.join(", ")));
+    }
+    if !only_der.is_empty() {
+        parts.push(format!("only in DER: {}", only_der.join(", ")));
+    }
+    if !differing.is_empty() {
+        parts.push(format!("different values: {}", differing.join(", ")));
+    }
+    Some(format!("XML and DER entitlements disagree ({})", parts.join("; ")))
 }
 
 /// Convert plist::Value to JSON-compatible HashMap
-fn plist_to_json_map(plist_value: plist::Value) -> Result<HashMap<String, Value>> {
+pub(crate) fn plist_to_json_map(plist_value: plist::Value) -> Result<HashMap<String, Value>> {
     match plist_value {
         plist::Value::Dictionary(dict) => {
             let mut result = HashMap::new();
@@ -115,16 +534,26 @@ fn hex_encode(data: &[u8]) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::Cursor;
     use std::path::PathBuf;
+    use macho::{CPU_TYPE_ARM64, CPU_TYPE_X86_64};
+
+    /// Signed thin image carrying the given boolean entitlements
+    fn signed_image(cputype: u32, keys: &[&str]) -> Vec<u8> {
+        let entitlements = fixtures::blob(CSMAGIC_EMBEDDED_ENTITLEMENTS, &fixtures::entitlements_plist(keys));
+        fixtures::MachOBuilder::new(cputype, 0)
+            .signature(fixtures::superblob(&[(CSSLOT_ENTITLEMENTS, entitlements)]))
+            .build()
+    }
 
     #[test]
     fn test_optimized_extraction_system_binary() {
         // Test with a known system binary that should have entitlements
         let test_binary = PathBuf::from("/usr/bin/top");
         if test_binary.exists() {
-            let result = extract_entitlements_optimized(&test_binary);
+            let result = read_signature_report(&test_binary);
             match result {
-                Ok(entitlements) => {
+                Ok(SignatureReport { entitlements, .. }) => {
                     if !entitlements.is_empty() {
                         println!("Found {} entitlements in /usr/bin/top", entitlements.len());
                         for (key, value) in &entitlements {
@@ -143,9 +572,478 @@ mod tests {
     fn test_optimized_extraction_unsigned_binary() {
         // Test with our own binary (likely unsigned in debug builds)
         let current_exe = std::env::current_exe().expect("Could not get current executable");
-        let result = extract_entitlements_optimized(&current_exe);
+        let result = read_signature_report(&current_exe);
 
         // Should succeed but might return empty entitlements for unsigned binaries
         assert!(result.is_ok(), "Optimized extraction should handle unsigned binaries gracefully");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_extracts_entitlements_from_fixture() {
+        let image = signed_image(CPU_TYPE_ARM64, &["com.apple.security.app-sandbox", "com.apple.security.network.client"]);
+        let entitlements = signature_report_from_reader(&mut Cursor::new(image)).unwrap().entitlements;
+
+        assert_eq!(entitlements.len(), 2);
+        assert_eq!(entitlements.get("com.apple.security.app-sandbox"), Some(&Value::Bool(true)));
+        assert_eq!(entitlements.get("com.apple.security.network.client"), Some(&Value::Bool(true)));
+    }
+
+    #[test]
+    fn test_unsigned_fixture_has_no_entitlements() {
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0).build();
+        assert!(signature_report_from_reader(&mut Cursor::new(image)).unwrap().entitlements.is_empty());
+    }
+
+    #[test]
+    fn test_signature_without_entitlements_slot() {
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .signature(fixtures::superblob(&[(0, fixtures::CodeDirectoryBuilder::new("com.example.tool").build())]))
+            .build();
+        assert!(signature_report_from_reader(&mut Cursor::new(image)).unwrap().entitlements.is_empty());
+    }
+
+    #[test]
+    fn test_universal_binary_uses_native_slice() {
+        let fat = fixtures::fat(vec![
+            signed_image(CPU_TYPE_X86_64, &["x86.only"]),
+            signed_image(CPU_TYPE_ARM64, &["arm.only"]),
+        ]);
+        let report = signature_report_from_reader(&mut Cursor::new(fat)).unwrap();
+        let expected = if cfg!(target_arch = "x86_64") { "x86.only" } else { "arm.only" };
+        assert!(report.entitlements.contains_key(expected));
+        assert_eq!(report.entitlements.len(), 1);
+    }
+
+    #[test]
+    fn test_universal_binary_reports_each_slice() {
+        let fat = fixtures::fat(vec![
+            signed_image(CPU_TYPE_X86_64, &["com.apple.security.cs.allow-jit"]),
+            signed_image(CPU_TYPE_ARM64, &["com.apple.security.cs.allow-jit", "com.apple.security.get-task-allow"]),
+        ]);
+        let report = signature_report_from_reader(&mut Cursor::new(fat)).unwrap();
+
+        let arches: Vec<&str> = report.slices.iter().map(|s| s.arch.as_str()).collect();
+        assert_eq!(arches, vec!["x86_64", "arm64"]);
+        assert_eq!(report.slices[1].entitlement_count, 2);
+        assert_eq!(report.slices_disagree(), Some(true));
+        assert!(report.all_entitlement_keys().contains(&"com.apple.security.get-task-allow".to_string()));
+    }
+
+    #[test]
+    fn test_universal_binary_with_matching_slices() {
+        let fat = fixtures::fat(vec![
+            signed_image(CPU_TYPE_X86_64, &["com.apple.security.app-sandbox"]),
+            signed_image(CPU_TYPE_ARM64, &["com.apple.security.app-sandbox"]),
+        ]);
+        let report = signature_report_from_reader(&mut Cursor::new(fat)).unwrap();
+        assert_eq!(report.slices.len(), 2);
+        assert_eq!(report.slices_disagree(), Some(false));
+    }
+
+    #[test]
+    fn test_thin_binary_has_no_slice_breakdown() {
+        let image = signed_image(CPU_TYPE_ARM64, &["com.apple.security.app-sandbox"]);
+        let report = signature_report_from_reader(&mut Cursor::new(image)).unwrap();
+        assert!(report.slices.is_empty());
+        assert_eq!(report.slices_disagree(), None);
+    }
+
+    #[test]
+    fn test_malformed_entitlements_blob_is_error() {
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .signature(fixtures::superblob(&[(CSSLOT_ENTITLEMENTS, fixtures::blob(CSMAGIC_EMBEDDED_ENTITLEMENTS, b"<plist><dict>"))]))
+            .build();
+        assert!(signature_report_from_reader(&mut Cursor::new(image)).is_err());
+    }
+
+    /// Signed image carrying the given XML and DER entitlement blobs
+    fn image_with_blobs(xml: Option<&[&str]>, der_entries: Option<&[(&str, Vec<u8>)]>) -> Vec<u8> {
+        let mut blobs = Vec::new();
+        if let Some(keys) = xml {
+            blobs.push((CSSLOT_ENTITLEMENTS, fixtures::blob(CSMAGIC_EMBEDDED_ENTITLEMENTS, &fixtures::entitlements_plist(keys))));
+        }
+        if let Some(entries) = der_entries {
+            blobs.push((CSSLOT_DER_ENTITLEMENTS, fixtures::blob(CSMAGIC_EMBEDDED_DER_ENTITLEMENTS, &fixtures::der::entitlements(entries))));
+        }
+        fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .signature(fixtures::superblob(&blobs))
+            .build()
+    }
+
+    #[test]
+    fn test_der_only_entitlements_are_decoded() {
+        let image = image_with_blobs(None, Some(&[("com.apple.security.get-task-allow", fixtures::der::boolean(true))]));
+        let report = signature_report_from_reader(&mut Cursor::new(image)).unwrap();
+
+        assert_eq!(report.entitlements.get("com.apple.security.get-task-allow"), Some(&Value::Bool(true)));
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_matching_xml_and_der_have_no_warning() {
+        let image = image_with_blobs(
+            Some(&["com.apple.security.app-sandbox"]),
+            Some(&[("com.apple.security.app-sandbox", fixtures::der::boolean(true))]),
+        );
+        let report = signature_report_from_reader(&mut Cursor::new(image)).unwrap();
+
+        assert_eq!(report.entitlements.len(), 1);
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_disagreeing_xml_and_der_are_reported() {
+        let image = image_with_blobs(
+            Some(&["com.apple.security.app-sandbox", "xml.only"]),
+            Some(&[
+                ("com.apple.security.app-sandbox", fixtures::der::boolean(false)),
+                ("der.only", fixtures::der::boolean(true)),
+            ]),
+        );
+        let report = signature_report_from_reader(&mut Cursor::new(image)).unwrap();
+
+        // XML copy wins, matching what codesign displays
+        assert!(report.entitlements.contains_key("xml.only"));
+        assert!(!report.entitlements.contains_key("der.only"));
+        assert_eq!(report.warnings.len(), 1);
+        let warning = &report.warnings[0];
+        assert!(warning.contains("only in XML: xml.only"), "{}", warning);
+        assert!(warning.contains("only in DER: der.only"), "{}", warning);
+        assert!(warning.contains("different values: com.apple.security.app-sandbox"), "{}", warning);
+    }
+
+    #[test]
+    fn test_code_directory_metadata_is_reported() {
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .signature(fixtures::superblob(&[
+                (code_directory::CSSLOT_CODEDIRECTORY, fixtures::CodeDirectoryBuilder::new("com.example.app")
+                    .hash_type(code_directory::CS_HASHTYPE_SHA1)
+                    .build()),
+                (code_directory::CSSLOT_ALTERNATE_CODEDIRECTORIES, fixtures::CodeDirectoryBuilder::new("com.example.app")
+                    .team_id("ABCDE12345")
+                    .flags(code_directory::CS_RUNTIME)
+                    .build()),
+            ]))
+            .build();
+        let info = signature_report_from_reader(&mut Cursor::new(image)).unwrap().code_directory.unwrap();
+
+        assert_eq!(info.identifier, "com.example.app");
+        assert_eq!(info.team_id.as_deref(), Some("ABCDE12345"));
+        assert_eq!(info.hash_type, "sha256");
+        assert_eq!(info.flag_names, vec!["runtime"]);
+        assert!(!info.platform_binary);
+    }
+
+    #[test]
+    fn test_universal_slices_carry_their_own_code_directory() {
+        let signed = |cputype, identifier| fixtures::MachOBuilder::new(cputype, 0)
+            .signature(fixtures::superblob(&[(0, fixtures::CodeDirectoryBuilder::new(identifier).build())]))
+            .build();
+        let fat = fixtures::fat(vec![signed(CPU_TYPE_X86_64, "com.example.intel"), signed(CPU_TYPE_ARM64, "com.example.arm")]);
+        let report = signature_report_from_reader(&mut Cursor::new(fat)).unwrap();
+
+        let identifiers: Vec<&str> = report.slices.iter()
+            .map(|s| s.code_directory.as_ref().unwrap().identifier.as_str())
+            .collect();
+        assert_eq!(identifiers, vec!["com.example.intel", "com.example.arm"]);
+        assert_ne!(report.slices[0].code_directory.as_ref().unwrap().cdhash, report.slices[1].code_directory.as_ref().unwrap().cdhash);
+        assert!(report.code_directory.is_some());
+    }
+
+    #[test]
+    fn test_damaged_code_directory_is_a_warning() {
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .signature(fixtures::superblob(&[
+                (0, fixtures::blob(code_directory::CSMAGIC_CODEDIRECTORY, &[0u8; 32])),
+                (CSSLOT_ENTITLEMENTS, fixtures::blob(CSMAGIC_EMBEDDED_ENTITLEMENTS, &fixtures::entitlements_plist(&["com.apple.security.app-sandbox"]))),
+            ]))
+            .build();
+        let report = signature_report_from_reader(&mut Cursor::new(image)).unwrap();
+
+        assert_eq!(report.entitlements.len(), 1);
+        assert!(report.code_directory.is_none());
+        assert!(report.warnings[0].starts_with("Could not read CodeDirectory"), "{:?}", report.warnings);
+    }
+
+    #[test]
+    fn test_unsigned_binary_has_no_code_directory() {
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0).build();
+        let report = signature_report_from_reader(&mut Cursor::new(image)).unwrap();
+        assert!(report.code_directory.is_none());
+        assert_eq!(report.signer.unwrap().kind, SignerKind::Unsigned);
+    }
+
+    /// A slice whose CodeDirectory hashes its own code in 64-byte pages
+    fn self_hashed(cputype: u32) -> Vec<u8> {
+        fixtures::MachOBuilder::new(cputype, 0).build_with_signature(|code| {
+            fixtures::superblob(&[(0, fixtures::CodeDirectoryBuilder::new("com.example.tool").code(code, 6).build())])
+        })
+    }
+
+    #[test]
+    fn test_verification_only_when_requested() {
+        let image = self_hashed(CPU_TYPE_ARM64);
+        assert!(signature_report_from_reader(&mut Cursor::new(image.clone())).unwrap().verification.is_none());
+
+        let options = ReadOptions { verify: true, ..ReadOptions::default() };
+        let report = signature_report_from_reader_with(&mut Cursor::new(image), &options).unwrap();
+        assert!(report.verification.unwrap().valid);
+    }
+
+    #[test]
+    fn test_tampered_slice_fails_universal_verification() {
+        let mut intel = self_hashed(CPU_TYPE_X86_64);
+        intel[60] ^= 0xff;
+        let fat = fixtures::fat(vec![intel, self_hashed(CPU_TYPE_ARM64)]);
+        let options = ReadOptions { verify: true, ..ReadOptions::default() };
+        let report = signature_report_from_reader_with(&mut Cursor::new(fat), &options).unwrap();
+
+        assert!(!report.slices[0].verification.as_ref().unwrap().valid);
+        assert!(report.slices[1].verification.as_ref().unwrap().valid);
+        assert_eq!(report.verification.unwrap().first_invalid_page, Some(0));
+    }
+
+    #[test]
+    fn test_resource_seal_is_bound_to_signature() {
+        let bundle = tempfile::tempdir().unwrap();
+        let contents = bundle.path().join("Tool.app/Contents");
+        std::fs::create_dir_all(contents.join("MacOS")).unwrap();
+        std::fs::create_dir_all(contents.join("_CodeSignature")).unwrap();
+        let code_resources = br#"<plist version="1.0"><dict><key>files2</key><dict/></dict></plist>"#;
+        std::fs::write(contents.join(seal::CODE_RESOURCES_PATH), code_resources).unwrap();
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .signature(fixtures::superblob(&[(0, fixtures::CodeDirectoryBuilder::new("com.example.tool")
+                .special_slot(3, code_resources)
+                .build())]))
+            .build();
+        std::fs::write(contents.join("MacOS/Tool"), image).unwrap();
+        std::fs::write(contents.join("MacOS/extra"), b"planted").unwrap();
+
+        let options = ReadOptions { verify_resources: true, ..ReadOptions::default() };
+        let verification = read_signature_report_with(&contents.join("MacOS/Tool"), &options).unwrap().resource_seal.unwrap();
+        assert!(verification.modified.is_empty());
+        assert!(verification.added.is_empty(), "no rules, so nothing else must be sealed");
+
+        std::fs::write(contents.join(seal::CODE_RESOURCES_PATH), br#"<plist version="1.0"><dict/></plist>"#).unwrap();
+        let verification = read_signature_report_with(&contents.join("MacOS/Tool"), &options).unwrap().resource_seal.unwrap();
+        assert_eq!(verification.modified, vec![seal::CODE_RESOURCES_PATH]);
+
+        assert!(read_signature_report_with(&contents.join("MacOS/Tool"), &ReadOptions::default()).unwrap().resource_seal.is_none());
+    }
+
+    #[test]
+    fn test_bundle_files_are_found() {
+        let bundle = tempfile::tempdir().unwrap();
+        let macos = bundle.path().join("Tool.app/Contents/MacOS");
+        std::fs::create_dir_all(&macos).unwrap();
+        std::fs::write(bundle.path().join("Tool.app/Contents/Info.plist"), b"<plist/>").unwrap();
+
+        assert_eq!(bundle_file(&macos.join("Tool"), "Info.plist", None).as_deref(), Some(&b"<plist/>"[..]));
+        assert_eq!(bundle_file(&bundle.path().join("Tool"), "Info.plist", None), None);
+
+        // Under a foreign root an absolute link points back into the image
+        std::fs::create_dir_all(bundle.path().join("Library")).unwrap();
+        std::fs::write(bundle.path().join("Library/Info.plist"), b"<plist><dict/></plist>").unwrap();
+        std::fs::remove_file(bundle.path().join("Tool.app/Contents/Info.plist")).unwrap();
+        std::os::unix::fs::symlink("/Library/Info.plist", bundle.path().join("Tool.app/Contents/Info.plist")).unwrap();
+        assert_eq!(bundle_file(&macos.join("Tool"), "Info.plist", Some(bundle.path())).as_deref(), Some(&b"<plist><dict/></plist>"[..]));
+    }
+
+    #[test]
+    fn test_embedded_info_plist_is_reported() {
+        let plist = br#"<plist version="1.0"><dict><key>CFBundleIdentifier</key><string>com.example.tool</string><key>CFBundleShortVersionString</key><string>2.0</string></dict></plist>"#;
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0).section("__TEXT", "__info_plist", plist).build();
+        let info = signature_report_from_reader(&mut Cursor::new(image.clone())).unwrap().bundle_info.unwrap();
+        assert_eq!(info.source, InfoPlistSource::Embedded);
+        assert_eq!(info.identifier.as_deref(), Some("com.example.tool"));
+        assert_eq!(info.short_version.as_deref(), Some("2.0"));
+
+        // The bundle's Info.plist takes precedence
+        let bundle = br#"<plist version="1.0"><dict><key>CFBundleIdentifier</key><string>com.example.app</string></dict></plist>"#;
+        let options = ReadOptions { info_plist: Some(bundle.to_vec()), ..ReadOptions::default() };
+        let info = signature_report_from_reader_with(&mut Cursor::new(image), &options).unwrap().bundle_info.unwrap();
+        assert_eq!(info.source, InfoPlistSource::Bundle);
+        assert_eq!(info.identifier.as_deref(), Some("com.example.app"));
+    }
+
+    #[test]
+    fn test_malformed_embedded_info_plist_is_a_warning() {
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0).section("__TEXT", "__info_plist", b"garbage").build();
+        let report = signature_report_from_reader(&mut Cursor::new(image)).unwrap();
+        assert!(report.bundle_info.is_none());
+        assert!(report.warnings[0].starts_with("Could not read embedded Info.plist"), "{:?}", report.warnings);
+    }
+
+    #[test]
+    fn test_section_entitlements_fill_in_for_signature() {
+        let mut section = fixtures::entitlements_plist(&["com.apple.security.get-task-allow"]);
+        section.extend_from_slice(&[0; 7]);
+        let unsigned = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0).section("__TEXT", "__entitlements", &section).build();
+        let report = signature_report_from_reader(&mut Cursor::new(unsigned)).unwrap();
+        assert_eq!(report.source, Some(EntitlementSource::Section));
+        assert!(report.entitlements.contains_key("com.apple.security.get-task-allow"));
+
+        // Signature entitlements win over the section
+        let signature = fixtures::entitlements_plist(&["com.apple.security.app-sandbox"]);
+        let signed = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .section("__TEXT", "__entitlements", &section)
+            .signature(fixtures::superblob(&[(CSSLOT_ENTITLEMENTS, fixtures::blob(CSMAGIC_EMBEDDED_ENTITLEMENTS, &signature))]))
+            .build();
+        let report = signature_report_from_reader(&mut Cursor::new(signed)).unwrap();
+        assert_eq!(report.source, Some(EntitlementSource::Signature));
+        assert_eq!(report.entitlements.keys().collect::<Vec<_>>(), vec!["com.apple.security.app-sandbox"]);
+
+        let bare = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0).build();
+        assert_eq!(signature_report_from_reader(&mut Cursor::new(bare)).unwrap().source, None);
+    }
+
+    #[test]
+    fn test_malformed_entitlements_section_is_a_warning() {
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0).section("__TEXT", "__entitlements", b"<plist").build();
+        let report = signature_report_from_reader(&mut Cursor::new(image)).unwrap();
+        assert!(report.entitlements.is_empty());
+        assert!(report.warnings[0].starts_with("Could not read __entitlements section"), "{:?}", report.warnings);
+    }
+
+    #[test]
+    fn test_provisioning_profile_is_checked() {
+        let contents = tempfile::tempdir().unwrap();
+        let macos = contents.path().join("Example.app/Contents/MacOS");
+        std::fs::create_dir_all(&macos).unwrap();
+
+        let entitlements = fixtures::entitlements_plist(&["com.apple.developer.endpoint-security.client", "com.apple.security.app-sandbox"]);
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .signature(fixtures::superblob(&[(CSSLOT_ENTITLEMENTS, fixtures::blob(CSMAGIC_EMBEDDED_ENTITLEMENTS, &entitlements))]))
+            .build();
+        std::fs::write(macos.join("Example"), image).unwrap();
+        let plist = br#"<plist version="1.0"><dict><key>Name</key><string>Example</string><key>Entitlements</key><dict/></dict></plist>"#;
+        std::fs::write(
+            contents.path().join("Example.app/Contents/embedded.provisionprofile"),
+            fixtures::cms::signed_content(Some(plist), &[], false),
+        ).unwrap();
+
+        let report = read_signature_report_with(&macos.join("Example"), &ReadOptions::default()).unwrap();
+        let profile = report.provisioning_profile.unwrap();
+        assert_eq!(profile.name.as_deref(), Some("Example"));
+        assert_eq!(profile.unauthorized_entitlements, vec!["com.apple.developer.endpoint-security.client"]);
+    }
+
+    #[test]
+    fn test_dylib_hijack_risks_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let entitlements = fixtures::entitlements_plist(&["com.apple.security.cs.disable-library-validation"]);
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .load_command(macho::LC_LOAD_DYLIB, &fixtures::dylib_command("/usr/lib/libSystem.B.dylib"))
+            .load_command(macho::LC_LOAD_WEAK_DYLIB, &fixtures::dylib_command("@rpath/Plugin.dylib"))
+            .load_command(macho::LC_RPATH, &fixtures::rpath_command("@executable_path/../Frameworks"))
+            .signature(fixtures::superblob(&[(CSSLOT_ENTITLEMENTS, fixtures::blob(CSMAGIC_EMBEDDED_ENTITLEMENTS, &entitlements))]))
+            .build();
+        std::fs::write(dir.path().join("tool"), &image).unwrap();
+
+        let report = read_signature_report_with(&dir.path().join("tool"), &ReadOptions::default()).unwrap();
+        assert_eq!(report.dylibs.len(), 2);
+        assert_eq!(report.rpaths, vec!["@executable_path/../Frameworks"]);
+        assert_eq!(report.dylib_hijack_risks.len(), 1);
+        assert_eq!(report.dylib_hijack_risks[0].summary(), "@rpath/Plugin.dylib (@rpath, weak, missing)");
+
+        // Without a path there is nothing to resolve against
+        let report = signature_report_from_reader(&mut Cursor::new(image)).unwrap();
+        assert_eq!(report.dylibs.len(), 2);
+        assert!(report.dylib_hijack_risks.is_empty());
+    }
+
+    #[test]
+    fn test_signer_is_classified_from_certificates() {
+        let certificates = fixtures::cms::chain(
+            "Developer ID Application: Example Corp (ABCDE12345)",
+            "Developer ID Certification Authority",
+            "Apple Root CA",
+        );
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .signature(fixtures::superblob(&[
+                (0, fixtures::CodeDirectoryBuilder::new("com.example.app").team_id("ABCDE12345").build()),
+                (cms::CSSLOT_SIGNATURESLOT, fixtures::blob(cms::CSMAGIC_BLOBWRAPPER, &fixtures::cms::signed_data(&certificates, true))),
+            ]))
+            .build();
+        let signer = signature_report_from_reader(&mut Cursor::new(image)).unwrap().signer.unwrap();
+
+        // The fixture root only carries Apple's root name, not its certificate
+        assert_eq!(signer.kind, SignerKind::Other);
+        assert_eq!(signer.common_name.as_deref(), Some("Developer ID Application: Example Corp (ABCDE12345)"));
+        assert_eq!(signer.chain.len(), 3);
+    }
+
+    #[test]
+    fn test_adhoc_signature_without_certificates() {
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .signature(fixtures::superblob(&[
+                (0, fixtures::CodeDirectoryBuilder::new("a.out").flags(code_directory::CS_ADHOC).build()),
+                (cms::CSSLOT_SIGNATURESLOT, fixtures::blob(cms::CSMAGIC_BLOBWRAPPER, &[])),
+            ]))
+            .build();
+        let signer = signature_report_from_reader(&mut Cursor::new(image)).unwrap().signer.unwrap();
+        assert_eq!(signer, SignerInfo::without_certificate(SignerKind::AdHoc));
+    }
+
+    #[test]
+    fn test_designated_requirement_is_decoded() {
+        use fixtures::requirement as req;
+        let designated = req::requirement(&req::and(req::ident("com.example.app"), req::op(15)));
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .signature(fixtures::superblob(&[
+                (0, fixtures::CodeDirectoryBuilder::new("com.example.app").build()),
+                (requirement::CSSLOT_REQUIREMENTS, req::requirements(&[(3, designated)])),
+            ]))
+            .build();
+        let report = signature_report_from_reader(&mut Cursor::new(image)).unwrap();
+
+        assert_eq!(
+            report.designated_requirement.as_deref(),
+            Some("identifier \"com.example.app\" and anchor apple generic")
+        );
+    }
+
+    #[test]
+    fn test_undecodable_requirement_is_a_warning() {
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .signature(fixtures::superblob(&[
+                (requirement::CSSLOT_REQUIREMENTS, fixtures::requirement::requirements(&[(3, fixtures::requirement::requirement(&[0, 0, 0, 99]))])),
+            ]))
+            .build();
+        let report = signature_report_from_reader(&mut Cursor::new(image)).unwrap();
+
+        assert!(report.designated_requirement.is_none());
+        assert!(report.warnings.iter().any(|w| w.starts_with("Could not decode designated requirement")));
+    }
+
+    #[test]
+    fn test_launch_constraints_are_reported() {
+        let self_constraint = fixtures::blob(
+            constraints::CSMAGIC_EMBEDDED_LAUNCH_CONSTRAINT,
+            &fixtures::der::entitlements(&[("ccat", fixtures::der::integer(1))]),
+        );
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .signature(fixtures::superblob(&[
+                (CSSLOT_ENTITLEMENTS, fixtures::blob(CSMAGIC_EMBEDDED_ENTITLEMENTS, &fixtures::entitlements_plist(&["com.apple.private.tcc.allow"]))),
+                (constraints::CSSLOT_LAUNCH_CONSTRAINT_SELF, self_constraint),
+            ]))
+            .build();
+        let report = signature_report_from_reader(&mut Cursor::new(image)).unwrap();
+
+        let constraints = report.constraints.unwrap();
+        assert_eq!(constraints.launch_self.unwrap()["ccat"], 1);
+        assert!(constraints.library.is_none());
+    }
+
+    #[test]
+    fn test_non_macho_has_no_signer() {
+        let mut data = Cursor::new(b"#!/bin/sh\necho hi\n".to_vec());
+        assert!(signature_report_from_reader(&mut data).unwrap().signer.is_none());
+    }
+
+    #[test]
+    fn test_non_macho_has_no_entitlements() {
+        let mut data = Cursor::new(b"plain text file".to_vec());
+        assert!(signature_report_from_reader(&mut data).unwrap().entitlements.is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+//! Deployment target decoding
+//!
+//! Modern binaries record their platform, minimum OS and SDK in
+//! `LC_BUILD_VERSION`; older ones use one of the per-platform
+//! `LC_VERSION_MIN_*` commands, which imply the platform.
+
+use anyhow::Result;
+use crate::models::{BuildVersion, OsVersion, Platform};
+use super::macho::{
+    MachOHeader, LC_BUILD_VERSION, LC_VERSION_MIN_IPHONEOS, LC_VERSION_MIN_MACOSX, LC_VERSION_MIN_TVOS,
+    LC_VERSION_MIN_WATCHOS,
+};
+
+/// Build versions of a slice, in load command order. Zippered Mac Catalyst
+/// binaries carry one for macOS and one for Mac Catalyst.
+pub fn build_versions(header: &MachOHeader) -> Result<Vec<BuildVersion>> {
+    let mut versions = Vec::new();
+    for command in &header.load_commands {
+        // build_version_command has the platform before minos and sdk
+        let (platform, fields) = match command.cmd {
+            LC_BUILD_VERSION => match platform_from_id(header.endian.u32(&command.data, 8)?) {
+                Some(platform) => (platform, 12),
+                None => continue,
+            },
+            LC_VERSION_MIN_MACOSX => (Platform::Macos, 8),
+            LC_VERSION_MIN_IPHONEOS => (Platform::Ios, 8),
+            LC_VERSION_MIN_TVOS => (Platform::Tvos, 8),
+            LC_VERSION_MIN_WATCHOS => (Platform::Watchos, 8),
+            _ => continue,
+        };
+        let sdk = header.endian.u32(&command.data, fields + 4)?;
+        versions.push(BuildVersion {
+            platform,
+            minimum_os: OsVersion::from_packed(header.endian.u32(&command.data, fields)?),
+            sdk: (sdk != 0).then(|| OsVersion::from_packed(sdk)),
+        });
+    }
+    Ok(versions)
+}
+
+/// `PLATFORM_*` values of `LC_BUILD_VERSION`; unknown platforms are skipped
+fn platform_from_id(id: u32) -> Option<Platform> {
+    let index = usize::try_from(id).ok()?.checked_sub(1)?;
+    Platform::ALL.get(index).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use crate::entitlements::native::fixtures::MachOBuilder;
+    use crate::entitlements::native::macho::{self, CPU_TYPE_ARM64, CPU_TYPE_X86_64};
+
+    fn versions(image: Vec<u8>) -> Vec<BuildVersion> {
+        let mut reader = Cursor::new(image);
+        let slice = macho::read_slices(&mut reader).unwrap().remove(0);
+        build_versions(&MachOHeader::read(&mut reader, &slice).unwrap()).unwrap()
+    }
+
+    fn words(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|value| value.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_reads_build_version() {
+        // DriverKit 20.0, SDK 22.1.2, no tools
+        let image = MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .load_command(LC_BUILD_VERSION, &words(&[10, 0x0014_0000, 0x0016_0102, 0]))
+            .build();
+
+        let versions = versions(image);
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].platform, Platform::Driverkit);
+        assert_eq!(versions[0].minimum_os.to_string(), "20.0");
+        assert_eq!(versions[0].sdk.unwrap().to_string(), "22.1.2");
+    }
+
+    #[test]
+    fn test_zippered_binary_has_two_platforms() {
+        let image = MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .load_command(LC_BUILD_VERSION, &words(&[1, 0x000b_0000, 0x000e_0000, 0]))
+            .load_command(LC_BUILD_VERSION, &words(&[6, 0x000e_0000, 0x0011_0000, 0]))
+            .load_command(LC_BUILD_VERSION, &words(&[99, 0x0001_0000, 0, 0]))
+            .build();
+
+        let platforms: Vec<Platform> = versions(image).iter().map(|v| v.platform).collect();
+        assert_eq!(platforms, vec![Platform::Macos, Platform::MacCatalyst]);
+    }
+
+    #[test]
+    fn test_reads_legacy_version_min() {
+        let image = MachOBuilder::new(CPU_TYPE_X86_64, 3)
+            .load_command(LC_VERSION_MIN_MACOSX, &words(&[0x000a_0d00, 0]))
+            .build();
+
+        let versions = versions(image);
+        assert_eq!(versions[0].summary(), "macOS 10.13+");
+    }
+}
@@ -0,0 +1,717 @@
+//! CMS signature decoding, verification and signer classification
+//!
+//! The `CSSLOT_SIGNATURESLOT` blob wraps a CMS SignedData structure whose
+//! certificate set holds the signing certificate and its issuers. A chain
+//! counts as Apple's only when it ends in one of Apple's root certificates,
+//! recognised by the SHA-256 digest of its DER encoding rather than by name,
+//! each certificate's signature verifies with its issuer's key, and the
+//! leaf's SignerInfo signs the CodeDirectory. A chain that ends in Apple's
+//! root but fails these checks, including one using keys other than RSA, is
+//! reported as unverified. Validity periods and revocation are not checked.
+//! Provisioning profiles use the same structure with the profile plist as
+//! the encapsulated content.
+
+use anyhow::{Result, anyhow, bail};
+use sha2::{Digest, Sha256};
+use crate::models::{CodeDirectoryInfo, SignerInfo, SignerKind};
+use super::code_directory::CSSLOT_CODEDIRECTORY;
+use super::der::{self, DerReader, Tlv, CONSTRUCTED, TAG_BIT_STRING, TAG_INTEGER, TAG_OCTET_STRING, TAG_OID, TAG_SEQUENCE, TAG_SET};
+use super::rsa::{HashAlgorithm, PublicKey, OID_RSA_ENCRYPTION};
+use super::superblob::SuperBlob;
+
+/// Slot holding the CMS signature
+pub const CSSLOT_SIGNATURESLOT: u32 = 0x10000;
+/// Magic of the blob wrapping the CMS signature
+pub const CSMAGIC_BLOBWRAPPER: u32 = 0xfade0b01;
+
+/// Context-specific constructed tag `[0]`
+const TAG_CONTEXT_0: u8 = 0xa0;
+/// Context-specific constructed tag `[1]`
+const TAG_CONTEXT_1: u8 = 0xa1;
+
+/// 1.2.840.113549.1.7.2 (pkcs7-signedData)
+const OID_SIGNED_DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02];
+/// 2.5.4.3 (commonName)
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+/// 2.5.4.10 (organizationName)
+const OID_ORGANIZATION: &[u8] = &[0x55, 0x04, 0x0a];
+/// 1.2.840.113549.1.9.4 (messageDigest)
+const OID_MESSAGE_DIGEST: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x04];
+
+/// SHA-256 digests of the DER encoding of Apple Root CA, Apple Root CA - G2
+/// and Apple Root CA - G3, as published at apple.com/certificateauthority
+const APPLE_ROOT_SHA256: &[&str] = &[
+    "b0b1730ecbc7ff4505142c49f1295e6eda6bcaed7e2c68c5be91b5a11001f024",
+    "c2b9b042dd57830e7d117dac55ac8ae19407d38e41d88f3215bc3a890444a050",
+    "63343abfb89a6a03ebb57e9b3f5fa7be7c4f5c756f3017b3a8c488c3653e9179",
+];
+
+/// Common names of the Apple certificates the classification relies on
+const APPLE_PLATFORM_LEAF: &str = "Software Signing";
+const APP_STORE_LEAVES: &[&str] = &["Apple Mac OS Application Signing", "Apple iPhone OS Application Signing"];
+const DEVELOPER_ID_PREFIX: &str = "Developer ID Application:";
+const DEVELOPMENT_PREFIXES: &[&str] = &[
+    "Apple Development:",
+    "Apple Distribution:",
+    "Mac Developer:",
+    "iPhone Developer:",
+    "iPhone Distribution:",
+    "3rd Party Mac Developer Application:",
+];
+/// Common names of the certificates installer packages are signed with
+const APPLE_INSTALLER_LEAF: &str = "Software Update";
+const DEVELOPER_ID_INSTALLER_PREFIX: &str = "Developer ID Installer:";
+const INSTALLER_DEVELOPMENT_PREFIXES: &[&str] = &["3rd Party Mac Developer Installer:", "Mac Installer Distribution:"];
+
+/// The names, key and signature of one X.509 certificate
+#[derive(Debug, Clone)]
+pub struct Certificate<'a> {
+    /// Raw DER content of the subject and issuer names, compared to link the chain
+    pub subject: &'a [u8],
+    pub issuer: &'a [u8],
+    /// Serial number, which with the issuer identifies the CMS signer
+    pub serial: &'a [u8],
+    /// Complete DER encoding, whose digest identifies Apple's root certificates
+    pub der: &'a [u8],
+    /// DER encoding of the TBSCertificate, the part the issuer signs
+    pub tbs: &'a [u8],
+    /// Content of the SubjectPublicKeyInfo
+    pub public_key: &'a [u8],
+    /// OID of the issuer's signature algorithm and the signature bits
+    pub signature_algorithm: &'a [u8],
+    pub signature: &'a [u8],
+    pub common_name: Option<String>,
+    pub organization: Option<String>,
+}
+
+impl Certificate<'_> {
+    fn is_self_signed(&self) -> bool {
+        self.subject == self.issuer
+    }
+
+    /// The RSA key of this certificate, if it has one
+    fn rsa_key(&self) -> Option<PublicKey> {
+        PublicKey::from_spki(self.public_key).ok().flatten()
+    }
+
+    /// Whether this certificate's RSA key verifies `signature` of `digest`
+    pub fn verifies(&self, hash: HashAlgorithm, digest: &[u8], signature: &[u8]) -> bool {
+        self.rsa_key().is_some_and(|key| key.verify(hash, digest, signature))
+    }
+
+    /// Whether `issuer`'s key verifies this certificate's signature
+    fn signed_by(&self, issuer: &Certificate) -> bool {
+        HashAlgorithm::from_signature_oid(self.signature_algorithm)
+            .is_some_and(|hash| issuer.verifies(hash, &hash.digest(self.tbs), self.signature))
+    }
+
+    /// Lowercase hex SHA-256 digest of the DER encoding
+    fn sha256(&self) -> String {
+        Sha256::digest(self.der).iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Name to show for this certificate in a chain
+    fn display_name(&self) -> String {
+        self.common_name.clone()
+            .or_else(|| self.organization.clone())
+            .unwrap_or_else(|| "(unnamed certificate)".to_string())
+    }
+}
+
+/// Certificates embedded in the CMS signature, in the order they are stored
+pub fn signature_certificates<'a>(superblob: &SuperBlob<'a>) -> Result<Vec<Certificate<'a>>> {
+    let blob = match superblob.slot(CSSLOT_SIGNATURESLOT) {
+        Some(blob) => blob,
+        None => return Ok(Vec::new()),
+    };
+    if blob.magic != CSMAGIC_BLOBWRAPPER {
+        bail!("Unexpected CMS blob magic {:#x}", blob.magic);
+    }
+    // Ad-hoc signatures keep an empty wrapper in the signature slot
+    if blob.payload().is_empty() {
+        return Ok(Vec::new());
+    }
+    parse_certificates(blob.payload())
+}
+
+/// Extract the certificate set of a CMS ContentInfo holding SignedData
+pub fn parse_certificates(cms: &[u8]) -> Result<Vec<Certificate<'_>>> {
+    let mut reader = signed_data(cms)?;
+    reader.expect(TAG_SEQUENCE)?;
+    let certificates = match reader.next_tlv()? {
+        Some(tlv) if tlv.tag == TAG_CONTEXT_0 => tlv,
+        _ => return Ok(Vec::new()),
+    };
+
+    let mut reader = DerReader::new(certificates.content);
+    let mut result = Vec::new();
+    while let Some((tlv, der)) = reader.next_element()? {
+        result.push(parse_certificate(tlv, der)?);
+    }
+    Ok(result)
+}
+
+/// Extract the encapsulated content (e.g. a provisioning profile's plist)
+///
+/// Returns `None` for detached signatures, which carry no content.
+pub fn encapsulated_content(cms: &[u8]) -> Result<Option<Vec<u8>>> {
+    let encap_content_info = signed_data(cms)?.expect(TAG_SEQUENCE)?;
+    let mut reader = DerReader::new(encap_content_info.content);
+    reader.expect(TAG_OID)?;
+    let explicit = match reader.next_tlv()? {
+        Some(tlv) if tlv.tag == TAG_CONTEXT_0 => tlv,
+        _ => return Ok(None),
+    };
+
+    let octets = DerReader::new(explicit.content).next_tlv()?
+        .ok_or_else(|| anyhow!("Empty CMS encapsulated content"))?;
+    match octets.tag {
+        TAG_OCTET_STRING => Ok(Some(octets.content.to_vec())),
+        // BER allows the content to be split into a constructed string of chunks
+        tag if tag == TAG_OCTET_STRING | CONSTRUCTED => {
+            let mut content = Vec::new();
+            let mut chunks = DerReader::new(octets.content);
+            while let Some(chunk) = chunks.next_tlv()? {
+                if chunk.tag != TAG_OCTET_STRING {
+                    bail!("Unexpected DER tag {:#04x} in constructed OCTET STRING", chunk.tag);
+                }
+                content.extend_from_slice(chunk.content);
+            }
+            Ok(Some(content))
+        }
+        tag => bail!("Expected OCTET STRING content, found DER tag {:#04x}", tag),
+    }
+}
+
+/// Unwrap ContentInfo down to the SignedData fields that follow
+/// `version` and `digestAlgorithms` (encapContentInfo, [0] certificates, ...)
+fn signed_data(cms: &[u8]) -> Result<DerReader<'_>> {
+    let content_info = DerReader::new(cms).expect(TAG_SEQUENCE)?;
+    let mut reader = DerReader::new(content_info.content);
+    if reader.expect(TAG_OID)?.content != OID_SIGNED_DATA {
+        bail!("CMS content is not SignedData");
+    }
+    let explicit = reader.expect(TAG_CONTEXT_0)?;
+    let signed_data = DerReader::new(explicit.content).expect(TAG_SEQUENCE)?;
+
+    let mut reader = DerReader::new(signed_data.content);
+    reader.expect(TAG_INTEGER)?;
+    reader.expect(TAG_SET)?;
+    Ok(reader)
+}
+
+/// Decode a single DER-encoded X.509 certificate (e.g. from an installer package's XML signature)
+pub fn parse_certificate_der(der: &[u8]) -> Result<Certificate<'_>> {
+    let (tlv, der) = DerReader::new(der).next_element()?.ok_or_else(|| anyhow!("Empty certificate"))?;
+    parse_certificate(tlv, der)
+}
+
+/// Decode the names, key and signature of a Certificate
+fn parse_certificate<'a>(tlv: Tlv<'a>, der: &'a [u8]) -> Result<Certificate<'a>> {
+    if tlv.tag != TAG_SEQUENCE {
+        bail!("Expected certificate, found DER tag {:#04x}", tlv.tag);
+    }
+    let mut outer = DerReader::new(tlv.content);
+    let (tbs, tbs_der) = outer.next_element()?.ok_or_else(|| anyhow!("Empty certificate"))?;
+    if tbs.tag != TAG_SEQUENCE {
+        bail!("Expected TBSCertificate, found DER tag {:#04x}", tbs.tag);
+    }
+    let signature_algorithm = algorithm_oid(outer.expect(TAG_SEQUENCE)?)?;
+    let signature = match outer.expect(TAG_BIT_STRING)?.content.split_first() {
+        Some((0, bits)) => bits,
+        _ => bail!("Certificate signature is not a whole number of bytes"),
+    };
+    let mut reader = DerReader::new(tbs.content);
+
+    // The explicit version is optional; the serial number always follows it
+    let serial = match reader.next_tlv()? {
+        Some(version) if version.tag == TAG_CONTEXT_0 => reader.expect(TAG_INTEGER)?,
+        Some(serial) if serial.tag == TAG_INTEGER => serial,
+        _ => bail!("Certificate has no serial number"),
+    }.content;
+    reader.expect(TAG_SEQUENCE)?; // signature algorithm
+    let issuer = reader.expect(TAG_SEQUENCE)?.content;
+    reader.expect(TAG_SEQUENCE)?; // validity
+    let subject = reader.expect(TAG_SEQUENCE)?.content;
+    let public_key = reader.expect(TAG_SEQUENCE)?.content;
+
+    Ok(Certificate {
+        subject,
+        issuer,
+        serial,
+        der,
+        tbs: tbs_der,
+        public_key,
+        signature_algorithm,
+        signature,
+        common_name: name_attribute(subject, OID_COMMON_NAME)?,
+        organization: name_attribute(subject, OID_ORGANIZATION)?,
+    })
+}
+
+/// OID of an AlgorithmIdentifier
+fn algorithm_oid(algorithm: Tlv<'_>) -> Result<&[u8]> {
+    Ok(DerReader::new(algorithm.content).expect(TAG_OID)?.content)
+}
+
+/// First value of the given attribute type in an X.509 Name
+fn name_attribute(name: &[u8], oid: &[u8]) -> Result<Option<String>> {
+    let mut rdns = DerReader::new(name);
+    while let Some(rdn) = rdns.next_tlv()? {
+        let mut attributes = DerReader::new(rdn.content);
+        while let Some(attribute) = attributes.next_tlv()? {
+            let mut pair = DerReader::new(attribute.content);
+            if pair.expect(TAG_OID)?.content == oid {
+                if let Some(value) = pair.next_tlv()? {
+                    return der::decode_string(value).map(Some);
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Order the certificates from the signing (leaf) certificate up to the root
+///
+/// The leaf is the certificate that issued no other certificate in the set;
+/// each following entry is the issuer of the previous one.
+pub fn certificate_chain<'c, 'a>(certificates: &'c [Certificate<'a>]) -> Vec<&'c Certificate<'a>> {
+    let issued_another = |cert: &Certificate| {
+        certificates.iter().any(|other| !other.is_self_signed() && other.issuer == cert.subject)
+    };
+    let mut current = match certificates.iter().find(|cert| !issued_another(cert)) {
+        Some(leaf) => leaf,
+        None => return Vec::new(),
+    };
+
+    let mut chain = vec![current];
+    while !current.is_self_signed() && chain.len() < certificates.len() {
+        match certificates.iter().find(|cert| cert.subject == current.issuer) {
+            Some(issuer) => {
+                chain.push(issuer);
+                current = issuer;
+            }
+            None => break,
+        }
+    }
+    chain
+}
+
+/// Whether the chain ends in one of the root certificates with these digests
+fn anchored_by(chain: &[&Certificate], roots: &[&str]) -> bool {
+    chain.last().is_some_and(|root| root.is_self_signed() && roots.contains(&root.sha256().as_str()))
+}
+
+/// Whether each certificate in the chain is signed by the key of the next
+///
+/// The root itself is trusted by its digest, so its self-signature is not checked.
+fn chain_verified(chain: &[&Certificate]) -> bool {
+    chain.windows(2).all(|pair| pair[0].signed_by(pair[1]))
+}
+
+/// Whether the SignerInfo of `leaf` in a CMS SignedData signs the detached `content`
+pub fn signed_by_leaf(cms: &[u8], content: &[u8], leaf: &Certificate) -> Result<bool> {
+    let mut reader = signed_data(cms)?;
+    reader.expect(TAG_SEQUENCE)?;
+    // The certificate and CRL sets are optional and precede the signer infos
+    let mut next = reader.next_tlv()?;
+    for optional in [TAG_CONTEXT_0, TAG_CONTEXT_1] {
+        if next.is_some_and(|tlv| tlv.tag == optional) {
+            next = reader.next_tlv()?;
+        }
+    }
+    let signer_infos = match next {
+        Some(tlv) if tlv.tag == TAG_SET => tlv,
+        _ => bail!("CMS SignedData has no signer infos"),
+    };
+
+    let mut signers = DerReader::new(signer_infos.content);
+    while let Some(signer) = signers.next_tlv()? {
+        if let Some(signed) = signer_signs(signer.content, content, leaf)? {
+            return Ok(signed);
+        }
+    }
+    Ok(false)
+}
+
+/// Verify one SignerInfo over `content`, or `None` if `leaf` did not make it
+///
+/// With signed attributes the signature covers them, and their messageDigest
+/// must be the digest of `content`; without, it covers `content` directly.
+fn signer_signs(signer_info: &[u8], content: &[u8], leaf: &Certificate) -> Result<Option<bool>> {
+    let mut reader = DerReader::new(signer_info);
+    reader.expect(TAG_INTEGER)?;
+    // Signers named by subjectKeyIdentifier instead of IssuerAndSerialNumber are not matched
+    let sid = reader.next_tlv()?.ok_or_else(|| anyhow!("Truncated CMS SignerInfo"))?;
+    if sid.tag != TAG_SEQUENCE {
+        return Ok(None);
+    }
+    let mut sid = DerReader::new(sid.content);
+    if sid.expect(TAG_SEQUENCE)?.content != leaf.issuer || sid.expect(TAG_INTEGER)?.content != leaf.serial {
+        return Ok(None);
+    }
+
+    let digest_algorithm = HashAlgorithm::from_digest_oid(algorithm_oid(reader.expect(TAG_SEQUENCE)?)?);
+    let first = reader.next_element()?.ok_or_else(|| anyhow!("Truncated CMS SignerInfo"))?;
+    let (signed_attributes, signature_algorithm) = if first.0.tag == TAG_CONTEXT_0 {
+        (Some(first), reader.expect(TAG_SEQUENCE)?)
+    } else if first.0.tag == TAG_SEQUENCE {
+        (None, first.0)
+    } else {
+        bail!("Expected CMS signature algorithm, found DER tag {:#04x}", first.0.tag);
+    };
+    let signature_algorithm = algorithm_oid(signature_algorithm)?;
+    let signature = reader.expect(TAG_OCTET_STRING)?.content;
+
+    let Some(digest_algorithm) = digest_algorithm else {
+        return Ok(Some(false));
+    };
+    let hash = if signature_algorithm == OID_RSA_ENCRYPTION {
+        Some(digest_algorithm)
+    } else {
+        HashAlgorithm::from_signature_oid(signature_algorithm)
+    };
+    let (Some(hash), Some(key)) = (hash, leaf.rsa_key()) else {
+        return Ok(Some(false));
+    };
+
+    let digest = match signed_attributes {
+        Some((attributes, der)) => {
+            if message_digest(attributes.content)? != Some(digest_algorithm.digest(content).as_slice()) {
+                return Ok(Some(false));
+            }
+            // The signature covers the attributes encoded as the SET OF they are,
+            // not with the implicit [0] tag they are stored under
+            let mut der = der.to_vec();
+            der[0] = TAG_SET;
+            hash.digest(&der)
+        }
+        None => hash.digest(content),
+    };
+    Ok(Some(key.verify(hash, &digest, signature)))
+}
+
+/// Value of the messageDigest attribute among CMS signed attributes
+fn message_digest(attributes: &[u8]) -> Result<Option<&[u8]>> {
+    let mut reader = DerReader::new(attributes);
+    while let Some(attribute) = reader.next_tlv()? {
+        let mut pair = DerReader::new(attribute.content);
+        if pair.expect(TAG_OID)?.content == OID_MESSAGE_DIGEST {
+            let values = pair.expect(TAG_SET)?;
+            return Ok(Some(DerReader::new(values.content).expect(TAG_OCTET_STRING)?.content));
+        }
+    }
+    Ok(None)
+}
+
+/// Classify the signer of a signed slice from its CodeDirectory and certificate chain
+///
+/// `signed` tells whether the leaf's CMS signature over the CodeDirectory verified.
+pub fn classify(code_directory: Option<&CodeDirectoryInfo>, chain: &[&Certificate], signed: bool) -> SignerKind {
+    classify_with_roots(code_directory, chain, signed, APPLE_ROOT_SHA256)
+}
+
+/// [`classify`] against the given Apple root digests
+fn classify_with_roots(code_directory: Option<&CodeDirectoryInfo>, chain: &[&Certificate], signed: bool, roots: &[&str]) -> SignerKind {
+    let code_directory = match code_directory {
+        Some(cd) => cd,
+        None => return SignerKind::Unsigned,
+    };
+    let leaf = match chain.first() {
+        Some(leaf) if !code_directory.flag_names.iter().any(|f| f == "adhoc") => leaf,
+        _ => return SignerKind::AdHoc,
+    };
+
+    if !anchored_by(chain, roots) {
+        return SignerKind::Other;
+    }
+    if !signed || !chain_verified(chain) {
+        return SignerKind::Unverified;
+    }
+
+    let common_name = leaf.common_name.as_deref().unwrap_or_default();
+    if common_name == APPLE_PLATFORM_LEAF {
+        SignerKind::ApplePlatform
+    } else if APP_STORE_LEAVES.contains(&common_name) {
+        SignerKind::AppStore
+    } else if common_name.starts_with(DEVELOPER_ID_PREFIX) {
+        SignerKind::DeveloperId
+    } else if DEVELOPMENT_PREFIXES.iter().any(|prefix| common_name.starts_with(prefix)) {
+        SignerKind::Development
+    } else {
+        SignerKind::Other
+    }
+}
+
+/// Build the signer report of a signed slice from its code signature
+///
+/// The CMS signature must sign the CodeDirectory in slot 0, which is the one
+/// `codesign` signs; a signature that cannot be decoded leaves the signer unverified.
+pub fn signature_signer(code_directory: Option<&CodeDirectoryInfo>, superblob: &SuperBlob) -> Result<SignerInfo> {
+    let certificates = signature_certificates(superblob)?;
+    let signed = certificate_chain(&certificates).first().is_some_and(|leaf| code_directory_signed(superblob, leaf));
+    Ok(signer_info(code_directory, &certificates, signed))
+}
+
+/// Whether the CMS signature of `leaf` signs the CodeDirectory in slot 0
+fn code_directory_signed(superblob: &SuperBlob, leaf: &Certificate) -> bool {
+    match (superblob.slot(CSSLOT_SIGNATURESLOT), superblob.slot(CSSLOT_CODEDIRECTORY)) {
+        (Some(cms), Some(cd)) => signed_by_leaf(cms.payload(), cd.data, leaf).unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Build the signer report of a signed slice
+pub fn signer_info(code_directory: Option<&CodeDirectoryInfo>, certificates: &[Certificate], signed: bool) -> SignerInfo {
+    let chain = certificate_chain(certificates);
+    let kind = classify(code_directory, &chain, signed);
+    match chain.first() {
+        Some(leaf) if kind != SignerKind::AdHoc => SignerInfo {
+            kind,
+            common_name: leaf.common_name.clone(),
+            organization: leaf.organization.clone(),
+            chain: chain.iter().map(|cert| cert.display_name()).collect(),
+        },
+        _ => SignerInfo::without_certificate(kind),
+    }
+}
+
+/// Build the signer report of an installer package from its certificates
+///
+/// `signed` tells whether the leaf's signature over the package's table of contents verified.
+pub fn installer_signer_info(certificates: &[Certificate], signed: bool) -> SignerInfo {
+    installer_signer_info_with_roots(certificates, signed, APPLE_ROOT_SHA256)
+}
+
+/// [`installer_signer_info`] against the given Apple root digests
+fn installer_signer_info_with_roots(certificates: &[Certificate], signed: bool, roots: &[&str]) -> SignerInfo {
+    let chain = certificate_chain(certificates);
+    let Some(leaf) = chain.first() else {
+        return SignerInfo::without_certificate(SignerKind::Unsigned);
+    };
+    let common_name = leaf.common_name.as_deref().unwrap_or_default();
+    let kind = if !anchored_by(&chain, roots) {
+        SignerKind::Other
+    } else if !signed || !chain_verified(&chain) {
+        SignerKind::Unverified
+    } else if common_name == APPLE_INSTALLER_LEAF {
+        SignerKind::ApplePlatform
+    } else if common_name.starts_with(DEVELOPER_ID_INSTALLER_PREFIX) {
+        SignerKind::DeveloperId
+    } else if INSTALLER_DEVELOPMENT_PREFIXES.iter().any(|prefix| common_name.starts_with(prefix)) {
+        SignerKind::Development
+    } else {
+        SignerKind::Other
+    };
+    SignerInfo {
+        kind,
+        common_name: leaf.common_name.clone(),
+        organization: leaf.organization.clone(),
+        chain: chain.iter().map(|cert| cert.display_name()).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entitlements::native::fixtures::{self, cms, rsa::OTHER_KEY};
+
+    const APPLE_ROOT_CA: &str = "Apple Root CA";
+
+    fn code_directory(flag_names: &[&str]) -> CodeDirectoryInfo {
+        CodeDirectoryInfo {
+            identifier: "com.example.tool".to_string(),
+            team_id: None,
+            hash_type: "sha256".to_string(),
+            cdhash: "00".repeat(20),
+            flags: 0,
+            flag_names: flag_names.iter().map(|f| f.to_string()).collect(),
+            platform_binary: false,
+        }
+    }
+
+    /// Digest of the fixture root certificate named `name`, standing in for Apple's
+    fn fixture_root(name: &str) -> String {
+        parse_certificate_der(&cms::chain("Leaf", "Intermediate", name)[2]).unwrap().sha256()
+    }
+
+    fn classify_leaf(leaf: &str, intermediate: &str, root: &str) -> SignerKind {
+        let data = cms::signed_data(&cms::chain(leaf, intermediate, root), false);
+        let certificates = parse_certificates(&data).unwrap();
+        let apple_root = fixture_root(APPLE_ROOT_CA);
+        classify_with_roots(Some(&code_directory(&[])), &certificate_chain(&certificates), true, &[apple_root.as_str()])
+    }
+
+    #[test]
+    fn test_parses_certificate_names() {
+        let data = cms::signed_data(&[cms::certificate(("Leaf", Some("Example Corp")), ("Issuer", None))], false);
+        let certificates = parse_certificates(&data).unwrap();
+
+        assert_eq!(certificates.len(), 1);
+        assert_eq!(certificates[0].common_name.as_deref(), Some("Leaf"));
+        assert_eq!(certificates[0].organization.as_deref(), Some("Example Corp"));
+    }
+
+    #[test]
+    fn test_parses_indefinite_length_cms() {
+        let data = cms::signed_data(&cms::chain("Software Signing", "Apple Code Signing Certification Authority", "Apple Root CA"), true);
+        assert_eq!(parse_certificates(&data).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_chain_is_ordered_from_leaf_to_root() {
+        let mut certificates = cms::chain("Developer ID Application: Example (ABCDE12345)", "Developer ID Certification Authority", "Apple Root CA");
+        certificates.reverse();
+        let data = cms::signed_data(&certificates, false);
+        let parsed = parse_certificates(&data).unwrap();
+
+        let names: Vec<String> = certificate_chain(&parsed).iter().map(|c| c.display_name()).collect();
+        assert_eq!(names, vec![
+            "Developer ID Application: Example (ABCDE12345)",
+            "Developer ID Certification Authority",
+            "Apple Root CA",
+        ]);
+    }
+
+    #[test]
+    fn test_classifies_apple_chains() {
+        let wwdr = "Apple Worldwide Developer Relations Certification Authority";
+        assert_eq!(classify_leaf("Software Signing", "Apple Code Signing Certification Authority", APPLE_ROOT_CA), SignerKind::ApplePlatform);
+        assert_eq!(classify_leaf("Apple Mac OS Application Signing", wwdr, APPLE_ROOT_CA), SignerKind::AppStore);
+        assert_eq!(classify_leaf("Developer ID Application: Example (ABCDE12345)", "Developer ID Certification Authority", APPLE_ROOT_CA), SignerKind::DeveloperId);
+        assert_eq!(classify_leaf("Apple Development: dev@example.com (ABCDE12345)", wwdr, APPLE_ROOT_CA), SignerKind::Development);
+    }
+
+    #[test]
+    fn test_non_apple_root_is_other() {
+        assert_eq!(classify_leaf("Software Signing", "Apple Code Signing Certification Authority", "Evil Root CA"), SignerKind::Other);
+    }
+
+    #[test]
+    fn test_spoofed_apple_root_is_other() {
+        // A self-made root carrying Apple's root name is not Apple's root certificate
+        let data = cms::signed_data(&cms::chain("Software Signing", "Apple Code Signing Certification Authority", APPLE_ROOT_CA), false);
+        let certificates = parse_certificates(&data).unwrap();
+        let chain = certificate_chain(&certificates);
+
+        assert_eq!(chain.last().unwrap().common_name.as_deref(), Some(APPLE_ROOT_CA));
+        assert_eq!(classify(Some(&code_directory(&[])), &chain, true), SignerKind::Other);
+        assert_eq!(installer_signer_info(&certificates, true).kind, SignerKind::Other);
+    }
+
+    #[test]
+    fn test_adhoc_and_unsigned() {
+        assert_eq!(classify(None, &[], false), SignerKind::Unsigned);
+        assert_eq!(classify(Some(&code_directory(&["adhoc", "linker-signed"])), &[], false), SignerKind::AdHoc);
+        assert_eq!(signer_info(Some(&code_directory(&["adhoc"])), &[], false), SignerInfo::without_certificate(SignerKind::AdHoc));
+    }
+
+    #[test]
+    fn test_empty_signature_slot_has_no_certificates() {
+        let signature = fixtures::superblob(&[(CSSLOT_SIGNATURESLOT, fixtures::blob(CSMAGIC_BLOBWRAPPER, &[]))]);
+        let superblob = SuperBlob::parse(&signature).unwrap();
+        assert!(signature_certificates(&superblob).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_encapsulated_content() {
+        let content = vec![b'x'; 150];
+        let certificates = cms::chain("Leaf", "Intermediate", "Root");
+        for indefinite in [false, true] {
+            let data = cms::signed_content(Some(&content), &certificates, indefinite);
+            assert_eq!(encapsulated_content(&data).unwrap(), Some(content.clone()), "indefinite: {}", indefinite);
+            assert_eq!(parse_certificates(&data).unwrap().len(), 3);
+        }
+        assert_eq!(encapsulated_content(&cms::signed_data(&certificates, false)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_truncated_cms_is_error() {
+        let mut data = cms::signed_data(&cms::chain("Leaf", "Intermediate", "Root"), false);
+        data.truncate(data.len() / 2);
+        assert!(parse_certificates(&data).is_err());
+    }
+
+    #[test]
+    fn test_installer_signer() {
+        let classify_installer = |leaf: &str| {
+            let chain = cms::chain(leaf, "Developer ID Certification Authority", "Apple Root CA");
+            let certificates: Vec<Certificate> = chain.iter().map(|der| parse_certificate_der(der).unwrap()).collect();
+            installer_signer_info_with_roots(&certificates, true, &[fixture_root(APPLE_ROOT_CA).as_str()]).kind
+        };
+        assert_eq!(classify_installer("Developer ID Installer: Example Corp (TEAM123456)"), SignerKind::DeveloperId);
+        assert_eq!(classify_installer("Software Update"), SignerKind::ApplePlatform);
+        assert_eq!(classify_installer("Developer ID Application: Example Corp (TEAM123456)"), SignerKind::Other);
+        assert_eq!(installer_signer_info(&[], false), SignerInfo::without_certificate(SignerKind::Unsigned));
+    }
+
+    #[test]
+    fn test_forged_certificate_is_unverified() {
+        // A leaf named like Apple's, bundled with the genuine intermediate and
+        // root but not signed by the intermediate's key
+        let org = Some("Apple Inc.");
+        let intermediate = "Apple Code Signing Certification Authority";
+        let mut certificates = cms::chain("Software Signing", intermediate, APPLE_ROOT_CA);
+        certificates[0] = cms::signed_certificate(("Software Signing", org), (intermediate, org), &OTHER_KEY);
+        let parsed: Vec<Certificate> = certificates.iter().map(|der| parse_certificate_der(der).unwrap()).collect();
+        let chain = certificate_chain(&parsed);
+        let apple_root = fixture_root(APPLE_ROOT_CA);
+        let roots = [apple_root.as_str()];
+
+        assert_eq!(chain.len(), 3);
+        assert_eq!(classify_with_roots(Some(&code_directory(&[])), &chain, true, &roots), SignerKind::Unverified);
+        assert_eq!(installer_signer_info_with_roots(&parsed, true, &roots).kind, SignerKind::Unverified);
+
+        // A genuine chain without a verified CMS signature is not trusted either
+        assert_eq!(classify_leaf("Software Signing", intermediate, APPLE_ROOT_CA), SignerKind::ApplePlatform);
+        let genuine = cms::chain("Software Signing", intermediate, APPLE_ROOT_CA);
+        let parsed: Vec<Certificate> = genuine.iter().map(|der| parse_certificate_der(der).unwrap()).collect();
+        assert_eq!(classify_with_roots(Some(&code_directory(&[])), &certificate_chain(&parsed), false, &roots), SignerKind::Unverified);
+    }
+
+    #[test]
+    fn test_signer_info_signs_the_code_directory() {
+        let code_directory = fixtures::CodeDirectoryBuilder::new("com.example.tool").build();
+        let certificates = cms::chain("Software Signing", "Apple Code Signing Certification Authority", APPLE_ROOT_CA);
+        for indefinite in [false, true] {
+            let data = cms::signed_detached(&code_directory, &certificates, indefinite);
+            let parsed = parse_certificates(&data).unwrap();
+            let leaf = certificate_chain(&parsed)[0];
+
+            assert!(signed_by_leaf(&data, &code_directory, leaf).unwrap(), "indefinite: {}", indefinite);
+            // Another CodeDirectory does not match the signed messageDigest
+            let other = fixtures::CodeDirectoryBuilder::new("com.example.other").build();
+            assert!(!signed_by_leaf(&data, &other, leaf).unwrap());
+            // Nor does another certificate's key sign it
+            assert!(!signed_by_leaf(&data, &code_directory, certificate_chain(&parsed)[1]).unwrap());
+        }
+
+        // Signatures without signer infos sign nothing
+        let data = cms::signed_data(&certificates, false);
+        let parsed = parse_certificates(&data).unwrap();
+        assert!(!signed_by_leaf(&data, &code_directory, &parsed[0]).unwrap());
+    }
+
+    #[test]
+    fn test_code_signature_must_sign_the_code_directory() {
+        let directory = fixtures::CodeDirectoryBuilder::new("com.example.tool").build();
+        let certificates = cms::chain("Software Signing", "Apple Code Signing Certification Authority", APPLE_ROOT_CA);
+        let apple_root = fixture_root(APPLE_ROOT_CA);
+        let classify_signature = |signed: &[u8]| {
+            let signature = fixtures::superblob(&[
+                (CSSLOT_CODEDIRECTORY, directory.clone()),
+                (CSSLOT_SIGNATURESLOT, fixtures::blob(CSMAGIC_BLOBWRAPPER, &cms::signed_detached(signed, &certificates, true))),
+            ]);
+            let superblob = SuperBlob::parse(&signature).unwrap();
+            let parsed = signature_certificates(&superblob).unwrap();
+            let chain = certificate_chain(&parsed);
+            let signed = code_directory_signed(&superblob, chain[0]);
+            classify_with_roots(Some(&code_directory(&[])), &chain, signed, &[apple_root.as_str()])
+        };
+
+        assert_eq!(classify_signature(&directory), SignerKind::ApplePlatform);
+        // A signature over another CodeDirectory does not carry over
+        let other = fixtures::CodeDirectoryBuilder::new("com.example.other").build();
+        assert_eq!(classify_signature(&other), SignerKind::Unverified);
+    }
+}
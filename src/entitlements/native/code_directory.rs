@@ -0,0 +1,319 @@
+//! CodeDirectory decoding
+//!
+//! The CodeDirectory is the signed core of a code signature: it names the
+//! signing identifier and team, records the code-signing flags and carries the
+//! page hashes. A signature may hold several CodeDirectories, one per hash type
+//! (slot 0 plus the alternate slots), all describing the same code.
+
+use anyhow::{Result, anyhow, bail};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384};
+use crate::models::CodeDirectoryInfo;
+use super::superblob::{be_u32, Blob, SuperBlob};
+
+/// Magic of a CodeDirectory blob
+pub const CSMAGIC_CODEDIRECTORY: u32 = 0xfade0c02;
+
+/// Slot of the primary CodeDirectory
+pub const CSSLOT_CODEDIRECTORY: u32 = 0;
+/// Range of slots holding alternate CodeDirectories
+pub const CSSLOT_ALTERNATE_CODEDIRECTORIES: u32 = 0x1000;
+const CSSLOT_ALTERNATE_CODEDIRECTORY_MAX: u32 = 5;
+
+/// First CodeDirectory versions carrying each optional field
+const CS_SUPPORTSTEAMID: u32 = 0x20200;
+const CS_SUPPORTSCODELIMIT64: u32 = 0x20300;
+
+/// Hash types
+pub const CS_HASHTYPE_SHA1: u8 = 1;
+pub const CS_HASHTYPE_SHA256: u8 = 2;
+pub const CS_HASHTYPE_SHA256_TRUNCATED: u8 = 3;
+pub const CS_HASHTYPE_SHA384: u8 = 4;
+
+/// CDHashes are truncated to this many bytes regardless of hash type
+const CDHASH_LEN: usize = 20;
+
+/// Code-signing flags, with the names `codesign -dv` prints for them
+pub const CS_ADHOC: u32 = 0x0000_0002;
+pub const CS_RUNTIME: u32 = 0x0001_0000;
+pub const CS_LINKER_SIGNED: u32 = 0x0002_0000;
+pub const CS_REQUIRE_LV: u32 = 0x0000_2000;
+const FLAG_NAMES: &[(u32, &str)] = &[
+    (0x0000_0001, "host"),
+    (CS_ADHOC, "adhoc"),
+    (0x0000_0004, "get-task-allow"),
+    (0x0000_0100, "hard"),
+    (0x0000_0200, "kill"),
+    (0x0000_0400, "expires"),
+    (0x0000_0800, "restrict"),
+    (0x0000_1000, "enforcement"),
+    (CS_REQUIRE_LV, "library-validation"),
+    (CS_RUNTIME, "runtime"),
+    (CS_LINKER_SIGNED, "linker-signed"),
+];
+
+/// The fields of one CodeDirectory that describe the signature
+#[derive(Debug, Clone)]
+pub struct CodeDirectory<'a> {
+    pub flags: u32,
+    pub hash_type: u8,
+    /// Non-zero for Apple platform binaries
+    pub platform: u8,
+    pub identifier: String,
+    pub team_id: Option<String>,
+    /// Offset of code slot 0 within the blob; special slots are stored just before it
+    pub hash_offset: usize,
+    pub hash_size: usize,
+    pub n_special_slots: u32,
+    pub n_code_slots: u32,
+    /// Number of bytes of the slice covered by code slots
+    pub code_limit: u64,
+    /// log2 of the page size; 0 means the whole code is a single page
+    pub page_shift: u8,
+    /// Full blob bytes, which is what the CDHash is computed over
+    pub data: &'a [u8],
+}
+
+impl<'a> CodeDirectory<'a> {
+    /// Parse a CodeDirectory blob
+    pub fn parse(blob: Blob<'a>) -> Result<Self> {
+        if blob.magic != CSMAGIC_CODEDIRECTORY {
+            bail!("Unexpected CodeDirectory magic {:#x}", blob.magic);
+        }
+        let data = blob.data;
+        let version = be_u32(data, 8)?;
+        let flags = be_u32(data, 12)?;
+        let hash_offset = be_u32(data, 16)? as usize;
+        let ident_offset = be_u32(data, 20)? as usize;
+        let n_special_slots = be_u32(data, 24)?;
+        let n_code_slots = be_u32(data, 28)?;
+        let (hash_size, hash_type, platform, page_shift) = match data.get(36..40) {
+            Some(bytes) => (bytes[0] as usize, bytes[1], bytes[2], bytes[3]),
+            None => bail!("Truncated CodeDirectory"),
+        };
+
+        let mut code_limit = u64::from(be_u32(data, 32)?);
+        if version >= CS_SUPPORTSCODELIMIT64 {
+            let high = u64::from(be_u32(data, 56)?);
+            let low = u64::from(be_u32(data, 60)?);
+            if (high << 32 | low) != 0 {
+                code_limit = high << 32 | low;
+            }
+        }
+
+        let identifier = c_string_at(data, ident_offset)?;
+        let team_id = if version >= CS_SUPPORTSTEAMID {
+            match be_u32(data, 48)? as usize {
+                0 => None,
+                offset => Some(c_string_at(data, offset)?),
+            }
+        } else {
+            None
+        };
+
+        Ok(Self {
+            flags,
+            hash_type,
+            platform,
+            identifier,
+            team_id,
+            hash_offset,
+            hash_size,
+            n_special_slots,
+            n_code_slots,
+            code_limit,
+            page_shift,
+            data,
+        })
+    }
+
+    /// Hash of the whole CodeDirectory, truncated to 20 bytes as the kernel does
+    pub fn cdhash(&self) -> Result<Vec<u8>> {
+        let mut digest = digest(self.hash_type, self.data)?;
+        digest.truncate(CDHASH_LEN);
+        Ok(digest)
+    }
+
+    /// Hash of `data` with this CodeDirectory's hash type, truncated to its slot size
+    pub fn hash(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut digest = digest(self.hash_type, data)?;
+        digest.truncate(self.hash_size);
+        Ok(digest)
+    }
+
+    /// Expected hash of code page `index`
+    pub fn code_slot(&self, index: u32) -> Result<&'a [u8]> {
+        let start = self.hash_offset + index as usize * self.hash_size;
+        self.data.get(start..start + self.hash_size)
+            .ok_or_else(|| anyhow!("Code slot {} lies outside the CodeDirectory", index))
+    }
+
+    /// Expected hash of special slot `slot` (1-based, stored before code slot 0)
+    pub fn special_slot(&self, slot: u32) -> Result<&'a [u8]> {
+        (slot as usize)
+            .checked_mul(self.hash_size)
+            .and_then(|back| self.hash_offset.checked_sub(back))
+            .and_then(|start| self.data.get(start..start + self.hash_size))
+            .ok_or_else(|| anyhow!("Special slot {} lies outside the CodeDirectory", slot))
+    }
+
+    /// Summary of this CodeDirectory for reporting
+    pub fn info(&self) -> Result<CodeDirectoryInfo> {
+        Ok(CodeDirectoryInfo {
+            identifier: self.identifier.clone(),
+            team_id: self.team_id.clone(),
+            hash_type: hash_type_name(self.hash_type).to_string(),
+            cdhash: self.cdhash()?.iter().map(|b| format!("{:02x}", b)).collect(),
+            flags: self.flags,
+            flag_names: flag_names(self.flags),
+            platform_binary: self.platform != 0,
+        })
+    }
+}
+
+/// Digest of `data` with the given hash type
+fn digest(hash_type: u8, data: &[u8]) -> Result<Vec<u8>> {
+    Ok(match hash_type {
+        CS_HASHTYPE_SHA1 => Sha1::digest(data).to_vec(),
+        CS_HASHTYPE_SHA256 | CS_HASHTYPE_SHA256_TRUNCATED => Sha256::digest(data).to_vec(),
+        CS_HASHTYPE_SHA384 => Sha384::digest(data).to_vec(),
+        other => bail!("Unsupported CodeDirectory hash type {}", other),
+    })
+}
+
+/// Every CodeDirectory in the signature, primary first
+pub fn code_directories<'a>(superblob: &SuperBlob<'a>) -> Result<Vec<CodeDirectory<'a>>> {
+    let alternates = (0..CSSLOT_ALTERNATE_CODEDIRECTORY_MAX).map(|i| CSSLOT_ALTERNATE_CODEDIRECTORIES + i);
+    std::iter::once(CSSLOT_CODEDIRECTORY)
+        .chain(alternates)
+        .filter_map(|slot| superblob.slot(slot))
+        .map(CodeDirectory::parse)
+        .collect()
+}
+
+/// The CodeDirectory with the strongest hash type, which is the one whose
+/// CDHash `codesign` reports
+pub fn best_code_directory<'a>(superblob: &SuperBlob<'a>) -> Result<Option<CodeDirectory<'a>>> {
+    Ok(code_directories(superblob)?
+        .into_iter()
+        .max_by_key(|cd| hash_type_rank(cd.hash_type)))
+}
+
+/// Display name of a hash type
+pub fn hash_type_name(hash_type: u8) -> &'static str {
+    match hash_type {
+        CS_HASHTYPE_SHA1 => "sha1",
+        CS_HASHTYPE_SHA256 => "sha256",
+        CS_HASHTYPE_SHA256_TRUNCATED => "sha256-truncated",
+        CS_HASHTYPE_SHA384 => "sha384",
+        _ => "unknown",
+    }
+}
+
+/// Names of the set code-signing flags
+pub fn flag_names(flags: u32) -> Vec<String> {
+    FLAG_NAMES.iter()
+        .filter(|(bit, _)| flags & bit != 0)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// Relative strength of a hash type, matching the kernel's preference order
+fn hash_type_rank(hash_type: u8) -> u8 {
+    match hash_type {
+        CS_HASHTYPE_SHA1 => 1,
+        CS_HASHTYPE_SHA256_TRUNCATED => 2,
+        CS_HASHTYPE_SHA256 => 3,
+        CS_HASHTYPE_SHA384 => 4,
+        _ => 0,
+    }
+}
+
+/// Read a NUL-terminated string starting at `offset`
+fn c_string_at(data: &[u8], offset: usize) -> Result<String> {
+    let tail = match data.get(offset..) {
+        Some(tail) if offset > 0 => tail,
+        _ => bail!("CodeDirectory string offset {} is out of range", offset),
+    };
+    let end = match tail.iter().position(|b| *b == 0) {
+        Some(end) => end,
+        None => bail!("Unterminated CodeDirectory string at offset {}", offset),
+    };
+    Ok(String::from_utf8_lossy(&tail[..end]).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entitlements::native::fixtures;
+
+    fn parse(data: &[u8]) -> CodeDirectory<'_> {
+        let blob = Blob { magic: be_u32(data, 0).unwrap(), data };
+        CodeDirectory::parse(blob).unwrap()
+    }
+
+    #[test]
+    fn test_parse_identifier_team_and_flags() {
+        let data = fixtures::CodeDirectoryBuilder::new("com.example.tool")
+            .team_id("ABCDE12345")
+            .flags(CS_RUNTIME | CS_REQUIRE_LV)
+            .build();
+        let cd = parse(&data);
+
+        assert_eq!(cd.identifier, "com.example.tool");
+        assert_eq!(cd.team_id.as_deref(), Some("ABCDE12345"));
+        assert_eq!(cd.hash_type, CS_HASHTYPE_SHA256);
+        assert_eq!(flag_names(cd.flags), vec!["library-validation", "runtime"]);
+    }
+
+    #[test]
+    fn test_old_version_has_no_team_id() {
+        let data = fixtures::CodeDirectoryBuilder::new("com.example.old")
+            .team_id("ABCDE12345")
+            .version(0x20100)
+            .build();
+        assert_eq!(parse(&data).team_id, None);
+    }
+
+    #[test]
+    fn test_cdhash_is_truncated_digest_of_blob() {
+        let data = fixtures::CodeDirectoryBuilder::new("com.example.tool").build();
+        let cd = parse(&data);
+        let expected = Sha256::digest(&data);
+        assert_eq!(cd.cdhash().unwrap(), expected[..CDHASH_LEN].to_vec());
+
+        let sha1 = fixtures::CodeDirectoryBuilder::new("com.example.tool").hash_type(CS_HASHTYPE_SHA1).build();
+        assert_eq!(parse(&sha1).cdhash().unwrap(), Sha1::digest(&sha1).to_vec());
+    }
+
+    #[test]
+    fn test_best_code_directory_prefers_strongest_hash() {
+        let signature = fixtures::superblob(&[
+            (CSSLOT_CODEDIRECTORY, fixtures::CodeDirectoryBuilder::new("com.example.tool").hash_type(CS_HASHTYPE_SHA1).build()),
+            (CSSLOT_ALTERNATE_CODEDIRECTORIES, fixtures::CodeDirectoryBuilder::new("com.example.tool").build()),
+        ]);
+        let superblob = SuperBlob::parse(&signature).unwrap();
+
+        assert_eq!(code_directories(&superblob).unwrap().len(), 2);
+        let best = best_code_directory(&superblob).unwrap().unwrap();
+        assert_eq!(best.hash_type, CS_HASHTYPE_SHA256);
+    }
+
+    #[test]
+    fn test_platform_binary_and_adhoc() {
+        let data = fixtures::CodeDirectoryBuilder::new("com.apple.ls").platform(15).flags(CS_ADHOC | CS_LINKER_SIGNED).build();
+        let info = parse(&data).info().unwrap();
+
+        assert!(info.platform_binary);
+        assert_eq!(info.flag_names, vec!["adhoc", "linker-signed"]);
+        assert_eq!(info.cdhash.len(), CDHASH_LEN * 2);
+    }
+
+    #[test]
+    fn test_out_of_range_identifier_is_error() {
+        let mut data = fixtures::CodeDirectoryBuilder::new("com.example.tool").build();
+        data[20..24].copy_from_slice(&0xffffu32.to_be_bytes());
+        let blob = Blob { magic: CSMAGIC_CODEDIRECTORY, data: &data };
+        assert!(CodeDirectory::parse(blob).is_err());
+    }
+}
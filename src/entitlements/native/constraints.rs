@@ -0,0 +1,92 @@
+//! Launch and library constraint decoding
+//!
+//! Since macOS 13 a signature can carry launch constraints on the binary
+//! itself, its parent and its responsible process, plus constraints on the
+//! libraries it may load. Each is a DER blob in the CoreEntitlements encoding
+//! holding a dictionary of facts (`ccat`, `comp`, `reqs`, `vers`, ...).
+
+use anyhow::{Result, bail};
+use serde_json::Value;
+use crate::models::SignatureConstraints;
+use super::der;
+use super::superblob::SuperBlob;
+
+/// Slots holding the constraint blobs
+pub const CSSLOT_LAUNCH_CONSTRAINT_SELF: u32 = 8;
+pub const CSSLOT_LAUNCH_CONSTRAINT_PARENT: u32 = 9;
+pub const CSSLOT_LAUNCH_CONSTRAINT_RESPONSIBLE: u32 = 10;
+pub const CSSLOT_LIBRARY_CONSTRAINT: u32 = 11;
+
+/// Magic of a constraint blob
+pub const CSMAGIC_EMBEDDED_LAUNCH_CONSTRAINT: u32 = 0xfade8181;
+
+/// Decode every constraint blob in the signature; `None` when there are none
+pub fn signature_constraints(superblob: &SuperBlob) -> Result<Option<SignatureConstraints>> {
+    let constraints = SignatureConstraints {
+        launch_self: constraint(superblob, CSSLOT_LAUNCH_CONSTRAINT_SELF)?,
+        launch_parent: constraint(superblob, CSSLOT_LAUNCH_CONSTRAINT_PARENT)?,
+        launch_responsible: constraint(superblob, CSSLOT_LAUNCH_CONSTRAINT_RESPONSIBLE)?,
+        library: constraint(superblob, CSSLOT_LIBRARY_CONSTRAINT)?,
+    };
+
+    if constraints.has_launch_constraints() || constraints.library.is_some() {
+        Ok(Some(constraints))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Decode the constraint dictionary stored in one slot
+fn constraint(superblob: &SuperBlob, slot: u32) -> Result<Option<Value>> {
+    let blob = match superblob.slot(slot) {
+        Some(blob) => blob,
+        None => return Ok(None),
+    };
+    if blob.magic != CSMAGIC_EMBEDDED_LAUNCH_CONSTRAINT {
+        bail!("Unexpected constraint blob magic {:#x} in slot {}", blob.magic, slot);
+    }
+
+    let facts = der::decode_der_entitlements(blob.payload())?;
+    Ok(Some(Value::Object(facts.into_iter().collect())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entitlements::native::fixtures::{self, der as d};
+
+    fn constraint_blob(entries: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        fixtures::blob(CSMAGIC_EMBEDDED_LAUNCH_CONSTRAINT, &d::entitlements(entries))
+    }
+
+    #[test]
+    fn test_decodes_launch_and_library_constraints() {
+        let signature = fixtures::superblob(&[
+            (CSSLOT_LAUNCH_CONSTRAINT_SELF, constraint_blob(&[("ccat", d::integer(1)), ("comp", d::integer(1)), ("vers", d::integer(1))])),
+            (CSSLOT_LAUNCH_CONSTRAINT_PARENT, constraint_blob(&[("reqs", d::dict(&[("is-init-proc", d::boolean(true))]))])),
+            (CSSLOT_LIBRARY_CONSTRAINT, constraint_blob(&[("signing-identifier", d::utf8("com.apple.foo"))])),
+        ]);
+        let superblob = SuperBlob::parse(&signature).unwrap();
+        let constraints = signature_constraints(&superblob).unwrap().unwrap();
+
+        assert_eq!(constraints.launch_self.as_ref().unwrap()["ccat"], 1);
+        assert_eq!(constraints.launch_parent.as_ref().unwrap()["reqs"]["is-init-proc"], true);
+        assert!(constraints.launch_responsible.is_none());
+        assert_eq!(constraints.library.as_ref().unwrap()["signing-identifier"], "com.apple.foo");
+        assert!(constraints.has_launch_constraints());
+    }
+
+    #[test]
+    fn test_no_constraint_slots() {
+        let signature = fixtures::superblob(&[]);
+        let superblob = SuperBlob::parse(&signature).unwrap();
+        assert!(signature_constraints(&superblob).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_wrong_magic_is_error() {
+        let signature = fixtures::superblob(&[(CSSLOT_LAUNCH_CONSTRAINT_SELF, fixtures::blob(0xfade7172, &d::entitlements(&[])))]);
+        let superblob = SuperBlob::parse(&signature).unwrap();
+        assert!(signature_constraints(&superblob).is_err());
+    }
+}
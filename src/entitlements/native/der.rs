@@ -0,0 +1,313 @@
+//! Minimal DER decoding
+//!
+//! Provides a TLV reader for the DER structures embedded in code signatures and
+//! a decoder for the CoreEntitlements DER format used by the
+//! `CSSLOT_DER_ENTITLEMENTS` blob. The reader also accepts the BER
+//! indefinite-length form, which `codesign` uses for the outer CMS layers.
+
+use std::collections::HashMap;
+use anyhow::{Result, anyhow, bail};
+use serde_json::Value;
+
+/// Universal tags
+pub const TAG_BOOLEAN: u8 = 0x01;
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_BIT_STRING: u8 = 0x03;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_OID: u8 = 0x06;
+pub const TAG_UTF8_STRING: u8 = 0x0c;
+pub const TAG_PRINTABLE_STRING: u8 = 0x13;
+pub const TAG_T61_STRING: u8 = 0x14;
+pub const TAG_IA5_STRING: u8 = 0x16;
+pub const TAG_BMP_STRING: u8 = 0x1e;
+pub const TAG_SEQUENCE: u8 = 0x30;
+pub const TAG_SET: u8 = 0x31;
+
+/// Constructed bit of an identifier octet
+pub const CONSTRUCTED: u8 = 0x20;
+
+/// CoreEntitlements wrapper: `[APPLICATION 16] { INTEGER version, dict }`
+const TAG_CE_WRAPPER: u8 = 0x70;
+/// CoreEntitlements dictionary: `[16] SET OF SEQUENCE { UTF8String key, value }`
+const TAG_CE_DICT: u8 = 0xb0;
+
+/// Deepest nesting accepted when decoding values
+const MAX_DEPTH: usize = 32;
+
+/// One decoded tag-length-value element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tlv<'a> {
+    pub tag: u8,
+    pub content: &'a [u8],
+}
+
+/// Sequential reader over concatenated DER elements
+#[derive(Debug, Clone)]
+pub struct DerReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    /// Nesting level of indefinite-length elements being measured
+    depth: usize,
+}
+
+impl<'a> DerReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0, depth: 0 }
+    }
+
+    /// Whether all elements have been consumed
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    /// Read the next element, or `None` at the end of input
+    pub fn next_tlv(&mut self) -> Result<Option<Tlv<'a>>> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+
+        let tag = self.data[self.pos];
+        if tag & 0x1f == 0x1f {
+            bail!("Unsupported multi-byte DER tag at offset {}", self.pos);
+        }
+
+        let first = *self.data.get(self.pos + 1)
+            .ok_or_else(|| anyhow!("Truncated DER length at offset {}", self.pos))?;
+        if first == 0x80 {
+            return self.next_indefinite(tag).map(Some);
+        }
+        let mut header = 2;
+        let length = if first & 0x80 == 0 {
+            first as usize
+        } else {
+            let count = (first & 0x7f) as usize;
+            if count == 0 || count > 4 {
+                bail!("Unsupported DER length encoding at offset {}", self.pos);
+            }
+            let bytes = self.data.get(self.pos + 2..self.pos + 2 + count)
+                .ok_or_else(|| anyhow!("Truncated DER length at offset {}", self.pos))?;
+            header += count;
+            bytes.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize)
+        };
+
+        let start = self.pos + header;
+        let content = start.checked_add(length)
+            .and_then(|end| self.data.get(start..end))
+            .ok_or_else(|| anyhow!("DER element at offset {} extends past end of data", self.pos))?;
+        self.pos = start + length;
+
+        Ok(Some(Tlv { tag, content }))
+    }
+
+    /// Read the next element along with its complete encoding (header included)
+    pub fn next_element(&mut self) -> Result<Option<(Tlv<'a>, &'a [u8])>> {
+        let start = self.pos;
+        Ok(self.next_tlv()?.map(|tlv| (tlv, &self.data[start..self.pos])))
+    }
+
+    /// Read a BER indefinite-length element, whose content runs up to a
+    /// matching end-of-contents marker (`00 00`)
+    fn next_indefinite(&mut self, tag: u8) -> Result<Tlv<'a>> {
+        if tag & CONSTRUCTED == 0 {
+            bail!("Indefinite length on primitive DER tag at offset {}", self.pos);
+        }
+        if self.depth >= MAX_DEPTH {
+            bail!("DER indefinite-length elements nested too deeply");
+        }
+
+        let start = self.pos + 2;
+        let mut inner = DerReader { data: &self.data[start..], pos: 0, depth: self.depth + 1 };
+        loop {
+            if inner.data[inner.pos..].starts_with(&[0, 0]) {
+                break;
+            }
+            if inner.next_tlv()?.is_none() {
+                bail!("Unterminated indefinite-length DER element at offset {}", self.pos);
+            }
+        }
+
+        let content = &self.data[start..start + inner.pos];
+        self.pos = start + inner.pos + 2;
+        Ok(Tlv { tag, content })
+    }
+
+    /// Read the next element, requiring it to have the given tag
+    pub fn expect(&mut self, tag: u8) -> Result<Tlv<'a>> {
+        match self.next_tlv()? {
+            Some(tlv) if tlv.tag == tag => Ok(tlv),
+            Some(tlv) => bail!("Expected DER tag {:#04x}, found {:#04x}", tag, tlv.tag),
+            None => bail!("Expected DER tag {:#04x}, found end of data", tag),
+        }
+    }
+}
+
+/// Decode a DER INTEGER (two's complement, big-endian) that fits in an i64
+pub fn decode_integer(content: &[u8]) -> Result<i64> {
+    if content.is_empty() || content.len() > 8 {
+        bail!("DER integer of {} bytes is out of range", content.len());
+    }
+    let negative = content[0] & 0x80 != 0;
+    let initial = if negative { -1i64 } else { 0 };
+    Ok(content.iter().fold(initial, |acc, b| (acc << 8) | *b as i64))
+}
+
+/// Decode one of the ASN.1 string types used in X.509 names
+pub fn decode_string(tlv: Tlv<'_>) -> Result<String> {
+    match tlv.tag {
+        TAG_UTF8_STRING | TAG_PRINTABLE_STRING | TAG_IA5_STRING | TAG_T61_STRING => {
+            Ok(String::from_utf8_lossy(tlv.content).into_owned())
+        }
+        TAG_BMP_STRING => {
+            let units: Vec<u16> = tlv.content.chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            Ok(String::from_utf16_lossy(&units))
+        }
+        other => bail!("Unsupported DER string tag {:#04x}", other),
+    }
+}
+
+/// Decode a CoreEntitlements DER blob payload into an entitlement map
+pub fn decode_der_entitlements(payload: &[u8]) -> Result<HashMap<String, Value>> {
+    let wrapper = DerReader::new(payload).expect(TAG_CE_WRAPPER)?;
+    let mut reader = DerReader::new(wrapper.content);
+    let version = decode_integer(reader.expect(TAG_INTEGER)?.content)?;
+    if version != 1 {
+        bail!("Unsupported DER entitlements version {}", version);
+    }
+
+    match decode_value(reader.expect(TAG_CE_DICT)?, 0)? {
+        Value::Object(map) => Ok(map.into_iter().collect()),
+        _ => unreachable!("dictionary tag always decodes to an object"),
+    }
+}
+
+/// Decode one CoreEntitlements value into its JSON equivalent
+pub fn decode_value(tlv: Tlv<'_>, depth: usize) -> Result<Value> {
+    if depth > MAX_DEPTH {
+        bail!("DER entitlements nested too deeply");
+    }
+
+    match tlv.tag {
+        TAG_BOOLEAN => Ok(Value::Bool(tlv.content.iter().any(|b| *b != 0))),
+        TAG_INTEGER => Ok(Value::Number(decode_integer(tlv.content)?.into())),
+        TAG_UTF8_STRING => Ok(Value::String(String::from_utf8(tlv.content.to_vec())?)),
+        TAG_SEQUENCE => {
+            let mut reader = DerReader::new(tlv.content);
+            let mut items = Vec::new();
+            while let Some(item) = reader.next_tlv()? {
+                items.push(decode_value(item, depth + 1)?);
+            }
+            Ok(Value::Array(items))
+        }
+        TAG_CE_DICT => {
+            let mut reader = DerReader::new(tlv.content);
+            let mut map = serde_json::Map::new();
+            while let Some(entry) = reader.next_tlv()? {
+                if entry.tag != TAG_SEQUENCE {
+                    bail!("Expected DER dictionary entry, found tag {:#04x}", entry.tag);
+                }
+                let mut pair = DerReader::new(entry.content);
+                let key = String::from_utf8(pair.expect(TAG_UTF8_STRING)?.content.to_vec())?;
+                let value = pair.next_tlv()?
+                    .ok_or_else(|| anyhow!("DER dictionary entry '{}' has no value", key))?;
+                map.insert(key, decode_value(value, depth + 1)?);
+            }
+            Ok(Value::Object(map))
+        }
+        other => bail!("Unsupported DER entitlement value tag {:#04x}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entitlements::native::fixtures::der;
+
+    #[test]
+    fn test_reads_short_and_long_lengths() {
+        let long = vec![0x41u8; 300];
+        let mut data = der::tlv(TAG_UTF8_STRING, b"hi");
+        data.extend(der::tlv(TAG_UTF8_STRING, &long));
+        let mut reader = DerReader::new(&data);
+
+        assert_eq!(reader.next_tlv().unwrap().unwrap().content, b"hi");
+        assert_eq!(reader.next_tlv().unwrap().unwrap().content.len(), 300);
+        assert!(reader.next_tlv().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_truncated_element_is_error() {
+        let mut data = der::tlv(TAG_UTF8_STRING, b"hello");
+        data.pop();
+        assert!(DerReader::new(&data).next_tlv().is_err());
+    }
+
+    #[test]
+    fn test_reads_indefinite_length_elements() {
+        // SEQUENCE (indefinite) { SEQUENCE (indefinite) { UTF8 "a" } UTF8 "b" }, then UTF8 "c"
+        let data = [
+            0x30, 0x80, 0x30, 0x80, 0x0c, 0x01, b'a', 0x00, 0x00, 0x0c, 0x01, b'b', 0x00, 0x00,
+            0x0c, 0x01, b'c',
+        ];
+        let mut reader = DerReader::new(&data);
+        let outer = reader.expect(TAG_SEQUENCE).unwrap();
+        assert_eq!(reader.expect(TAG_UTF8_STRING).unwrap().content, b"c");
+
+        let mut inner = DerReader::new(outer.content);
+        let nested = inner.expect(TAG_SEQUENCE).unwrap();
+        assert_eq!(DerReader::new(nested.content).expect(TAG_UTF8_STRING).unwrap().content, b"a");
+        assert_eq!(inner.expect(TAG_UTF8_STRING).unwrap().content, b"b");
+        assert!(inner.is_empty());
+    }
+
+    #[test]
+    fn test_unterminated_indefinite_length_is_error() {
+        let data = [0x30, 0x80, 0x0c, 0x01, b'a'];
+        assert!(DerReader::new(&data).next_tlv().is_err());
+        // Primitive types cannot use the indefinite form
+        assert!(DerReader::new(&[0x0c, 0x80, 0x00, 0x00]).next_tlv().is_err());
+    }
+
+    #[test]
+    fn test_decode_string_types() {
+        assert_eq!(decode_string(Tlv { tag: TAG_PRINTABLE_STRING, content: b"Apple Inc." }).unwrap(), "Apple Inc.");
+        assert_eq!(decode_string(Tlv { tag: TAG_BMP_STRING, content: &[0x00, 0x41, 0x00, 0x42] }).unwrap(), "AB");
+        assert!(decode_string(Tlv { tag: TAG_INTEGER, content: &[1] }).is_err());
+    }
+
+    #[test]
+    fn test_decode_integer_sign() {
+        assert_eq!(decode_integer(&[0x01]).unwrap(), 1);
+        assert_eq!(decode_integer(&[0x00, 0xff]).unwrap(), 255);
+        assert_eq!(decode_integer(&[0xff]).unwrap(), -1);
+        assert!(decode_integer(&[]).is_err());
+    }
+
+    #[test]
+    fn test_decode_der_entitlements() {
+        let payload = der::entitlements(&[
+            ("com.apple.security.app-sandbox", der::boolean(true)),
+            ("com.apple.developer.team-identifier", der::utf8("ABCDE12345")),
+            ("com.apple.security.application-groups", der::sequence(&[der::utf8("group.a"), der::utf8("group.b")])),
+            ("some.integer", der::integer(42)),
+            ("nested", der::dict(&[("inner", der::boolean(false))])),
+        ]);
+        let map = decode_der_entitlements(&payload).unwrap();
+
+        assert_eq!(map.len(), 5);
+        assert_eq!(map["com.apple.security.app-sandbox"], Value::Bool(true));
+        assert_eq!(map["com.apple.developer.team-identifier"], Value::String("ABCDE12345".into()));
+        assert_eq!(map["com.apple.security.application-groups"], serde_json::json!(["group.a", "group.b"]));
+        assert_eq!(map["some.integer"], serde_json::json!(42));
+        assert_eq!(map["nested"], serde_json::json!({"inner": false}));
+    }
+
+    #[test]
+    fn test_decode_rejects_unknown_version() {
+        let mut payload = der::entitlements(&[]);
+        // Version INTEGER content sits right after the wrapper and integer headers
+        payload[4] = 2;
+        assert!(decode_der_entitlements(&payload).is_err());
+    }
+}
@@ -0,0 +1,299 @@
+//! Linked libraries and dylib hijacking
+//!
+//! Lists the dylib and rpath load commands of a slice, and flags library loads
+//! an attacker could satisfy with a planted dylib when the binary has relaxed
+//! library validation: relative (`@rpath`, `@executable_path`, `@loader_path`)
+//! install names, weak imports, and libraries that do not exist. Under a
+//! foreign root, absolute paths are looked up inside the image.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use serde_json::Value;
+use crate::models::{DylibDependency, DylibHijackRisk, DylibKind};
+use super::root;
+use super::macho::{
+    MachOHeader, LC_LAZY_LOAD_DYLIB, LC_LOAD_DYLIB, LC_LOAD_UPWARD_DYLIB, LC_LOAD_WEAK_DYLIB,
+    LC_REEXPORT_DYLIB, LC_RPATH,
+};
+
+/// Entitlement that turns off library validation under the hardened runtime
+pub const DISABLE_LIBRARY_VALIDATION: &str = "com.apple.security.cs.disable-library-validation";
+
+/// Entitlements that let unsigned or foreign-team libraries into the process
+pub const LIBRARY_VALIDATION_ENTITLEMENTS: [&str; 2] = [
+    DISABLE_LIBRARY_VALIDATION,
+    "com.apple.security.cs.allow-dyld-environment-variables",
+];
+
+/// Install name prefixes dyld resolves relative to the binary or its rpaths
+const RELATIVE_PREFIXES: [&str; 3] = ["@rpath", "@executable_path", "@loader_path"];
+
+/// System libraries live in the dyld shared cache and are absent from disk since macOS 11
+const SHARED_CACHE_PREFIXES: [&str; 2] = ["/usr/lib/", "/System/Library/"];
+
+/// Offset of the name offset field in `dylib_command` and the path offset in `rpath_command`
+const STRING_OFFSET_FIELD: usize = 8;
+
+/// Libraries named by the dylib load commands of a slice, in load order
+pub fn linked_dylibs(header: &MachOHeader) -> Result<Vec<DylibDependency>> {
+    let mut dylibs = Vec::new();
+    for command in &header.load_commands {
+        let kind = match command.cmd {
+            LC_LOAD_DYLIB => DylibKind::Load,
+            LC_LOAD_WEAK_DYLIB => DylibKind::Weak,
+            LC_REEXPORT_DYLIB => DylibKind::Reexport,
+            LC_LAZY_LOAD_DYLIB => DylibKind::Lazy,
+            LC_LOAD_UPWARD_DYLIB => DylibKind::Upward,
+            _ => continue,
+        };
+        dylibs.push(DylibDependency { path: command.string_at(header.endian, STRING_OFFSET_FIELD)?, kind });
+    }
+    Ok(dylibs)
+}
+
+/// The `LC_RPATH` search paths of a slice
+pub fn rpaths(header: &MachOHeader) -> Result<Vec<String>> {
+    header
+        .load_commands
+        .iter()
+        .filter(|command| command.cmd == LC_RPATH)
+        .map(|command| command.string_at(header.endian, STRING_OFFSET_FIELD))
+        .collect()
+}
+
+/// Whether the entitlements relax library validation
+pub fn relaxes_library_validation(entitlements: &HashMap<String, Value>) -> bool {
+    LIBRARY_VALIDATION_ENTITLEMENTS
+        .iter()
+        .any(|key| entitlements.get(*key) == Some(&Value::Bool(true)))
+}
+
+/// Library loads of the binary at `binary_path` that could be hijacked.
+///
+/// Empty unless the entitlements relax library validation; otherwise dyld
+/// refuses libraries not signed by Apple or the binary's own team.
+/// `executable_path` is the main executable of the process loading the
+/// binary, which `@executable_path` names; `@loader_path` is the binary's own
+/// directory. `root` is the foreign filesystem root both live under, if any.
+pub fn hijack_risks(
+    binary_path: &Path,
+    executable_path: &Path,
+    entitlements: &HashMap<String, Value>,
+    dylibs: &[DylibDependency],
+    rpaths: &[String],
+    root: Option<&Path>,
+) -> Vec<DylibHijackRisk> {
+    if !relaxes_library_validation(entitlements) {
+        return Vec::new();
+    }
+
+    let dirs = LoaderDirs {
+        executable: executable_path.parent().unwrap_or(Path::new("")),
+        loader: binary_path.parent().unwrap_or(Path::new("")),
+    };
+    let mut risks = Vec::new();
+    for dylib in dylibs {
+        let mut reasons: Vec<String> = RELATIVE_PREFIXES
+            .iter()
+            .filter(|prefix| dylib.path.strip_prefix(**prefix).is_some_and(|rest| rest.starts_with('/')))
+            .map(|prefix| prefix.to_string())
+            .collect();
+        if dylib.kind == DylibKind::Weak {
+            reasons.push("weak".to_string());
+        }
+        if let Some(candidates) = candidate_paths(&dylib.path, &dirs, rpaths, root) {
+            if !candidates.iter().any(|candidate| exists(candidate, root)) {
+                reasons.push("missing".to_string());
+            }
+        }
+        if !reasons.is_empty() {
+            risks.push(DylibHijackRisk { path: dylib.path.clone(), reasons });
+        }
+    }
+    risks
+}
+
+/// Directories `@executable_path` and `@loader_path` expand to
+struct LoaderDirs<'a> {
+    executable: &'a Path,
+    loader: &'a Path,
+}
+
+/// Where dyld would look for an install name; `None` when it cannot be checked on disk
+fn candidate_paths(install_name: &str, dirs: &LoaderDirs, rpaths: &[String], root: Option<&Path>) -> Option<Vec<PathBuf>> {
+    if let Some(rest) = install_name.strip_prefix("@rpath/") {
+        return Some(
+            rpaths
+                .iter()
+                .filter_map(|rpath| resolve_loader_relative(rpath, dirs, root))
+                .map(|dir| dir.join(rest))
+                .collect(),
+        );
+    }
+    if install_name.starts_with('@') {
+        return resolve_loader_relative(install_name, dirs, root).map(|path| vec![path]);
+    }
+    if install_name.starts_with('/') && !SHARED_CACHE_PREFIXES.iter().any(|prefix| install_name.starts_with(prefix)) {
+        return Some(vec![absolute_path(install_name, root)]);
+    }
+    None
+}
+
+/// Resolve an absolute, `@executable_path` or `@loader_path` path
+fn resolve_loader_relative(path: &str, dirs: &LoaderDirs, root: Option<&Path>) -> Option<PathBuf> {
+    for (prefix, dir) in [("@executable_path", dirs.executable), ("@loader_path", dirs.loader)] {
+        if let Some(rest) = path.strip_prefix(prefix) {
+            return Some(dir.join(rest.trim_start_matches('/')));
+        }
+    }
+    path.starts_with('/').then(|| absolute_path(path, root))
+}
+
+/// Host path of an absolute install name or rpath
+fn absolute_path(path: &str, root: Option<&Path>) -> PathBuf {
+    match root {
+        Some(root) => root::host_path(root, Path::new(path)),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Whether a candidate exists, following symlinks inside the image under a foreign root
+fn exists(candidate: &Path, root: Option<&Path>) -> bool {
+    match root {
+        Some(root) => root::resolve(root, candidate).is_ok(),
+        None => candidate.exists(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use serde_json::json;
+    use crate::entitlements::native::fixtures::{self, MachOBuilder};
+    use crate::entitlements::native::macho::{self, CPU_TYPE_ARM64};
+
+    fn header(image: Vec<u8>) -> MachOHeader {
+        let mut reader = Cursor::new(image);
+        let slice = macho::read_slices(&mut reader).unwrap().remove(0);
+        MachOHeader::read(&mut reader, &slice).unwrap()
+    }
+
+    fn dylib(path: &str, kind: DylibKind) -> DylibDependency {
+        DylibDependency { path: path.to_string(), kind }
+    }
+
+    #[test]
+    fn test_lists_dylibs_and_rpaths() {
+        let header = header(
+            MachOBuilder::new(CPU_TYPE_ARM64, 0)
+                .load_command(LC_LOAD_DYLIB, &fixtures::dylib_command("/usr/lib/libSystem.B.dylib"))
+                .load_command(LC_RPATH, &fixtures::rpath_command("@executable_path/../Frameworks"))
+                .load_command(LC_LOAD_WEAK_DYLIB, &fixtures::dylib_command("@rpath/Plugin.dylib"))
+                .load_command(LC_REEXPORT_DYLIB, &fixtures::dylib_command("/usr/lib/libc++.1.dylib"))
+                .build(),
+        );
+
+        assert_eq!(
+            linked_dylibs(&header).unwrap(),
+            vec![
+                dylib("/usr/lib/libSystem.B.dylib", DylibKind::Load),
+                dylib("@rpath/Plugin.dylib", DylibKind::Weak),
+                dylib("/usr/lib/libc++.1.dylib", DylibKind::Reexport),
+            ]
+        );
+        assert_eq!(rpaths(&header).unwrap(), vec!["@executable_path/../Frameworks"]);
+    }
+
+    #[test]
+    fn test_rejects_string_offset_outside_command() {
+        let mut body = fixtures::dylib_command("/usr/lib/libSystem.B.dylib");
+        body[0..4].copy_from_slice(&400u32.to_le_bytes());
+        let header = header(MachOBuilder::new(CPU_TYPE_ARM64, 0).load_command(LC_LOAD_DYLIB, &body).build());
+
+        assert!(linked_dylibs(&header).is_err());
+    }
+
+    #[test]
+    fn test_no_risks_without_relaxed_library_validation() {
+        let dylibs = vec![dylib("@rpath/Missing.dylib", DylibKind::Weak)];
+        assert!(hijack_risks(Path::new("/nonexistent/tool"), Path::new("/nonexistent/tool"), &HashMap::new(), &dylibs, &[], None).is_empty());
+
+        let entitlements = HashMap::from([(LIBRARY_VALIDATION_ENTITLEMENTS[0].to_string(), json!(false))]);
+        assert!(hijack_risks(Path::new("/nonexistent/tool"), Path::new("/nonexistent/tool"), &entitlements, &dylibs, &[], None).is_empty());
+    }
+
+    #[test]
+    fn test_flags_relative_weak_and_missing_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("Frameworks")).unwrap();
+        std::fs::write(dir.path().join("Frameworks/Present.dylib"), b"").unwrap();
+        let binary = dir.path().join("tool");
+        let entitlements = HashMap::from([(LIBRARY_VALIDATION_ENTITLEMENTS[1].to_string(), json!(true))]);
+        let rpaths = vec!["@loader_path/Frameworks".to_string()];
+        let dylibs = vec![
+            dylib("/usr/lib/libSystem.B.dylib", DylibKind::Load),
+            dylib("@rpath/Present.dylib", DylibKind::Load),
+            dylib("@rpath/Absent.dylib", DylibKind::Load),
+            dylib("@executable_path/Frameworks/Present.dylib", DylibKind::Weak),
+            dylib("/Library/Vendor/libgone.dylib", DylibKind::Load),
+        ];
+
+        let risks = hijack_risks(&binary, &binary, &entitlements, &dylibs, &rpaths, None);
+        let summaries: Vec<String> = risks.iter().map(DylibHijackRisk::summary).collect();
+        assert_eq!(
+            summaries,
+            vec![
+                "@rpath/Present.dylib (@rpath)",
+                "@rpath/Absent.dylib (@rpath, missing)",
+                "@executable_path/Frameworks/Present.dylib (@executable_path, weak)",
+                "/Library/Vendor/libgone.dylib (missing)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_executable_path_names_the_loading_executable() {
+        let dir = tempfile::tempdir().unwrap();
+        let contents = dir.path().join("Example.app/Contents");
+        std::fs::create_dir_all(contents.join("Frameworks/Kit.framework/Versions/A")).unwrap();
+        std::fs::create_dir_all(contents.join("MacOS")).unwrap();
+        std::fs::write(contents.join("Frameworks/Shared.dylib"), b"").unwrap();
+        std::fs::write(contents.join("Frameworks/Kit.framework/Versions/A/Local.dylib"), b"").unwrap();
+        let main = contents.join("MacOS/Example");
+        let kit = contents.join("Frameworks/Kit.framework/Versions/A/Kit");
+        let entitlements = HashMap::from([(LIBRARY_VALIDATION_ENTITLEMENTS[1].to_string(), json!(true))]);
+        let dylibs = vec![
+            dylib("@executable_path/../Frameworks/Shared.dylib", DylibKind::Load),
+            dylib("@loader_path/Local.dylib", DylibKind::Load),
+        ];
+
+        let risks = hijack_risks(&kit, &main, &entitlements, &dylibs, &[], None);
+        let summaries: Vec<String> = risks.iter().map(DylibHijackRisk::summary).collect();
+        assert_eq!(summaries, vec!["@executable_path/../Frameworks/Shared.dylib (@executable_path)", "@loader_path/Local.dylib (@loader_path)"]);
+
+        // Taken as its own executable, the framework's load would be missing
+        let risks = hijack_risks(&kit, &kit, &entitlements, &dylibs, &[], None);
+        assert_eq!(risks[0].summary(), "@executable_path/../Frameworks/Shared.dylib (@executable_path, missing)");
+    }
+
+    #[test]
+    fn test_absolute_loads_are_checked_inside_the_root() {
+        let image = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(image.path().join("Library/Vendor")).unwrap();
+        std::fs::write(image.path().join("Library/Vendor/libreal.dylib"), b"").unwrap();
+        std::os::unix::fs::symlink("/Library/Vendor/libreal.dylib", image.path().join("Library/Vendor/liblinked.dylib")).unwrap();
+        let binary = image.path().join("tool");
+        let entitlements = HashMap::from([(LIBRARY_VALIDATION_ENTITLEMENTS[1].to_string(), json!(true))]);
+        let dylibs = vec![
+            dylib("/Library/Vendor/libreal.dylib", DylibKind::Load),
+            dylib("/Library/Vendor/liblinked.dylib", DylibKind::Load),
+            dylib("/Library/Vendor/libgone.dylib", DylibKind::Load),
+        ];
+
+        let risks = hijack_risks(&binary, &binary, &entitlements, &dylibs, &[], Some(image.path()));
+        let summaries: Vec<String> = risks.iter().map(DylibHijackRisk::summary).collect();
+        assert_eq!(summaries, vec!["/Library/Vendor/libgone.dylib (missing)"]);
+    }
+}
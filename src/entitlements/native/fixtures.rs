@@ -0,0 +1,667 @@
+//! Byte-level fixture builders for native parser tests
+//!
+//! Builds small but structurally valid Mach-O images, universal binaries and
+//! code signature blobs so extraction can be tested without real binaries.
+
+use super::macho::{LC_CODE_SIGNATURE, LC_SEGMENT_64, LC_SYMTAB};
+
+const MH_MAGIC_64: u32 = 0xfeedfacf;
+const MH_EXECUTE: u32 = 2;
+const FAT_MAGIC: u32 = 0xcafebabe;
+const FAT_ALIGN: u32 = 12;
+
+/// Builder for a 64-bit little-endian Mach-O image
+pub struct MachOBuilder {
+    cputype: u32,
+    cpusubtype: u32,
+    signature: Option<Vec<u8>>,
+    sections: Vec<(&'static str, &'static str, Vec<u8>)>,
+    load_commands: Vec<(u32, Vec<u8>)>,
+    flags: u32,
+    string_table: Option<Vec<u8>>,
+}
+
+impl MachOBuilder {
+    pub fn new(cputype: u32, cpusubtype: u32) -> Self {
+        Self { cputype, cpusubtype, signature: None, sections: Vec::new(), load_commands: Vec::new(), flags: 0, string_table: None }
+    }
+
+    /// Set the header flags
+    pub fn flags(mut self, flags: u32) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Add an `LC_SYMTAB` whose string table holds the given names
+    pub fn strings(mut self, names: &[&str]) -> Self {
+        let mut table = vec![b' ', 0];
+        for name in names {
+            table.extend_from_slice(name.as_bytes());
+            table.push(0);
+        }
+        self.string_table = Some(table);
+        self
+    }
+
+    /// Add a load command; `body` follows the cmd/cmdsize header and is padded to 8 bytes
+    pub fn load_command(mut self, cmd: u32, body: &[u8]) -> Self {
+        let mut body = body.to_vec();
+        body.resize((body.len() + 8).next_multiple_of(8) - 8, 0);
+        self.load_commands.push((cmd, body));
+        self
+    }
+
+    /// Add a segment holding a single section with the given contents
+    pub fn section(mut self, segname: &'static str, sectname: &'static str, data: &[u8]) -> Self {
+        self.sections.push((segname, sectname, data.to_vec()));
+        self
+    }
+
+    /// Embed the given SuperBlob behind an `LC_CODE_SIGNATURE` command
+    pub fn signature(mut self, signature: Vec<u8>) -> Self {
+        self.signature = Some(signature);
+        self
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        let mut image = self.layout();
+        if let Some(signature) = self.signature {
+            image.extend_from_slice(&signature);
+        }
+        image
+    }
+
+    /// Build a signed image whose signature is computed from the code it covers.
+    /// `make_signature` is called twice and must return the same size both times.
+    pub fn build_with_signature(mut self, make_signature: impl Fn(&[u8]) -> Vec<u8>) -> Vec<u8> {
+        // The signature size is part of the hashed header, so size it first
+        self.signature = Some(vec![0; make_signature(&self.layout()).len()]);
+        self.signature = Some(make_signature(&self.layout()));
+        self.build()
+    }
+
+    /// Header, load commands, section data and padding up to the signature offset
+    fn layout(&self) -> Vec<u8> {
+        let mut commands: Vec<u8> = Vec::new();
+        let mut ncmds = 0u32;
+        let header_size = 32usize;
+        let commands_size = self.load_commands.iter().map(|(_, body)| 8 + body.len()).sum::<usize>()
+            + self.sections.len() * (72 + 80)
+            + if self.string_table.is_some() { 24 } else { 0 }
+            + if self.signature.is_some() { 16 } else { 0 };
+
+        for (cmd, body) in &self.load_commands {
+            commands.extend_from_slice(&cmd.to_le_bytes());
+            commands.extend_from_slice(&(8 + body.len() as u32).to_le_bytes());
+            commands.extend_from_slice(body);
+            ncmds += 1;
+        }
+
+        let mut data = Vec::new();
+        let mut offset = header_size + commands_size;
+        for (segname, sectname, contents) in &self.sections {
+            commands.extend_from_slice(&LC_SEGMENT_64.to_le_bytes());
+            commands.extend_from_slice(&(72u32 + 80).to_le_bytes());
+            commands.extend_from_slice(&name16(segname));
+            for field in [0, contents.len() as u64, offset as u64, contents.len() as u64] {
+                commands.extend_from_slice(&field.to_le_bytes());
+            }
+            for field in [0u32, 0, 1, 0] {
+                commands.extend_from_slice(&field.to_le_bytes());
+            }
+            commands.extend_from_slice(&name16(sectname));
+            commands.extend_from_slice(&name16(segname));
+            for field in [0, contents.len() as u64] {
+                commands.extend_from_slice(&field.to_le_bytes());
+            }
+            for field in [offset as u32, 0, 0, 0, 0, 0, 0, 0] {
+                commands.extend_from_slice(&field.to_le_bytes());
+            }
+            ncmds += 1;
+            data.extend_from_slice(contents);
+            offset += contents.len();
+        }
+
+        if let Some(ref strings) = self.string_table {
+            for field in [LC_SYMTAB, 24, 0, 0, offset as u32, strings.len() as u32] {
+                commands.extend_from_slice(&field.to_le_bytes());
+            }
+            ncmds += 1;
+            data.extend_from_slice(strings);
+            offset += strings.len();
+        }
+
+        // Code signatures start on a 16-byte boundary after the code
+        let signature_offset = (offset + 64).next_multiple_of(16);
+        if let Some(ref signature) = self.signature {
+            commands.extend_from_slice(&LC_CODE_SIGNATURE.to_le_bytes());
+            commands.extend_from_slice(&16u32.to_le_bytes());
+            commands.extend_from_slice(&(signature_offset as u32).to_le_bytes());
+            commands.extend_from_slice(&(signature.len() as u32).to_le_bytes());
+            ncmds += 1;
+        }
+
+        let mut image = Vec::new();
+        for field in [MH_MAGIC_64, self.cputype, self.cpusubtype, MH_EXECUTE, ncmds, commands.len() as u32, self.flags, 0] {
+            image.extend_from_slice(&field.to_le_bytes());
+        }
+        image.extend_from_slice(&commands);
+        image.extend_from_slice(&data);
+        image.resize(signature_offset, 0);
+        image
+    }
+}
+
+/// Body of a dylib command: the name offset, placeholder timestamp and versions, then the path
+pub fn dylib_command(path: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+    for field in [24u32, 2, 0x10000, 0x10000] {
+        body.extend_from_slice(&field.to_le_bytes());
+    }
+    body.extend_from_slice(path.as_bytes());
+    body.push(0);
+    body
+}
+
+/// Body of an `LC_RPATH` command
+pub fn rpath_command(path: &str) -> Vec<u8> {
+    let mut body = 12u32.to_le_bytes().to_vec();
+    body.extend_from_slice(path.as_bytes());
+    body.push(0);
+    body
+}
+
+/// A segment or section name, NUL-padded to 16 bytes
+fn name16(name: &str) -> [u8; 16] {
+    let mut field = [0u8; 16];
+    field[..name.len()].copy_from_slice(name.as_bytes());
+    field
+}
+
+/// Wrap thin images into a universal binary, taking each slice's CPU type from its header
+pub fn fat(images: Vec<Vec<u8>>) -> Vec<u8> {
+    let align = 1usize << FAT_ALIGN;
+    let mut data = Vec::new();
+    data.extend_from_slice(&FAT_MAGIC.to_be_bytes());
+    data.extend_from_slice(&(images.len() as u32).to_be_bytes());
+
+    let mut offset = (8 + 20 * images.len()).next_multiple_of(align);
+    for image in &images {
+        data.extend_from_slice(&image[4..8].iter().rev().copied().collect::<Vec<u8>>());
+        data.extend_from_slice(&image[8..12].iter().rev().copied().collect::<Vec<u8>>());
+        data.extend_from_slice(&(offset as u32).to_be_bytes());
+        data.extend_from_slice(&(image.len() as u32).to_be_bytes());
+        data.extend_from_slice(&FAT_ALIGN.to_be_bytes());
+        offset = (offset + image.len()).next_multiple_of(align);
+    }
+
+    for image in images {
+        data.resize(data.len().next_multiple_of(align), 0);
+        data.extend_from_slice(&image);
+    }
+    data
+}
+
+/// A single blob: big-endian magic and total length followed by the payload
+pub fn blob(magic: u32, payload: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(8 + payload.len());
+    data.extend_from_slice(&magic.to_be_bytes());
+    data.extend_from_slice(&((8 + payload.len()) as u32).to_be_bytes());
+    data.extend_from_slice(payload);
+    data
+}
+
+/// An embedded signature SuperBlob holding the given `(slot, blob)` pairs
+pub fn superblob(blobs: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let header_size = 12 + 8 * blobs.len();
+    let total = header_size + blobs.iter().map(|(_, b)| b.len()).sum::<usize>();
+
+    let mut data = Vec::with_capacity(total);
+    data.extend_from_slice(&0xfade0cc0u32.to_be_bytes());
+    data.extend_from_slice(&(total as u32).to_be_bytes());
+    data.extend_from_slice(&(blobs.len() as u32).to_be_bytes());
+
+    let mut offset = header_size;
+    for (slot, blob) in blobs {
+        data.extend_from_slice(&slot.to_be_bytes());
+        data.extend_from_slice(&(offset as u32).to_be_bytes());
+        offset += blob.len();
+    }
+    for (_, blob) in blobs {
+        data.extend_from_slice(blob);
+    }
+    data
+}
+
+/// Builder for a CodeDirectory blob, optionally hashing code pages and special slots
+pub struct CodeDirectoryBuilder {
+    identifier: String,
+    team_id: Option<String>,
+    version: u32,
+    flags: u32,
+    hash_type: u8,
+    platform: u8,
+    code: Vec<u8>,
+    page_shift: u8,
+    special_slots: Vec<(u32, Vec<u8>)>,
+}
+
+impl CodeDirectoryBuilder {
+    pub fn new(identifier: &str) -> Self {
+        Self {
+            identifier: identifier.to_string(),
+            team_id: None,
+            version: 0x20400,
+            flags: 0,
+            hash_type: 2,
+            platform: 0,
+            code: Vec::new(),
+            page_shift: 12,
+            special_slots: Vec::new(),
+        }
+    }
+
+    /// Hash `code` in pages of `1 << page_shift` bytes
+    pub fn code(mut self, code: &[u8], page_shift: u8) -> Self {
+        self.code = code.to_vec();
+        self.page_shift = page_shift;
+        self
+    }
+
+    /// Hash `data` into the given special slot
+    pub fn special_slot(mut self, slot: u32, data: &[u8]) -> Self {
+        self.special_slots.push((slot, data.to_vec()));
+        self
+    }
+
+    pub fn team_id(mut self, team_id: &str) -> Self {
+        self.team_id = Some(team_id.to_string());
+        self
+    }
+
+    pub fn version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn flags(mut self, flags: u32) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    pub fn hash_type(mut self, hash_type: u8) -> Self {
+        self.hash_type = hash_type;
+        self
+    }
+
+    pub fn platform(mut self, platform: u8) -> Self {
+        self.platform = platform;
+        self
+    }
+
+    fn hash(&self, data: &[u8]) -> Vec<u8> {
+        use sha2::Digest;
+        match self.hash_type {
+            1 => sha1::Sha1::digest(data).to_vec(),
+            4 => sha2::Sha384::digest(data).to_vec(),
+            _ => sha2::Sha256::digest(data).to_vec(),
+        }
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        // Fixed header of a version 0x20400 CodeDirectory, magic and length included
+        let header_size = 88usize;
+        let ident_offset = header_size;
+        let team_offset = ident_offset + self.identifier.len() + 1;
+        let hash_size: usize = match self.hash_type {
+            1 => 20,
+            4 => 48,
+            _ => 32,
+        };
+        let n_special_slots = self.special_slots.iter().map(|(slot, _)| *slot).max().unwrap_or(0);
+        let pages: Vec<&[u8]> = self.code.chunks(1 << self.page_shift).collect();
+        let hash_offset = team_offset
+            + self.team_id.as_ref().map_or(0, |t| t.len() + 1)
+            + n_special_slots as usize * hash_size;
+
+        let mut payload = vec![0u8; header_size - 8];
+        let mut put = |offset: usize, value: u32| payload[offset - 8..offset - 4].copy_from_slice(&value.to_be_bytes());
+        put(8, self.version);
+        put(12, self.flags);
+        put(16, hash_offset as u32);
+        put(20, ident_offset as u32);
+        put(24, n_special_slots);
+        put(28, pages.len() as u32);
+        put(32, self.code.len() as u32);
+        if self.team_id.is_some() {
+            put(48, team_offset as u32);
+        }
+        payload[36 - 8] = hash_size as u8;
+        payload[37 - 8] = self.hash_type;
+        payload[38 - 8] = self.platform;
+        payload[39 - 8] = self.page_shift;
+
+        payload.extend_from_slice(self.identifier.as_bytes());
+        payload.push(0);
+        if let Some(team_id) = &self.team_id {
+            payload.extend_from_slice(team_id.as_bytes());
+            payload.push(0);
+        }
+
+        // Special slots are stored in descending order before code slot 0
+        for slot in (1..=n_special_slots).rev() {
+            let hash = match self.special_slots.iter().find(|(s, _)| *s == slot) {
+                Some((_, data)) => self.hash(data)[..hash_size].to_vec(),
+                None => vec![0; hash_size],
+            };
+            payload.extend_from_slice(&hash);
+        }
+        for page in &pages {
+            payload.extend_from_slice(&self.hash(page)[..hash_size]);
+        }
+        blob(0xfade0c02, &payload)
+    }
+}
+
+/// An XML entitlements plist for the given boolean keys
+pub fn entitlements_plist(keys: &[&str]) -> Vec<u8> {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<plist version=\"1.0\">\n<dict>\n",
+    );
+    for key in keys {
+        xml.push_str(&format!("\t<key>{}</key>\n\t<true/>\n", key));
+    }
+    xml.push_str("</dict>\n</plist>\n");
+    xml.into_bytes()
+}
+
+/// Bytes of a hex string
+pub fn unhex(hex: &str) -> Vec<u8> {
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect()
+}
+
+/// DER element builders for CoreEntitlements-encoded blobs
+pub mod der {
+    /// A tag-length-value element with a minimally encoded length
+    pub fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut data = vec![tag];
+        let len = content.len();
+        if len < 0x80 {
+            data.push(len as u8);
+        } else {
+            let bytes: Vec<u8> = len.to_be_bytes().iter().copied().skip_while(|b| *b == 0).collect();
+            data.push(0x80 | bytes.len() as u8);
+            data.extend_from_slice(&bytes);
+        }
+        data.extend_from_slice(content);
+        data
+    }
+
+    pub fn boolean(value: bool) -> Vec<u8> {
+        tlv(0x01, &[if value { 0xff } else { 0x00 }])
+    }
+
+    pub fn integer(value: i64) -> Vec<u8> {
+        let bytes = value.to_be_bytes();
+        // Drop redundant leading bytes while keeping the sign bit intact
+        let mut start = 0;
+        while start < 7
+            && ((bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+                || (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0))
+        {
+            start += 1;
+        }
+        tlv(0x02, &bytes[start..])
+    }
+
+    pub fn utf8(value: &str) -> Vec<u8> {
+        tlv(0x0c, value.as_bytes())
+    }
+
+    pub fn sequence(items: &[Vec<u8>]) -> Vec<u8> {
+        tlv(0x30, &items.concat())
+    }
+
+    pub fn dict(entries: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let pairs: Vec<Vec<u8>> = entries
+            .iter()
+            .map(|(key, value)| sequence(&[utf8(key), value.clone()]))
+            .collect();
+        tlv(0xb0, &pairs.concat())
+    }
+
+    /// A complete `CSSLOT_DER_ENTITLEMENTS` blob payload
+    pub fn entitlements(entries: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        tlv(0x70, &[integer(1), dict(entries)].concat())
+    }
+}
+
+/// 1024-bit RSA test keys (public exponent 65537) that sign like `openssl dgst -sign`
+pub mod rsa {
+    use super::der::{sequence, tlv};
+    use super::unhex;
+    use crate::entitlements::native::rsa::{encode, from_limbs, mod_pow, to_limbs, HashAlgorithm, PublicKey, OID_RSA_ENCRYPTION};
+
+    const PUBLIC_EXPONENT: &[u8] = &[0x01, 0x00, 0x01];
+
+    /// Hex modulus and private exponent of a key
+    pub struct Key {
+        modulus: &'static str,
+        private_exponent: &'static str,
+    }
+
+    /// The key every fixture certificate holds and signs with
+    pub const KEY: Key = Key {
+        modulus: "af76fe8d04e4aaa3c9668c1729536a9b0a2e8e4e21a87a33cd398e86b8ed7052a1ad695a02aa4ba967f66dd228dbe1a3896d7a2f72eb71ec89195327e972b8602e3581ab51b0ef2cc35cca27031a78001b9c73b403bcad51c9c99131b8bf34c55e62706bb7ff5439606280dffe54fa85a2b780f5d2257e6353f2902c207b0f17",
+        private_exponent: "53ff05da6226491dc1130b2b27485c773dc78dae0f64c4a339b23ced77678ae6b3a8fd0eeec9b110b6c8fc47a7f1a2b193df49a79f3ee0b997e3fcafd58c6a802ce1b0361c5d04e6ca24d67e0825d36deed2037b5dbbd01252fe9865e847d3e4ee13e80130f7a5e65c3b16084914aa1bb6a77f1f4a3d66d715445e47535aa681",
+    };
+
+    /// A second key, whose signatures [`KEY`] does not verify
+    pub const OTHER_KEY: Key = Key {
+        modulus: "f13711dc62163e1ce76913eda3c7529b5b81dc987f03661d6635f47a3e9dfb1a336e2c7cf688c9e57ee37bb4aafdd429861797f3dda81d45a39910e630d522daf7b2460ed1fbb9f92f98e7c0d549d270d5750f999632f499298ddd6db0a9d23106cb348cd6b7c76837a0fd2d775532c2d18c09956237dce264a94984e89e446d",
+        private_exponent: "80a2127749237d6a2289e9a3afd44a9c28b75ef13fc28b412051a397512f244a3ba5043a7baa44a88afb3aec04c7a9cd9f0193f9eb798c6aa7aca7d369b52f8936b647bb6cf657c0e12ddbbb7de0dd6c123eb112a221d37d24583b7a7faa7f96367451cc90bf2b0948d28f7b558791c473769db08e83ea4dc843a549654ab0a1",
+    };
+
+    impl Key {
+        pub fn public(&self) -> PublicKey {
+            PublicKey::new(&unhex(self.modulus), PUBLIC_EXPONENT).unwrap()
+        }
+
+        /// Content of a SubjectPublicKeyInfo holding this key
+        pub fn spki_content(&self) -> Vec<u8> {
+            let positive = |bytes: &[u8]| tlv(0x02, &[&[0x00][..], bytes].concat());
+            let key = sequence(&[positive(&unhex(self.modulus)), positive(PUBLIC_EXPONENT)]);
+            [
+                sequence(&[tlv(0x06, OID_RSA_ENCRYPTION), tlv(0x05, &[])]),
+                tlv(0x03, &[&[0x00][..], &key].concat()),
+            ].concat()
+        }
+
+        /// PKCS #1 v1.5 signature of `digest`
+        pub fn sign(&self, hash: HashAlgorithm, digest: &[u8]) -> Vec<u8> {
+            let modulus = unhex(self.modulus);
+            let block = to_limbs(&encode(hash, digest, modulus.len()));
+            from_limbs(&mod_pow(&block, &unhex(self.private_exponent), &to_limbs(&modulus)), modulus.len())
+        }
+    }
+}
+
+/// CMS SignedData builders with certificates and signatures made with the fixture keys
+pub mod cms {
+    use super::der::{integer, sequence, tlv, utf8};
+    use super::rsa::{Key, KEY};
+    use crate::entitlements::native::cms::parse_certificate_der;
+    use crate::entitlements::native::rsa::{HashAlgorithm, OID_RSA_ENCRYPTION};
+
+    /// 1.2.840.113549.1.7.1 (data)
+    const OID_DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x01];
+    /// 2.16.840.1.101.3.4.2.1 (sha256)
+    const OID_SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+
+    /// A `(common name, organization)` pair
+    pub type Name<'a> = (&'a str, Option<&'a str>);
+
+    fn name((common_name, organization): Name) -> Vec<u8> {
+        let attribute = |oid: &[u8], value: &str| tlv(0x31, &sequence(&[tlv(0x06, oid), utf8(value)]));
+        let mut rdns = vec![attribute(&[0x55, 0x04, 0x03], common_name)];
+        if let Some(organization) = organization {
+            rdns.push(attribute(&[0x55, 0x04, 0x0a], organization));
+        }
+        sequence(&rdns)
+    }
+
+    /// A certificate with the given subject and issuer, holding [`KEY`] and signed with it
+    pub fn certificate(subject: Name, issuer: Name) -> Vec<u8> {
+        signed_certificate(subject, issuer, &KEY)
+    }
+
+    /// A certificate holding [`KEY`] whose signature is made with `signer`
+    pub fn signed_certificate(subject: Name, issuer: Name, signer: &Key) -> Vec<u8> {
+        let algorithm = sequence(&[tlv(0x06, &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b])]);
+        let tbs = sequence(&[
+            tlv(0xa0, &integer(2)),
+            integer(1),
+            algorithm.clone(),
+            name(issuer),
+            sequence(&[]),
+            name(subject),
+            tlv(0x30, &KEY.spki_content()),
+        ]);
+        let signature = signer.sign(HashAlgorithm::Sha256, &HashAlgorithm::Sha256.digest(&tbs));
+        sequence(&[tbs, algorithm, tlv(0x03, &[&[0x00][..], &signature].concat())])
+    }
+
+    /// Leaf, intermediate and self-signed root certificates, leaf first
+    pub fn chain(leaf: &str, intermediate: &str, root: &str) -> Vec<Vec<u8>> {
+        let org = Some("Apple Inc.");
+        vec![
+            certificate((leaf, org), (intermediate, org)),
+            certificate((intermediate, org), (root, org)),
+            certificate((root, org), (root, org)),
+        ]
+    }
+
+    /// A ContentInfo holding SignedData with the given certificates, optionally
+    /// using the indefinite-length form `codesign` emits for the outer layers
+    pub fn signed_data(certificates: &[Vec<u8>], indefinite: bool) -> Vec<u8> {
+        signed_content(None, certificates, indefinite)
+    }
+
+    /// SignedData whose encapsulated content is `content`, as in provisioning profiles
+    pub fn signed_content(content: Option<&[u8]>, certificates: &[Vec<u8>], indefinite: bool) -> Vec<u8> {
+        build(content, certificates, &[], indefinite)
+    }
+
+    /// SignedData in which the first certificate signs the detached `content`
+    /// with [`KEY`], through signed attributes carrying its SHA-256 digest
+    pub fn signed_detached(content: &[u8], certificates: &[Vec<u8>], indefinite: bool) -> Vec<u8> {
+        let attributes = [
+            sequence(&[tlv(0x06, &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x03]), tlv(0x31, &tlv(0x06, OID_DATA))]),
+            sequence(&[
+                tlv(0x06, &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x04]),
+                tlv(0x31, &tlv(0x04, &HashAlgorithm::Sha256.digest(content))),
+            ]),
+        ].concat();
+        let signature = KEY.sign(HashAlgorithm::Sha256, &HashAlgorithm::Sha256.digest(&tlv(0x31, &attributes)));
+        let leaf = parse_certificate_der(&certificates[0]).unwrap();
+        let signer_info = sequence(&[
+            integer(1),
+            sequence(&[tlv(0x30, leaf.issuer), tlv(0x02, leaf.serial)]),
+            sequence(&[tlv(0x06, OID_SHA256), tlv(0x05, &[])]),
+            tlv(0xa0, &attributes),
+            sequence(&[tlv(0x06, OID_RSA_ENCRYPTION), tlv(0x05, &[])]),
+            tlv(0x04, &signature),
+        ]);
+        build(None, certificates, &signer_info, indefinite)
+    }
+
+    fn build(content: Option<&[u8]>, certificates: &[Vec<u8>], signer_infos: &[u8], indefinite: bool) -> Vec<u8> {
+        let wrap = |tag: u8, content: Vec<u8>| {
+            if indefinite {
+                [vec![tag, 0x80], content, vec![0x00, 0x00]].concat()
+            } else {
+                tlv(tag, &content)
+            }
+        };
+        let data_oid = tlv(0x06, OID_DATA);
+        let encap_content_info = match content {
+            // BER producers split long content into a constructed OCTET STRING
+            Some(content) if indefinite => wrap(0x30, [
+                data_oid,
+                wrap(0xa0, wrap(0x24, content.chunks(64).map(|chunk| tlv(0x04, chunk)).collect::<Vec<_>>().concat())),
+            ].concat()),
+            Some(content) => sequence(&[data_oid, tlv(0xa0, &tlv(0x04, content))]),
+            None => sequence(&[data_oid]),
+        };
+        let signed_data = wrap(0x30, [
+            integer(1),
+            tlv(0x31, &[]),
+            encap_content_info,
+            wrap(0xa0, certificates.concat()),
+            tlv(0x31, signer_infos),
+        ].concat());
+        let oid = tlv(0x06, &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02]);
+        wrap(0x30, [oid, wrap(0xa0, signed_data)].concat())
+    }
+}
+
+/// Compiled code requirement builders
+pub mod requirement {
+    use super::blob;
+
+    pub fn op(opcode: u32) -> Vec<u8> {
+        opcode.to_be_bytes().to_vec()
+    }
+
+    /// Length-prefixed operand padded to four bytes
+    pub fn data(bytes: &[u8]) -> Vec<u8> {
+        let mut out = (bytes.len() as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(bytes);
+        out.resize(out.len().next_multiple_of(4), 0);
+        out
+    }
+
+    pub fn and(left: Vec<u8>, right: Vec<u8>) -> Vec<u8> {
+        [op(6), left, right].concat()
+    }
+
+    pub fn or(left: Vec<u8>, right: Vec<u8>) -> Vec<u8> {
+        [op(7), left, right].concat()
+    }
+
+    pub fn not(expr: Vec<u8>) -> Vec<u8> {
+        [op(9), expr].concat()
+    }
+
+    pub fn ident(identifier: &str) -> Vec<u8> {
+        [op(2), data(identifier.as_bytes())].concat()
+    }
+
+    pub fn cert_field(slot: i32, field: &str, match_op: u32, value: &str) -> Vec<u8> {
+        [op(11), slot.to_be_bytes().to_vec(), data(field.as_bytes()), op(match_op), data(value.as_bytes())].concat()
+    }
+
+    /// Existence test for a certificate extension OID
+    pub fn cert_generic(slot: i32, oid: &[u8]) -> Vec<u8> {
+        [op(14), slot.to_be_bytes().to_vec(), data(oid), op(0)].concat()
+    }
+
+    /// A requirement blob in expression form
+    pub fn requirement(program: &[u8]) -> Vec<u8> {
+        blob(0xfade0c00, &[1u32.to_be_bytes().to_vec(), program.to_vec()].concat())
+    }
+
+    /// A requirements set holding `(type, requirement blob)` pairs
+    pub fn requirements(entries: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        let header_size = 12 + 8 * entries.len();
+        let mut index = (entries.len() as u32).to_be_bytes().to_vec();
+        let mut offset = header_size;
+        for (kind, requirement) in entries {
+            index.extend_from_slice(&kind.to_be_bytes());
+            index.extend_from_slice(&(offset as u32).to_be_bytes());
+            offset += requirement.len();
+        }
+        let payload = [index, entries.iter().flat_map(|(_, r)| r.clone()).collect()].concat();
+        blob(0xfade0c01, &payload)
+    }
+}
@@ -0,0 +1,114 @@
+//! Exploit mitigation checks
+//!
+//! Reports the `checksec`-style properties of a slice: PIE, a `__RESTRICT`
+//! segment, stack canaries, Objective-C and ARC, arm64e pointer authentication,
+//! and the hardened runtime and library validation signing flags. Entitlements
+//! that weaken the hardened runtime only matter in light of these.
+
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+use anyhow::Result;
+use serde_json::Value;
+use crate::models::{CodeDirectoryInfo, HardeningInfo};
+use super::code_directory::{CS_REQUIRE_LV, CS_RUNTIME};
+use super::dylibs::DISABLE_LIBRARY_VALIDATION;
+use super::macho::{MachOHeader, MachOSlice, MH_PIE};
+
+/// Symbols imported by code built with `-fstack-protector`
+const STACK_CHECK_SYMBOLS: [&str; 2] = ["___stack_chk_guard", "___stack_chk_fail"];
+
+/// Symbol every ARC-compiled Objective-C binary imports
+const ARC_SYMBOL: &str = "_objc_release";
+
+/// Segment dyld checks before honouring `DYLD_*` environment variables
+const RESTRICT_SEGMENT: &str = "__RESTRICT";
+const RESTRICT_SECTION: &str = "__restrict";
+
+/// Assess the mitigations of one slice
+pub fn hardening_info<R: Read + Seek>(
+    reader: &mut R,
+    slice: &MachOSlice,
+    header: &MachOHeader,
+    entitlements: &HashMap<String, Value>,
+    code_directory: Option<&CodeDirectoryInfo>,
+) -> Result<HardeningInfo> {
+    let sections = header.sections()?;
+    let strings = header.read_string_table(reader)?.unwrap_or_default();
+    let imports = |names: &[&str]| strings.split(|b| *b == 0).any(|symbol| names.iter().any(|name| symbol == name.as_bytes()));
+
+    let flags = code_directory.map_or(0, |cd| cd.flags);
+    let hardened_runtime = flags & CS_RUNTIME != 0;
+    let library_validation_disabled = entitlements.get(DISABLE_LIBRARY_VALIDATION) == Some(&Value::Bool(true));
+
+    Ok(HardeningInfo {
+        pie: header.flags & MH_PIE != 0,
+        restrict_segment: sections.iter().any(|s| s.segname == RESTRICT_SEGMENT && s.sectname == RESTRICT_SECTION),
+        stack_canary: imports(&STACK_CHECK_SYMBOLS),
+        objc: sections.iter().any(|s| s.sectname == "__objc_imageinfo" || s.segname == "__OBJC"),
+        arc: imports(&[ARC_SYMBOL]),
+        pointer_authentication: slice.arch_name() == "arm64e",
+        hardened_runtime,
+        library_validation: (hardened_runtime || flags & CS_REQUIRE_LV != 0) && !library_validation_disabled,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use serde_json::json;
+    use crate::entitlements::native::fixtures::MachOBuilder;
+    use crate::entitlements::native::macho::{self, CPU_SUBTYPE_ARM64E, CPU_TYPE_ARM64, CPU_TYPE_X86_64};
+
+    fn assess(image: Vec<u8>, entitlements: &HashMap<String, Value>, flags: Option<u32>) -> HardeningInfo {
+        let mut reader = Cursor::new(image);
+        let slice = macho::read_slices(&mut reader).unwrap().remove(0);
+        let header = MachOHeader::read(&mut reader, &slice).unwrap();
+        let code_directory = flags.map(|flags| CodeDirectoryInfo {
+            identifier: "tool".to_string(),
+            team_id: None,
+            hash_type: "sha256".to_string(),
+            cdhash: String::new(),
+            flags,
+            flag_names: Vec::new(),
+            platform_binary: false,
+        });
+        hardening_info(&mut reader, &slice, &header, entitlements, code_directory.as_ref()).unwrap()
+    }
+
+    #[test]
+    fn test_bare_binary_has_no_mitigations() {
+        let info = assess(MachOBuilder::new(CPU_TYPE_X86_64, 3).build(), &HashMap::new(), None);
+        assert_eq!(info, HardeningInfo::default());
+    }
+
+    #[test]
+    fn test_detects_mitigations() {
+        let image = MachOBuilder::new(CPU_TYPE_ARM64, CPU_SUBTYPE_ARM64E)
+            .flags(MH_PIE)
+            .section("__RESTRICT", "__restrict", b"\0")
+            .section("__DATA_CONST", "__objc_imageinfo", &[0; 8])
+            .strings(&["___stack_chk_fail", "_objc_release", "_main"])
+            .build();
+        let info = assess(image, &HashMap::new(), Some(CS_RUNTIME));
+
+        assert!(info.pie && info.restrict_segment && info.stack_canary);
+        assert!(info.objc && info.arc && info.pointer_authentication);
+        assert!(info.hardened_runtime && info.library_validation);
+    }
+
+    #[test]
+    fn test_symbol_prefixes_do_not_match() {
+        let image = MachOBuilder::new(CPU_TYPE_ARM64, 0).strings(&["___stack_chk_fail_local", "_objc_release_x"]).build();
+        let info = assess(image, &HashMap::new(), None);
+        assert!(!info.stack_canary && !info.arc);
+    }
+
+    #[test]
+    fn test_entitlement_disables_library_validation() {
+        let entitlements = HashMap::from([(DISABLE_LIBRARY_VALIDATION.to_string(), json!(true))]);
+        let info = assess(MachOBuilder::new(CPU_TYPE_ARM64, 0).build(), &entitlements, Some(CS_RUNTIME | CS_REQUIRE_LV));
+        assert!(info.hardened_runtime);
+        assert!(!info.library_validation);
+    }
+}
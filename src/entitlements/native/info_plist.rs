@@ -0,0 +1,83 @@
+//! Info.plist metadata
+//!
+//! Command-line tools embed their Info.plist in the `__TEXT,__info_plist`
+//! section; bundle executables have one at `Contents/Info.plist`. Only the
+//! identity and version keys are kept.
+
+use std::io::{Read, Seek};
+use anyhow::{Result, anyhow};
+use crate::models::{BundleInfo, InfoPlistSource};
+use super::macho::MachOHeader;
+
+/// Section holding the embedded Info.plist
+const INFO_PLIST_SEGMENT: &str = "__TEXT";
+const INFO_PLIST_SECTION: &str = "__info_plist";
+
+/// Read the Info.plist embedded in a slice, if it has one
+pub fn embedded_info_plist<R: Read + Seek>(reader: &mut R, header: &MachOHeader) -> Result<Option<Vec<u8>>> {
+    header.read_section(reader, INFO_PLIST_SEGMENT, INFO_PLIST_SECTION)
+}
+
+/// Parse the identity and version keys of an XML or binary Info.plist
+pub fn parse_info_plist(data: &[u8], source: InfoPlistSource) -> Result<BundleInfo> {
+    // Embedded plists are often NUL-padded to the section alignment
+    let end = data.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+    let plist: plist::Dictionary = plist::from_bytes(&data[..end])
+        .map_err(|e| anyhow!("Failed to parse Info.plist: {}", e))?;
+    let string = |key: &str| plist.get(key).and_then(|value| value.as_string()).map(str::to_string);
+
+    Ok(BundleInfo {
+        source,
+        identifier: string("CFBundleIdentifier"),
+        name: string("CFBundleName"),
+        short_version: string("CFBundleShortVersionString"),
+        version: string("CFBundleVersion"),
+        minimum_system_version: string("LSMinimumSystemVersion"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INFO_PLIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>CFBundleIdentifier</key>
+	<string>com.example.tool</string>
+	<key>CFBundleShortVersionString</key>
+	<string>1.2</string>
+	<key>CFBundleVersion</key>
+	<string>34</string>
+	<key>LSMinimumSystemVersion</key>
+	<string>12.0</string>
+	<key>CFBundleSupportedPlatforms</key>
+	<array><string>MacOSX</string></array>
+</dict>
+</plist>
+"#;
+
+    #[test]
+    fn test_parses_identity_and_version_keys() {
+        let info = parse_info_plist(INFO_PLIST.as_bytes(), InfoPlistSource::Embedded).unwrap();
+        assert_eq!(info.identifier.as_deref(), Some("com.example.tool"));
+        assert_eq!(info.short_version.as_deref(), Some("1.2"));
+        assert_eq!(info.version.as_deref(), Some("34"));
+        assert_eq!(info.minimum_system_version.as_deref(), Some("12.0"));
+        assert_eq!(info.name, None);
+    }
+
+    #[test]
+    fn test_section_padding_is_ignored() {
+        let mut data = INFO_PLIST.as_bytes().to_vec();
+        data.resize(data.len() + 13, 0);
+        assert!(parse_info_plist(&data, InfoPlistSource::Embedded).is_ok());
+    }
+
+    #[test]
+    fn test_non_dictionary_is_error() {
+        assert!(parse_info_plist(b"<plist><array/></plist>", InfoPlistSource::Bundle).is_err());
+        assert!(parse_info_plist(b"not a plist", InfoPlistSource::Bundle).is_err());
+    }
+}
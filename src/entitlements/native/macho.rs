@@ -0,0 +1,542 @@
+//! Minimal Mach-O reader
+//!
+//! Reads just enough of the Mach-O format to locate code signatures:
+//! - Universal (fat) headers and their per-architecture slices
+//! - Thin Mach-O headers and load commands
+//! - The `LC_CODE_SIGNATURE` linkedit blob of a slice
+//! - Section contents, such as the embedded `__TEXT,__info_plist`
+//! - String arguments of load commands (dylib install names, rpaths)
+//! - The `LC_SYMTAB` string table, for spotting imported symbols
+
+use std::io::{Read, Seek, SeekFrom};
+use anyhow::{Result, anyhow, bail};
+
+/// Thin Mach-O magic numbers, as read in big-endian order
+const MH_MAGIC: u32 = 0xfeedface;
+const MH_CIGAM: u32 = 0xcefaedfe;
+const MH_MAGIC_64: u32 = 0xfeedfacf;
+const MH_CIGAM_64: u32 = 0xcffaedfe;
+
+/// Universal binary magic numbers (fat headers are always big-endian)
+const FAT_MAGIC: u32 = 0xcafebabe;
+const FAT_MAGIC_64: u32 = 0xcafebabf;
+
+/// Java class files share FAT_MAGIC; real universal binaries never carry this many slices
+const FAT_MAX_ARCHS: u32 = 32;
+
+/// Upper bounds that keep malformed files from triggering huge allocations
+const MAX_LOAD_COMMANDS_SIZE: u32 = 16 * 1024 * 1024;
+const MAX_CODE_SIGNATURE_SIZE: u32 = 64 * 1024 * 1024;
+const MAX_STRING_TABLE_SIZE: u32 = 64 * 1024 * 1024;
+
+/// Header flag set on position-independent executables
+pub const MH_PIE: u32 = 0x0020_0000;
+
+/// Load command locating the symbol and string tables
+pub const LC_SYMTAB: u32 = 0x2;
+
+/// Load command carrying the code signature location
+pub const LC_CODE_SIGNATURE: u32 = 0x1d;
+
+/// Dylib and rpath load commands
+pub const LC_LOAD_DYLIB: u32 = 0xc;
+pub const LC_LOAD_WEAK_DYLIB: u32 = 0x8000_0018;
+pub const LC_RPATH: u32 = 0x8000_001c;
+pub const LC_REEXPORT_DYLIB: u32 = 0x8000_001f;
+pub const LC_LAZY_LOAD_DYLIB: u32 = 0x20;
+pub const LC_LOAD_UPWARD_DYLIB: u32 = 0x8000_0023;
+
+/// Deployment target load commands
+pub const LC_BUILD_VERSION: u32 = 0x32;
+pub const LC_VERSION_MIN_MACOSX: u32 = 0x24;
+pub const LC_VERSION_MIN_IPHONEOS: u32 = 0x25;
+pub const LC_VERSION_MIN_TVOS: u32 = 0x2f;
+pub const LC_VERSION_MIN_WATCHOS: u32 = 0x30;
+
+/// Segment load commands and the size of their fixed parts
+pub const LC_SEGMENT: u32 = 0x1;
+pub const LC_SEGMENT_64: u32 = 0x19;
+const SEGMENT_COMMAND_SIZE: usize = 56;
+const SEGMENT_COMMAND_64_SIZE: usize = 72;
+const SECTION_SIZE: usize = 68;
+const SECTION_64_SIZE: usize = 80;
+const MAX_SECTION_SIZE: u64 = 16 * 1024 * 1024;
+
+/// CPU types
+pub const CPU_TYPE_X86: u32 = 7;
+pub const CPU_TYPE_X86_64: u32 = 0x0100_0007;
+pub const CPU_TYPE_ARM: u32 = 12;
+pub const CPU_TYPE_ARM64: u32 = 0x0100_000c;
+pub const CPU_TYPE_ARM64_32: u32 = 0x0200_000c;
+pub const CPU_TYPE_POWERPC: u32 = 18;
+pub const CPU_TYPE_POWERPC64: u32 = 0x0100_0012;
+
+/// CPU subtypes that change the reported architecture name
+pub const CPU_SUBTYPE_X86_64_H: u32 = 8;
+pub const CPU_SUBTYPE_ARM64E: u32 = 2;
+
+/// Capability bits stored in the top byte of cpusubtype
+const CPU_SUBTYPE_MASK: u32 = 0xff00_0000;
+
+/// Byte order of a thin Mach-O image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+impl Endian {
+    /// Read a u32 at `offset`, failing on truncated input
+    pub fn u32(self, data: &[u8], offset: usize) -> Result<u32> {
+        let bytes: [u8; 4] = data
+            .get(offset..offset + 4)
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| anyhow!("Truncated Mach-O data at offset {}", offset))?;
+        Ok(match self {
+            Endian::Little => u32::from_le_bytes(bytes),
+            Endian::Big => u32::from_be_bytes(bytes),
+        })
+    }
+
+    /// Read a u64 at `offset`, failing on truncated input
+    pub fn u64(self, data: &[u8], offset: usize) -> Result<u64> {
+        let bytes: [u8; 8] = data
+            .get(offset..offset + 8)
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| anyhow!("Truncated Mach-O data at offset {}", offset))?;
+        Ok(match self {
+            Endian::Little => u64::from_le_bytes(bytes),
+            Endian::Big => u64::from_be_bytes(bytes),
+        })
+    }
+}
+
+/// One architecture slice of a (possibly universal) Mach-O file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MachOSlice {
+    pub cputype: u32,
+    pub cpusubtype: u32,
+    /// Offset of the slice from the start of the file
+    pub offset: u64,
+    /// Size of the slice in bytes
+    pub size: u64,
+}
+
+impl MachOSlice {
+    /// Architecture name as used by `lipo` (x86_64, arm64, arm64e, ...)
+    pub fn arch_name(&self) -> String {
+        let subtype = self.cpusubtype & !CPU_SUBTYPE_MASK;
+        match self.cputype {
+            CPU_TYPE_X86_64 if subtype == CPU_SUBTYPE_X86_64_H => "x86_64h".to_string(),
+            CPU_TYPE_X86_64 => "x86_64".to_string(),
+            CPU_TYPE_X86 => "i386".to_string(),
+            CPU_TYPE_ARM64 if subtype == CPU_SUBTYPE_ARM64E => "arm64e".to_string(),
+            CPU_TYPE_ARM64 => "arm64".to_string(),
+            CPU_TYPE_ARM64_32 => "arm64_32".to_string(),
+            CPU_TYPE_ARM => "arm".to_string(),
+            CPU_TYPE_POWERPC => "ppc".to_string(),
+            CPU_TYPE_POWERPC64 => "ppc64".to_string(),
+            other => format!("cpu_{:#x}", other),
+        }
+    }
+}
+
+/// A raw load command (including its 8-byte cmd/cmdsize header)
+#[derive(Debug, Clone)]
+pub struct LoadCommand {
+    pub cmd: u32,
+    pub data: Vec<u8>,
+}
+
+impl LoadCommand {
+    /// The NUL-terminated string whose offset within the command is stored at `offset_field`
+    pub fn string_at(&self, endian: Endian, offset_field: usize) -> Result<String> {
+        let offset = endian.u32(&self.data, offset_field)? as usize;
+        let bytes = self
+            .data
+            .get(offset..)
+            .filter(|bytes| offset > offset_field && !bytes.is_empty())
+            .ok_or_else(|| anyhow!("Load command {:#x} has string offset {} outside its {} bytes", self.cmd, offset, self.data.len()))?;
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        Ok(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    }
+}
+
+/// A section of a segment load command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub segname: String,
+    pub sectname: String,
+    /// File offset relative to the slice; zero for zero-fill sections
+    pub offset: u32,
+    pub size: u64,
+}
+
+/// Parsed header and load commands of a thin Mach-O slice
+#[derive(Debug, Clone)]
+pub struct MachOHeader {
+    pub endian: Endian,
+    pub is_64: bool,
+    /// Header flags (`MH_PIE`, ...)
+    pub flags: u32,
+    /// Offset of the slice this header belongs to
+    pub slice_offset: u64,
+    pub load_commands: Vec<LoadCommand>,
+}
+
+/// Read the architecture slices of a Mach-O file.
+///
+/// Thin files yield a single slice covering the whole file. Files that are not
+/// Mach-O at all yield an empty list rather than an error.
+pub fn read_slices<R: Read + Seek>(reader: &mut R) -> Result<Vec<MachOSlice>> {
+    let file_size = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    let mut magic = [0u8; 4];
+    if file_size < 8 || reader.read_exact(&mut magic).is_err() {
+        return Ok(Vec::new());
+    }
+
+    match u32::from_be_bytes(magic) {
+        FAT_MAGIC | FAT_MAGIC_64 => read_fat_slices(reader, u32::from_be_bytes(magic) == FAT_MAGIC_64, file_size),
+        MH_MAGIC | MH_CIGAM | MH_MAGIC_64 | MH_CIGAM_64 => {
+            let mut header = [0u8; 8];
+            reader.read_exact(&mut header)?;
+            let endian = thin_endian(u32::from_be_bytes(magic));
+            Ok(vec![MachOSlice {
+                cputype: endian.u32(&header, 0)?,
+                cpusubtype: endian.u32(&header, 4)?,
+                offset: 0,
+                size: file_size,
+            }])
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Read the slice table of a universal binary
+fn read_fat_slices<R: Read + Seek>(reader: &mut R, is_64: bool, file_size: u64) -> Result<Vec<MachOSlice>> {
+    let mut count = [0u8; 4];
+    reader.read_exact(&mut count)?;
+    let nfat_arch = u32::from_be_bytes(count);
+    if nfat_arch == 0 || nfat_arch > FAT_MAX_ARCHS {
+        // Not a universal binary (most likely a Java class file)
+        return Ok(Vec::new());
+    }
+
+    let entry_size = if is_64 { 32 } else { 20 };
+    let mut table = vec![0u8; entry_size * nfat_arch as usize];
+    reader.read_exact(&mut table)?;
+
+    let mut slices = Vec::with_capacity(nfat_arch as usize);
+    for entry in table.chunks_exact(entry_size) {
+        let be = Endian::Big;
+        let (offset, size) = if is_64 {
+            let offset = (u64::from(be.u32(entry, 8)?) << 32) | u64::from(be.u32(entry, 12)?);
+            let size = (u64::from(be.u32(entry, 16)?) << 32) | u64::from(be.u32(entry, 20)?);
+            (offset, size)
+        } else {
+            (u64::from(be.u32(entry, 8)?), u64::from(be.u32(entry, 12)?))
+        };
+
+        if offset.checked_add(size).is_none_or(|end| end > file_size) {
+            bail!("Universal binary slice extends past end of file");
+        }
+
+        slices.push(MachOSlice {
+            cputype: be.u32(entry, 0)?,
+            cpusubtype: be.u32(entry, 4)?,
+            offset,
+            size,
+        });
+    }
+
+    Ok(slices)
+}
+
+/// Pick the slice `codesign` would report on this host: the native architecture
+/// when present, otherwise the first slice.
+pub fn preferred_slice(slices: &[MachOSlice]) -> Option<&MachOSlice> {
+    let native_cputype = if cfg!(target_arch = "x86_64") {
+        CPU_TYPE_X86_64
+    } else {
+        CPU_TYPE_ARM64
+    };
+
+    slices
+        .iter()
+        .find(|slice| slice.cputype == native_cputype)
+        .or_else(|| slices.first())
+}
+
+fn thin_endian(magic: u32) -> Endian {
+    match magic {
+        MH_MAGIC | MH_MAGIC_64 => Endian::Big,
+        _ => Endian::Little,
+    }
+}
+
+impl MachOHeader {
+    /// Read the header and load commands of the given slice
+    pub fn read<R: Read + Seek>(reader: &mut R, slice: &MachOSlice) -> Result<Self> {
+        reader.seek(SeekFrom::Start(slice.offset))?;
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        let magic = u32::from_be_bytes(magic);
+        let is_64 = match magic {
+            MH_MAGIC_64 | MH_CIGAM_64 => true,
+            MH_MAGIC | MH_CIGAM => false,
+            _ => bail!("Slice at offset {} is not a Mach-O image", slice.offset),
+        };
+        let endian = thin_endian(magic);
+
+        // cputype, cpusubtype, filetype, ncmds, sizeofcmds, flags (+ reserved on 64-bit)
+        let mut fields = vec![0u8; if is_64 { 28 } else { 24 }];
+        reader.read_exact(&mut fields)?;
+        let ncmds = endian.u32(&fields, 12)?;
+        let sizeofcmds = endian.u32(&fields, 16)?;
+        if sizeofcmds > MAX_LOAD_COMMANDS_SIZE || u64::from(sizeofcmds) > slice.size {
+            bail!("Mach-O load commands size {} is out of range", sizeofcmds);
+        }
+
+        let mut commands = vec![0u8; sizeofcmds as usize];
+        reader.read_exact(&mut commands)?;
+
+        let mut load_commands = Vec::with_capacity(ncmds.min(sizeofcmds / 8) as usize);
+        let mut offset = 0usize;
+        for _ in 0..ncmds {
+            let cmd = endian.u32(&commands, offset)?;
+            let cmdsize = endian.u32(&commands, offset + 4)? as usize;
+            if cmdsize < 8 || offset + cmdsize > commands.len() {
+                bail!("Malformed load command {:#x} at offset {}", cmd, offset);
+            }
+            load_commands.push(LoadCommand {
+                cmd,
+                data: commands[offset..offset + cmdsize].to_vec(),
+            });
+            offset += cmdsize;
+        }
+
+        Ok(Self {
+            endian,
+            is_64,
+            flags: endian.u32(&fields, 20)?,
+            slice_offset: slice.offset,
+            load_commands,
+        })
+    }
+
+    /// First load command of the given type
+    pub fn find_command(&self, cmd: u32) -> Option<&LoadCommand> {
+        self.load_commands.iter().find(|lc| lc.cmd == cmd)
+    }
+
+    /// Sections of every segment, in load command order
+    pub fn sections(&self) -> Result<Vec<Section>> {
+        let (segment_cmd, header_size, section_size) = if self.is_64 {
+            (LC_SEGMENT_64, SEGMENT_COMMAND_64_SIZE, SECTION_64_SIZE)
+        } else {
+            (LC_SEGMENT, SEGMENT_COMMAND_SIZE, SECTION_SIZE)
+        };
+
+        let mut sections = Vec::new();
+        for lc in self.load_commands.iter().filter(|lc| lc.cmd == segment_cmd) {
+            let nsects = self.endian.u32(&lc.data, header_size - 8)? as usize;
+            for index in 0..nsects {
+                let base = header_size + index * section_size;
+                let section = lc.data.get(base..base + section_size)
+                    .ok_or_else(|| anyhow!("Truncated section {} in segment load command", index))?;
+                let (size, offset) = if self.is_64 {
+                    (self.endian.u64(section, 40)?, self.endian.u32(section, 48)?)
+                } else {
+                    (u64::from(self.endian.u32(section, 36)?), self.endian.u32(section, 40)?)
+                };
+                sections.push(Section {
+                    sectname: fixed_name(&section[0..16]),
+                    segname: fixed_name(&section[16..32]),
+                    offset,
+                    size,
+                });
+            }
+        }
+        Ok(sections)
+    }
+
+    /// Read the contents of a section, if the slice has it
+    pub fn read_section<R: Read + Seek>(&self, reader: &mut R, segname: &str, sectname: &str) -> Result<Option<Vec<u8>>> {
+        let section = match self.sections()?.into_iter().find(|s| s.segname == segname && s.sectname == sectname) {
+            Some(section) => section,
+            None => return Ok(None),
+        };
+        if section.offset == 0 || section.size == 0 {
+            return Ok(None);
+        }
+        if section.size > MAX_SECTION_SIZE {
+            bail!("Section {},{} size {} is out of range", segname, sectname, section.size);
+        }
+
+        reader.seek(SeekFrom::Start(self.slice_offset + u64::from(section.offset)))?;
+        let mut data = vec![0u8; section.size as usize];
+        reader.read_exact(&mut data)?;
+        Ok(Some(data))
+    }
+
+    /// Read the string table named by `LC_SYMTAB`, if the slice has one
+    pub fn read_string_table<R: Read + Seek>(&self, reader: &mut R) -> Result<Option<Vec<u8>>> {
+        let (stroff, strsize) = match self.find_command(LC_SYMTAB) {
+            Some(lc) => (self.endian.u32(&lc.data, 16)?, self.endian.u32(&lc.data, 20)?),
+            None => return Ok(None),
+        };
+        if strsize == 0 {
+            return Ok(None);
+        }
+        if strsize > MAX_STRING_TABLE_SIZE {
+            bail!("String table size {} is out of range", strsize);
+        }
+
+        reader.seek(SeekFrom::Start(self.slice_offset + u64::from(stroff)))?;
+        let mut strings = vec![0u8; strsize as usize];
+        reader.read_exact(&mut strings)?;
+        Ok(Some(strings))
+    }
+
+    /// `(dataoff, datasize)` of the code signature, relative to the slice
+    pub fn code_signature_range(&self) -> Result<Option<(u32, u32)>> {
+        match self.find_command(LC_CODE_SIGNATURE) {
+            Some(lc) => Ok(Some((self.endian.u32(&lc.data, 8)?, self.endian.u32(&lc.data, 12)?))),
+            None => Ok(None),
+        }
+    }
+
+    /// Read the raw code signature (SuperBlob) bytes, if the slice is signed
+    pub fn read_code_signature<R: Read + Seek>(&self, reader: &mut R) -> Result<Option<Vec<u8>>> {
+        let (dataoff, datasize) = match self.code_signature_range()? {
+            Some(range) => range,
+            None => return Ok(None),
+        };
+        if datasize == 0 {
+            return Ok(None);
+        }
+        if datasize > MAX_CODE_SIGNATURE_SIZE {
+            bail!("Code signature size {} is out of range", datasize);
+        }
+
+        reader.seek(SeekFrom::Start(self.slice_offset + u64::from(dataoff)))?;
+        let mut signature = vec![0u8; datasize as usize];
+        reader.read_exact(&mut signature)?;
+        Ok(Some(signature))
+    }
+}
+
+/// A NUL-padded 16-byte segment or section name
+fn fixed_name(field: &[u8]) -> String {
+    let end = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entitlements::native::fixtures;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_non_macho_has_no_slices() {
+        let mut data = Cursor::new(b"#!/bin/sh\necho hello\n".to_vec());
+        assert!(read_slices(&mut data).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_java_class_is_not_universal() {
+        // 0xcafebabe followed by class file version 52.0
+        let mut data = Cursor::new(vec![0xca, 0xfe, 0xba, 0xbe, 0x00, 0x00, 0x00, 0x34, 0, 0, 0, 0]);
+        assert!(read_slices(&mut data).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_thin_slice_covers_whole_file() {
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0).build();
+        let len = image.len() as u64;
+        let slices = read_slices(&mut Cursor::new(image)).unwrap();
+        assert_eq!(slices, vec![MachOSlice { cputype: CPU_TYPE_ARM64, cpusubtype: 0, offset: 0, size: len }]);
+        assert_eq!(slices[0].arch_name(), "arm64");
+    }
+
+    #[test]
+    fn test_fat_slices_and_arch_names() {
+        let fat = fixtures::fat(vec![
+            fixtures::MachOBuilder::new(CPU_TYPE_X86_64, 3).build(),
+            fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0).build(),
+            // arm64e with the pointer authentication ABI capability bit set
+            fixtures::MachOBuilder::new(CPU_TYPE_ARM64, CPU_SUBTYPE_ARM64E | 0x8000_0000).build(),
+        ]);
+        let slices = read_slices(&mut Cursor::new(fat)).unwrap();
+        let names: Vec<String> = slices.iter().map(MachOSlice::arch_name).collect();
+        assert_eq!(names, vec!["x86_64", "arm64", "arm64e"]);
+        assert!(slices[1].offset > 0);
+    }
+
+    #[test]
+    fn test_fat_slice_past_end_is_error() {
+        let mut fat = fixtures::fat(vec![fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0).build()]);
+        fat.truncate(fat.len() - 16);
+        assert!(read_slices(&mut Cursor::new(fat)).is_err());
+    }
+
+    #[test]
+    fn test_unsigned_image_has_no_signature() {
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_X86_64, 3).build();
+        let mut reader = Cursor::new(image);
+        let slices = read_slices(&mut reader).unwrap();
+        let header = MachOHeader::read(&mut reader, &slices[0]).unwrap();
+        assert_eq!(header.endian, Endian::Little);
+        assert!(header.read_code_signature(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_reads_code_signature_bytes() {
+        let signature = fixtures::superblob(&[(5, fixtures::blob(0xfade7171, b"<plist/>"))]);
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .signature(signature.clone())
+            .build();
+        let mut reader = Cursor::new(image);
+        let slices = read_slices(&mut reader).unwrap();
+        let header = MachOHeader::read(&mut reader, &slices[0]).unwrap();
+        assert_eq!(header.read_code_signature(&mut reader).unwrap(), Some(signature));
+    }
+
+    #[test]
+    fn test_reads_section_contents() {
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .section("__TEXT", "__info_plist", b"<plist/>")
+            .section("__DATA", "__data", b"data")
+            .build();
+        let mut reader = Cursor::new(image);
+        let slices = read_slices(&mut reader).unwrap();
+        let header = MachOHeader::read(&mut reader, &slices[0]).unwrap();
+
+        let names: Vec<(String, String)> = header.sections().unwrap().into_iter().map(|s| (s.segname, s.sectname)).collect();
+        assert_eq!(names, vec![("__TEXT".into(), "__info_plist".into()), ("__DATA".into(), "__data".into())]);
+        assert_eq!(header.read_section(&mut reader, "__TEXT", "__info_plist").unwrap().as_deref(), Some(&b"<plist/>"[..]));
+        assert_eq!(header.read_section(&mut reader, "__TEXT", "__text").unwrap(), None);
+    }
+
+    #[test]
+    fn test_truncated_load_commands_is_error() {
+        let mut image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0)
+            .signature(fixtures::superblob(&[]))
+            .build();
+        image.truncate(40);
+        let mut reader = Cursor::new(image);
+        let slices = read_slices(&mut reader).unwrap();
+        assert!(MachOHeader::read(&mut reader, &slices[0]).is_err());
+    }
+
+    #[test]
+    fn test_preferred_slice_falls_back_to_first() {
+        let slices = vec![MachOSlice { cputype: CPU_TYPE_POWERPC, cpusubtype: 0, offset: 0, size: 0 }];
+        assert_eq!(preferred_slice(&slices), slices.first());
+        assert_eq!(preferred_slice(&[]), None);
+    }
+}
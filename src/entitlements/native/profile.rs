@@ -0,0 +1,184 @@
+//! Embedded provisioning profiles
+//!
+//! An app bundle's `Contents/embedded.provisionprofile` is a CMS SignedData
+//! message whose content is a plist naming the team, the expiry date and the
+//! entitlements the app may claim. Restricted entitlements signed into the
+//! binary must be authorised by that plist or the app will not launch.
+
+use std::collections::HashMap;
+use std::time::SystemTime;
+use anyhow::{Result, anyhow, bail};
+use serde_json::Value;
+use crate::models::ProvisioningProfile;
+use super::cms;
+
+/// File name of the profile inside a bundle's `Contents` directory
+pub const EMBEDDED_PROFILE_NAME: &str = "embedded.provisionprofile";
+
+/// Entitlements any signed app may claim without a profile (App Sandbox and
+/// hardened runtime exceptions)
+const UNRESTRICTED_PREFIXES: &[&str] = &["com.apple.security."];
+
+/// Decode a provisioning profile
+pub fn parse_profile(data: &[u8]) -> Result<ProvisioningProfile> {
+    let content = cms::encapsulated_content(data)?
+        .ok_or_else(|| anyhow!("Provisioning profile has no content"))?;
+    let plist: plist::Dictionary = plist::from_bytes(&content)
+        .map_err(|e| anyhow!("Failed to parse provisioning profile plist: {}", e))?;
+    let string = |key: &str| plist.get(key).and_then(|value| value.as_string()).map(str::to_string);
+
+    let expiration = plist.get("ExpirationDate").and_then(|value| value.as_date());
+    let entitlements = match plist.get("Entitlements") {
+        Some(value @ plist::Value::Dictionary(_)) => super::plist_to_json_map(value.clone())?,
+        Some(_) => bail!("Provisioning profile Entitlements is not a dictionary"),
+        None => HashMap::new(),
+    };
+
+    Ok(ProvisioningProfile {
+        name: string("Name"),
+        team_identifier: plist.get("TeamIdentifier")
+            .and_then(|value| value.as_array())
+            .and_then(|teams| teams.first())
+            .and_then(|team| team.as_string())
+            .map(str::to_string),
+        team_name: string("TeamName"),
+        expiration_date: expiration.map(|date| date.to_xml_format()),
+        expired: expiration.is_some_and(|date| SystemTime::from(date) < SystemTime::now()),
+        entitlements,
+        unauthorized_entitlements: Vec::new(),
+    })
+}
+
+/// Whether an expiration date in RFC 3339 format has passed
+pub fn has_expired(expiration_date: &str) -> bool {
+    plist::Date::from_xml_format(expiration_date).is_ok_and(|date| SystemTime::from(date) < SystemTime::now())
+}
+
+/// Keys of the binary's entitlements that the profile does not authorise, sorted
+pub fn unauthorized_entitlements(entitlements: &HashMap<String, Value>, profile: &ProvisioningProfile) -> Vec<String> {
+    let mut unauthorized: Vec<String> = entitlements.iter()
+        .filter(|(key, _)| !UNRESTRICTED_PREFIXES.iter().any(|prefix| key.starts_with(prefix)))
+        .filter(|(key, value)| !profile.entitlements.get(*key).is_some_and(|allowed| value_allowed(allowed, value)))
+        .map(|(key, _)| key.clone())
+        .collect();
+    unauthorized.sort();
+    unauthorized
+}
+
+/// Whether a profile value allows the signed value
+///
+/// Profile strings may use `*` wildcards (e.g. `TEAMID.*`); a profile array
+/// allows any value, or array of values, that its elements allow.
+fn value_allowed(allowed: &Value, actual: &Value) -> bool {
+    match (allowed, actual) {
+        (Value::String(pattern), Value::String(value)) => wildcard_match(pattern, value),
+        (Value::String(_), Value::Array(values)) => values.iter().all(|value| value_allowed(allowed, value)),
+        (Value::Array(patterns), Value::Array(values)) => values.iter().all(|value| patterns.iter().any(|p| value_allowed(p, value))),
+        (Value::Array(patterns), value) => patterns.iter().any(|p| value_allowed(p, value)),
+        (allowed, actual) => allowed == actual,
+    }
+}
+
+/// Match `value` against a pattern where `*` matches any run of characters
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == value,
+        Some((prefix, rest)) => {
+            let Some(remainder) = value.strip_prefix(prefix) else { return false };
+            (0..=remainder.len())
+                .filter(|i| remainder.is_char_boundary(*i))
+                .any(|i| wildcard_match(rest, &remainder[i..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::entitlements::native::fixtures;
+
+    const PROFILE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+	<key>Name</key>
+	<string>Example App Development</string>
+	<key>TeamIdentifier</key>
+	<array><string>ABCDE12345</string></array>
+	<key>TeamName</key>
+	<string>Example Corp</string>
+	<key>ExpirationDate</key>
+	<date>2020-01-01T00:00:00Z</date>
+	<key>Entitlements</key>
+	<dict>
+		<key>com.apple.application-identifier</key>
+		<string>ABCDE12345.*</string>
+		<key>keychain-access-groups</key>
+		<array><string>ABCDE12345.*</string></array>
+		<key>com.apple.developer.networking.vpn.api</key>
+		<array><string>allow-vpn</string></array>
+		<key>com.apple.developer.team-identifier</key>
+		<string>ABCDE12345</string>
+	</dict>
+</dict>
+</plist>
+"#;
+
+    fn profile() -> ProvisioningProfile {
+        let certificates = fixtures::cms::chain("Apple iPhone OS Provisioning Profile Signing", "Apple iPhone Certification Authority", "Apple Root CA");
+        parse_profile(&fixtures::cms::signed_content(Some(PROFILE.as_bytes()), &certificates, true)).unwrap()
+    }
+
+    #[test]
+    fn test_parses_profile_metadata() {
+        let profile = profile();
+        assert_eq!(profile.name.as_deref(), Some("Example App Development"));
+        assert_eq!(profile.team_identifier.as_deref(), Some("ABCDE12345"));
+        assert_eq!(profile.team_name.as_deref(), Some("Example Corp"));
+        assert_eq!(profile.expiration_date.as_deref(), Some("2020-01-01T00:00:00Z"));
+        assert!(profile.expired);
+        assert_eq!(profile.entitlements.len(), 4);
+    }
+
+    #[test]
+    fn test_profile_authorizes_matching_entitlements() {
+        let entitlements: HashMap<String, Value> = [
+            ("com.apple.application-identifier", json!("ABCDE12345.com.example.app")),
+            ("keychain-access-groups", json!(["ABCDE12345.com.example.shared"])),
+            ("com.apple.developer.networking.vpn.api", json!(["allow-vpn"])),
+            ("com.apple.security.app-sandbox", json!(true)),
+        ].into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+
+        assert!(unauthorized_entitlements(&entitlements, &profile()).is_empty());
+    }
+
+    #[test]
+    fn test_profile_flags_unauthorized_entitlements() {
+        let entitlements: HashMap<String, Value> = [
+            ("com.apple.application-identifier", json!("OTHERTEAM1.com.example.app")),
+            ("keychain-access-groups", json!(["ABCDE12345.shared", "OTHERTEAM1.shared"])),
+            ("com.apple.developer.endpoint-security.client", json!(true)),
+        ].into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+
+        assert_eq!(unauthorized_entitlements(&entitlements, &profile()), vec![
+            "com.apple.application-identifier",
+            "com.apple.developer.endpoint-security.client",
+            "keychain-access-groups",
+        ]);
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("TEAM.*", "TEAM.com.example"));
+        assert!(wildcard_match("a*c*e", "abcde"));
+        assert!(!wildcard_match("TEAM.*", "OTHER.com.example"));
+        assert!(!wildcard_match("exact", "exactly"));
+    }
+
+    #[test]
+    fn test_profile_without_content_is_error() {
+        let detached = fixtures::cms::signed_data(&[], false);
+        assert!(parse_profile(&detached).is_err());
+    }
+}
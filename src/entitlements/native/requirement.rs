@@ -0,0 +1,393 @@
+//! Code requirement decoding
+//!
+//! The `CSSLOT_REQUIREMENTS` blob is a small SuperBlob of compiled requirement
+//! programs keyed by requirement type. Each program is a prefix-encoded
+//! expression tree, which is decompiled here into the requirement language
+//! `codesign -d -r-` prints, e.g.
+//! `identifier "com.foo" and anchor apple generic and certificate leaf[subject.OU] = XYZ`.
+
+use anyhow::{Result, anyhow, bail};
+use super::superblob::{be_u32, SuperBlob};
+
+/// Slot holding the requirements set
+pub const CSSLOT_REQUIREMENTS: u32 = 2;
+/// Magic of the requirements set and of a single requirement
+pub const CSMAGIC_REQUIREMENTS: u32 = 0xfade0c01;
+pub const CSMAGIC_REQUIREMENT: u32 = 0xfade0c00;
+
+/// Requirement type of the designated requirement
+const DESIGNATED_REQUIREMENT_TYPE: u32 = 3;
+/// The only requirement kind in use: an expression program
+const EXPR_FORM: u32 = 1;
+
+/// Flag bits carried in the top byte of an opcode
+const OP_FLAG_MASK: u32 = 0xff00_0000;
+
+/// Expression opcodes
+const OP_FALSE: u32 = 0;
+const OP_TRUE: u32 = 1;
+const OP_IDENT: u32 = 2;
+const OP_APPLE_ANCHOR: u32 = 3;
+const OP_ANCHOR_HASH: u32 = 4;
+const OP_INFO_KEY_VALUE: u32 = 5;
+const OP_AND: u32 = 6;
+const OP_OR: u32 = 7;
+const OP_CD_HASH: u32 = 8;
+const OP_NOT: u32 = 9;
+const OP_INFO_KEY_FIELD: u32 = 10;
+const OP_CERT_FIELD: u32 = 11;
+const OP_TRUSTED_CERT: u32 = 12;
+const OP_TRUSTED_CERTS: u32 = 13;
+const OP_CERT_GENERIC: u32 = 14;
+const OP_APPLE_GENERIC_ANCHOR: u32 = 15;
+const OP_ENTITLEMENT_FIELD: u32 = 16;
+const OP_CERT_POLICY: u32 = 17;
+const OP_NAMED_ANCHOR: u32 = 18;
+const OP_NAMED_CODE: u32 = 19;
+const OP_PLATFORM: u32 = 20;
+const OP_NOTARIZED: u32 = 21;
+const OP_CERT_FIELD_DATE: u32 = 22;
+const OP_LEGACY_DEV_ID: u32 = 23;
+
+/// Match operations
+const MATCH_EXISTS: u32 = 0;
+const MATCH_EQUAL: u32 = 1;
+const MATCH_CONTAINS: u32 = 2;
+const MATCH_BEGINS_WITH: u32 = 3;
+const MATCH_ENDS_WITH: u32 = 4;
+const MATCH_LESS_THAN: u32 = 5;
+const MATCH_GREATER_THAN: u32 = 6;
+const MATCH_LESS_EQUAL: u32 = 7;
+const MATCH_GREATER_EQUAL: u32 = 8;
+const MATCH_ON: u32 = 9;
+const MATCH_BEFORE: u32 = 10;
+const MATCH_AFTER: u32 = 11;
+const MATCH_ON_OR_BEFORE: u32 = 12;
+const MATCH_ON_OR_AFTER: u32 = 13;
+const MATCH_ABSENT: u32 = 14;
+
+/// Deepest expression nesting accepted
+const MAX_DEPTH: usize = 64;
+
+/// Operator precedence, lowest first, used to decide on parentheses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    Or,
+    And,
+    Primary,
+}
+
+/// Decode the designated requirement of a signature, if it carries one
+pub fn designated_requirement(superblob: &SuperBlob) -> Result<Option<String>> {
+    let blob = match superblob.slot(CSSLOT_REQUIREMENTS) {
+        Some(blob) => blob,
+        None => return Ok(None),
+    };
+    if blob.magic != CSMAGIC_REQUIREMENTS {
+        bail!("Unexpected requirements blob magic {:#x}", blob.magic);
+    }
+
+    let data = blob.data;
+    let count = be_u32(data, 8)? as usize;
+    for index in 0..count {
+        let kind = be_u32(data, 12 + index * 8)?;
+        let offset = be_u32(data, 16 + index * 8)? as usize;
+        if kind == DESIGNATED_REQUIREMENT_TYPE {
+            let length = be_u32(data, offset + 4)? as usize;
+            let requirement = offset.checked_add(length)
+                .and_then(|end| data.get(offset..end))
+                .ok_or_else(|| anyhow!("Designated requirement extends past its blob"))?;
+            return decompile(requirement).map(Some);
+        }
+    }
+    Ok(None)
+}
+
+/// Decompile a single requirement blob into the requirement language
+pub fn decompile(requirement: &[u8]) -> Result<String> {
+    let magic = be_u32(requirement, 0)?;
+    if magic != CSMAGIC_REQUIREMENT {
+        bail!("Unexpected requirement magic {:#x}", magic);
+    }
+    let kind = be_u32(requirement, 8)?;
+    if kind != EXPR_FORM {
+        bail!("Unsupported requirement kind {}", kind);
+    }
+
+    let mut reader = ExprReader { data: requirement, pos: 12 };
+    reader.expression(Precedence::Or, 0)
+}
+
+/// Cursor over a requirement program
+struct ExprReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl ExprReader<'_> {
+    fn u32(&mut self) -> Result<u32> {
+        let value = be_u32(self.data, self.pos)?;
+        self.pos += 4;
+        Ok(value)
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        self.u32().map(|v| v as i32)
+    }
+
+    fn i64(&mut self) -> Result<i64> {
+        let high = self.u32()? as i64;
+        let low = self.u32()? as i64;
+        Ok((high << 32) | low)
+    }
+
+    /// Length-prefixed data, padded to a four-byte boundary
+    fn data(&mut self) -> Result<&[u8]> {
+        let length = self.u32()? as usize;
+        let start = self.pos;
+        let bytes = start.checked_add(length)
+            .and_then(|end| self.data.get(start..end))
+            .ok_or_else(|| anyhow!("Requirement data at offset {} is truncated", start))?;
+        self.pos = (start + length).next_multiple_of(4);
+        Ok(bytes)
+    }
+
+    fn string(&mut self, dot_ok: bool) -> Result<String> {
+        self.data().map(|bytes| format_value(bytes, dot_ok))
+    }
+
+    /// Decompile one expression, parenthesizing it when it binds looser than `context`
+    fn expression(&mut self, context: Precedence, depth: usize) -> Result<String> {
+        if depth > MAX_DEPTH {
+            bail!("Requirement expression nested too deeply");
+        }
+
+        let op = self.u32()? & !OP_FLAG_MASK;
+        let (text, precedence) = match op {
+            OP_AND | OP_OR => {
+                let (precedence, keyword) = if op == OP_AND { (Precedence::And, "and") } else { (Precedence::Or, "or") };
+                let left = self.expression(precedence, depth + 1)?;
+                // Operators are left-associative, so a right operand of equal precedence needs parentheses
+                let right = self.expression(next(precedence), depth + 1)?;
+                (format!("{} {} {}", left, keyword, right), precedence)
+            }
+            OP_NOT => (format!("! {}", self.expression(Precedence::Primary, depth + 1)?), Precedence::Primary),
+            _ => (self.primary(op)?, Precedence::Primary),
+        };
+
+        if precedence < context {
+            Ok(format!("({})", text))
+        } else {
+            Ok(text)
+        }
+    }
+
+    /// Decompile a leaf expression
+    fn primary(&mut self, op: u32) -> Result<String> {
+        Ok(match op {
+            OP_FALSE => "never".to_string(),
+            OP_TRUE => "always".to_string(),
+            OP_IDENT => format!("identifier {}", self.string(false)?),
+            OP_APPLE_ANCHOR => "anchor apple".to_string(),
+            OP_APPLE_GENERIC_ANCHOR => "anchor apple generic".to_string(),
+            OP_TRUSTED_CERTS => "anchor trusted".to_string(),
+            OP_NAMED_ANCHOR => format!("anchor apple {}", self.string(false)?),
+            OP_NAMED_CODE => format!("({})", self.string(false)?),
+            OP_NOTARIZED => "notarized".to_string(),
+            OP_LEGACY_DEV_ID => "legacy".to_string(),
+            OP_PLATFORM => format!("platform = {}", self.i32()?),
+            OP_CD_HASH => format!("cdhash H\"{}\"", hex(self.data()?)),
+            OP_ANCHOR_HASH => {
+                let slot = cert_slot(self.i32()?);
+                format!("certificate {} = H\"{}\"", slot, hex(self.data()?))
+            }
+            OP_TRUSTED_CERT => format!("certificate {} trusted", cert_slot(self.i32()?)),
+            OP_INFO_KEY_VALUE => {
+                let key = self.string(true)?;
+                format!("info[{}] = {}", key, self.string(true)?)
+            }
+            OP_INFO_KEY_FIELD => {
+                let key = self.string(true)?;
+                format!("info[{}]{}", key, self.match_suffix()?)
+            }
+            OP_ENTITLEMENT_FIELD => {
+                let key = format_value(self.data()?, false);
+                format!("entitlement[{}]{}", key, self.match_suffix()?)
+            }
+            OP_CERT_FIELD => {
+                let slot = cert_slot(self.i32()?);
+                let field = String::from_utf8_lossy(self.data()?).into_owned();
+                format!("certificate {}[{}]{}", slot, field, self.match_suffix()?)
+            }
+            OP_CERT_GENERIC | OP_CERT_POLICY | OP_CERT_FIELD_DATE => {
+                let slot = cert_slot(self.i32()?);
+                let prefix = match op {
+                    OP_CERT_GENERIC => "field",
+                    OP_CERT_POLICY => "policy",
+                    _ => "timestamp",
+                };
+                let oid = oid_to_string(self.data()?);
+                format!("certificate {}[{}.{}]{}", slot, prefix, oid, self.match_suffix()?)
+            }
+            other => bail!("Unknown requirement opcode {}", other),
+        })
+    }
+
+    /// A match operation and its operand, rendered as the trailing part of a field test
+    fn match_suffix(&mut self) -> Result<String> {
+        let operation = self.u32()?;
+        Ok(match operation {
+            MATCH_EXISTS => " /* exists */".to_string(),
+            MATCH_ABSENT => " absent".to_string(),
+            MATCH_EQUAL => format!(" = {}", self.string(true)?),
+            MATCH_CONTAINS => format!(" ~ {}", self.string(true)?),
+            MATCH_BEGINS_WITH => format!(" = {}*", self.string(true)?),
+            MATCH_ENDS_WITH => format!(" = *{}", self.string(true)?),
+            MATCH_LESS_THAN => format!(" < {}", self.string(true)?),
+            MATCH_GREATER_THAN => format!(" > {}", self.string(true)?),
+            MATCH_LESS_EQUAL => format!(" <= {}", self.string(true)?),
+            MATCH_GREATER_EQUAL => format!(" >= {}", self.string(true)?),
+            MATCH_ON => format!(" = timestamp {}", self.i64()?),
+            MATCH_BEFORE => format!(" < timestamp {}", self.i64()?),
+            MATCH_AFTER => format!(" > timestamp {}", self.i64()?),
+            MATCH_ON_OR_BEFORE => format!(" <= timestamp {}", self.i64()?),
+            MATCH_ON_OR_AFTER => format!(" >= timestamp {}", self.i64()?),
+            other => bail!("Unknown requirement match operation {}", other),
+        })
+    }
+}
+
+/// The precedence one step tighter than `precedence`
+fn next(precedence: Precedence) -> Precedence {
+    match precedence {
+        Precedence::Or => Precedence::And,
+        _ => Precedence::Primary,
+    }
+}
+
+/// Name of a certificate position in the chain
+fn cert_slot(slot: i32) -> String {
+    match slot {
+        0 => "leaf".to_string(),
+        -1 => "root".to_string(),
+        n => n.to_string(),
+    }
+}
+
+/// Render a string operand bare when it is a simple token, quoted otherwise,
+/// and as hex when it is not printable
+fn format_value(bytes: &[u8], dot_ok: bool) -> String {
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) if !text.chars().any(|c| c.is_control()) => text,
+        _ => return format!("H\"{}\"", hex(bytes)),
+    };
+
+    let simple = text.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || (dot_ok && c == '.'));
+    if simple {
+        text.to_string()
+    } else {
+        format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+/// Dotted form of a DER-encoded object identifier
+fn oid_to_string(oid: &[u8]) -> String {
+    let mut arcs: Vec<u64> = Vec::new();
+    let mut value = 0u64;
+    for byte in oid {
+        value = (value << 7) | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            if arcs.is_empty() {
+                let first = (value / 40).min(2);
+                arcs.push(first);
+                arcs.push(value - first * 40);
+            } else {
+                arcs.push(value);
+            }
+            value = 0;
+        }
+    }
+    arcs.iter().map(|arc| arc.to_string()).collect::<Vec<_>>().join(".")
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entitlements::native::fixtures::{self, requirement as req};
+
+    #[test]
+    fn test_decompiles_typical_developer_id_requirement() {
+        let program = req::and(
+            req::and(req::ident("com.example.tool"), req::op(OP_APPLE_GENERIC_ANCHOR)),
+            req::cert_field(0, "subject.OU", MATCH_EQUAL, "ABCDE12345"),
+        );
+        assert_eq!(
+            decompile(&req::requirement(&program)).unwrap(),
+            "identifier \"com.example.tool\" and anchor apple generic and certificate leaf[subject.OU] = ABCDE12345"
+        );
+    }
+
+    #[test]
+    fn test_or_inside_and_is_parenthesized() {
+        let program = req::and(
+            req::op(OP_APPLE_GENERIC_ANCHOR),
+            req::or(
+                req::cert_generic(1, &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x63, 0x64, 0x06, 0x02, 0x06]),
+                req::op(OP_NOTARIZED),
+            ),
+        );
+        assert_eq!(
+            decompile(&req::requirement(&program)).unwrap(),
+            "anchor apple generic and (certificate 1[field.1.2.840.113635.100.6.2.6] /* exists */ or notarized)"
+        );
+    }
+
+    #[test]
+    fn test_not_and_cdhash() {
+        let program = req::or(
+            req::not(req::op(OP_APPLE_ANCHOR)),
+            [req::op(OP_CD_HASH), req::data(&[0xab, 0xcd])].concat(),
+        );
+        assert_eq!(decompile(&req::requirement(&program)).unwrap(), "! anchor apple or cdhash H\"abcd\"");
+    }
+
+    #[test]
+    fn test_values_needing_quotes() {
+        assert_eq!(format_value(b"ABCDE12345", true), "ABCDE12345");
+        assert_eq!(format_value(b"Apple Inc.", true), "\"Apple Inc.\"");
+        assert_eq!(format_value(b"com.apple.ls", false), "\"com.apple.ls\"");
+        assert_eq!(format_value(&[0x00, 0x01], true), "H\"0001\"");
+    }
+
+    #[test]
+    fn test_designated_requirement_from_signature() {
+        let host = req::requirement(&req::op(OP_TRUE));
+        let designated = req::requirement(&req::and(req::ident("com.apple.ls"), req::op(OP_APPLE_ANCHOR)));
+        let signature = fixtures::superblob(&[(CSSLOT_REQUIREMENTS, req::requirements(&[(1, host), (3, designated)]))]);
+        let superblob = SuperBlob::parse(&signature).unwrap();
+        assert_eq!(
+            designated_requirement(&superblob).unwrap().as_deref(),
+            Some("identifier \"com.apple.ls\" and anchor apple")
+        );
+    }
+
+    #[test]
+    fn test_missing_designated_requirement() {
+        let library = req::requirement(&req::op(OP_TRUE));
+        let signature = fixtures::superblob(&[(CSSLOT_REQUIREMENTS, req::requirements(&[(4, library)]))]);
+        let superblob = SuperBlob::parse(&signature).unwrap();
+        assert_eq!(designated_requirement(&superblob).unwrap(), None);
+    }
+
+    #[test]
+    fn test_unknown_opcode_is_error() {
+        assert!(decompile(&req::requirement(&req::op(99))).is_err());
+        let truncated = req::requirement(&req::op(OP_IDENT));
+        assert!(decompile(&truncated).is_err());
+    }
+}
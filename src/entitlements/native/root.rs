@@ -0,0 +1,135 @@
+//! Foreign filesystem roots
+//!
+//! With `--root`, listent reads a mounted or extracted macOS filesystem as if
+//! it were `/`: scan paths and absolute install names are looked up under the
+//! root, symlinks are resolved inside the image (an absolute target points
+//! back into the image, and `..` cannot climb above it), and results are
+//! reported with in-image paths.
+
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Symlinks followed while resolving one path, as in the kernel's `MAXSYMLINKS`
+pub const MAX_SYMLINKS: usize = 32;
+
+/// Host path of an in-image path, without following symlinks
+pub fn host_path(root: &Path, image_path: &Path) -> PathBuf {
+    let mut path = root.to_path_buf();
+    path.extend(image_path.components().filter(|component| matches!(component, Component::Normal(_) | Component::ParentDir)));
+    path
+}
+
+/// In-image path of a host path under `root`; other paths are returned unchanged
+pub fn image_path(root: &Path, host_path: &Path) -> PathBuf {
+    match host_path.strip_prefix(root) {
+        Ok(relative) => Path::new("/").join(relative),
+        Err(_) => host_path.to_path_buf(),
+    }
+}
+
+/// Resolve the symlinks of a host path under `root` the way the image sees
+/// them, returning the host path of the target.
+///
+/// Fails like `realpath` when a component is missing or links loop.
+pub fn resolve(root: &Path, host_path: &Path) -> io::Result<PathBuf> {
+    // Components still to walk, the next one last
+    let mut pending: Vec<OsString> = components(&image_path(root, host_path));
+    pending.reverse();
+    let mut resolved = PathBuf::new();
+    let mut links = 0;
+
+    while let Some(component) = pending.pop() {
+        if component == ".." {
+            resolved.pop();
+            continue;
+        }
+        let candidate = resolved.join(&component);
+        let link = root.join(&candidate);
+        if !fs::symlink_metadata(&link)?.file_type().is_symlink() {
+            resolved = candidate;
+            continue;
+        }
+
+        links += 1;
+        if links > MAX_SYMLINKS {
+            return Err(io::Error::other(format!("Too many levels of symbolic links: {}", host_path.display())));
+        }
+        let target = fs::read_link(&link)?;
+        if target.is_absolute() {
+            resolved.clear();
+        }
+        pending.extend(components(&target).into_iter().rev());
+    }
+    Ok(root.join(resolved))
+}
+
+/// Where a host path can be read: under a foreign root its symlinks resolve
+/// inside the image, otherwise it is read as is
+pub fn readable(root: Option<&Path>, host_path: &Path) -> io::Result<PathBuf> {
+    match root {
+        Some(root) => resolve(root, host_path),
+        None => Ok(host_path.to_path_buf()),
+    }
+}
+
+/// Normal and `..` components of a path; the root and `.` are dropped
+fn components(path: &Path) -> Vec<OsString> {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_os_string()),
+            Component::ParentDir => Some(OsString::from("..")),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    /// An image with `/usr/bin/tool`, an absolute and a relative link to it, and a loop
+    fn image() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("usr/bin")).unwrap();
+        fs::create_dir_all(dir.path().join("private/etc")).unwrap();
+        fs::write(dir.path().join("usr/bin/tool"), b"tool").unwrap();
+        symlink("/usr/bin/tool", dir.path().join("usr/bin/absolute")).unwrap();
+        symlink("../../../../usr/bin/tool", dir.path().join("usr/bin/climbing")).unwrap();
+        symlink("/private/etc", dir.path().join("etc")).unwrap();
+        symlink("loop-b", dir.path().join("loop-a")).unwrap();
+        symlink("loop-a", dir.path().join("loop-b")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_host_and_image_paths() {
+        let root = Path::new("/mnt/image");
+        assert_eq!(host_path(root, Path::new("/usr/bin")), Path::new("/mnt/image/usr/bin"));
+        assert_eq!(host_path(root, Path::new("Applications")), Path::new("/mnt/image/Applications"));
+        assert_eq!(image_path(root, Path::new("/mnt/image/usr/bin/ls")), Path::new("/usr/bin/ls"));
+        assert_eq!(image_path(root, Path::new("/mnt/image")), Path::new("/"));
+        assert_eq!(image_path(root, Path::new("/elsewhere")), Path::new("/elsewhere"));
+    }
+
+    #[test]
+    fn test_symlinks_resolve_inside_the_image() {
+        let dir = image();
+        let root = dir.path();
+        let tool = root.join("usr/bin/tool");
+
+        assert_eq!(resolve(root, &tool).unwrap(), tool);
+        assert_eq!(resolve(root, &root.join("usr/bin/absolute")).unwrap(), tool);
+        assert_eq!(resolve(root, &root.join("usr/bin/climbing")).unwrap(), tool);
+        assert_eq!(resolve(root, &root.join("etc")).unwrap(), root.join("private/etc"));
+    }
+
+    #[test]
+    fn test_unresolvable_paths_fail() {
+        let dir = image();
+        assert!(resolve(dir.path(), &dir.path().join("loop-a")).is_err());
+        assert!(resolve(dir.path(), &dir.path().join("usr/bin/missing")).is_err());
+    }
+}
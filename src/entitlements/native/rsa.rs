@@ -0,0 +1,340 @@
+//! RSA signature verification
+//!
+//! Checks PKCS #1 v1.5 signatures (RFC 8017), the scheme Apple's root,
+//! intermediate and signing certificates use, with SHA-1 or SHA-2 digests.
+//! Only the public-key operation is needed, so the arithmetic is a plain
+//! Montgomery exponentiation over 32-bit limbs: nothing secret passes through
+//! it, and it need not run in constant time.
+
+use anyhow::{Result, bail};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use super::der::{DerReader, TAG_BIT_STRING, TAG_INTEGER, TAG_OID, TAG_SEQUENCE};
+
+/// 1.2.840.113549.1.1.1 (rsaEncryption)
+pub const OID_RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+
+/// Largest modulus accepted, in bits
+const MAX_MODULUS_BITS: usize = 8192;
+
+/// Digest a PKCS #1 v1.5 signature is made over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    /// The digest algorithm of an `AlgorithmIdentifier` OID (`id-sha256`, ...)
+    pub fn from_digest_oid(oid: &[u8]) -> Option<Self> {
+        match oid {
+            [0x2b, 0x0e, 0x03, 0x02, 0x1a] => Some(HashAlgorithm::Sha1),
+            [0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, id] => match id {
+                1 => Some(HashAlgorithm::Sha256),
+                2 => Some(HashAlgorithm::Sha384),
+                3 => Some(HashAlgorithm::Sha512),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The digest of an `shaNWithRSAEncryption` signature algorithm OID
+    pub fn from_signature_oid(oid: &[u8]) -> Option<Self> {
+        match oid {
+            [0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, id] => match id {
+                5 => Some(HashAlgorithm::Sha1),
+                11 => Some(HashAlgorithm::Sha256),
+                12 => Some(HashAlgorithm::Sha384),
+                13 => Some(HashAlgorithm::Sha512),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Sha1 => Sha1::digest(data).to_vec(),
+            HashAlgorithm::Sha256 => Sha256::digest(data).to_vec(),
+            HashAlgorithm::Sha384 => Sha384::digest(data).to_vec(),
+            HashAlgorithm::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+
+    /// DER `DigestInfo` header that precedes the digest in the signed block
+    fn digest_info_prefix(&self) -> &'static [u8] {
+        match self {
+            HashAlgorithm::Sha1 => &[0x30, 0x21, 0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00, 0x04, 0x14],
+            HashAlgorithm::Sha256 => &[0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05, 0x00, 0x04, 0x20],
+            HashAlgorithm::Sha384 => &[0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02, 0x05, 0x00, 0x04, 0x30],
+            HashAlgorithm::Sha512 => &[0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03, 0x05, 0x00, 0x04, 0x40],
+        }
+    }
+}
+
+/// An RSA public key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
+    /// Modulus as little-endian 32-bit limbs, without leading zero limbs
+    modulus: Vec<u32>,
+    /// Public exponent, big-endian
+    exponent: Vec<u8>,
+    /// Length of the modulus in bytes
+    size: usize,
+}
+
+impl PublicKey {
+    /// Build a key from its big-endian modulus and exponent
+    pub fn new(modulus: &[u8], exponent: &[u8]) -> Result<Self> {
+        let modulus = trim_leading_zeros(modulus);
+        if modulus.len() * 8 > MAX_MODULUS_BITS {
+            bail!("RSA modulus of {} bits is too large", modulus.len() * 8);
+        }
+        if modulus.last().is_none_or(|byte| byte & 1 == 0) {
+            bail!("RSA modulus must be odd");
+        }
+        Ok(Self { modulus: to_limbs(modulus), exponent: trim_leading_zeros(exponent).to_vec(), size: modulus.len() })
+    }
+
+    /// Decode the content of a `SubjectPublicKeyInfo`; `None` for keys other than RSA
+    pub fn from_spki(spki: &[u8]) -> Result<Option<Self>> {
+        let mut reader = DerReader::new(spki);
+        let algorithm = reader.expect(TAG_SEQUENCE)?;
+        if DerReader::new(algorithm.content).expect(TAG_OID)?.content != OID_RSA_ENCRYPTION {
+            return Ok(None);
+        }
+        let bits = reader.expect(TAG_BIT_STRING)?.content;
+        let Some((0, key)) = bits.split_first() else {
+            bail!("RSA public key is not a whole number of bytes");
+        };
+        let key = DerReader::new(key).expect(TAG_SEQUENCE)?;
+        let mut reader = DerReader::new(key.content);
+        let modulus = reader.expect(TAG_INTEGER)?.content;
+        let exponent = reader.expect(TAG_INTEGER)?.content;
+        Self::new(modulus, exponent).map(Some)
+    }
+
+    /// Whether `signature` is this key's PKCS #1 v1.5 signature of `digest`
+    pub fn verify(&self, hash: HashAlgorithm, digest: &[u8], signature: &[u8]) -> bool {
+        let signature = trim_leading_zeros(signature);
+        if signature.len() > self.size {
+            return false;
+        }
+        let signature = to_limbs(signature);
+        if compare(&signature, &self.modulus) != std::cmp::Ordering::Less {
+            return false;
+        }
+        let message = from_limbs(&mod_pow(&signature, &self.exponent, &self.modulus), self.size);
+        message == encode(hash, digest, self.size)
+    }
+}
+
+/// The block `00 01 FF .. FF 00 DigestInfo` a signature of `digest` decrypts to
+pub(crate) fn encode(hash: HashAlgorithm, digest: &[u8], size: usize) -> Vec<u8> {
+    let prefix = hash.digest_info_prefix();
+    let padding = size.saturating_sub(prefix.len() + digest.len() + 3).max(8);
+    [&[0x00, 0x01][..], &vec![0xff; padding], &[0x00], prefix, digest].concat()
+}
+
+/// `base ^ exponent mod modulus` for an odd modulus
+pub(crate) fn mod_pow(base: &[u32], exponent: &[u8], modulus: &[u32]) -> Vec<u32> {
+    let montgomery = Montgomery::new(modulus);
+    let base = montgomery.to_montgomery(base);
+    let mut result = montgomery.to_montgomery(&[1]);
+    for byte in exponent {
+        for bit in (0..8).rev() {
+            result = montgomery.multiply(&result, &result);
+            if byte >> bit & 1 == 1 {
+                result = montgomery.multiply(&result, &base);
+            }
+        }
+    }
+    montgomery.multiply(&result, &[1])
+}
+
+/// Multiplication modulo an odd number in Montgomery form (`x * R mod n`, `R = 2^(32 * limbs)`)
+struct Montgomery<'a> {
+    modulus: &'a [u32],
+    /// `-modulus^-1 mod 2^32`
+    inverse: u32,
+    /// `R^2 mod modulus`, to bring numbers into Montgomery form
+    r_squared: Vec<u32>,
+}
+
+impl<'a> Montgomery<'a> {
+    fn new(modulus: &'a [u32]) -> Self {
+        // Newton's iteration doubles the correct low bits of the inverse each step
+        let mut inverse: u32 = 1;
+        for _ in 0..5 {
+            inverse = inverse.wrapping_mul(2u32.wrapping_sub(modulus[0].wrapping_mul(inverse)));
+        }
+
+        // R^2 mod n by doubling 1 modulo n, 2 * 32 * limbs times
+        let mut r_squared = vec![0u32; modulus.len()];
+        r_squared[0] = 1;
+        for _ in 0..64 * modulus.len() {
+            let carry = shift_left(&mut r_squared);
+            if carry || compare(&r_squared, modulus) != std::cmp::Ordering::Less {
+                subtract(&mut r_squared, modulus);
+            }
+        }
+        Self { modulus, inverse: inverse.wrapping_neg(), r_squared }
+    }
+
+    fn to_montgomery(&self, value: &[u32]) -> Vec<u32> {
+        self.multiply(value, &self.r_squared)
+    }
+
+    /// `a * b / R mod n`, for `a, b < n` (shorter inputs are zero-extended)
+    fn multiply(&self, a: &[u32], b: &[u32]) -> Vec<u32> {
+        let n = self.modulus;
+        let limbs = n.len();
+        let limb = |value: &[u32], i: usize| value.get(i).copied().unwrap_or(0) as u64;
+        let mut t = vec![0u32; limbs + 2];
+        for i in 0..limbs {
+            let b_i = limb(b, i);
+            let mut carry = 0u64;
+            for (j, t_j) in t.iter_mut().enumerate().take(limbs) {
+                let sum = *t_j as u64 + limb(a, j) * b_i + carry;
+                *t_j = sum as u32;
+                carry = sum >> 32;
+            }
+            let sum = t[limbs] as u64 + carry;
+            t[limbs] = sum as u32;
+            t[limbs + 1] = (sum >> 32) as u32;
+
+            // Add m * n so the lowest limb becomes zero, then shift it out
+            let m = t[0].wrapping_mul(self.inverse) as u64;
+            let mut carry = (t[0] as u64 + m * n[0] as u64) >> 32;
+            for j in 1..limbs {
+                let sum = t[j] as u64 + m * n[j] as u64 + carry;
+                t[j - 1] = sum as u32;
+                carry = sum >> 32;
+            }
+            let sum = t[limbs] as u64 + carry;
+            t[limbs - 1] = sum as u32;
+            t[limbs] = t[limbs + 1] + (sum >> 32) as u32;
+            t[limbs + 1] = 0;
+        }
+
+        let mut result = t[..limbs].to_vec();
+        if t[limbs] != 0 || compare(&result, n) != std::cmp::Ordering::Less {
+            subtract(&mut result, n);
+        }
+        result
+    }
+}
+
+/// Shift left by one bit in place, returning the bit shifted out
+fn shift_left(value: &mut [u32]) -> bool {
+    let mut carry = 0;
+    for limb in value.iter_mut() {
+        let next = *limb >> 31;
+        *limb = *limb << 1 | carry;
+        carry = next;
+    }
+    carry == 1
+}
+
+/// Subtract in place, wrapping around past zero
+fn subtract(value: &mut [u32], other: &[u32]) {
+    let mut borrow = 0u64;
+    for (i, limb) in value.iter_mut().enumerate() {
+        let difference = (*limb as u64).wrapping_sub(other.get(i).copied().unwrap_or(0) as u64).wrapping_sub(borrow);
+        *limb = difference as u32;
+        borrow = difference >> 63;
+    }
+}
+
+/// Compare numbers of any limb count
+fn compare(a: &[u32], b: &[u32]) -> std::cmp::Ordering {
+    let limbs = a.len().max(b.len());
+    (0..limbs)
+        .rev()
+        .map(|i| a.get(i).copied().unwrap_or(0).cmp(&b.get(i).copied().unwrap_or(0)))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(std::cmp::Ordering::Equal)
+}
+
+fn trim_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|byte| *byte != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+/// Little-endian limbs of a big-endian number
+pub(crate) fn to_limbs(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .rchunks(4)
+        .map(|chunk| chunk.iter().fold(0u32, |limb, byte| limb << 8 | *byte as u32))
+        .collect()
+}
+
+/// Big-endian bytes of a number, left-padded to `size`
+pub(crate) fn from_limbs(limbs: &[u32], size: usize) -> Vec<u8> {
+    let bytes: Vec<u8> = limbs.iter().rev().flat_map(|limb| limb.to_be_bytes()).collect();
+    let significant = trim_leading_zeros(&bytes);
+    let mut padded = vec![0u8; size.saturating_sub(significant.len())];
+    padded.extend_from_slice(significant);
+    padded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entitlements::native::fixtures::{self, rsa::{KEY, OTHER_KEY}};
+
+    /// `openssl dgst -sha256 -sign` and `-sha1 -sign` of "listent" with [`KEY`]
+    const OPENSSL_SHA256: &str = "78f19fcbbf06d7daf4b117750dd0ea62de47f6d6eb782c2cef590c765c4862cbcf48c573faaf3932b8073a75f5b61f9f88e32b51351bcbed1b452e10a2f49785b3213423f5994d5bf5896f59186e10f770a284baee868ac71c3b865fd1af216d52aac2d9da898a561e98afc836418325256051de8c5c17a11109c22056d5bd34";
+    const OPENSSL_SHA1: &str = "a335160faf4642d8bc4401c9ea4ebe5371567c43719471d74d3411aa03e575093437071e15c30b2bf9b82e1ee9f02007e6e0732470a6fdb8f62c270e032a90e5795ca9c75b4a7913474b609f956f45df4056fb3d9d931973a8d451baaee6337946670a47e4e972495df2f3055463219aa000d17730feef8cebb302bc144097d3";
+
+    #[test]
+    fn test_verifies_openssl_signatures() {
+        let key = KEY.public();
+        let sha256 = HashAlgorithm::Sha256.digest(b"listent");
+        let sha1 = HashAlgorithm::Sha1.digest(b"listent");
+        assert!(key.verify(HashAlgorithm::Sha256, &sha256, &fixtures::unhex(OPENSSL_SHA256)));
+        assert!(key.verify(HashAlgorithm::Sha1, &sha1, &fixtures::unhex(OPENSSL_SHA1)));
+
+        // Another digest, algorithm or key does not verify
+        assert!(!key.verify(HashAlgorithm::Sha256, &HashAlgorithm::Sha256.digest(b"listen"), &fixtures::unhex(OPENSSL_SHA256)));
+        assert!(!key.verify(HashAlgorithm::Sha1, &sha256[..20], &fixtures::unhex(OPENSSL_SHA256)));
+        assert!(!OTHER_KEY.public().verify(HashAlgorithm::Sha256, &sha256, &fixtures::unhex(OPENSSL_SHA256)));
+    }
+
+    #[test]
+    fn test_rejects_altered_signatures() {
+        let mut signature = fixtures::unhex(OPENSSL_SHA256);
+        signature[40] ^= 1;
+        let digest = HashAlgorithm::Sha256.digest(b"listent");
+        assert!(!KEY.public().verify(HashAlgorithm::Sha256, &digest, &signature));
+        assert!(!KEY.public().verify(HashAlgorithm::Sha256, &digest, &[0xff; 129]));
+    }
+
+    #[test]
+    fn test_signs_and_verifies_with_the_fixture_keys() {
+        let digest = HashAlgorithm::Sha384.digest(b"payload");
+        let signature = OTHER_KEY.sign(HashAlgorithm::Sha384, &digest);
+        assert!(OTHER_KEY.public().verify(HashAlgorithm::Sha384, &digest, &signature));
+        assert!(!KEY.public().verify(HashAlgorithm::Sha384, &digest, &signature));
+    }
+
+    #[test]
+    fn test_small_modular_exponentiation() {
+        // 4^13 mod 497 = 445, 7^560 mod 561 = 1 (561 is a Carmichael number)
+        assert_eq!(mod_pow(&[4], &[13], &[497]), vec![445]);
+        assert_eq!(mod_pow(&[7], &560u32.to_be_bytes(), &[561]), vec![1]);
+        // 2^65 mod (2^64 + 15) = 2^64 - 15, across limbs
+        let modulus = to_limbs(&[0x01, 0, 0, 0, 0, 0, 0, 0, 0x0f]);
+        assert_eq!(from_limbs(&mod_pow(&[2], &[65], &modulus), 9), vec![0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xf1]);
+    }
+
+    #[test]
+    fn test_public_key_from_spki() {
+        let key = PublicKey::from_spki(&KEY.spki_content()).unwrap().unwrap();
+        assert_eq!(key, KEY.public());
+        assert!(PublicKey::new(&[0x10], &[3]).is_err());
+    }
+}
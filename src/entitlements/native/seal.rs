@@ -0,0 +1,377 @@
+//! Bundle resource seal verification
+//!
+//! A bundle's signature seals its other files through
+//! `_CodeSignature/CodeResources`: a plist of per-file hashes (`files2`, or the
+//! legacy `files`) plus regex rules deciding which files must be sealed. The
+//! main executable's resources slot commits to the CodeResources file itself.
+//! Checking the seal reports files added, removed or modified since signing.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use anyhow::{Result, anyhow};
+use regex::Regex;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use crate::models::ResourceSealVerification;
+use super::root;
+
+/// Location of the seal relative to the sealed directory
+pub const CODE_RESOURCES_PATH: &str = "_CodeSignature/CodeResources";
+const CODE_SIGNATURE_DIR: &str = "_CodeSignature";
+
+/// The sealed directory of a bundle and the main executable it belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleSeal {
+    /// `Foo.app/Contents`, `Foo.framework/Versions/A`, or the root of a shallow bundle
+    pub root: PathBuf,
+    /// Main executable relative to `root`; sealed by the signature, not the seal
+    pub executable: String,
+}
+
+impl BundleSeal {
+    pub fn code_resources_path(&self) -> PathBuf {
+        self.root.join(CODE_RESOURCES_PATH)
+    }
+}
+
+/// Find the seal a binary is the main executable of (`.app`, `.appex`, `.xpc`,
+/// `.framework`, ...). Helpers and other nested files have no seal of their own.
+///
+/// Under a foreign `image_root`, the seal and Info.plist are read inside the image.
+pub fn find_seal(binary_path: &Path, image_root: Option<&Path>) -> Option<BundleSeal> {
+    let name = binary_path.file_name()?.to_str()?;
+    let parent = binary_path.parent()?;
+    let is_file = |path: PathBuf| root::readable(image_root, &path).is_ok_and(|path| path.is_file());
+    let (root, executable) = match parent.parent() {
+        Some(contents) if parent.file_name()? == "MacOS" && is_file(contents.join(CODE_RESOURCES_PATH)) => {
+            (contents.to_path_buf(), format!("MacOS/{}", name))
+        }
+        _ if is_file(parent.join(CODE_RESOURCES_PATH)) => (parent.to_path_buf(), name.to_string()),
+        _ => return None,
+    };
+
+    // Frameworks keep their Info.plist in Resources
+    let declared = ["Info.plist", "Resources/Info.plist"].iter().find_map(|plist| {
+        let dict: plist::Dictionary = plist::from_file(root::readable(image_root, &root.join(plist)).ok()?).ok()?;
+        dict.get("CFBundleExecutable")?.as_string().map(str::to_string)
+    });
+    if declared.is_some_and(|declared| declared != name) {
+        return None;
+    }
+    Some(BundleSeal { root, executable })
+}
+
+/// What a seal records for one path
+#[derive(Debug)]
+enum SealedEntry {
+    Hash { sha1: Option<Vec<u8>>, sha256: Option<Vec<u8>>, optional: bool },
+    Symlink { target: String, optional: bool },
+    /// Nested code (frameworks, helpers) carries its own signature
+    Nested { optional: bool },
+}
+
+/// A sealing rule: paths matching `pattern` are sealed unless omitted
+#[derive(Debug)]
+struct Rule {
+    pattern: Regex,
+    weight: f64,
+    omit: bool,
+}
+
+/// Compare the files under the sealed directory with the seal; under a
+/// foreign `image_root`, symlinks on the way to each file resolve inside the image.
+///
+/// Also returns warnings for entries naming paths outside the sealed
+/// directory (`..`, absolute paths), which are not checked.
+pub fn verify_seal(seal: &BundleSeal, code_resources: &[u8], image_root: Option<&Path>) -> Result<(ResourceSealVerification, Vec<String>)> {
+    let plist: plist::Dictionary = plist::from_bytes(code_resources)
+        .map_err(|e| anyhow!("Failed to parse CodeResources: {}", e))?;
+    let (files_key, rules_key) = if plist.contains_key("files2") { ("files2", "rules2") } else { ("files", "rules") };
+    let entries = parse_entries(plist.get(files_key).and_then(|v| v.as_dictionary()))?;
+    let rules = parse_rules(plist.get(rules_key).and_then(|v| v.as_dictionary()));
+
+    let sealed_root = root::readable(image_root, &seal.root)?;
+    let mut verification = ResourceSealVerification::default();
+    let mut warnings = Vec::new();
+    for (path, entry) in &entries {
+        if !Path::new(path).components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir)) {
+            warnings.push(format!("Ignoring CodeResources entry outside the bundle: {}", path));
+            continue;
+        }
+        let Some(full) = entry_path(&sealed_root.join(path), image_root) else {
+            if !entry_optional(entry) {
+                verification.removed.push(path.clone());
+            }
+            continue;
+        };
+        if fs::symlink_metadata(&full).is_err() {
+            if !entry_optional(entry) {
+                verification.removed.push(path.clone());
+            }
+            continue;
+        }
+        if !entry_matches(entry, &full)? {
+            verification.modified.push(path.clone());
+        }
+    }
+
+    walk_unsealed(&sealed_root, "", seal, &entries, &rules, &mut verification.added)?;
+    verification.added.sort();
+    Ok((verification, warnings))
+}
+
+fn entry_optional(entry: &SealedEntry) -> bool {
+    match entry {
+        SealedEntry::Hash { optional, .. } | SealedEntry::Symlink { optional, .. } | SealedEntry::Nested { optional } => *optional,
+    }
+}
+
+/// Host path of a sealed entry with the directories leading to it resolved,
+/// so the entry itself can be checked without following it; `None` when a
+/// directory on the way is missing
+fn entry_path(path: &Path, image_root: Option<&Path>) -> Option<PathBuf> {
+    match image_root {
+        Some(image_root) => Some(root::resolve(image_root, path.parent()?).ok()?.join(path.file_name()?)),
+        None => Some(path.to_path_buf()),
+    }
+}
+
+/// Whether a present file still matches its seal entry
+fn entry_matches(entry: &SealedEntry, path: &Path) -> Result<bool> {
+    Ok(match entry {
+        SealedEntry::Hash { sha256: Some(expected), .. } => fs::symlink_metadata(path)?.is_file() && hash_file::<Sha256>(path)? == *expected,
+        SealedEntry::Hash { sha1: Some(expected), .. } => fs::symlink_metadata(path)?.is_file() && hash_file::<Sha1>(path)? == *expected,
+        SealedEntry::Hash { .. } => true,
+        SealedEntry::Symlink { target, .. } => fs::read_link(path).is_ok_and(|actual| actual == Path::new(target)),
+        SealedEntry::Nested { .. } => true,
+    })
+}
+
+fn hash_file<D: Digest + io::Write>(path: &Path) -> Result<Vec<u8>> {
+    let mut hasher = D::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().to_vec())
+}
+
+/// Collect files under `dir` that the rules require to be sealed but the seal lacks
+fn walk_unsealed(
+    dir: &Path,
+    prefix: &str,
+    seal: &BundleSeal,
+    entries: &BTreeMap<String, SealedEntry>,
+    rules: &[Rule],
+    added: &mut Vec<String>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let relative = format!("{}{}", prefix, name);
+        if relative == CODE_SIGNATURE_DIR || relative == seal.executable {
+            continue;
+        }
+        // Sealed entries were checked above; sealed directories are nested code
+        if entries.contains_key(&relative) {
+            continue;
+        }
+
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk_unsealed(&entry.path(), &format!("{}/", relative), seal, entries, rules, added)?;
+        } else if best_rule(rules, &relative).is_some_and(|rule| !rule.omit) {
+            added.push(relative);
+        }
+    }
+    Ok(())
+}
+
+/// The highest-weight rule matching a path
+fn best_rule<'a>(rules: &'a [Rule], path: &str) -> Option<&'a Rule> {
+    rules
+        .iter()
+        .filter(|rule| rule.pattern.is_match(path))
+        .max_by(|a, b| a.weight.total_cmp(&b.weight))
+}
+
+fn parse_entries(files: Option<&plist::Dictionary>) -> Result<BTreeMap<String, SealedEntry>> {
+    let mut entries = BTreeMap::new();
+    for (path, value) in files.into_iter().flatten() {
+        let entry = match value {
+            // Legacy `files` entries are the bare SHA-1
+            plist::Value::Data(sha1) => SealedEntry::Hash { sha1: Some(sha1.clone()), sha256: None, optional: false },
+            plist::Value::Dictionary(dict) => {
+                let data = |key: &str| dict.get(key).and_then(|v| v.as_data()).map(<[u8]>::to_vec);
+                let optional = dict.get("optional").and_then(|v| v.as_boolean()).unwrap_or(false);
+                if let Some(target) = dict.get("symlink").and_then(|v| v.as_string()) {
+                    SealedEntry::Symlink { target: target.to_string(), optional }
+                } else if dict.contains_key("cdhash") || dict.contains_key("requirement") {
+                    SealedEntry::Nested { optional }
+                } else {
+                    SealedEntry::Hash { sha1: data("hash"), sha256: data("hash2"), optional }
+                }
+            }
+            _ => return Err(anyhow!("Unexpected CodeResources entry for {}", path)),
+        };
+        entries.insert(path.clone(), entry);
+    }
+    Ok(entries)
+}
+
+/// Parse the sealing rules; patterns the regex crate rejects are skipped
+fn parse_rules(rules: Option<&plist::Dictionary>) -> Vec<Rule> {
+    let mut parsed = Vec::new();
+    for (pattern, value) in rules.into_iter().flatten() {
+        let Ok(pattern) = Regex::new(pattern) else { continue };
+        let dict = value.as_dictionary();
+        let field = |key: &str| dict.and_then(|d| d.get(key));
+        parsed.push(Rule {
+            pattern,
+            weight: field("weight").and_then(|v| v.as_real().or_else(|| v.as_signed_integer().map(|i| i as f64))).unwrap_or(1.0),
+            omit: field("omit").and_then(|v| v.as_boolean()).unwrap_or(false),
+        });
+    }
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An app bundle with a sealed resource, a sealed symlink and nested code
+    fn sealed_app() -> (tempfile::TempDir, BundleSeal) {
+        let dir = tempfile::tempdir().unwrap();
+        let contents = dir.path().join("Example.app/Contents");
+        fs::create_dir_all(contents.join("MacOS")).unwrap();
+        fs::create_dir_all(contents.join("Resources")).unwrap();
+        fs::create_dir_all(contents.join("Frameworks/Kit.framework")).unwrap();
+        fs::write(contents.join("MacOS/Example"), b"binary").unwrap();
+        fs::write(contents.join("Info.plist"), b"<plist><dict><key>CFBundleExecutable</key><string>Example</string></dict></plist>").unwrap();
+        fs::write(contents.join("Resources/icon.png"), b"icon").unwrap();
+        fs::write(contents.join("Frameworks/Kit.framework/Kit"), b"kit").unwrap();
+        std::os::unix::fs::symlink("icon.png", contents.join("Resources/link.png")).unwrap();
+
+        let mut files2 = plist::Dictionary::new();
+        let mut icon = plist::Dictionary::new();
+        icon.insert("hash2".into(), plist::Value::Data(Sha256::digest(b"icon").to_vec()));
+        files2.insert("Resources/icon.png".into(), icon.into());
+        let mut link = plist::Dictionary::new();
+        link.insert("symlink".into(), "icon.png".into());
+        files2.insert("Resources/link.png".into(), link.into());
+        let mut kit = plist::Dictionary::new();
+        kit.insert("cdhash".into(), plist::Value::Data(vec![0; 20]));
+        files2.insert("Frameworks/Kit.framework".into(), kit.into());
+        let mut optional = plist::Dictionary::new();
+        optional.insert("hash2".into(), plist::Value::Data(vec![0; 32]));
+        optional.insert("optional".into(), true.into());
+        files2.insert("Resources/en.lproj/Optional.strings".into(), optional.into());
+
+        let mut rules2 = plist::Dictionary::new();
+        rules2.insert("^.*".into(), true.into());
+        let mut omit = plist::Dictionary::new();
+        omit.insert("omit".into(), true.into());
+        omit.insert("weight".into(), 20.0.into());
+        rules2.insert("^Info\\.plist$".into(), omit.clone().into());
+        rules2.insert("^(.*/)?\\.DS_Store$".into(), omit.into());
+
+        let mut seal = plist::Dictionary::new();
+        seal.insert("files2".into(), files2.into());
+        seal.insert("rules2".into(), rules2.into());
+        fs::create_dir_all(contents.join(CODE_SIGNATURE_DIR)).unwrap();
+        plist::to_file_xml(contents.join(CODE_RESOURCES_PATH), &seal).unwrap();
+
+        (dir, BundleSeal { root: contents, executable: "MacOS/Example".to_string() })
+    }
+
+    fn verify(seal: &BundleSeal) -> ResourceSealVerification {
+        let (verification, warnings) = verify_seal(seal, &fs::read(seal.code_resources_path()).unwrap(), None).unwrap();
+        assert_eq!(warnings, Vec::<String>::new());
+        verification
+    }
+
+    #[test]
+    fn test_finds_seal_of_main_executable_only() {
+        let (dir, seal) = sealed_app();
+        let macos = dir.path().join("Example.app/Contents/MacOS");
+        assert_eq!(find_seal(&macos.join("Example"), None), Some(seal));
+        assert_eq!(find_seal(&macos.join("helper"), None), None);
+        assert_eq!(find_seal(&dir.path().join("Example.app/Contents/Resources/icon.png"), None), None);
+    }
+
+    #[test]
+    fn test_untouched_bundle_is_intact() {
+        let (_dir, seal) = sealed_app();
+        fs::write(seal.root.join("Resources/.DS_Store"), b"finder").unwrap();
+        assert!(verify(&seal).is_intact(), "{:?}", verify(&seal));
+    }
+
+    #[test]
+    fn test_reports_added_removed_and_modified_files() {
+        let (_dir, seal) = sealed_app();
+        fs::write(seal.root.join("Resources/icon.png"), b"evil").unwrap();
+        fs::remove_file(seal.root.join("Resources/link.png")).unwrap();
+        std::os::unix::fs::symlink("other.png", seal.root.join("Resources/link.png")).unwrap();
+        fs::remove_dir_all(seal.root.join("Frameworks/Kit.framework")).unwrap();
+        fs::write(seal.root.join("Resources/payload.dylib"), b"payload").unwrap();
+        fs::write(seal.root.join("MacOS/Example"), b"changed").unwrap();
+
+        let verification = verify(&seal);
+        assert_eq!(verification.modified, vec!["Resources/icon.png", "Resources/link.png"]);
+        assert_eq!(verification.removed, vec!["Frameworks/Kit.framework"]);
+        assert_eq!(verification.added, vec!["Resources/payload.dylib"]);
+        assert_eq!(verification.summary(), "2 modified, 1 added, 1 removed");
+    }
+
+    #[test]
+    fn test_links_resolve_inside_a_foreign_root() {
+        let (dir, seal) = sealed_app();
+        fs::rename(seal.root.join("Resources"), dir.path().join("Shared")).unwrap();
+        std::os::unix::fs::symlink("/Shared", seal.root.join("Resources")).unwrap();
+
+        let data = fs::read(seal.code_resources_path()).unwrap();
+        let (verification, _) = verify_seal(&seal, &data, Some(dir.path())).unwrap();
+        assert_eq!(verification.removed, Vec::<String>::new());
+        assert_eq!(verification.modified, Vec::<String>::new());
+        assert_eq!(verify_seal(&seal, &data, None).unwrap().0.removed, vec!["Resources/icon.png", "Resources/link.png"]);
+    }
+
+    #[test]
+    fn test_entries_outside_the_bundle_are_ignored() {
+        let (dir, seal) = sealed_app();
+        fs::write(dir.path().join("outside"), b"secret").unwrap();
+        let mut files2 = plist::Dictionary::new();
+        for path in ["../../outside", "/etc/passwd", "Resources/../../../outside"] {
+            let mut entry = plist::Dictionary::new();
+            entry.insert("hash2".into(), plist::Value::Data(vec![0; 32]));
+            files2.insert(path.into(), entry.into());
+        }
+        let mut escaping = plist::Dictionary::new();
+        escaping.insert("files2".into(), files2.into());
+        let mut data = Vec::new();
+        plist::to_writer_xml(&mut data, &escaping).unwrap();
+
+        let (verification, warnings) = verify_seal(&seal, &data, None).unwrap();
+        assert!(verification.is_intact(), "{:?}", verification);
+        assert_eq!(warnings, vec![
+            "Ignoring CodeResources entry outside the bundle: ../../outside",
+            "Ignoring CodeResources entry outside the bundle: /etc/passwd",
+            "Ignoring CodeResources entry outside the bundle: Resources/../../../outside",
+        ]);
+    }
+
+    #[test]
+    fn test_legacy_files_hold_sha1() {
+        let (_dir, seal) = sealed_app();
+        let mut files = plist::Dictionary::new();
+        files.insert("Resources/icon.png".into(), plist::Value::Data(Sha1::digest(b"icon").to_vec()));
+        let mut legacy = plist::Dictionary::new();
+        legacy.insert("files".into(), files.into());
+        let mut data = Vec::new();
+        plist::to_writer_xml(&mut data, &legacy).unwrap();
+
+        // Without rules nothing else must be sealed
+        assert!(verify_seal(&seal, &data, None).unwrap().0.is_intact());
+        fs::write(seal.root.join("Resources/icon.png"), b"evil").unwrap();
+        assert_eq!(verify_seal(&seal, &data, None).unwrap().0.modified, vec!["Resources/icon.png"]);
+    }
+}
@@ -0,0 +1,135 @@
+//! Code signature SuperBlob decoding
+//!
+//! The embedded signature referenced by `LC_CODE_SIGNATURE` is a big-endian
+//! SuperBlob: a magic, a length and an index of `(slot, offset)` pairs, each
+//! pointing at a child blob with its own magic and length.
+
+use anyhow::{Result, anyhow, bail};
+
+/// Magic of an embedded signature SuperBlob
+pub const CSMAGIC_EMBEDDED_SIGNATURE: u32 = 0xfade0cc0;
+/// Magic of an XML entitlements blob
+pub const CSMAGIC_EMBEDDED_ENTITLEMENTS: u32 = 0xfade7171;
+/// Magic of a DER entitlements blob
+pub const CSMAGIC_EMBEDDED_DER_ENTITLEMENTS: u32 = 0xfade7172;
+
+/// Slot holding the XML entitlements plist
+pub const CSSLOT_ENTITLEMENTS: u32 = 5;
+/// Slot holding the DER-encoded entitlements
+pub const CSSLOT_DER_ENTITLEMENTS: u32 = 7;
+
+/// Size of a blob header (magic + length)
+const BLOB_HEADER_SIZE: usize = 8;
+
+/// Read a big-endian u32 at `offset`
+pub fn be_u32(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .and_then(|b| b.try_into().ok())
+        .map(u32::from_be_bytes)
+        .ok_or_else(|| anyhow!("Truncated signature data at offset {}", offset))
+}
+
+/// A child blob of a SuperBlob
+#[derive(Debug, Clone, Copy)]
+pub struct Blob<'a> {
+    pub magic: u32,
+    /// Full blob bytes, header included
+    pub data: &'a [u8],
+}
+
+impl<'a> Blob<'a> {
+    /// Blob contents after the magic/length header
+    pub fn payload(&self) -> &'a [u8] {
+        &self.data[BLOB_HEADER_SIZE..]
+    }
+}
+
+/// A parsed embedded signature SuperBlob
+#[derive(Debug, Clone)]
+pub struct SuperBlob<'a> {
+    entries: Vec<(u32, Blob<'a>)>,
+}
+
+impl<'a> SuperBlob<'a> {
+    /// Parse a SuperBlob, validating every index entry against its bounds
+    pub fn parse(data: &'a [u8]) -> Result<Self> {
+        let magic = be_u32(data, 0)?;
+        if magic != CSMAGIC_EMBEDDED_SIGNATURE {
+            bail!("Unexpected code signature magic {:#x}", magic);
+        }
+        let length = be_u32(data, 4)? as usize;
+        if length > data.len() || length < 12 {
+            bail!("Code signature length {} is out of range", length);
+        }
+        let data = &data[..length];
+        let count = be_u32(data, 8)? as usize;
+
+        let mut entries = Vec::with_capacity(count.min(64));
+        for index in 0..count {
+            let slot = be_u32(data, 12 + index * 8)?;
+            let offset = be_u32(data, 16 + index * 8)? as usize;
+            entries.push((slot, blob_at(data, offset)?));
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Blob stored in the given slot
+    pub fn slot(&self, slot: u32) -> Option<Blob<'a>> {
+        self.entries.iter().find(|(s, _)| *s == slot).map(|(_, blob)| *blob)
+    }
+}
+
+/// Read the blob starting at `offset`, bounded by its own length field
+fn blob_at(data: &[u8], offset: usize) -> Result<Blob<'_>> {
+    let magic = be_u32(data, offset)?;
+    let length = be_u32(data, offset + 4)? as usize;
+    let end = offset
+        .checked_add(length)
+        .filter(|end| length >= BLOB_HEADER_SIZE && *end <= data.len())
+        .ok_or_else(|| anyhow!("Blob {:#x} at offset {} has invalid length {}", magic, offset, length))?;
+    Ok(Blob { magic, data: &data[offset..end] })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entitlements::native::fixtures;
+
+    #[test]
+    fn test_parse_finds_slots() {
+        let data = fixtures::superblob(&[
+            (0, fixtures::blob(0xfade0c02, &[0u8; 16])),
+            (CSSLOT_ENTITLEMENTS, fixtures::blob(CSMAGIC_EMBEDDED_ENTITLEMENTS, b"<plist/>")),
+        ]);
+        let superblob = SuperBlob::parse(&data).unwrap();
+
+        let entitlements = superblob.slot(CSSLOT_ENTITLEMENTS).unwrap();
+        assert_eq!(entitlements.magic, CSMAGIC_EMBEDDED_ENTITLEMENTS);
+        assert_eq!(entitlements.payload(), b"<plist/>");
+        assert!(superblob.slot(2).is_none());
+    }
+
+    #[test]
+    fn test_parse_rejects_wrong_magic() {
+        let mut data = fixtures::superblob(&[]);
+        data[3] = 0x00;
+        assert!(SuperBlob::parse(&data).is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_blob_past_end() {
+        let mut data = fixtures::superblob(&[(CSSLOT_ENTITLEMENTS, fixtures::blob(CSMAGIC_EMBEDDED_ENTITLEMENTS, b"<plist/>"))]);
+        // Inflate the child blob length beyond the SuperBlob
+        let blob_offset = be_u32(&data, 16).unwrap() as usize;
+        data[blob_offset + 4..blob_offset + 8].copy_from_slice(&0x1000u32.to_be_bytes());
+        assert!(SuperBlob::parse(&data).is_err());
+    }
+
+    #[test]
+    fn test_parse_ignores_trailing_padding() {
+        let mut data = fixtures::superblob(&[]);
+        data.extend_from_slice(&[0u8; 32]);
+        assert!(SuperBlob::parse(&data).is_ok());
+    }
+}
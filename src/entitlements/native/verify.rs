@@ -0,0 +1,265 @@
+//! Code signature hash verification
+//!
+//! Recomputes the hashes every CodeDirectory commits to: one per page of code
+//! up to `codeLimit`, plus the special slots covering the other signature
+//! components (requirements, entitlements, Info.plist, ...). The CMS signature
+//! over the CodeDirectory itself is not checked, so this detects modified
+//! binaries but not a re-signed forgery.
+
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+use anyhow::{Result, bail};
+use crate::models::SignatureVerification;
+use super::code_directory::{self, CodeDirectory};
+use super::superblob::SuperBlob;
+
+/// Largest page size accepted, 2^16 bytes; Apple's tools use 2^12 or 2^14
+const MAX_PAGE_SHIFT: u8 = 16;
+
+/// Special slots whose contents live outside the signature
+const CSSLOT_INFOSLOT: u32 = 1;
+const CSSLOT_RESOURCEDIR: u32 = 3;
+
+/// Names of the special slots, indexed by slot number
+const SPECIAL_SLOT_NAMES: &[&str] = &[
+    "",
+    "info-plist",
+    "requirements",
+    "resources",
+    "application",
+    "entitlements",
+    "rep-specific",
+    "der-entitlements",
+    "launch-constraint-self",
+    "launch-constraint-parent",
+    "launch-constraint-responsible",
+    "library-constraint",
+];
+
+/// Verify the page and special-slot hashes of a signed slice
+///
+/// `info_plist` is the bundle's Info.plist, when the binary lives in one; the
+/// resource seal is not checked here.
+pub fn verify_signature<R: Read + Seek>(
+    reader: &mut R,
+    slice_offset: u64,
+    slice_size: u64,
+    superblob: &SuperBlob,
+    info_plist: Option<&[u8]>,
+) -> Result<SignatureVerification> {
+    let code_directories = code_directory::code_directories(superblob)?;
+    if code_directories.is_empty() {
+        bail!("Signature has no CodeDirectory");
+    }
+
+    let mut verification = SignatureVerification { valid: true, first_invalid_page: None, invalid_special_slots: Vec::new() };
+    for cd in &code_directories {
+        for name in invalid_special_slots(cd, superblob, info_plist)? {
+            if !verification.invalid_special_slots.contains(&name) {
+                verification.invalid_special_slots.push(name);
+            }
+        }
+        if let Some(page) = first_invalid_page(reader, slice_offset, slice_size, cd)? {
+            verification.first_invalid_page = Some(verification.first_invalid_page.map_or(page, |p| p.min(page)));
+        }
+    }
+
+    verification.valid = verification.first_invalid_page.is_none() && verification.invalid_special_slots.is_empty();
+    Ok(verification)
+}
+
+/// Whether the signature's resources slot commits to these `CodeResources` bytes.
+/// Every CodeDirectory with a resources slot must match, and at least one must have it.
+pub fn resources_match(superblob: &SuperBlob, code_resources: &[u8]) -> Result<bool> {
+    let mut sealed = false;
+    for cd in code_directory::code_directories(superblob)? {
+        if cd.n_special_slots < CSSLOT_RESOURCEDIR {
+            continue;
+        }
+        let expected = cd.special_slot(CSSLOT_RESOURCEDIR)?;
+        if expected.iter().all(|b| *b == 0) {
+            continue;
+        }
+        if cd.hash(code_resources)? != expected {
+            return Ok(false);
+        }
+        sealed = true;
+    }
+    Ok(sealed)
+}
+
+/// Names of the special slots whose hash does not match their contents
+fn invalid_special_slots(cd: &CodeDirectory, superblob: &SuperBlob, info_plist: Option<&[u8]>) -> Result<Vec<String>> {
+    let mut invalid = Vec::new();
+    for slot in 1..=cd.n_special_slots {
+        let expected = cd.special_slot(slot)?;
+        let unused = expected.iter().all(|b| *b == 0);
+        let contents = match slot {
+            CSSLOT_INFOSLOT => match info_plist {
+                Some(data) => Some(data),
+                // No bundle to compare against
+                None => continue,
+            },
+            CSSLOT_RESOURCEDIR => continue,
+            _ => superblob.slot(slot).map(|blob| blob.data),
+        };
+
+        let matches = match contents {
+            Some(data) => cd.hash(data)? == expected,
+            None => unused,
+        };
+        if !matches {
+            let name = SPECIAL_SLOT_NAMES.get(slot as usize).map_or_else(|| format!("slot-{}", slot), |n| n.to_string());
+            invalid.push(name);
+        }
+    }
+    Ok(invalid)
+}
+
+/// Index of the first code page whose hash does not match, if any
+///
+/// The page size and code limit are checked against the slice before anything
+/// is allocated, so a crafted CodeDirectory cannot request more memory than the
+/// slice itself holds.
+fn first_invalid_page<R: Read + Seek>(reader: &mut R, slice_offset: u64, slice_size: u64, cd: &CodeDirectory) -> Result<Option<u64>> {
+    if cd.code_limit > slice_size {
+        bail!("CodeDirectory code limit {} exceeds the {}-byte slice", cd.code_limit, slice_size);
+    }
+    let page_size = if cd.page_shift == 0 {
+        cd.code_limit
+    } else if cd.page_shift <= MAX_PAGE_SHIFT {
+        1u64 << cd.page_shift
+    } else {
+        bail!("CodeDirectory page size 2^{} is out of range", cd.page_shift);
+    };
+
+    let mut buffer = vec![0u8; page_size.min(cd.code_limit) as usize];
+    for index in 0..cd.n_code_slots {
+        let start = u64::from(index) * page_size;
+        if start >= cd.code_limit {
+            bail!("CodeDirectory has more code slots than pages");
+        }
+        let page = &mut buffer[..page_size.min(cd.code_limit - start) as usize];
+
+        reader.seek(SeekFrom::Start(slice_offset + start))?;
+        match reader.read_exact(page) {
+            Ok(()) => {}
+            // A truncated file cannot match the hashes of the missing pages
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(Some(u64::from(index))),
+            Err(e) => return Err(e.into()),
+        }
+        if cd.hash(page)? != cd.code_slot(index)? {
+            return Ok(Some(u64::from(index)));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use crate::entitlements::native::fixtures;
+    use crate::entitlements::native::macho::{self, MachOHeader, CPU_TYPE_ARM64};
+    use crate::entitlements::native::superblob::{CSMAGIC_EMBEDDED_ENTITLEMENTS, CSSLOT_ENTITLEMENTS};
+
+    /// A signed image whose CodeDirectory hashes its code and entitlements with 64-byte pages
+    fn signed_image(hash_type: u8) -> Vec<u8> {
+        let entitlements = fixtures::blob(CSMAGIC_EMBEDDED_ENTITLEMENTS, &fixtures::entitlements_plist(&["com.apple.security.app-sandbox"]));
+        fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0).build_with_signature(|code| {
+            let cd = fixtures::CodeDirectoryBuilder::new("com.example.tool")
+                .hash_type(hash_type)
+                .code(code, 6)
+                .special_slot(CSSLOT_ENTITLEMENTS, &entitlements)
+                .build();
+            fixtures::superblob(&[(0, cd), (CSSLOT_ENTITLEMENTS, entitlements.clone())])
+        })
+    }
+
+    fn verify(image: &[u8]) -> SignatureVerification {
+        let mut reader = Cursor::new(image.to_vec());
+        let slices = macho::read_slices(&mut reader).unwrap();
+        let header = MachOHeader::read(&mut reader, &slices[0]).unwrap();
+        let signature = header.read_code_signature(&mut reader).unwrap().unwrap();
+        let superblob = SuperBlob::parse(&signature).unwrap();
+        verify_signature(&mut reader, header.slice_offset, slices[0].size, &superblob, None).unwrap()
+    }
+
+    #[test]
+    fn test_untouched_binary_verifies() {
+        for hash_type in [code_directory::CS_HASHTYPE_SHA1, code_directory::CS_HASHTYPE_SHA256, code_directory::CS_HASHTYPE_SHA384] {
+            let verification = verify(&signed_image(hash_type));
+            assert!(verification.valid, "hash type {}: {:?}", hash_type, verification);
+        }
+    }
+
+    #[test]
+    fn test_modified_page_is_reported() {
+        let mut image = signed_image(code_directory::CS_HASHTYPE_SHA256);
+        // Patch a byte in the second 64-byte page
+        image[70] ^= 0xff;
+        let verification = verify(&image);
+
+        assert!(!verification.valid);
+        assert_eq!(verification.first_invalid_page, Some(1));
+        assert!(verification.invalid_special_slots.is_empty());
+    }
+
+    #[test]
+    fn test_modified_entitlements_are_reported() {
+        let mut image = signed_image(code_directory::CS_HASHTYPE_SHA256);
+        let needle = b"app-sandbox";
+        let position = image.windows(needle.len()).position(|w| w == needle).unwrap();
+        image[position] = b'A';
+        let verification = verify(&image);
+
+        assert!(!verification.valid);
+        assert_eq!(verification.first_invalid_page, None);
+        assert_eq!(verification.invalid_special_slots, vec!["entitlements"]);
+    }
+
+    #[test]
+    fn test_out_of_range_page_geometry_is_rejected() {
+        // Offsets of codeLimit and pageSize in the CodeDirectory header
+        let patched = |offset: usize, bytes: &[u8]| {
+            let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0).build_with_signature(|code| {
+                let mut cd = fixtures::CodeDirectoryBuilder::new("com.example.tool").code(code, 6).build();
+                cd[offset..offset + bytes.len()].copy_from_slice(bytes);
+                fixtures::superblob(&[(0, cd)])
+            });
+            let mut reader = Cursor::new(image);
+            let slices = macho::read_slices(&mut reader).unwrap();
+            let header = MachOHeader::read(&mut reader, &slices[0]).unwrap();
+            let signature = header.read_code_signature(&mut reader).unwrap().unwrap();
+            let superblob = SuperBlob::parse(&signature).unwrap();
+            verify_signature(&mut reader, 0, slices[0].size, &superblob, None).unwrap_err().to_string()
+        };
+
+        assert!(patched(32, &u32::MAX.to_be_bytes()).contains("exceeds"), "code limit past the slice");
+        assert!(patched(39, &[31]).contains("out of range"), "2 GiB pages");
+    }
+
+    #[test]
+    fn test_info_plist_slot() {
+        let info_plist = b"<plist><dict/></plist>".to_vec();
+        let image = fixtures::MachOBuilder::new(CPU_TYPE_ARM64, 0).build_with_signature(|code| {
+            let cd = fixtures::CodeDirectoryBuilder::new("com.example.app")
+                .code(code, 12)
+                .special_slot(CSSLOT_INFOSLOT, &info_plist)
+                .build();
+            fixtures::superblob(&[(0, cd)])
+        });
+
+        let mut reader = Cursor::new(image);
+        let slices = macho::read_slices(&mut reader).unwrap();
+        let header = MachOHeader::read(&mut reader, &slices[0]).unwrap();
+        let signature = header.read_code_signature(&mut reader).unwrap().unwrap();
+        let superblob = SuperBlob::parse(&signature).unwrap();
+
+        let unchecked = verify_signature(&mut reader, 0, slices[0].size, &superblob, None).unwrap();
+        assert!(unchecked.valid, "Info.plist is skipped outside a bundle");
+        let good = verify_signature(&mut reader, 0, slices[0].size, &superblob, Some(&info_plist)).unwrap();
+        assert!(good.valid);
+        let bad = verify_signature(&mut reader, 0, slices[0].size, &superblob, Some(b"<plist/>")).unwrap();
+        assert_eq!(bad.invalid_special_slots, vec!["info-plist"]);
+    }
+}
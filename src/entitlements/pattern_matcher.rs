@@ -4,6 +4,7 @@
 //! Supports both exact string matching (for backwards compatibility) and glob 
 //! pattern matching with auto-detection based on pattern characters.
 
+use std::collections::HashMap;
 use glob::Pattern;
 use anyhow::{Result, anyhow};
 
@@ -51,6 +52,21 @@ pub fn entitlements_match_filters(entitlements: &[String], filters: &[String]) -
     })
 }
 
+/// Keep only the entitlements matching any of the filters (all of them if no filters are given)
+pub fn filter_entitlements(
+    entitlements: HashMap<String, serde_json::Value>,
+    filters: &[String],
+) -> HashMap<String, serde_json::Value> {
+    if filters.is_empty() {
+        return entitlements;
+    }
+
+    entitlements
+        .into_iter()
+        .filter(|(key, _)| filters.iter().any(|filter| matches_entitlement_filter(key, filter)))
+        .collect()
+}
+
 /// Validate that all filters are syntactically correct glob patterns
 pub fn validate_entitlement_filters(filters: &[String]) -> Result<()> {
     for filter in filters {
@@ -150,6 +166,26 @@ mod tests {
         assert!(!entitlements_match_filters(&empty_entitlements, &filters));
     }
 
+    #[test]
+    fn test_filter_entitlements() {
+        let entitlements: HashMap<String, serde_json::Value> = [
+            "com.apple.security.network.client",
+            "com.apple.security.app-sandbox",
+            "com.apple.private.something",
+        ]
+        .iter()
+        .map(|k| (k.to_string(), serde_json::Value::Bool(true)))
+        .collect();
+
+        // No filters keeps everything
+        assert_eq!(filter_entitlements(entitlements.clone(), &[]).len(), 3);
+
+        // Only matching keys survive
+        let filtered = filter_entitlements(entitlements, &["com.apple.security.*".to_string()]);
+        assert_eq!(filtered.len(), 2);
+        assert!(!filtered.contains_key("com.apple.private.something"));
+    }
+
     #[test]
     fn test_validate_entitlement_filters() {
         // Valid exact filters
@@ -12,8 +12,8 @@ mod constants;
 use anyhow::{Result, Context};
 use std::time::Instant;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::Arc;
-use rayon::prelude::*;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use crate::constants::{APP_SUBSYSTEM, OS_ERROR_PERMISSION, PERMISSION_DENIED, LOG_COMMAND, LOG_STYLE, LOG_JSON_SEPARATOR, LAUNCHD_DAEMONS_DIR, LAUNCHD_PLIST_NAME};
 
 fn main() {
@@ -21,8 +21,8 @@ fn main() {
     let result = (|| -> Result<()> {
         match cli::get_execution_mode()? {
             cli::ExecutionMode::Scan(args) => run_scan_mode(args),
-            cli::ExecutionMode::Monitor { path, entitlement, interval, json, quiet } => {
-                run_monitor_mode(path, entitlement, interval, json, quiet)
+            cli::ExecutionMode::Monitor { path, entitlement, interval, backend, json, quiet } => {
+                run_monitor_mode(path, entitlement, interval, backend, json, quiet)
             }
             cli::ExecutionMode::Daemon(action) => run_daemon_command(action),
         }
@@ -45,6 +45,29 @@ fn main() {
 
 fn run_scan_mode(args: cli::Args) -> Result<()> {
     let config = cli::parse_args_from(args)?;
+    let read_options = entitlements::native::ReadOptions {
+        verify: config.verify_signatures,
+        hardening: config.report_hardening,
+        verify_resources: config.verify_resources,
+        root: config.root.clone(),
+        loading_executable: Some(scan::bundles::loading_executable),
+        ..Default::default()
+    };
+    // Replayed fixtures are already a record of earlier extractions
+    let cache = if config.use_cache && !matches!(config.backend, models::ExtractorBackend::Fixture(_)) {
+        match scan::cache::ExtractionCache::default_dir() {
+            Ok(dir) => Some(scan::cache::ExtractionCache::open(&dir, &config.backend, &read_options, config.rebuild_cache)),
+            Err(e) => {
+                if !config.quiet_mode {
+                    eprintln!("Warning: Extraction cache disabled: {}", e);
+                }
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let extractor = entitlements::extractor::create_extractor(&config.backend, read_options)?;
 
     // Set up interrupt handling using signal-hook
     let interrupted = Arc::new(AtomicBool::new(false));
@@ -55,215 +78,378 @@ fn run_scan_mode(args: cli::Args) -> Result<()> {
 
     let start_time = Instant::now();
 
-    // Progress indicator for animated scanning
-    let mut progress = if !config.quiet_mode {
-        Some(output::progress::ScanProgress::new())
+    // Progress indicator for animated scanning; its total grows as directories are listed
+    let progress = Mutex::new(if !config.quiet_mode {
+        let mut progress = output::progress::ScanProgress::new();
+        progress.start_scanning();
+        Some(progress)
     } else {
         None
+    });
+
+    let visitor = ScanVisitor {
+        config: &config,
+        extractor: extractor.as_ref(),
+        cache: cache.as_ref(),
+        interrupted: &interrupted,
+        progress: &progress,
+        scanned: AtomicUsize::new(0),
+        matched: AtomicUsize::new(0),
+        skipped_unreadable: AtomicUsize::new(0),
+        held_entitlements: Mutex::new(HashMap::new()),
+        results: Mutex::new(Vec::new()),
+        packages: Mutex::new(Vec::new()),
+        streamed: Mutex::new(std::collections::HashSet::new()),
     };
 
-    // Fast count total files (like find command) with interrupt support
-    let total_files = scan::count_total_files_with_interrupt(&config.scan_paths, &interrupted)
-        .context("Failed to count total files")?;
+    // Walk the scan paths once, extracting each binary on the rayon pool as soon as it is found
+    let walk_options = scan::walk::WalkOptions {
+        root: config.root.clone(),
+        follow_symlinks: config.follow_symlinks,
+        one_file_system: config.one_file_system,
+        exclude: scan::filter::PathFilter::from_patterns(&config.exclude),
+        include: scan::filter::PathFilter::from_patterns(&config.include),
+        expand_archives: config.expand_archives,
+    };
+    let aliases = scan::walk::walk(&config.scan_paths, &walk_options, &interrupted, &visitor);
 
-    // Check if interrupted during counting
-    if interrupted.load(Ordering::Relaxed) {
-        return Ok(());
+    let ScanVisitor { scanned, matched, skipped_unreadable, held_entitlements, results, packages, streamed, .. } = visitor;
+    if let Some(mut progress) = progress.into_inner().unwrap() {
+        progress.complete_scanning();
     }
 
-    // Start progress with total file count
-    if let Some(ref mut progress) = progress {
-        progress.start_scanning(total_files);
+    // Nothing to report when interrupted before any binary was read
+    if interrupted.load(Ordering::Relaxed) && scanned.load(Ordering::Relaxed) == 0 {
+        return Ok(());
     }
 
-    // ========== PHASE 1: Collect all binaries (sequential, fast) ==========
-    let mut discovered_binaries = Vec::new();
-    let mut skipped_count = 0usize;
+    let duration_ms = start_time.elapsed().as_millis() as u64;
+    let was_interrupted = interrupted.load(Ordering::Relaxed);
+    let summary = models::ScanSummary {
+        scanned: scanned.load(Ordering::Relaxed),
+        matched: matched.load(Ordering::Relaxed),
+        skipped_unreadable: skipped_unreadable.load(Ordering::Relaxed),
+        duration_ms,
+        interrupted: if was_interrupted { Some(true) } else { None },
+        cache_hits: cache.as_ref().map(|cache| cache.hits()),
+        cache_misses: cache.as_ref().map(|cache| cache.misses()),
+    };
 
-    for path_str in &config.scan_paths {
-        let path = std::path::Path::new(path_str);
-        if path.exists() {
-            // Update progress to show current top-level directory
-            if let Some(ref mut progress) = progress {
-                progress.set_current_directory(path);
-            }
-
-            if path.is_file() {
-                if let Some(binary) = scan::check_single_file(path) {
-                    discovered_binaries.push(binary);
-                    if let Some(ref mut progress) = progress {
-                        progress.increment_scanned();
-                    }
-                } else {
-                    skipped_count += 1;
-                    if let Some(ref mut progress) = progress {
-                        progress.increment_skipped();
-                    }
-                }
-            } else {
-                collect_binaries_from_directory(
-                    path,
-                    &mut discovered_binaries,
-                    &mut skipped_count,
-                    &mut progress,
-                    &interrupted
-                )?;
+    if let Some(cache) = cache {
+        if let Err(e) = cache.save() {
+            if !config.quiet_mode {
+                eprintln!("Warning: Could not save extraction cache: {:#}", e);
             }
         }
+    }
 
-        if interrupted.load(Ordering::Relaxed) {
-            break;
-        }
+    // Results and packages have already been written; a reader that went away needs no summary
+    if config.output_format == models::OutputFormat::Ndjson {
+        let streamed = streamed.into_inner().unwrap();
+        let written = alias_groups(aliases, &streamed, config.root.as_deref())
+            .iter()
+            .try_for_each(|group| output::write_record(&models::ScanRecord::Aliases(group)));
+        return match written.and_then(|_| output::write_record(&models::ScanRecord::Summary(&summary))) {
+            Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(e.into()),
+            _ => Ok(()),
+        };
     }
 
-    // Complete progress indicator after discovery phase
-    if let Some(mut progress) = progress {
-        progress.complete_scanning();
+    let extractor_ref = extractor.as_ref();
+    let mut packages = packages.into_inner().unwrap();
+
+    // Sort results by path for deterministic output
+    let mut results = results.into_inner().unwrap();
+    let mut held_entitlements = held_entitlements.into_inner().unwrap();
+    merge_aliases(&mut results, aliases, &mut held_entitlements);
+    results.sort_by(|a, b| a.path.cmp(&b.path));
+    scan::bundles::annotate(&mut results, &held_entitlements, extractor_ref, config.root.as_deref());
+    if config.group_bundles {
+        scan::bundles::tree_order(&mut results);
+    }
+    if let Some(root) = &config.root {
+        report_image_paths(root, &mut results, &mut packages);
     }
 
-    // Check if interrupted during discovery
-    let was_interrupted_early = interrupted.load(Ordering::Relaxed);
-    if was_interrupted_early && discovered_binaries.is_empty() {
-        return Ok(());
+    let output = models::EntitlementScanOutput { results, packages, summary };
+
+    if config.output_format == models::OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else if config.group_bundles {
+        output::format_bundles(&output)?;
+    } else if config.scan_paths.len() == 1
+        && std::path::Path::new(&config.scan_paths[0]).is_file()
+        && !scan::archive::is_archive(std::path::Path::new(&config.scan_paths[0]))
+        && !scan::package::is_package(std::path::Path::new(&config.scan_paths[0]))
+    {
+        // A single file gets the full signature breakdown
+        output::format_detail(&output)?;
+    } else {
+        output::format_human(&output)?;
     }
 
-    // ========== PHASE 2: Extract entitlements in parallel (slow part) ==========
-    let scanned = AtomicUsize::new(0);
-    let matched = AtomicUsize::new(0);
-    let skipped_unreadable = AtomicUsize::new(0);
-    let config_ref = &config;
-    let interrupted_ref = &interrupted;
-
-    // Process binaries in parallel using rayon
-    let results: Vec<models::BinaryResult> = discovered_binaries
-        .par_iter()
-        .filter_map(|binary| {
-            // Check for interruption
-            if interrupted_ref.load(Ordering::Relaxed) {
-                return None;
-            }
+    Ok(())
+}
 
-            scanned.fetch_add(1, Ordering::Relaxed);
+/// Extracts, filters and collects (or, for `--format ndjson`, writes) binaries as the walk finds them
+struct ScanVisitor<'a> {
+    config: &'a models::ScanConfig,
+    extractor: &'a dyn entitlements::extractor::EntitlementExtractor,
+    cache: Option<&'a scan::cache::ExtractionCache>,
+    interrupted: &'a AtomicBool,
+    progress: &'a Mutex<Option<output::progress::ScanProgress>>,
+    scanned: AtomicUsize,
+    matched: AtomicUsize,
+    skipped_unreadable: AtomicUsize,
+    /// Unfiltered entitlements of every binary read, to compare helpers with their host app
+    held_entitlements: Mutex<HashMap<std::path::PathBuf, Vec<String>>>,
+    results: Mutex<Vec<models::BinaryResult>>,
+    packages: Mutex<Vec<models::PackageReport>>,
+    /// Paths of the results already written with `--format ndjson`, whose aliases follow them
+    streamed: Mutex<std::collections::HashSet<std::path::PathBuf>>,
+}
+
+impl ScanVisitor<'_> {
+    /// Write a streamed record; once stdout is closed there is no one left to scan for
+    fn write(&self, record: &models::ScanRecord) {
+        if output::write_record(record).is_err() {
+            self.interrupted.store(true, Ordering::Relaxed);
+        }
+    }
 
-            match entitlements::extract_entitlements(&binary.path) {
-                Ok(entitlement_map) => {
-                    let entitlement_keys: Vec<String> = entitlement_map.keys().cloned().collect();
+    /// Extract a binary's entitlements; `None` when unreadable or filtered out
+    fn extract(&self, binary: scan::DiscoveredBinary) -> Option<models::BinaryResult> {
+        // Check for interruption
+        if self.interrupted.load(Ordering::Relaxed) {
+            return None;
+        }
 
-                    if entitlements::pattern_matcher::entitlements_match_filters(
-                        &entitlement_keys,
-                        &config_ref.filters.entitlements
-                    ) {
-                        let filtered_entitlements = if config_ref.filters.entitlements.is_empty() {
-                            entitlement_map
-                        } else {
-                            entitlement_map.into_iter()
-                                .filter(|(key, _)| {
-                                    config_ref.filters.entitlements.iter().any(|filter| {
-                                        entitlements::pattern_matcher::matches_entitlement_filter(key, filter)
-                                    })
-                                })
-                                .collect()
-                        };
-
-                        matched.fetch_add(1, Ordering::Relaxed);
-                        Some(models::BinaryResult {
-                            path: binary.path.to_string_lossy().to_string(),
-                            entitlement_count: filtered_entitlements.len(),
-                            entitlements: filtered_entitlements,
+        self.scanned.fetch_add(1, Ordering::Relaxed);
+
+        let extracted = match (&binary.contents, self.cache) {
+            (Some(contents), _) => self.extractor.extract_member(&binary.path, contents),
+            (None, Some(cache)) => {
+                let source = binary.source.as_deref().unwrap_or(&binary.path);
+                cache.get_or_extract(source, || self.extractor.extract(source))
+            }
+            (None, None) => self.extractor.extract(binary.source.as_deref().unwrap_or(&binary.path)),
+        };
+        match extracted {
+            Ok(report) => {
+                self.held_entitlements.lock().unwrap().insert(
+                    binary.path.clone(),
+                    scan::bundles::held_entitlements(&report.entitlements),
+                );
+
+                // Match against every slice so single-architecture entitlements are not missed
+                let entitlement_keys = report.all_entitlement_keys();
+
+                if entitlements::pattern_matcher::entitlements_match_filters(
+                    &entitlement_keys,
+                    &self.config.filters.entitlements
+                ) && self.config.filters.matches_signer(report.signer.as_ref())
+                    && self.config.filters.matches_constraints(&report)
+                    && self.config.filters.matches_build(&report.build_versions)
+                {
+                    let filters = &self.config.filters.entitlements;
+                    let slices_disagree = report.slices_disagree();
+                    let filtered_entitlements =
+                        entitlements::pattern_matcher::filter_entitlements(report.entitlements, filters);
+                    let slices = report.slices.into_iter()
+                        .map(|mut slice| {
+                            slice.entitlements =
+                                entitlements::pattern_matcher::filter_entitlements(slice.entitlements, filters);
+                            slice.entitlement_count = slice.entitlements.len();
+                            slice
                         })
-                    } else {
-                        None
-                    }
-                },
-                Err(_) => {
-                    skipped_unreadable.fetch_add(1, Ordering::Relaxed);
+                        .collect();
+
+                    self.matched.fetch_add(1, Ordering::Relaxed);
+                    Some(models::BinaryResult {
+                        path: binary.path.to_string_lossy().to_string(),
+                        entitlement_count: filtered_entitlements.len(),
+                        entitlements: filtered_entitlements,
+                        warnings: report.warnings,
+                        slices,
+                        slices_disagree,
+                        code_directory: report.code_directory,
+                        signer: report.signer,
+                        designated_requirement: report.designated_requirement,
+                        constraints: report.constraints,
+                        signature_valid: report.verification.as_ref().map(|v| v.valid),
+                        first_invalid_page: report.verification.as_ref().and_then(|v| v.first_invalid_page),
+                        invalid_special_slots: report.verification.map(|v| v.invalid_special_slots).unwrap_or_default(),
+                        bundle_info: report.bundle_info,
+                        provisioning_profile: report.provisioning_profile,
+                        dylibs: report.dylibs,
+                        rpaths: report.rpaths,
+                        dylib_hijack_risks: report.dylib_hijack_risks,
+                        hardening: report.hardening,
+                        build_versions: report.build_versions,
+                        source: report.source,
+                        resource_seal: report.resource_seal,
+                        ..Default::default()
+                    })
+                } else {
                     None
                 }
+            },
+            Err(_) => {
+                self.skipped_unreadable.fetch_add(1, Ordering::Relaxed);
+                None
             }
-        })
-        .collect();
-
-    // Sort results by path for deterministic output
-    let mut results = results;
-    results.sort_by(|a, b| a.path.cmp(&b.path));
-
-    let duration_ms = start_time.elapsed().as_millis() as u64;
-    let was_interrupted = interrupted.load(Ordering::Relaxed);
+        }
+    }
+}
 
-    let output = models::EntitlementScanOutput {
-        results,
-        summary: models::ScanSummary {
-            scanned: scanned.load(Ordering::Relaxed),
-            matched: matched.load(Ordering::Relaxed),
-            skipped_unreadable: skipped_unreadable.load(Ordering::Relaxed),
-            duration_ms,
-            interrupted: if was_interrupted { Some(true) } else { None },
-        },
-    };
+impl scan::walk::WalkVisitor for ScanVisitor<'_> {
+    fn entering(&self, path: &std::path::Path) {
+        if let Some(ref mut progress) = *self.progress.lock().unwrap() {
+            progress.set_current_directory(path);
+        }
+    }
 
-    if config.json_output {
-        println!("{}", serde_json::to_string_pretty(&output)?);
-    } else {
-        output::format_human(&output)?;
+    fn files_found(&self, count: usize) {
+        if let Some(ref mut progress) = *self.progress.lock().unwrap() {
+            progress.add_files(count);
+        }
     }
 
-    Ok(())
-}
+    fn binary(&self, binary: scan::DiscoveredBinary) {
+        let Some(mut result) = self.extract(binary) else {
+            return;
+        };
+        if self.config.output_format != models::OutputFormat::Ndjson {
+            self.results.lock().unwrap().push(result);
+            return;
+        }
 
-/// Collect all binaries from a directory recursively (Phase 1 - fast)
-fn collect_binaries_from_directory(
-    dir_path: &std::path::Path,
-    binaries: &mut Vec<scan::DiscoveredBinary>,
-    skipped: &mut usize,
-    progress: &mut Option<output::progress::ScanProgress>,
-    interrupted: &Arc<AtomicBool>,
-) -> Result<()> {
-    use std::fs;
+        // Streamed results are annotated with the hosts read so far and carry no aliases,
+        // as other paths to the same file may only be reached later; those are written
+        // after the walk. Only the entries needed are copied out of the lock, so reading
+        // an unscanned host blocks no one.
+        let root = self.config.root.as_deref();
+        let path = std::path::PathBuf::from(&result.path);
+        self.streamed.lock().unwrap().insert(path.clone());
+        let host = scan::bundles::host_executable(
+            &path,
+            &|candidate| self.held_entitlements.lock().unwrap().contains_key(candidate),
+            root,
+        );
+        let held: HashMap<std::path::PathBuf, Vec<String>> = {
+            let held_entitlements = self.held_entitlements.lock().unwrap();
+            [Some(path), host]
+                .into_iter()
+                .flatten()
+                .filter_map(|path| Some((path.clone(), held_entitlements.get(&path)?.clone())))
+                .collect()
+        };
+        scan::bundles::annotate(std::slice::from_mut(&mut result), &held, self.extractor, root);
+        if let Some(root) = &self.config.root {
+            report_image_paths(root, std::slice::from_mut(&mut result), &mut []);
+        }
+        self.write(&models::ScanRecord::Result(&result));
+    }
 
-    let entries = match fs::read_dir(dir_path) {
-        Ok(entries) => entries,
-        Err(_) => return Ok(()), // Skip unreadable directories silently
-    };
+    fn package(&self, mut package: models::PackageReport) {
+        if self.config.output_format != models::OutputFormat::Ndjson {
+            self.packages.lock().unwrap().push(package);
+            return;
+        }
 
-    for entry in entries {
-        if interrupted.load(Ordering::Relaxed) {
-            return Ok(());
+        if let Some(root) = &self.config.root {
+            report_image_paths(root, &mut [], std::slice::from_mut(&mut package));
         }
+        self.write(&models::ScanRecord::Package(&package));
+    }
 
-        let entry = match entry {
-            Ok(e) => e,
-            Err(_) => continue, // Skip unreadable entries
-        };
-        let path = entry.path();
+    fn unreadable(&self, _path: &std::path::Path) {
+        self.skipped_unreadable.fetch_add(1, Ordering::Relaxed);
+    }
 
-        if path.is_file() {
-            if let Some(binary) = scan::check_single_file(&path) {
-                binaries.push(binary);
-                if let Some(ref mut progress) = progress {
-                    progress.increment_scanned();
-                }
+    fn file_checked(&self, found: bool) {
+        if let Some(ref mut progress) = *self.progress.lock().unwrap() {
+            if found {
+                progress.increment_scanned();
             } else {
-                *skipped += 1;
-                if let Some(ref mut progress) = progress {
-                    progress.increment_skipped();
-                }
+                progress.increment_skipped();
             }
-        } else if path.is_dir() {
-            collect_binaries_from_directory(&path, binaries, skipped, progress, interrupted)?;
         }
     }
+}
 
-    Ok(())
+/// Attach the other paths of files reached more than once to their result.
+///
+/// Which path the walk reached first varies between runs, so the one the walk
+/// prefers (the first in sort order not through a symlink) is reported and
+/// the rest become aliases.
+fn merge_aliases(
+    results: &mut [models::BinaryResult],
+    mut aliases: HashMap<std::path::PathBuf, Vec<std::path::PathBuf>>,
+    held_entitlements: &mut HashMap<std::path::PathBuf, Vec<String>>,
+) {
+    for result in results {
+        let visited = std::path::PathBuf::from(&result.path);
+        let Some(paths) = aliases.remove(&visited) else {
+            continue;
+        };
+        let mut paths: Vec<String> = paths.iter().map(|path| path.to_string_lossy().to_string()).collect();
+        result.path = paths.remove(0);
+        paths.sort();
+        result.aliases = paths;
+        if let Some(held) = held_entitlements.remove(&visited) {
+            held_entitlements.insert(std::path::PathBuf::from(&result.path), held);
+        }
+    }
+}
+
+/// Alias groups of the streamed results, keyed by the path each was written with
+fn alias_groups(
+    aliases: HashMap<std::path::PathBuf, Vec<std::path::PathBuf>>,
+    streamed: &std::collections::HashSet<std::path::PathBuf>,
+    root: Option<&std::path::Path>,
+) -> Vec<models::AliasGroup> {
+    let reported = |path: &std::path::Path| match root {
+        Some(root) => entitlements::native::root::image_path(root, path).to_string_lossy().to_string(),
+        None => path.to_string_lossy().to_string(),
+    };
+    let mut groups: Vec<models::AliasGroup> = aliases
+        .into_iter()
+        .filter(|(visited, _)| streamed.contains(visited))
+        .map(|(visited, paths)| {
+            let mut others: Vec<String> = paths.iter().filter(|path| **path != visited).map(|path| reported(path)).collect();
+            others.sort();
+            models::AliasGroup { path: reported(&visited), aliases: others }
+        })
+        .collect();
+    groups.sort_by(|a, b| a.path.cmp(&b.path));
+    groups
+}
+
+/// Report paths under a foreign root as the image sees them
+fn report_image_paths(root: &std::path::Path, results: &mut [models::BinaryResult], packages: &mut [models::PackageReport]) {
+    use entitlements::native::root::image_path;
+
+    let in_image = |path: &str| image_path(root, std::path::Path::new(path)).to_string_lossy().to_string();
+    for result in results {
+        result.path = in_image(&result.path);
+        result.parent_bundle = result.parent_bundle.as_deref().map(in_image);
+        result.aliases = result.aliases.iter().map(|alias| in_image(alias)).collect();
+    }
+    for package in packages {
+        package.path = in_image(&package.path);
+    }
 }
 
 fn run_monitor_mode(
     path: Vec<std::path::PathBuf>,
     entitlement: Vec<String>,
     interval: f64,
+    backend: models::ExtractorBackend,
     json: bool,
     quiet: bool,
 ) -> Result<()> {
-    let config = cli::parse_monitor_config(path, entitlement, interval, json, quiet)?;
+    let config = cli::parse_monitor_config(path, entitlement, interval, backend, json, quiet)?;
 
     // Set up interrupt handling using signal-hook (same as scan mode)
     let interrupted = Arc::new(AtomicBool::new(false));
@@ -4,6 +4,14 @@
 //! - BinaryRecord: Discovered executable metadata
 //! - EntitlementSet: Parsed entitlement key-value pairs
 //! - ScanResult: Successful entitlement enumeration
+//! - SignatureReport: Everything read from a binary's code signature
+//! - CodeDirectoryInfo: Signing identity and flags from the CodeDirectory
+//! - SignerInfo: Signing certificate chain and signer classification
+//! - SignatureConstraints: Launch and library constraints
+//! - SignatureVerification: Result of recomputing the signature's hashes
+//! - BundleInfo: Identity and version keys from an Info.plist
+//! - ProvisioningProfile: Embedded provisioning profile and what it authorises
+//! - ExtractorBackend: Which entitlement extractor to use
 //! - ScanSummary: Aggregated scan statistics
 
 use serde::{Deserialize, Serialize};
@@ -12,7 +20,7 @@ use std::path::PathBuf;
 use std::time::{Duration, SystemTime};
 
 /// Represents a single binary file with its entitlements
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BinaryResult {
     /// Absolute path to the binary file
     pub path: String,
@@ -20,6 +28,787 @@ pub struct BinaryResult {
     pub entitlements: HashMap<String, serde_json::Value>,
     /// Count of entitlements for quick reference
     pub entitlement_count: usize,
+    /// Problems noticed while reading the signature (e.g. XML and DER entitlements disagree)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// Per-architecture breakdown for universal binaries
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub slices: Vec<SliceEntitlements>,
+    /// Whether the slices of a universal binary carry different entitlements
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slices_disagree: Option<bool>,
+    /// Signing identifier, team, CDHash and flags; absent for unsigned binaries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_directory: Option<CodeDirectoryInfo>,
+    /// Who signed the binary; absent when the signature could not be read natively
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer: Option<SignerInfo>,
+    /// Designated requirement in the requirement language, if the signature embeds one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub designated_requirement: Option<String>,
+    /// Launch and library constraints, if the signature embeds any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constraints: Option<SignatureConstraints>,
+    /// Whether the code and special-slot hashes match the file; only set with --verify
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature_valid: Option<bool>,
+    /// Index of the first code page whose hash does not match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_invalid_page: Option<u64>,
+    /// Special slots (entitlements, info-plist, requirements, ...) whose hash does not match
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub invalid_special_slots: Vec<String>,
+    /// Bundle identifier and version from the embedded or bundle Info.plist
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_info: Option<BundleInfo>,
+    /// Embedded provisioning profile of the enclosing app bundle
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provisioning_profile: Option<ProvisioningProfile>,
+    /// Libraries named by the dylib load commands, in load order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dylibs: Vec<DylibDependency>,
+    /// `LC_RPATH` search paths
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rpaths: Vec<String>,
+    /// Libraries that could be planted by an attacker while library validation is relaxed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dylib_hijack_risks: Vec<DylibHijackRisk>,
+    /// Exploit mitigations, reported with `--hardening`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hardening: Option<HardeningInfo>,
+    /// Target platforms with minimum OS and SDK versions (two for zippered Mac Catalyst binaries)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub build_versions: Vec<BuildVersion>,
+    /// Where the entitlements were read from; absent when there are none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<EntitlementSource>,
+    /// Bundle resource seal check; only set with --verify-resources for bundle executables
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_seal: Option<ResourceSealVerification>,
+    /// Innermost `.app`, `.appex`, `.xpc`, `.framework` or `.systemextension` containing the binary
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_bundle: Option<String>,
+    /// Entitlements this nested binary holds that the main executable of its host app does not
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entitlements_beyond_host: Vec<String>,
+    /// Other paths to the same file (hardlinks, firmlinks, followed symlinks), read only once
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+impl BinaryResult {
+    /// One-line verification outcome, e.g. "modified (page 3, entitlements)"; None unless verified
+    pub fn verification_summary(&self) -> Option<String> {
+        let valid = self.signature_valid?;
+        if valid {
+            return Some("valid".to_string());
+        }
+        let mut details: Vec<String> = self.first_invalid_page.iter().map(|page| format!("page {}", page)).collect();
+        details.extend(self.invalid_special_slots.iter().cloned());
+        Some(format!("modified ({})", details.join(", ")))
+    }
+}
+
+/// Entitlements of one architecture slice of a universal binary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SliceEntitlements {
+    /// Architecture name (x86_64, arm64, arm64e, ...)
+    pub arch: String,
+    /// Entitlements signed into this slice
+    pub entitlements: HashMap<String, serde_json::Value>,
+    /// Count of entitlements for quick reference
+    pub entitlement_count: usize,
+    /// Problems noticed while reading this slice's signature
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// CodeDirectory of this slice (each slice is signed separately)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_directory: Option<CodeDirectoryInfo>,
+    /// Hash verification of this slice, when requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<SignatureVerification>,
+}
+
+/// Outcome of recomputing the hashes a CodeDirectory commits to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignatureVerification {
+    /// Whether every page and special-slot hash matched
+    pub valid: bool,
+    /// Index of the first code page whose hash does not match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_invalid_page: Option<u64>,
+    /// Names of the special slots whose hash does not match
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub invalid_special_slots: Vec<String>,
+}
+
+/// Outcome of checking a bundle's files against `_CodeSignature/CodeResources`.
+/// Paths are relative to the sealed directory (`Contents` for apps).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceSealVerification {
+    /// Files present on disk that the seal does not cover
+    pub added: Vec<String>,
+    /// Sealed files that are missing
+    pub removed: Vec<String>,
+    /// Sealed files whose hash or symlink target changed
+    pub modified: Vec<String>,
+}
+
+impl ResourceSealVerification {
+    /// Whether the bundle matches its seal
+    pub fn is_intact(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+    /// One-line summary, e.g. "intact" or "2 modified, 1 added"
+    pub fn summary(&self) -> String {
+        if self.is_intact() {
+            return "intact".to_string();
+        }
+        [("modified", &self.modified), ("added", &self.added), ("removed", &self.removed)]
+            .iter()
+            .filter(|(_, paths)| !paths.is_empty())
+            .map(|(label, paths)| format!("{} {}", paths.len(), label))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Where a binary's entitlements were read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EntitlementSource {
+    /// The entitlements blob of the code signature
+    Signature,
+    /// The linker's `__TEXT,__entitlements` section, used by simulator and
+    /// linker-signed builds that carry no signature entitlements
+    Section,
+}
+
+/// Where an Info.plist was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum InfoPlistSource {
+    /// The `__TEXT,__info_plist` section of a command-line tool
+    Embedded,
+    /// `Contents/Info.plist` of the enclosing bundle
+    Bundle,
+}
+
+/// Identity and version keys from a binary's Info.plist
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleInfo {
+    pub source: InfoPlistSource,
+    /// CFBundleIdentifier
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
+    /// CFBundleName
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// CFBundleShortVersionString (marketing version)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_version: Option<String>,
+    /// CFBundleVersion (build number)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// LSMinimumSystemVersion
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimum_system_version: Option<String>,
+}
+
+impl BundleInfo {
+    /// One-line summary, e.g. "com.foo.tool 1.2 (34), macOS 12.0+"
+    pub fn summary(&self) -> String {
+        let mut text = self.identifier.clone().or_else(|| self.name.clone()).unwrap_or_else(|| "(no identifier)".to_string());
+        match (&self.short_version, &self.version) {
+            (Some(short), Some(build)) if short != build => text.push_str(&format!(" {} ({})", short, build)),
+            (Some(version), _) | (None, Some(version)) => text.push_str(&format!(" {}", version)),
+            (None, None) => {}
+        }
+        if let Some(minimum) = &self.minimum_system_version {
+            text.push_str(&format!(", macOS {}+", minimum));
+        }
+        text
+    }
+}
+
+/// Embedded provisioning profile (`Contents/embedded.provisionprofile`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProvisioningProfile {
+    /// Profile name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Team identifier the profile was issued to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team_identifier: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team_name: Option<String>,
+    /// Expiration date in RFC 3339 format
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiration_date: Option<String>,
+    /// Whether the profile had expired when it was read
+    pub expired: bool,
+    /// Entitlements the profile allows; string values may contain `*` wildcards
+    pub entitlements: HashMap<String, serde_json::Value>,
+    /// Entitlements of the binary that the profile does not authorise
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unauthorized_entitlements: Vec<String>,
+}
+
+impl ProvisioningProfile {
+    /// One-line summary, e.g. "Example App Dev (team ABCDE12345, expires 2026-01-01T00:00:00Z)"
+    pub fn summary(&self) -> String {
+        let mut details = Vec::new();
+        if let Some(team) = &self.team_identifier {
+            details.push(format!("team {}", team));
+        }
+        if let Some(expiration) = &self.expiration_date {
+            details.push(format!("{} {}", if self.expired { "expired" } else { "expires" }, expiration));
+        }
+        let name = self.name.as_deref().unwrap_or("(unnamed profile)");
+        if details.is_empty() {
+            name.to_string()
+        } else {
+            format!("{} ({})", name, details.join(", "))
+        }
+    }
+}
+
+/// How a dylib load command links its library
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DylibKind {
+    /// `LC_LOAD_DYLIB`
+    Load,
+    /// `LC_LOAD_WEAK_DYLIB`; a missing library is silently skipped
+    Weak,
+    /// `LC_REEXPORT_DYLIB`
+    Reexport,
+    /// `LC_LAZY_LOAD_DYLIB`
+    Lazy,
+    /// `LC_LOAD_UPWARD_DYLIB`
+    Upward,
+}
+
+impl std::fmt::Display for DylibKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DylibKind::Load => "load",
+            DylibKind::Weak => "weak",
+            DylibKind::Reexport => "reexport",
+            DylibKind::Lazy => "lazy",
+            DylibKind::Upward => "upward",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A library named by a dylib load command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DylibDependency {
+    /// Install name as recorded, e.g. `@rpath/Sparkle.framework/Versions/B/Sparkle`
+    pub path: String,
+    pub kind: DylibKind,
+}
+
+/// A library load an attacker could satisfy with their own dylib
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DylibHijackRisk {
+    /// Install name of the library
+    pub path: String,
+    /// Why the load is hijackable: `@rpath`, `@executable_path`, `@loader_path`, `weak` or `missing`
+    pub reasons: Vec<String>,
+}
+
+impl DylibHijackRisk {
+    /// One-line summary, e.g. "@rpath/Foo.dylib (@rpath, missing)"
+    pub fn summary(&self) -> String {
+        format!("{} ({})", self.path, self.reasons.join(", "))
+    }
+}
+
+/// Platform a Mach-O slice was built for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Platform {
+    Macos,
+    Ios,
+    Tvos,
+    Watchos,
+    Bridgeos,
+    MacCatalyst,
+    IosSimulator,
+    TvosSimulator,
+    WatchosSimulator,
+    Driverkit,
+    Visionos,
+    VisionosSimulator,
+}
+
+impl Platform {
+    /// Every platform, in `LC_BUILD_VERSION` numbering order
+    pub const ALL: [Platform; 12] = [
+        Platform::Macos,
+        Platform::Ios,
+        Platform::Tvos,
+        Platform::Watchos,
+        Platform::Bridgeos,
+        Platform::MacCatalyst,
+        Platform::IosSimulator,
+        Platform::TvosSimulator,
+        Platform::WatchosSimulator,
+        Platform::Driverkit,
+        Platform::Visionos,
+        Platform::VisionosSimulator,
+    ];
+
+    /// Name used on the command line and in JSON output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Platform::Macos => "macos",
+            Platform::Ios => "ios",
+            Platform::Tvos => "tvos",
+            Platform::Watchos => "watchos",
+            Platform::Bridgeos => "bridgeos",
+            Platform::MacCatalyst => "mac-catalyst",
+            Platform::IosSimulator => "ios-simulator",
+            Platform::TvosSimulator => "tvos-simulator",
+            Platform::WatchosSimulator => "watchos-simulator",
+            Platform::Driverkit => "driverkit",
+            Platform::Visionos => "visionos",
+            Platform::VisionosSimulator => "visionos-simulator",
+        }
+    }
+
+    /// Name as Apple writes it, e.g. "Mac Catalyst"
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Platform::Macos => "macOS",
+            Platform::Ios => "iOS",
+            Platform::Tvos => "tvOS",
+            Platform::Watchos => "watchOS",
+            Platform::Bridgeos => "bridgeOS",
+            Platform::MacCatalyst => "Mac Catalyst",
+            Platform::IosSimulator => "iOS Simulator",
+            Platform::TvosSimulator => "tvOS Simulator",
+            Platform::WatchosSimulator => "watchOS Simulator",
+            Platform::Driverkit => "DriverKit",
+            Platform::Visionos => "visionOS",
+            Platform::VisionosSimulator => "visionOS Simulator",
+        }
+    }
+}
+
+impl std::fmt::Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Platform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Platform::ALL.iter()
+            .find(|platform| platform.as_str() == s)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<&str> = Platform::ALL.iter().map(|platform| platform.as_str()).collect();
+                format!("unknown platform '{}' (expected one of: {})", s, names.join(", "))
+            })
+    }
+}
+
+/// An OS or SDK version as encoded in Mach-O load commands (`xxxx.yy.zz`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OsVersion {
+    pub major: u16,
+    pub minor: u8,
+    pub patch: u8,
+}
+
+impl OsVersion {
+    /// Decode the nibble-packed `xxxx.yy.zz` form
+    pub fn from_packed(packed: u32) -> Self {
+        Self { major: (packed >> 16) as u16, minor: (packed >> 8) as u8, patch: packed as u8 }
+    }
+}
+
+impl std::fmt::Display for OsVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)?;
+        if self.patch != 0 {
+            write!(f, ".{}", self.patch)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for OsVersion {
+    type Err = String;
+
+    /// Parse "13", "13.0" or "13.0.1"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid version '{}' (expected MAJOR[.MINOR[.PATCH]])", s);
+        let mut parts = s.split('.');
+        let major = parts.next().and_then(|part| part.parse().ok()).ok_or_else(invalid)?;
+        let minor = parts.next().map_or(Ok(0), |part| part.parse().map_err(|_| invalid()))?;
+        let patch = parts.next().map_or(Ok(0), |part| part.parse().map_err(|_| invalid()))?;
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(Self { major, minor, patch })
+    }
+}
+
+impl Serialize for OsVersion {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for OsVersion {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Target platform and versions from `LC_BUILD_VERSION` or `LC_VERSION_MIN_*`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildVersion {
+    pub platform: Platform,
+    pub minimum_os: OsVersion,
+    /// SDK the binary was linked against; absent when the linker did not record it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sdk: Option<OsVersion>,
+}
+
+impl BuildVersion {
+    /// One-line summary, e.g. "macOS 12.0+ (SDK 14.2)"
+    pub fn summary(&self) -> String {
+        match self.sdk {
+            Some(sdk) => format!("{} {}+ (SDK {})", self.platform.display_name(), self.minimum_os, sdk),
+            None => format!("{} {}+", self.platform.display_name(), self.minimum_os),
+        }
+    }
+}
+
+/// Exploit mitigations of a binary, in the spirit of `checksec`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HardeningInfo {
+    /// Position-independent executable (`MH_PIE`)
+    pub pie: bool,
+    /// Has a `__RESTRICT,__restrict` section, so dyld ignores `DYLD_*` variables
+    pub restrict_segment: bool,
+    /// Imports `___stack_chk_guard` or `___stack_chk_fail`
+    pub stack_canary: bool,
+    /// Contains Objective-C metadata
+    pub objc: bool,
+    /// Imports `_objc_release`, as code built with ARC does
+    pub arc: bool,
+    /// arm64e slice with pointer authentication
+    pub pointer_authentication: bool,
+    /// Signed with the hardened runtime flag
+    pub hardened_runtime: bool,
+    /// Library validation is enforced and not disabled by entitlement
+    pub library_validation: bool,
+}
+
+impl HardeningInfo {
+    /// Each mitigation with its display name; ARC only applies to Objective-C code
+    pub fn properties(&self) -> Vec<(&'static str, bool)> {
+        let mut properties = vec![
+            ("PIE", self.pie),
+            ("__RESTRICT", self.restrict_segment),
+            ("stack canary", self.stack_canary),
+        ];
+        if self.objc {
+            properties.push(("ARC", self.arc));
+        }
+        properties.extend([
+            ("PAC", self.pointer_authentication),
+            ("hardened runtime", self.hardened_runtime),
+            ("library validation", self.library_validation),
+        ]);
+        properties
+    }
+
+    /// One-line summary, e.g. "PIE, stack canary, hardened runtime; missing: __RESTRICT, PAC"
+    pub fn summary(&self) -> String {
+        let (present, missing): (Vec<_>, Vec<_>) = self.properties().into_iter().partition(|(_, enabled)| *enabled);
+        let names = |properties: Vec<(&str, bool)>| properties.into_iter().map(|(name, _)| name).collect::<Vec<_>>().join(", ");
+        match (present.is_empty(), missing.is_empty()) {
+            (_, true) => names(present),
+            (true, false) => format!("missing: {}", names(missing)),
+            (false, false) => format!("{}; missing: {}", names(present), names(missing)),
+        }
+    }
+}
+
+/// Signing metadata read from a CodeDirectory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodeDirectoryInfo {
+    /// Signing identifier (usually the bundle identifier)
+    pub identifier: String,
+    /// Team identifier of the signing certificate, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team_id: Option<String>,
+    /// Hash type of the reported CodeDirectory (sha1, sha256, ...)
+    pub hash_type: String,
+    /// CDHash as lowercase hex, computed over the strongest CodeDirectory
+    pub cdhash: String,
+    /// Raw code-signing flags
+    pub flags: u32,
+    /// Names of the set flags (adhoc, runtime, linker-signed, library-validation, ...)
+    pub flag_names: Vec<String>,
+    /// Whether the CodeDirectory marks an Apple platform binary
+    pub platform_binary: bool,
+}
+
+/// Classification of a binary by the kind of identity that signed it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SignerKind {
+    /// Signed by Apple as part of the operating system
+    ApplePlatform,
+    /// Distributed through the App Store (re-signed by Apple)
+    AppStore,
+    /// Signed with a Developer ID certificate for distribution outside the App Store
+    DeveloperId,
+    /// Signed with a development or pre-submission distribution certificate
+    Development,
+    /// Signed without a certificate (ad-hoc or linker-signed)
+    AdHoc,
+    /// Mach-O binary without a code signature
+    Unsigned,
+    /// Chain ends in Apple's root, but its certificate or CMS signatures do not verify
+    Unverified,
+    /// Signed by a certificate chain that is not one of Apple's
+    Other,
+}
+
+impl SignerKind {
+    /// Every kind, in the order shown to users
+    pub const ALL: [SignerKind; 8] = [
+        SignerKind::ApplePlatform,
+        SignerKind::AppStore,
+        SignerKind::DeveloperId,
+        SignerKind::Development,
+        SignerKind::AdHoc,
+        SignerKind::Unsigned,
+        SignerKind::Unverified,
+        SignerKind::Other,
+    ];
+
+    /// Name used on the command line and in JSON output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignerKind::ApplePlatform => "apple-platform",
+            SignerKind::AppStore => "app-store",
+            SignerKind::DeveloperId => "developer-id",
+            SignerKind::Development => "development",
+            SignerKind::AdHoc => "ad-hoc",
+            SignerKind::Unsigned => "unsigned",
+            SignerKind::Unverified => "unverified",
+            SignerKind::Other => "other",
+        }
+    }
+}
+
+impl std::fmt::Display for SignerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for SignerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SignerKind::ALL.iter()
+            .find(|kind| kind.as_str() == s)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<&str> = SignerKind::ALL.iter().map(|kind| kind.as_str()).collect();
+                format!("unknown signer '{}' (expected one of: {})", s, names.join(", "))
+            })
+    }
+}
+
+/// Signing certificate details and classification of a binary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignerInfo {
+    /// Signer classification
+    pub kind: SignerKind,
+    /// Common name of the leaf (signing) certificate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub common_name: Option<String>,
+    /// Organization of the leaf certificate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+    /// Common names of the certificate chain, from the leaf up to the root
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chain: Vec<String>,
+}
+
+impl SignerInfo {
+    /// Signer without any certificate details
+    pub fn without_certificate(kind: SignerKind) -> Self {
+        Self { kind, common_name: None, organization: None, chain: Vec::new() }
+    }
+
+    /// One-line description: the classification followed by the chain
+    pub fn summary(&self) -> String {
+        if self.chain.is_empty() {
+            self.kind.to_string()
+        } else {
+            format!("{} ({})", self.kind, self.chain.join(" -> "))
+        }
+    }
+}
+
+impl CodeDirectoryInfo {
+    /// One-line description in the spirit of `codesign -dv`
+    pub fn summary(&self) -> String {
+        let flags = if self.flag_names.is_empty() {
+            "none".to_string()
+        } else {
+            self.flag_names.join(",")
+        };
+        format!(
+            "{} (team: {}, {}flags: {:#x}({}), cdhash: {})",
+            self.identifier,
+            self.team_id.as_deref().unwrap_or("none"),
+            if self.platform_binary { "platform binary, " } else { "" },
+            self.flags,
+            flags,
+            self.cdhash,
+        )
+    }
+}
+
+/// Everything read from a binary's code signature
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SignatureReport {
+    /// Entitlements found in the signature (key-value pairs)
+    pub entitlements: HashMap<String, serde_json::Value>,
+    /// Problems noticed while reading the signature
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// Per-architecture breakdown, only filled in for universal binaries
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub slices: Vec<SliceEntitlements>,
+    /// CodeDirectory metadata, absent for unsigned binaries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_directory: Option<CodeDirectoryInfo>,
+    /// Signer classification; absent for files that are not Mach-O
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer: Option<SignerInfo>,
+    /// Designated requirement decompiled to the requirement language
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub designated_requirement: Option<String>,
+    /// Launch and library constraints (macOS 13+)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constraints: Option<SignatureConstraints>,
+    /// Hash verification; for universal binaries, the first slice that fails
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<SignatureVerification>,
+    /// Info.plist keys; the bundle's Info.plist wins over an embedded one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_info: Option<BundleInfo>,
+    /// Provisioning profile of the enclosing app bundle
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provisioning_profile: Option<ProvisioningProfile>,
+    /// Dylib load commands of the reported slice
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dylibs: Vec<DylibDependency>,
+    /// `LC_RPATH` entries of the reported slice
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rpaths: Vec<String>,
+    /// Hijackable library loads; only assessed when the file path is known
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dylib_hijack_risks: Vec<DylibHijackRisk>,
+    /// Exploit mitigations of the reported slice, when requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hardening: Option<HardeningInfo>,
+    /// `LC_BUILD_VERSION` / `LC_VERSION_MIN_*` of the reported slice
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub build_versions: Vec<BuildVersion>,
+    /// Whether the entitlements came from the signature or the `__entitlements` section
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<EntitlementSource>,
+    /// Files of the enclosing bundle checked against its resource seal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_seal: Option<ResourceSealVerification>,
+}
+
+/// Launch constraints on a binary, its parent and its responsible process,
+/// and constraints on the libraries it loads. Each is the decoded fact dictionary.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SignatureConstraints {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub launch_self: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub launch_parent: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub launch_responsible: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub library: Option<serde_json::Value>,
+}
+
+impl SignatureConstraints {
+    /// Whether any of the self, parent or responsible launch constraints is present
+    pub fn has_launch_constraints(&self) -> bool {
+        self.launch_self.is_some() || self.launch_parent.is_some() || self.launch_responsible.is_some()
+    }
+
+    /// Present constraints with their display labels
+    pub fn labeled(&self) -> Vec<(&'static str, &serde_json::Value)> {
+        [
+            ("self", &self.launch_self),
+            ("parent", &self.launch_parent),
+            ("responsible", &self.launch_responsible),
+            ("library", &self.library),
+        ]
+        .into_iter()
+        .filter_map(|(label, value)| value.as_ref().map(|value| (label, value)))
+        .collect()
+    }
+}
+
+/// Whether an entitlement key is a private Apple entitlement
+pub fn is_private_entitlement(key: &str) -> bool {
+    crate::constants::PRIVATE_ENTITLEMENT_PREFIXES.iter().any(|prefix| key.starts_with(prefix))
+}
+
+impl SignatureReport {
+    /// Whether the slices of a universal binary disagree on their entitlements.
+    /// `None` for thin binaries, which have nothing to compare.
+    pub fn slices_disagree(&self) -> Option<bool> {
+        let first = self.slices.first()?;
+        if self.slices.len() < 2 {
+            return None;
+        }
+        Some(self.slices.iter().any(|slice| slice.entitlements != first.entitlements))
+    }
+
+    /// Keys of every entitlement held by any slice, so filters see entitlements
+    /// that only one architecture carries
+    pub fn all_entitlement_keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.entitlements.keys()
+            .chain(self.slices.iter().flat_map(|slice| slice.entitlements.keys()))
+            .cloned()
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    /// Whether any entitlement held by any slice is a private Apple entitlement
+    pub fn has_private_entitlements(&self) -> bool {
+        self.all_entitlement_keys().iter().any(|key| is_private_entitlement(key))
+    }
 }
 
 /// Summary statistics for the scan operation
@@ -36,6 +825,12 @@ pub struct ScanSummary {
     /// Whether the scan was interrupted by user signal
     #[serde(skip_serializing_if = "Option::is_none")]
     pub interrupted: Option<bool>,
+    /// Files whose report came from the extraction cache; `None` when the cache is off
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_hits: Option<usize>,
+    /// Files extracted and added to the extraction cache; `None` when the cache is off
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_misses: Option<usize>,
 }
 
 /// Complete output structure for JSON serialization
@@ -43,15 +838,232 @@ pub struct ScanSummary {
 pub struct EntitlementScanOutput {
     /// Array of binary results
     pub results: Vec<BinaryResult>,
+    /// Installer packages scanned; their executables are in `results`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<PackageReport>,
     /// Summary statistics
     pub summary: ScanSummary,
 }
 
+/// One line of `--format ndjson` output, tagged with its `type`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScanRecord<'a> {
+    /// A matching binary, written as soon as it has been read
+    Result(&'a BinaryResult),
+    /// An installer package, written once its payload has been listed
+    Package(&'a PackageReport),
+    /// Other paths to the file of a result, written once the walk is done
+    Aliases(&'a AliasGroup),
+    /// Written last, after every result
+    Summary(&'a ScanSummary),
+}
+
+/// The other paths a streamed result's file was reached through
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AliasGroup {
+    /// Path the result was written with
+    pub path: String,
+    /// Every other path to the same file, sorted
+    pub aliases: Vec<String>,
+}
+
+/// An installer package (`.pkg` flat package) and what it would run and install
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageReport {
+    /// Path of the package file
+    pub path: String,
+    /// Who signed the package; `unsigned` when it carries no signature
+    pub signer: SignerInfo,
+    /// Component packages with their identifiers and install locations
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<PackageComponent>,
+    /// Scripts the installer runs (preinstall, postinstall, ...)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scripts: Vec<PackageScript>,
+    /// Problems noticed while reading the package
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// A component package inside an installer package
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageComponent {
+    /// Package identifier from PackageInfo (e.g. com.example.app.pkg)
+    pub identifier: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Directory the payload is installed into
+    pub install_location: String,
+}
+
+/// An installer script of a component package
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageScript {
+    /// Identifier of the component the script belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    /// Path inside the component's Scripts archive, e.g. `postinstall`
+    pub name: String,
+    /// Script text; invalid UTF-8 is replaced
+    pub contents: String,
+}
+
+impl PackageScript {
+    /// One-line description, e.g. "com.example.pkg/postinstall (12 lines)"
+    pub fn summary(&self) -> String {
+        let name = match &self.component {
+            Some(component) => format!("{}/{}", component, self.name),
+            None => self.name.clone(),
+        };
+        format!("{} ({} lines)", name, self.contents.lines().count())
+    }
+}
+
+impl PackageComponent {
+    /// One-line description, e.g. "com.example.pkg 1.2 -> /Applications"
+    pub fn summary(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{} {} -> {}", self.identifier, version, self.install_location),
+            None => format!("{} -> {}", self.identifier, self.install_location),
+        }
+    }
+}
+
 /// Filter criteria for scanning operations
 #[derive(Debug, Clone, Default)]
 pub struct ScanFilters {
     /// Filter by specific entitlement keys
     pub entitlements: Vec<String>,
+    /// Only report binaries signed by one of these kinds of signer (any if empty)
+    pub signers: Vec<SignerKind>,
+    /// Only report binaries with private entitlements but no launch constraints
+    pub unconstrained_private: bool,
+    /// Only report binaries built for one of these platforms (any if empty)
+    pub platforms: Vec<Platform>,
+    /// Only report binaries linked against an SDK older than this
+    pub sdk_before: Option<OsVersion>,
+    /// Only report binaries whose minimum OS is older than this
+    pub min_os_before: Option<OsVersion>,
+}
+
+impl ScanFilters {
+    /// Whether a binary's signer passes the signer filter.
+    /// Binaries whose signer could not be determined only pass when no filter is set.
+    pub fn matches_signer(&self, signer: Option<&SignerInfo>) -> bool {
+        self.signers.is_empty() || signer.is_some_and(|signer| self.signers.contains(&signer.kind))
+    }
+
+    /// Whether a signature passes the launch constraint filter
+    pub fn matches_constraints(&self, report: &SignatureReport) -> bool {
+        if !self.unconstrained_private {
+            return true;
+        }
+        let constrained = report.constraints.as_ref().is_some_and(|c| c.has_launch_constraints());
+        !constrained && report.has_private_entitlements()
+    }
+
+    /// Whether a binary's build versions pass the platform, SDK and minimum OS filters.
+    /// Each filter is met when any build version satisfies it; binaries without
+    /// build versions only pass when none of these filters is set.
+    pub fn matches_build(&self, build_versions: &[BuildVersion]) -> bool {
+        let platform = self.platforms.is_empty()
+            || build_versions.iter().any(|build| self.platforms.contains(&build.platform));
+        let sdk = self.sdk_before
+            .is_none_or(|limit| build_versions.iter().any(|build| build.sdk.is_some_and(|sdk| sdk < limit)));
+        let min_os = self.min_os_before
+            .is_none_or(|limit| build_versions.iter().any(|build| build.minimum_os < limit));
+        platform && sdk && min_os
+    }
+}
+
+/// Scan output format selected with `--format` (`--json` is short for `json`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable text after the scan
+    #[default]
+    Human,
+    /// One JSON document after the scan, with results sorted by path
+    Json,
+    /// One JSON record per line as each binary is read, then a summary record
+    Ndjson,
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            OutputFormat::Human => "human",
+            OutputFormat::Json => "json",
+            OutputFormat::Ndjson => "ndjson",
+        })
+    }
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(OutputFormat::Human),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            _ => Err(format!("unknown format '{}' (expected one of: human, json, ndjson)", s)),
+        }
+    }
+}
+
+/// Entitlement extraction backend selected with `--backend`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ExtractorBackend {
+    /// Native parser, falling back to codesign when it is installed
+    #[default]
+    Auto,
+    /// Native Mach-O parser only
+    Native,
+    /// `codesign` subprocess only
+    Codesign,
+    /// Reports recorded in a JSON file, keyed by path
+    Fixture(PathBuf),
+}
+
+impl std::fmt::Display for ExtractorBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtractorBackend::Auto => f.write_str("auto"),
+            ExtractorBackend::Native => f.write_str("native"),
+            ExtractorBackend::Codesign => f.write_str("codesign"),
+            ExtractorBackend::Fixture(path) => write!(f, "fixture:{}", path.display()),
+        }
+    }
+}
+
+impl std::str::FromStr for ExtractorBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ExtractorBackend::Auto),
+            "native" => Ok(ExtractorBackend::Native),
+            "codesign" => Ok(ExtractorBackend::Codesign),
+            _ => match s.strip_prefix("fixture:") {
+                Some(path) if !path.is_empty() => Ok(ExtractorBackend::Fixture(PathBuf::from(path))),
+                _ => Err(format!("unknown backend '{}' (expected one of: auto, native, codesign, fixture:FILE)", s)),
+            },
+        }
+    }
+}
+
+// Stored as its command-line spelling in the daemon configuration
+impl Serialize for ExtractorBackend {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ExtractorBackend {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
 }
 
 /// Configuration for the scan operation
@@ -61,10 +1073,36 @@ pub struct ScanConfig {
     pub scan_paths: Vec<String>,
     /// Filter criteria
     pub filters: ScanFilters,
-    /// Whether to output JSON format
-    pub json_output: bool,
+    /// Output format (--format, or --json)
+    pub output_format: OutputFormat,
     /// Whether to run in quiet mode (suppress warnings)
     pub quiet_mode: bool,
+    /// Whether to recompute code signature hashes (--verify)
+    pub verify_signatures: bool,
+    /// Whether to report exploit mitigations (--hardening)
+    pub report_hardening: bool,
+    /// Whether to check bundle resource seals (--verify-resources)
+    pub verify_resources: bool,
+    /// Whether to group human output by containing bundle (--bundles)
+    pub group_bundles: bool,
+    /// Foreign filesystem root scan paths live under (--root); results report in-image paths
+    pub root: Option<PathBuf>,
+    /// Whether to follow symlinks found while walking (--no-follow-symlinks turns it off)
+    pub follow_symlinks: bool,
+    /// Whether to stay on the filesystem of each scan path (--one-file-system)
+    pub one_file_system: bool,
+    /// Whether to read inside archives found while walking (--expand-archives)
+    pub expand_archives: bool,
+    /// Patterns of paths not to walk (--exclude)
+    pub exclude: Vec<String>,
+    /// Patterns of the only files to check, when not empty (--include)
+    pub include: Vec<String>,
+    /// Whether to reuse and fill the on-disk extraction cache (--cache or --rebuild-cache, unless --no-cache)
+    pub use_cache: bool,
+    /// Whether to discard the extraction cache before scanning (--rebuild-cache)
+    pub rebuild_cache: bool,
+    /// Entitlement extraction backend
+    pub backend: ExtractorBackend,
 }
 
 //
@@ -85,6 +1123,12 @@ pub struct MonitoredProcess {
     pub executable_path: PathBuf,
     /// Entitlements found in the process executable (key-value pairs)
     pub entitlements: HashMap<String, serde_json::Value>,
+    /// CodeDirectory metadata of the process executable
+    #[serde(default)]
+    pub code_directory: Option<CodeDirectoryInfo>,
+    /// Bundle identifier and version of the process executable
+    #[serde(default)]
+    pub bundle_info: Option<BundleInfo>,
     /// Timestamp when this process was first discovered
     pub discovery_timestamp: SystemTime,
 }
@@ -94,14 +1138,16 @@ pub struct MonitoredProcess {
 pub struct PollingConfiguration {
     /// Polling interval
     pub interval: Duration,
-    /// Path filters for process monitoring
-    pub path_filters: Vec<PathBuf>,
+    /// Path filters for process monitoring: absolute paths or patterns (gitignore syntax)
+    pub path_filters: Vec<String>,
     /// Entitlement filters for process monitoring
     pub entitlement_filters: Vec<String>,
     /// Whether to output JSON format
     pub output_json: bool,
     /// Whether to run in quiet mode
     pub quiet_mode: bool,
+    /// Entitlement extraction backend
+    pub backend: ExtractorBackend,
 }
 
 /// Snapshot of process state at a given moment
@@ -145,6 +1191,12 @@ pub struct ProcessDetectionEvent {
     pub entitlement_count: usize,
     /// Entitlements as a list of key names
     pub entitlements: Vec<String>,
+    /// Signing identifier, team, CDHash and flags of the executable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_directory: Option<CodeDirectoryInfo>,
+    /// Bundle identifier and version of the executable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_info: Option<BundleInfo>,
 }
 
 impl ProcessSnapshot {
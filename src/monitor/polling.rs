@@ -1,5 +1,8 @@
-use crate::models::{MonitoredProcess, PollingConfiguration, ProcessSnapshot};
+use crate::entitlements::extractor::{self, EntitlementExtractor};
+use crate::entitlements::native::ReadOptions;
+use crate::models::{MonitoredProcess, PollingConfiguration, ProcessSnapshot, SignatureReport};
 use crate::monitor::ProcessTracker;
+use crate::scan::filter::PathFilter;
 use anyhow::Result;
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -27,6 +30,9 @@ pub fn start_monitoring_with_interrupt(config: PollingConfiguration, interrupted
 /// Internal monitoring implementation
 fn start_monitoring_internal(config: PollingConfiguration, running: Arc<AtomicBool>) -> Result<()> {
 
+    let extractor = extractor::create_extractor(&config.backend, ReadOptions::default())?;
+    let path_filter = PathFilter::from_patterns(&config.path_filters);
+
     // Initialize process tracker and system info
     let mut tracker = ProcessTracker::new();
     let mut system = System::new_all();
@@ -34,11 +40,7 @@ fn start_monitoring_internal(config: PollingConfiguration, running: Arc<AtomicBo
     if !config.quiet_mode {
         println!("Starting process monitoring (interval: {:.1}s)...", config.interval.as_secs_f64());
         if !config.path_filters.is_empty() {
-            println!("Monitoring {} for processes",
-                config.path_filters.iter()
-                    .map(|p| p.display().to_string())
-                    .collect::<Vec<_>>()
-                    .join(", "));
+            println!("Monitoring {} for processes", config.path_filters.join(", "));
         }
         if !config.entitlement_filters.is_empty() {
             println!("Monitoring for processes with entitlement: {}",
@@ -62,12 +64,15 @@ fn start_monitoring_internal(config: PollingConfiguration, running: Arc<AtomicBo
 
         // Extract entitlements only for new processes
         for process in &mut new_processes {
-            process.entitlements = extract_process_entitlements(&process.executable_path)
+            let report = extract_process_signature(extractor.as_ref(), &process.executable_path)
                 .unwrap_or_default();
+            process.entitlements = report.entitlements;
+            process.code_directory = report.code_directory;
+            process.bundle_info = report.bundle_info;
         }
 
         // Apply filters
-        let filtered_processes = apply_filters(new_processes, &config);
+        let filtered_processes = apply_filters(new_processes, &config, &path_filter);
 
         // Output detected processes
         for process in &filtered_processes {
@@ -111,6 +116,8 @@ fn create_process_snapshot(system: &System) -> Result<ProcessSnapshot> {
             name,
             executable_path,
             entitlements: HashMap::new(), // Will be populated later for new processes only
+            code_directory: None,
+            bundle_info: None,
             discovery_timestamp: timestamp,
         };
 
@@ -124,13 +131,14 @@ fn create_process_snapshot(system: &System) -> Result<ProcessSnapshot> {
     })
 }
 
-fn extract_process_entitlements(executable_path: &std::path::Path) -> Result<HashMap<String, serde_json::Value>> {
-    crate::entitlements::extract_entitlements(executable_path)
+fn extract_process_signature(extractor: &dyn EntitlementExtractor, executable_path: &std::path::Path) -> Result<SignatureReport> {
+    extractor.extract(executable_path)
 }
 
 fn apply_filters(
     processes: Vec<MonitoredProcess>,
     config: &PollingConfiguration,
+    path_filter: &PathFilter,
 ) -> Vec<MonitoredProcess> {
     // Filter out processes with no entitlements (reduce noise)
     let filtered: Vec<_> = processes
@@ -139,7 +147,7 @@ fn apply_filters(
         .collect();
 
     // Apply path filters
-    let filtered = ProcessTracker::apply_path_filters(filtered, &config.path_filters);
+    let filtered = ProcessTracker::apply_path_filters(filtered, path_filter);
 
     // Apply entitlement filters
     ProcessTracker::apply_entitlement_filters(filtered, &config.entitlement_filters)
@@ -159,6 +167,7 @@ fn output_process_detection(process: &MonitoredProcess, config: &PollingConfigur
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::ExtractorBackend;
     use std::path::PathBuf;
 
     // ==================== create_process_snapshot tests ====================
@@ -213,27 +222,27 @@ mod tests {
         );
     }
 
-    // ==================== extract_process_entitlements tests ====================
+    // ==================== extract_process_signature tests ====================
 
     #[test]
-    fn test_extract_process_entitlements_nonexistent_file() {
+    fn test_extract_process_signature_nonexistent_file() {
         let path = PathBuf::from("/nonexistent/binary");
-        let result = extract_process_entitlements(&path);
+        let result = extract_process_signature(&extractor::AutoExtractor::new(ReadOptions::default()), &path);
 
         // Should either succeed with empty vec or return an error
         // Either way, it shouldn't panic
         match result {
-            Ok(entitlements) => assert!(entitlements.is_empty() || !entitlements.is_empty()),
+            Ok(report) => assert!(report.entitlements.is_empty() || !report.entitlements.is_empty()),
             Err(_) => {} // Error is acceptable for nonexistent file
         }
     }
 
     #[test]
-    fn test_extract_process_entitlements_from_system_binary() {
+    fn test_extract_process_signature_from_system_binary() {
         // Test with a known system binary
         let path = PathBuf::from("/usr/bin/sudo");
         if path.exists() {
-            let result = extract_process_entitlements(&path);
+            let result = extract_process_signature(&extractor::AutoExtractor::new(ReadOptions::default()), &path);
             // Should not panic, may or may not have entitlements
             assert!(result.is_ok() || result.is_err());
         }
@@ -250,6 +259,8 @@ mod tests {
                 name: "test1".to_string(),
                 executable_path: PathBuf::from("/bin/test1"),
                 entitlements: ents(&[]), // No entitlements
+                code_directory: None,
+                bundle_info: None,
                 discovery_timestamp: SystemTime::now(),
             },
             MonitoredProcess {
@@ -258,6 +269,8 @@ mod tests {
                 name: "test2".to_string(),
                 executable_path: PathBuf::from("/bin/test2"),
                 entitlements: ents(&["com.apple.security.app-sandbox"]),
+                code_directory: None,
+                bundle_info: None,
                 discovery_timestamp: SystemTime::now(),
             },
         ];
@@ -268,9 +281,10 @@ mod tests {
             entitlement_filters: vec![],
             output_json: false,
             quiet_mode: false,
+            backend: ExtractorBackend::Auto,
         };
 
-        let filtered = apply_filters(processes, &config);
+        let filtered = apply_filters(processes, &config, &PathFilter::from_patterns(&config.path_filters));
 
         // Should only keep process with entitlements
         assert_eq!(filtered.len(), 1);
@@ -286,6 +300,8 @@ mod tests {
                 name: "test1".to_string(),
                 executable_path: PathBuf::from("/Applications/Test.app/test1"),
                 entitlements: ents(&["com.apple.security.app-sandbox"]),
+                code_directory: None,
+                bundle_info: None,
                 discovery_timestamp: SystemTime::now(),
             },
             MonitoredProcess {
@@ -294,19 +310,22 @@ mod tests {
                 name: "test2".to_string(),
                 executable_path: PathBuf::from("/usr/bin/test2"),
                 entitlements: ents(&["com.apple.security.app-sandbox"]),
+                code_directory: None,
+                bundle_info: None,
                 discovery_timestamp: SystemTime::now(),
             },
         ];
 
         let config = PollingConfiguration {
             interval: std::time::Duration::from_secs(1),
-            path_filters: vec![PathBuf::from("/Applications")],
+            path_filters: vec!["/Applications".to_string()],
             entitlement_filters: vec![],
             output_json: false,
             quiet_mode: false,
+            backend: ExtractorBackend::Auto,
         };
 
-        let filtered = apply_filters(processes, &config);
+        let filtered = apply_filters(processes, &config, &PathFilter::from_patterns(&config.path_filters));
 
         // Should only keep process in /Applications
         assert_eq!(filtered.len(), 1);
@@ -322,6 +341,8 @@ mod tests {
                 name: "test1".to_string(),
                 executable_path: PathBuf::from("/bin/test1"),
                 entitlements: ents(&["com.apple.security.app-sandbox"]),
+                code_directory: None,
+                bundle_info: None,
                 discovery_timestamp: SystemTime::now(),
             },
             MonitoredProcess {
@@ -330,6 +351,8 @@ mod tests {
                 name: "test2".to_string(),
                 executable_path: PathBuf::from("/bin/test2"),
                 entitlements: ents(&["com.apple.security.network.client"]),
+                code_directory: None,
+                bundle_info: None,
                 discovery_timestamp: SystemTime::now(),
             },
         ];
@@ -340,9 +363,10 @@ mod tests {
             entitlement_filters: vec!["com.apple.security.app-sandbox".to_string()],
             output_json: false,
             quiet_mode: false,
+            backend: ExtractorBackend::Auto,
         };
 
-        let filtered = apply_filters(processes, &config);
+        let filtered = apply_filters(processes, &config, &PathFilter::from_patterns(&config.path_filters));
 
         // Should only keep process with sandbox entitlement
         assert_eq!(filtered.len(), 1);
@@ -358,6 +382,8 @@ mod tests {
                 name: "test1".to_string(),
                 executable_path: PathBuf::from("/Applications/Test.app/test1"),
                 entitlements: ents(&["com.apple.security.app-sandbox"]),
+                code_directory: None,
+                bundle_info: None,
                 discovery_timestamp: SystemTime::now(),
             },
             MonitoredProcess {
@@ -366,6 +392,8 @@ mod tests {
                 name: "test2".to_string(),
                 executable_path: PathBuf::from("/Applications/Other.app/test2"),
                 entitlements: ents(&["com.apple.security.network.client"]),
+                code_directory: None,
+                bundle_info: None,
                 discovery_timestamp: SystemTime::now(),
             },
             MonitoredProcess {
@@ -374,19 +402,22 @@ mod tests {
                 name: "test3".to_string(),
                 executable_path: PathBuf::from("/usr/bin/test3"),
                 entitlements: ents(&["com.apple.security.app-sandbox"]),
+                code_directory: None,
+                bundle_info: None,
                 discovery_timestamp: SystemTime::now(),
             },
         ];
 
         let config = PollingConfiguration {
             interval: std::time::Duration::from_secs(1),
-            path_filters: vec![PathBuf::from("/Applications")],
+            path_filters: vec!["/Applications".to_string()],
             entitlement_filters: vec!["com.apple.security.app-sandbox".to_string()],
             output_json: false,
             quiet_mode: false,
+            backend: ExtractorBackend::Auto,
         };
 
-        let filtered = apply_filters(processes, &config);
+        let filtered = apply_filters(processes, &config, &PathFilter::from_patterns(&config.path_filters));
 
         // Should only keep process matching both filters
         assert_eq!(filtered.len(), 1);
@@ -403,9 +434,10 @@ mod tests {
             entitlement_filters: vec![],
             output_json: false,
             quiet_mode: false,
+            backend: ExtractorBackend::Auto,
         };
 
-        let filtered = apply_filters(processes, &config);
+        let filtered = apply_filters(processes, &config, &PathFilter::from_patterns(&config.path_filters));
         assert!(filtered.is_empty());
     }
 
@@ -420,6 +452,7 @@ mod tests {
             entitlement_filters: vec![],
             output_json: false,
             quiet_mode: false,
+            backend: ExtractorBackend::Auto,
         };
         assert_eq!(min_config.interval.as_millis(), 100);
 
@@ -430,6 +463,7 @@ mod tests {
             entitlement_filters: vec![],
             output_json: false,
             quiet_mode: false,
+            backend: ExtractorBackend::Auto,
         };
         assert_eq!(max_config.interval.as_secs(), 300);
     }
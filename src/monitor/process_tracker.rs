@@ -1,4 +1,5 @@
 use crate::models::{MonitoredProcess, ProcessSnapshot};
+use crate::scan::filter::PathFilter;
 
 /// Manages process state tracking between polling cycles
 pub struct ProcessTracker {
@@ -30,22 +31,18 @@ impl ProcessTracker {
         new_processes
     }
 
-    /// Apply path filters to processes (reusing existing scan logic)
+    /// Apply path filters to processes, with the same syntax as scan `--include`
     pub fn apply_path_filters(
         processes: Vec<MonitoredProcess>,
-        path_filters: &[std::path::PathBuf],
+        path_filter: &PathFilter,
     ) -> Vec<MonitoredProcess> {
-        if path_filters.is_empty() {
+        if path_filter.is_empty() {
             return processes;
         }
 
         processes
             .into_iter()
-            .filter(|process| {
-                path_filters.iter().any(|filter_path| {
-                    process.executable_path.starts_with(filter_path)
-                })
-            })
+            .filter(|process| path_filter.selects(&process.executable_path))
             .collect()
     }
 
@@ -83,6 +80,8 @@ mod tests {
             entitlements: entitlements.into_iter()
                 .map(|e| (e.to_string(), serde_json::Value::Bool(true)))
                 .collect(),
+            code_directory: None,
+            bundle_info: None,
             discovery_timestamp: SystemTime::now(),
         }
     }
@@ -234,7 +233,7 @@ mod tests {
             create_test_process(102, "Finder", "/System/Library/CoreServices/Finder.app", vec![]),
         ];
 
-        let filtered = ProcessTracker::apply_path_filters(processes.clone(), &[]);
+        let filtered = ProcessTracker::apply_path_filters(processes.clone(), &PathFilter::default());
         assert_eq!(filtered.len(), 3, "Empty filter should return all processes");
     }
 
@@ -246,8 +245,8 @@ mod tests {
             create_test_process(102, "TextEdit", "/Applications/TextEdit.app/Contents/MacOS/TextEdit", vec![]),
         ];
 
-        let filters = vec![PathBuf::from("/Applications")];
-        let filtered = ProcessTracker::apply_path_filters(processes, &filters);
+        let filters = vec!["/Applications"];
+        let filtered = ProcessTracker::apply_path_filters(processes, &PathFilter::from_patterns(&filters));
 
         assert_eq!(filtered.len(), 2, "Should only include /Applications processes");
         assert!(filtered.iter().all(|p| p.executable_path.starts_with("/Applications")));
@@ -263,10 +262,10 @@ mod tests {
         ];
 
         let filters = vec![
-            PathBuf::from("/Applications"),
-            PathBuf::from("/usr/bin"),
+            "/Applications",
+            "/usr/bin",
         ];
-        let filtered = ProcessTracker::apply_path_filters(processes, &filters);
+        let filtered = ProcessTracker::apply_path_filters(processes, &PathFilter::from_patterns(&filters));
 
         assert_eq!(filtered.len(), 2, "Should match /Applications and /usr/bin");
         let pids: Vec<u32> = filtered.iter().map(|p| p.pid).collect();
@@ -281,8 +280,8 @@ mod tests {
             create_test_process(101, "ls", "/usr/bin/ls", vec![]),
         ];
 
-        let filters = vec![PathBuf::from("/nonexistent/path")];
-        let filtered = ProcessTracker::apply_path_filters(processes, &filters);
+        let filters = vec!["/nonexistent/path"];
+        let filtered = ProcessTracker::apply_path_filters(processes, &PathFilter::from_patterns(&filters));
 
         assert!(filtered.is_empty(), "No processes should match nonexistent path");
     }
@@ -295,13 +294,29 @@ mod tests {
         ];
 
         // Filter for the deeper path
-        let filters = vec![PathBuf::from("/Applications/Safari.app/Contents/MacOS")];
-        let filtered = ProcessTracker::apply_path_filters(processes, &filters);
+        let filters = vec!["/Applications/Safari.app/Contents/MacOS"];
+        let filtered = ProcessTracker::apply_path_filters(processes, &PathFilter::from_patterns(&filters));
 
         assert_eq!(filtered.len(), 1, "Should only match exact nested path");
         assert_eq!(filtered[0].pid, 100);
     }
 
+    #[test]
+    fn test_path_filter_patterns() {
+        let processes = vec![
+            create_test_process(100, "Safari", "/Applications/Safari.app/Contents/MacOS/Safari", vec![]),
+            create_test_process(101, "Xcode", "/Applications/Xcode.app/Contents/MacOS/Xcode", vec![]),
+            create_test_process(102, "ls", "/usr/bin/ls", vec![]),
+        ];
+
+        // Same syntax as scan --include: globs, with negations taking precedence when later
+        let filters = vec!["/Applications/*.app", "!Xcode.app"];
+        let filtered = ProcessTracker::apply_path_filters(processes, &PathFilter::from_patterns(&filters));
+
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].pid, 100);
+    }
+
     // ==================== apply_entitlement_filters() tests ====================
 
     #[test]
@@ -441,8 +456,8 @@ mod tests {
         ];
 
         // Apply path filter first
-        let path_filters = vec![PathBuf::from("/Applications")];
-        let after_path = ProcessTracker::apply_path_filters(processes, &path_filters);
+        let path_filters = vec!["/Applications"];
+        let after_path = ProcessTracker::apply_path_filters(processes, &PathFilter::from_patterns(&path_filters));
         assert_eq!(after_path.len(), 2); // Safari and Calculator
 
         // Then apply entitlement filter
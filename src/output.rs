@@ -2,14 +2,20 @@
 //!
 //! Handles:
 //! - Human-readable output formatting per contracts/output-human-format.md
+//! - Detailed signature view when a single file is scanned
+//! - Bundle tree view grouping nested code under its host (--bundles)
+//! - Installer package signers, components and scripts
 //! - JSON output conforming to contracts/output-json-schema.json
 //! - Summary statistics generation
 //! - Quiet/verbose mode behavior
 //! - Progress indicators for long-running operations
 
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use anyhow::Result;
 use crate::constants::EVENT_PROCESS_DETECTED;
-use crate::models::{EntitlementScanOutput, MonitoredProcess, ProcessDetectionEvent};
+use crate::models::{BinaryResult, EntitlementScanOutput, EntitlementSource, InfoPlistSource, MonitoredProcess, PackageReport, ProcessDetectionEvent, ScanRecord, ScanSummary};
+use crate::scan::bundles;
 
 pub mod progress;
 
@@ -33,6 +39,8 @@ pub fn create_detection_event(process: &MonitoredProcess) -> Result<ProcessDetec
         path: process.executable_path.display().to_string(),
         entitlement_count: entitlement_keys.len(),
         entitlements: entitlement_keys,
+        code_directory: process.code_directory.clone(),
+        bundle_info: process.bundle_info.clone(),
     })
 }
 
@@ -45,10 +53,17 @@ pub fn format_event_human(event: &ProcessDetectionEvent) -> String {
         event.entitlements.join(", ")
     };
 
-    format!(
+    let mut text = format!(
         "[{}] New process detected: {} (PID: {})\n  Path: {}\n  Entitlements: {}",
         event.timestamp, event.name, event.pid, event.path, ent_list
-    )
+    );
+    if let Some(bundle_info) = &event.bundle_info {
+        text.push_str(&format!("\n  Bundle: {}", bundle_info.summary()));
+    }
+    if let Some(code_directory) = &event.code_directory {
+        text.push_str(&format!("\n  Signature: {}", code_directory.summary()));
+    }
+    text
 }
 
 /// Format a process detection event as JSON string.
@@ -56,6 +71,14 @@ pub fn format_event_json(event: &ProcessDetectionEvent) -> Result<String> {
     Ok(serde_json::to_string(event)?)
 }
 
+/// Write one `--format ndjson` record as a line of stdout.
+///
+/// Errors are returned rather than panicking, so a closed pipe can end the scan.
+pub fn write_record(record: &ScanRecord) -> std::io::Result<()> {
+    let line = serde_json::to_string(record).map_err(std::io::Error::other)?;
+    writeln!(std::io::stdout().lock(), "{}", line)
+}
+
 /// Format output in human-readable format
 pub fn format_human(output: &EntitlementScanOutput) -> Result<()> {
     if output.results.is_empty() {
@@ -71,25 +94,265 @@ pub fn format_human(output: &EntitlementScanOutput) -> Result<()> {
 
         for result in &output.results {
             println!("{}:", result.path);
+            if !result.aliases.is_empty() {
+                println!("  Also at: {}", result.aliases.join(", "));
+            }
+            if let Some(bundle_info) = &result.bundle_info {
+                println!("  Bundle: {}", bundle_info.summary());
+            }
+            for build in &result.build_versions {
+                println!("  Platform: {}", build.summary());
+            }
+            if let Some(code_directory) = &result.code_directory {
+                println!("  Signature: {}", code_directory.summary());
+            }
+            if let Some(signer) = &result.signer {
+                println!("  Signer: {}", signer.summary());
+            }
+            if let Some(verification) = result.verification_summary() {
+                println!("  Verification: {}", verification);
+            }
+            if let Some(seal) = &result.resource_seal {
+                println!("  Resource seal: {}", seal.summary());
+            }
+            if let Some(profile) = &result.provisioning_profile {
+                println!("  Provisioning profile: {}", profile.summary());
+            }
+            if let Some(hardening) = &result.hardening {
+                println!("  Hardening: {}", hardening.summary());
+            }
+            if result.source == Some(EntitlementSource::Section) {
+                println!("  Entitlements from: __TEXT,__entitlements section (no signature entitlements)");
+            }
+            print_entitlements(result, "  ");
+            print_constraints(result);
+            print_warnings(result);
+            println!();
+        }
+    }
 
-            // Sort entitlements for consistent output
-            let mut sorted_entitlements: Vec<_> = result.entitlements.iter().collect();
-            sorted_entitlements.sort_by_key(|(k, _)| *k);
+    print_packages(&output.packages);
+    print_summary(&output.summary);
+    Ok(())
+}
+
+/// Format the full signature breakdown of a single scanned file
+pub fn format_detail(output: &EntitlementScanOutput) -> Result<()> {
+    if output.results.is_empty() {
+        println!("No binaries found with entitlements.");
+    }
 
-            for (key, value) in sorted_entitlements {
-                match value {
-                    serde_json::Value::Bool(b) => println!("  {}: {}", key, b),
-                    serde_json::Value::String(s) => println!("  {}: {}", key, s),
-                    serde_json::Value::Number(n) => println!("  {}: {}", key, n),
-                    _ => println!("  {}: {}", key, value),
+    for result in &output.results {
+        println!("{}:", result.path);
+        if let Some(info) = &result.bundle_info {
+            let source = match info.source {
+                InfoPlistSource::Embedded => "embedded __info_plist section",
+                InfoPlistSource::Bundle => "bundle Info.plist",
+            };
+            println!("  Bundle identifier: {} ({})", info.identifier.as_deref().unwrap_or("(none)"), source);
+            if let Some(version) = &info.short_version {
+                println!("  Version: {}", version);
+            }
+            if let Some(build) = &info.version {
+                println!("  Build: {}", build);
+            }
+            if let Some(minimum) = &info.minimum_system_version {
+                println!("  Minimum macOS: {}", minimum);
+            }
+        }
+        for build in &result.build_versions {
+            println!("  Platform: {}", build.platform.display_name());
+            println!("  Minimum OS: {}", build.minimum_os);
+            println!("  SDK: {}", build.sdk.map_or("(unknown)".to_string(), |sdk| sdk.to_string()));
+        }
+        if let Some(cd) = &result.code_directory {
+            println!("  Identifier: {}", cd.identifier);
+            println!("  Team ID: {}", cd.team_id.as_deref().unwrap_or("(none)"));
+            println!("  Platform binary: {}", if cd.platform_binary { "yes" } else { "no" });
+            println!("  Hash type: {}", cd.hash_type);
+            println!("  CDHash: {}", cd.cdhash);
+            println!("  Flags: {:#x}({})", cd.flags,
+                     if cd.flag_names.is_empty() { "none".to_string() } else { cd.flag_names.join(",") });
+        }
+        if let Some(signer) = &result.signer {
+            println!("  Signer: {}", signer.kind);
+            if let Some(organization) = &signer.organization {
+                println!("  Organization: {}", organization);
+            }
+            for (depth, name) in signer.chain.iter().enumerate() {
+                let label = if depth == 0 { "  Certificate chain: " } else { "                     " };
+                println!("{}{}{}", label, if depth == 0 { "" } else { "-> " }, name);
+            }
+        }
+        if let Some(requirement) = &result.designated_requirement {
+            println!("  Designated requirement: {}", requirement);
+        }
+        if let Some(verification) = result.verification_summary() {
+            println!("  Verification: {}", verification);
+        }
+        if let Some(seal) = &result.resource_seal {
+            println!("  Resource seal: {}", seal.summary());
+            for (label, paths) in [("Modified", &seal.modified), ("Added", &seal.added), ("Removed", &seal.removed)] {
+                for path in paths {
+                    println!("    {}: {}", label, path);
                 }
             }
-            println!();
         }
+        if let Some(profile) = &result.provisioning_profile {
+            println!("  Provisioning profile: {}", profile.summary());
+            if let Some(team_name) = &profile.team_name {
+                println!("  Profile team: {}", team_name);
+            }
+            let mut allowed: Vec<&String> = profile.entitlements.keys().collect();
+            allowed.sort();
+            println!("  Profile entitlements ({}):", allowed.len());
+            for key in allowed {
+                println!("    {}: {}", key, profile.entitlements[key]);
+            }
+        }
+        if let Some(hardening) = &result.hardening {
+            println!("  Hardening:");
+            println!("    Objective-C: {}", if hardening.objc { "yes" } else { "no" });
+            for (name, enabled) in hardening.properties() {
+                println!("    {}: {}", name, if enabled { "yes" } else { "no" });
+            }
+        }
+        if !result.dylibs.is_empty() {
+            println!("  Linked libraries ({}):", result.dylibs.len());
+            for dylib in &result.dylibs {
+                println!("    [{}] {}", dylib.kind, dylib.path);
+            }
+        }
+        for rpath in &result.rpaths {
+            println!("  Rpath: {}", rpath);
+        }
+        match result.source {
+            Some(EntitlementSource::Section) => println!("  Entitlements ({}, from __TEXT,__entitlements section):", result.entitlement_count),
+            _ => println!("  Entitlements ({}):", result.entitlement_count),
+        }
+        print_entitlements(result, "    ");
+        print_constraints(result);
+        print_warnings(result);
+        println!();
     }
 
-    // Print summary
-    let summary = &output.summary;
+    print_summary(&output.summary);
+    Ok(())
+}
+
+/// Format results as a tree of the bundles containing them (--bundles)
+///
+/// Expects results in `scan::bundles::tree_order`, so each bundle is printed
+/// once, before its binaries and nested bundles.
+pub fn format_bundles(output: &EntitlementScanOutput) -> Result<()> {
+    if output.results.is_empty() {
+        println!("No binaries found with entitlements.");
+    } else {
+        let total_entitlements: usize = output.results.iter().map(|r| r.entitlement_count).sum();
+        println!("Found {} binaries with {} total entitlements:\n", output.results.len(), total_entitlements);
+
+        let mut open_bundles: Vec<PathBuf> = Vec::new();
+        for result in &output.results {
+            let path = Path::new(&result.path);
+            let bundles = bundles::containing_bundles(path);
+            let shared = open_bundles.iter().zip(&bundles).take_while(|(open, bundle)| open == bundle).count();
+            open_bundles.truncate(shared);
+            for bundle in &bundles[shared..] {
+                let label = open_bundles.last().and_then(|outer| bundle.strip_prefix(outer).ok()).unwrap_or(bundle);
+                let kind = bundles::BundleKind::of(bundle).map(|kind| kind.as_str()).unwrap_or("bundle");
+                println!("{}{} ({})", "  ".repeat(open_bundles.len()), label.display(), kind);
+                open_bundles.push(bundle.clone());
+            }
+
+            let indent = "  ".repeat(open_bundles.len());
+            let label = open_bundles.last().and_then(|bundle| path.strip_prefix(bundle).ok()).unwrap_or(path);
+            let noun = if result.entitlement_count == 1 { "entitlement" } else { "entitlements" };
+            println!("{}{} [{} {}]", indent, label.display(), result.entitlement_count, noun);
+            print_entitlements(result, &format!("{}  ", indent));
+            if !result.entitlements_beyond_host.is_empty() {
+                println!("{}  Warning: entitlements beyond host app: {}", indent, result.entitlements_beyond_host.join(", "));
+            }
+        }
+        println!();
+    }
+
+    print_packages(&output.packages);
+    print_summary(&output.summary);
+    Ok(())
+}
+
+/// Print the signer, components and scripts of each scanned installer package
+fn print_packages(packages: &[PackageReport]) {
+    for package in packages {
+        println!("Package: {}", package.path);
+        println!("  Signer: {}", package.signer.summary());
+        for component in &package.components {
+            println!("  Component: {}", component.summary());
+        }
+        for script in &package.scripts {
+            println!("  Script: {}", script.summary());
+        }
+        for warning in &package.warnings {
+            println!("  Warning: {}", warning);
+        }
+        println!();
+    }
+}
+
+/// Print the entitlements of a result, sorted by key
+fn print_entitlements(result: &BinaryResult, indent: &str) {
+    // Sort entitlements for consistent output
+    let mut sorted_entitlements: Vec<_> = result.entitlements.iter().collect();
+    sorted_entitlements.sort_by_key(|(k, _)| *k);
+
+    for (key, value) in sorted_entitlements {
+        match value {
+            serde_json::Value::Bool(b) => println!("{}{}: {}", indent, key, b),
+            serde_json::Value::String(s) => println!("{}{}: {}", indent, key, s),
+            serde_json::Value::Number(n) => println!("{}{}: {}", indent, key, n),
+            _ => println!("{}{}: {}", indent, key, value),
+        }
+    }
+}
+
+/// Print launch and library constraints next to the entitlements
+fn print_constraints(result: &BinaryResult) {
+    if let Some(constraints) = &result.constraints {
+        for (label, facts) in constraints.labeled() {
+            let kind = if label == "library" { "Library constraint" } else { "Launch constraint" };
+            println!("  {} ({}): {}", kind, label, facts);
+        }
+    }
+}
+
+/// Print signature warnings, including disagreeing architecture slices
+fn print_warnings(result: &BinaryResult) {
+    for warning in &result.warnings {
+        println!("  Warning: {}", warning);
+    }
+    if let Some(profile) = result.provisioning_profile.as_ref().filter(|p| !p.unauthorized_entitlements.is_empty()) {
+        println!("  Warning: not authorized by provisioning profile: {}", profile.unauthorized_entitlements.join(", "));
+    }
+    if !result.entitlements_beyond_host.is_empty() {
+        println!("  Warning: entitlements beyond host app: {}", result.entitlements_beyond_host.join(", "));
+    }
+    for risk in &result.dylib_hijack_risks {
+        println!("  Warning: dylib hijack risk with relaxed library validation: {}", risk.summary());
+    }
+    if let Some(true) = result.slices_disagree {
+        println!("  Warning: architecture slices carry different entitlements");
+        for slice in &result.slices {
+            let mut keys: Vec<&String> = slice.entitlements.keys().collect();
+            keys.sort();
+            let keys: Vec<&str> = keys.into_iter().map(String::as_str).collect();
+            println!("    [{}] {}", slice.arch,
+                     if keys.is_empty() { "(none)".to_string() } else { keys.join(", ") });
+        }
+    }
+}
+
+/// Print the scan summary block
+fn print_summary(summary: &ScanSummary) {
     println!("Scan Summary:");
     println!("  Scanned: {} files", summary.scanned);
     println!("  Matched: {} files", summary.matched);
@@ -106,9 +369,11 @@ pub fn format_human(output: &EntitlementScanOutput) -> Result<()> {
         println!("  Duration: {:.2}s", duration_sec);
     }
 
+    if let (Some(hits), Some(misses)) = (summary.cache_hits, summary.cache_misses) {
+        println!("  Cache: {} hits, {} misses", hits, misses);
+    }
+
     if let Some(true) = summary.interrupted {
         println!("  Status: Interrupted by user");
     }
-
-    Ok(())
-}
\ No newline at end of file
+}
@@ -1,7 +1,9 @@
 //! Progress indicator for static scans
 //!
 //! Provides real-time progress display during directory scanning operations.
-//! Shows per-directory status with file counts and completion indicators.
+//! Shows per-directory status with file counts and completion indicators. The
+//! total grows as the walk lists directories, so no separate counting pass is
+//! needed.
 
 use std::io::{self, Write};
 
@@ -27,13 +29,13 @@ impl ScanProgress {
     }
 
 
-    /// Start scanning with total file count
-    pub fn start_scanning(&mut self, total_files: usize) {
+    /// Start scanning; the total grows with `add_files`
+    pub fn start_scanning(&mut self) {
         if self.quiet_mode {
             return;
         }
 
-        self.total_files = total_files;
+        self.total_files = 0;
         self.scanned_files = 0;
         self.skipped_files = 0;
 
@@ -60,6 +62,15 @@ impl ScanProgress {
         io::stderr().flush().unwrap_or(());
     }
 
+    /// Add files found in a directory listing to the total
+    pub fn add_files(&mut self, count: usize) {
+        if self.quiet_mode {
+            return;
+        }
+
+        self.total_files += count;
+    }
+
     /// Increment the scanned file count
     pub fn increment_scanned(&mut self) {
         if self.quiet_mode {
@@ -159,7 +170,9 @@ mod tests {
         assert!(progress.current_directory.is_some());
 
         // Test scanning operations
-        progress.start_scanning(100);
+        progress.start_scanning();
+        progress.add_files(100);
+        assert_eq!(progress.total_files, 100);
         progress.increment_scanned();
         progress.increment_skipped();
         progress.complete_scanning();
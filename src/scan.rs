@@ -1,75 +1,35 @@
 //! Filesystem scanning and binary discovery module
 //!
 //! Responsible for:
-//! - Traversing directory trees to find executable binaries
+//! - Traversing directory trees in parallel to find executable binaries
 //! - Detecting Mach-O binaries by magic bytes
 //! - Checking file executable permissions
-//! - Fast file counting for progress tracking
+//! - Relating binaries to the bundles that contain them
+//! - Reading Mach-O members of `.zip`, `.ipa` and `.tar` archives in memory
+//! - Reading installer packages and the binaries they would install
+//! - Caching signature reports between scans
+//! - Filtering paths with gitignore-style patterns
 
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::os::unix::fs::PermissionsExt;
-use anyhow::Result;
+
+pub mod archive;
+pub mod bundles;
+pub mod cache;
+pub mod filter;
+pub mod package;
+pub mod walk;
 
 /// Represents a discovered binary file
 #[derive(Debug, Clone)]
 pub struct DiscoveredBinary {
     pub path: PathBuf,
-}
-
-/// Fast file counting (like find) - only uses filesystem metadata
-fn count_files_in_directory_with_interrupt(path: &Path, interrupted: &std::sync::Arc<std::sync::atomic::AtomicBool>) -> Result<usize> {
-    let mut count = 0;
-
-    let entries = match fs::read_dir(path) {
-        Ok(entries) => entries,
-        Err(_) => return Ok(0), // Skip unreadable directories silently
-    };
-
-    for entry in entries {
-        // Check for interruption frequently
-        if interrupted.load(std::sync::atomic::Ordering::Relaxed) {
-            return Ok(count); // Return partial count on interrupt
-        }
-
-        let entry = match entry {
-            Ok(e) => e,
-            Err(_) => continue, // Skip unreadable entries
-        };
-        let entry_path = entry.path();
-
-        // Count files and symlinks that point to files (consistent with processing logic)
-        if entry_path.is_file() {
-            count += 1;
-        } else if entry_path.is_dir() {
-            count += count_files_in_directory_with_interrupt(&entry_path, interrupted)?;
-        }
-    }
-
-    Ok(count)
-}
-
-/// Fast counting of total files in all scan paths with interrupt support
-pub fn count_total_files_with_interrupt(scan_paths: &[String], interrupted: &std::sync::Arc<std::sync::atomic::AtomicBool>) -> Result<usize> {
-    let mut total = 0;
-
-    for path_str in scan_paths {
-        // Check for interruption between directories
-        if interrupted.load(std::sync::atomic::Ordering::Relaxed) {
-            return Ok(total); // Return partial count on interrupt
-        }
-
-        let path = Path::new(path_str);
-        if path.exists() {
-            if path.is_file() {
-                total += 1;
-            } else if path.is_dir() {
-                total += count_files_in_directory_with_interrupt(path, interrupted)?;
-            }
-        }
-    }
-
-    Ok(total)
+    /// Contents of an archive member, read into memory; `None` for files on disk
+    pub contents: Option<Vec<u8>>,
+    /// Where the file is read when that differs from `path`: a symlink
+    /// resolved inside a foreign root
+    pub source: Option<PathBuf>,
 }
 
 /// Check a single file to see if it's a binary
@@ -94,6 +54,8 @@ fn check_file(path: &Path) -> Option<DiscoveredBinary> {
     if is_executable || is_mach_o {
         Some(DiscoveredBinary {
             path: path.to_path_buf(),
+            contents: None,
+            source: None,
         })
     } else {
         None
@@ -123,6 +85,11 @@ fn is_likely_mach_o(path: &Path) -> bool {
         return false;
     }
 
+    is_mach_o_magic(&buffer)
+}
+
+/// Whether the first four bytes of a file are a Mach-O or universal binary magic
+pub fn is_mach_o_magic(buffer: &[u8; 4]) -> bool {
     const MACH_O_MAGICS: [[u8; 4]; 8] = [
         MH_MAGIC, MH_CIGAM,
         MH_MAGIC_64, MH_CIGAM_64,
@@ -130,5 +97,5 @@ fn is_likely_mach_o(path: &Path) -> bool {
         FAT_MAGIC_64, FAT_CIGAM_64,
     ];
 
-    MACH_O_MAGICS.contains(&buffer)
-}
\ No newline at end of file
+    MACH_O_MAGICS.contains(buffer)
+}
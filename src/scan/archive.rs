@@ -0,0 +1,198 @@
+//! Archive members
+//!
+//! Reads the Mach-O members of `.zip`, `.ipa` and `.tar` archives into memory
+//! one at a time so build artefacts can be scanned without unpacking them.
+//! Members are reported as `archive.ipa!/Payload/Foo.app/Foo` and read by the
+//! native parser; `codesign` cannot see inside archives. The bytes read out of
+//! one archive are capped, so a decompression bomb ends the archive early.
+
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result, bail};
+use super::{is_mach_o_magic, DiscoveredBinary};
+
+/// Archive extensions scanned member by member
+pub const ARCHIVE_EXTENSIONS: [&str; 3] = ["zip", "ipa", "tar"];
+
+/// Separates the archive path from the member path in reported paths
+pub const MEMBER_SEPARATOR: &str = "!/";
+
+/// Members larger than this are not read into memory
+const MAX_MEMBER_SIZE: u64 = 1 << 30;
+
+/// Most member bytes read out of one archive or package
+pub const MAX_READ_SIZE: u64 = 4 << 30;
+
+/// Whether `path` names an archive to scan inside
+pub fn is_archive(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| ARCHIVE_EXTENSIONS.iter().any(|known| extension.eq_ignore_ascii_case(known)))
+}
+
+/// Reported path of a member: `archive.ipa!/Payload/Foo.app/Foo`
+pub fn member_path(archive: &Path, member: &str) -> PathBuf {
+    PathBuf::from(format!("{}{}{}", archive.display(), MEMBER_SEPARATOR, member.trim_start_matches('/')))
+}
+
+/// Hand each Mach-O member of an archive, with its contents, to `found` as it is read.
+///
+/// Members found before the archive turns out to be corrupt have already been handed over.
+pub fn read_archive(path: &Path, found: &mut dyn FnMut(DiscoveredBinary)) -> Result<()> {
+    let reader = BufReader::new(File::open(path).with_context(|| format!("Failed to open archive: {}", path.display()))?);
+    let is_tar = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("tar"));
+    if is_tar {
+        read_tar(path, reader, found)
+    } else {
+        read_zip(path, reader, found)
+    }
+    .with_context(|| format!("Failed to read archive: {}", path.display()))
+}
+
+/// Mach-O members of a zip archive (`.zip`, `.ipa`)
+fn read_zip<R: Read + Seek>(archive_path: &Path, reader: R, found: &mut dyn FnMut(DiscoveredBinary)) -> Result<()> {
+    let mut archive = zip::ZipArchive::new(reader)?;
+    let mut budget = MAX_READ_SIZE;
+    for index in 0..archive.len() {
+        let mut member = archive.by_index(index)?;
+        if !member.is_file() {
+            continue;
+        }
+        let name = member.name().to_string();
+        let size = member.size();
+        if let Some(contents) = read_mach_o(&mut member, size, &mut budget)? {
+            found(DiscoveredBinary { path: member_path(archive_path, &name), contents: Some(contents), source: None });
+        }
+    }
+    Ok(())
+}
+
+/// Mach-O members of a tar archive
+fn read_tar<R: Read>(archive_path: &Path, reader: R, found: &mut dyn FnMut(DiscoveredBinary)) -> Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    let mut budget = MAX_READ_SIZE;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.to_string_lossy().to_string();
+        let size = entry.size();
+        if let Some(contents) = read_mach_o(&mut entry, size, &mut budget)? {
+            found(DiscoveredBinary { path: member_path(archive_path, &name), contents: Some(contents), source: None });
+        }
+    }
+    Ok(())
+}
+
+/// Read a member if it starts with a Mach-O magic; other members are left unread.
+///
+/// At most the `size` the archive declares is read, and taken out of `budget`;
+/// a member larger than what is left of the budget is an error.
+pub fn read_mach_o<R: Read + ?Sized>(member: &mut R, size: u64, budget: &mut u64) -> Result<Option<Vec<u8>>> {
+    if !(4..=MAX_MEMBER_SIZE).contains(&size) {
+        return Ok(None);
+    }
+    let mut magic = [0u8; 4];
+    member.read_exact(&mut magic)?;
+    if !is_mach_o_magic(&magic) {
+        return Ok(None);
+    }
+    if size > *budget {
+        bail!("Members exceed the {} GiB read limit", MAX_READ_SIZE >> 30);
+    }
+    *budget -= size;
+    let mut contents = Vec::with_capacity(size as usize);
+    contents.extend_from_slice(&magic);
+    member.take(size - 4).read_to_end(&mut contents)?;
+    Ok(Some(contents))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use crate::entitlements::native::fixtures::MachOBuilder;
+    use crate::entitlements::native::macho::CPU_TYPE_ARM64;
+
+    fn binary() -> Vec<u8> {
+        MachOBuilder::new(CPU_TYPE_ARM64, 0).build()
+    }
+
+    fn paths(binaries: &[DiscoveredBinary]) -> Vec<String> {
+        binaries.iter().map(|binary| binary.path.to_string_lossy().to_string()).collect()
+    }
+
+    fn collect(read: impl FnOnce(&mut dyn FnMut(DiscoveredBinary)) -> Result<()>) -> Result<Vec<DiscoveredBinary>> {
+        let mut binaries = Vec::new();
+        read(&mut |binary| binaries.push(binary))?;
+        Ok(binaries)
+    }
+
+    #[test]
+    fn test_is_archive() {
+        assert!(is_archive(Path::new("build/App.ipa")));
+        assert!(is_archive(Path::new("build/App.ZIP")));
+        assert!(is_archive(Path::new("dist/tools.tar")));
+        assert!(!is_archive(Path::new("/usr/bin/zip")));
+    }
+
+    #[test]
+    fn test_reads_mach_o_zip_members() {
+        let mut data = Cursor::new(Vec::new());
+        let mut writer = zip::ZipWriter::new(&mut data);
+        let options = zip::write::SimpleFileOptions::default();
+        writer.add_directory("Payload/Foo.app/", options).unwrap();
+        writer.start_file("Payload/Foo.app/Foo", options).unwrap();
+        writer.write_all(&binary()).unwrap();
+        writer.start_file("Payload/Foo.app/Info.plist", options).unwrap();
+        writer.write_all(b"<plist/>").unwrap();
+        writer.finish().unwrap();
+        data.set_position(0);
+
+        let binaries = collect(|found| read_zip(Path::new("App.ipa"), data, found)).unwrap();
+        assert_eq!(paths(&binaries), vec!["App.ipa!/Payload/Foo.app/Foo"]);
+        assert_eq!(binaries[0].contents.as_deref(), Some(binary().as_slice()));
+    }
+
+    #[test]
+    fn test_reads_mach_o_tar_members() {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, contents) in [("bin/tool", binary()), ("README", b"text".to_vec()), ("bin/empty", Vec::new())] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder.append_data(&mut header, name, contents.as_slice()).unwrap();
+        }
+        let data = builder.into_inner().unwrap();
+
+        let binaries = collect(|found| read_tar(Path::new("/ci/tools.tar"), Cursor::new(data), found)).unwrap();
+        assert_eq!(paths(&binaries), vec!["/ci/tools.tar!/bin/tool"]);
+    }
+
+    #[test]
+    fn test_corrupt_archive_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.zip");
+        std::fs::write(&path, b"not a zip").unwrap();
+        assert!(read_archive(&path, &mut |_| {}).is_err());
+    }
+
+    #[test]
+    fn test_reads_stop_at_the_budget() {
+        let member = binary();
+        let size = member.len() as u64;
+        let mut budget = size + 1;
+        assert!(read_mach_o(&mut member.as_slice(), size, &mut budget).unwrap().is_some());
+        assert_eq!(budget, 1);
+        assert!(read_mach_o(&mut member.as_slice(), size, &mut budget).is_err());
+
+        // Bytes past the declared size are not read
+        let mut budget = MAX_READ_SIZE;
+        let mut padded = member.clone();
+        padded.extend_from_slice(&[0; 64]);
+        assert_eq!(read_mach_o(&mut padded.as_slice(), size, &mut budget).unwrap(), Some(member));
+    }
+}
@@ -0,0 +1,422 @@
+//! Bundle structure
+//!
+//! Relates each binary to the bundles it sits in (`.app`, `.appex`, `.xpc`,
+//! `.framework`, `.systemextension`, and login items under
+//! `Contents/Library/LoginItems`). Nested code is compared with the main
+//! executable of its host app: a helper holding entitlements the app itself
+//! lacks is a privilege boundary worth reviewing.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use serde_json::Value;
+use crate::entitlements::extractor::EntitlementExtractor;
+use crate::models::BinaryResult;
+
+/// Where an app keeps the helper apps it registers as login items
+const LOGIN_ITEMS_DIR: &str = "Contents/Library/LoginItems";
+
+/// Info.plist locations relative to the bundle, for deep, shallow and versioned bundles
+const INFO_PLIST_PATHS: [&str; 4] = [
+    "Contents/Info.plist",
+    "Info.plist",
+    "Resources/Info.plist",
+    "Versions/Current/Resources/Info.plist",
+];
+
+/// What a bundle directory is, judged by its extension and location
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleKind {
+    App,
+    LoginItem,
+    AppExtension,
+    XpcService,
+    Framework,
+    SystemExtension,
+}
+
+impl BundleKind {
+    /// The kind of bundle `path` names, if it is one
+    pub fn of(path: &Path) -> Option<Self> {
+        let kind = match path.extension()?.to_str()? {
+            "app" if path.parent().is_some_and(|parent| parent.ends_with(LOGIN_ITEMS_DIR)) => BundleKind::LoginItem,
+            "app" => BundleKind::App,
+            "appex" => BundleKind::AppExtension,
+            "xpc" => BundleKind::XpcService,
+            "framework" => BundleKind::Framework,
+            "systemextension" => BundleKind::SystemExtension,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BundleKind::App => "app",
+            BundleKind::LoginItem => "login item",
+            BundleKind::AppExtension => "app extension",
+            BundleKind::XpcService => "XPC service",
+            BundleKind::Framework => "framework",
+            BundleKind::SystemExtension => "system extension",
+        }
+    }
+}
+
+impl fmt::Display for BundleKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The bundles containing `path`, outermost first
+pub fn containing_bundles(path: &Path) -> Vec<PathBuf> {
+    let mut bundles: Vec<PathBuf> = path
+        .ancestors()
+        .skip(1)
+        .filter(|ancestor| BundleKind::of(ancestor).is_some())
+        .map(Path::to_path_buf)
+        .collect();
+    bundles.reverse();
+    bundles
+}
+
+/// The innermost bundle containing `path`
+pub fn parent_bundle(path: &Path) -> Option<PathBuf> {
+    containing_bundles(path).pop()
+}
+
+/// Where `path` can be read on this host: archive members cannot, and paths
+/// under a foreign root resolve their symlinks inside the image
+fn on_disk(path: &Path, root: Option<&Path>) -> Option<PathBuf> {
+    if path.to_string_lossy().contains(super::archive::MEMBER_SEPARATOR) {
+        return None;
+    }
+    match root {
+        Some(root) => crate::entitlements::native::root::resolve(root, path).ok(),
+        None => Some(path.to_path_buf()),
+    }
+}
+
+/// Name of the bundle's main executable: `CFBundleExecutable`, or the bundle name
+fn executable_name(bundle: &Path, root: Option<&Path>) -> Option<String> {
+    INFO_PLIST_PATHS
+        .iter()
+        .find_map(|plist| {
+            let dict: plist::Dictionary = plist::from_file(on_disk(&bundle.join(plist), root)?).ok()?;
+            dict.get("CFBundleExecutable")?.as_string().map(str::to_string)
+        })
+        .or_else(|| Some(bundle.file_stem()?.to_str()?.to_string()))
+}
+
+/// Whether `binary` is the main executable of `bundle`
+pub fn is_main_executable(binary: &Path, bundle: &Path, root: Option<&Path>) -> bool {
+    let (Ok(relative), Some(name)) = (binary.strip_prefix(bundle), executable_name(bundle, root)) else {
+        return false;
+    };
+    let components: Vec<&str> = relative
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect();
+    match components.as_slice() {
+        ["Contents", "MacOS", executable] | [executable] | ["Versions", _, executable] => *executable == name,
+        _ => false,
+    }
+}
+
+/// The main executable of `bundle`: a binary `is_scanned`, or else a file on disk
+pub fn main_executable(bundle: &Path, is_scanned: &dyn Fn(&Path) -> bool, root: Option<&Path>) -> Option<PathBuf> {
+    let name = executable_name(bundle, root)?;
+    let candidates = [
+        bundle.join("Contents/MacOS").join(&name),
+        bundle.join(&name),
+        bundle.join("Versions/Current").join(&name),
+    ];
+    candidates.iter().find(|candidate| is_scanned(candidate)).cloned().or_else(|| {
+        candidates
+            .into_iter()
+            .find(|candidate| on_disk(candidate, root).is_some_and(|path| path.is_file()))
+    })
+}
+
+/// Main executable of the app hosting `binary`.
+///
+/// A bundle's main executable is hosted by the enclosing bundle; any other
+/// binary by its own bundle. Frameworks are libraries of their host, not
+/// hosts themselves, so they are skipped.
+pub fn host_executable(binary: &Path, is_scanned: &dyn Fn(&Path) -> bool, root: Option<&Path>) -> Option<PathBuf> {
+    let bundles = containing_bundles(binary);
+    let mut depth = bundles.len();
+    if bundles.last().is_some_and(|bundle| is_main_executable(binary, bundle, root)) {
+        depth -= 1;
+    }
+    bundles[..depth]
+        .iter()
+        .rev()
+        .filter(|bundle| BundleKind::of(bundle) != Some(BundleKind::Framework))
+        .find_map(|bundle| main_executable(bundle, is_scanned, root))
+        .filter(|host| host != binary)
+}
+
+/// Main executable of the process that loads `binary`, which
+/// `@executable_path` names.
+///
+/// Binaries outside bundles, in a `Contents/MacOS` directory, or the main
+/// executable of a bundle other than a framework run as their own process;
+/// other nested code (frameworks, plug-ins, dylibs) is loaded by its host.
+pub fn loading_executable(binary: &Path, root: Option<&Path>) -> Option<PathBuf> {
+    let own_process = match containing_bundles(binary).last() {
+        None => true,
+        Some(bundle) => {
+            binary.parent().is_some_and(|parent| parent.ends_with("Contents/MacOS"))
+                || (BundleKind::of(bundle) != Some(BundleKind::Framework) && is_main_executable(binary, bundle, root))
+        }
+    };
+    if own_process {
+        return Some(binary.to_path_buf());
+    }
+    host_executable(binary, &|_| false, root)
+}
+
+/// Entitlement keys a binary actually holds; keys set to `false` grant nothing
+pub fn held_entitlements(entitlements: &HashMap<String, Value>) -> Vec<String> {
+    let mut keys: Vec<String> = entitlements
+        .iter()
+        .filter(|(_, value)| **value != Value::Bool(false))
+        .map(|(key, _)| key.clone())
+        .collect();
+    keys.sort();
+    keys
+}
+
+/// Fill in `parent_bundle` and `entitlements_beyond_host` for scan results.
+///
+/// `held` maps every scanned binary to its unfiltered entitlement keys; hosts
+/// outside the scan are looked up under `root` and read with `extractor`.
+pub fn annotate(
+    results: &mut [BinaryResult],
+    held: &HashMap<PathBuf, Vec<String>>,
+    extractor: &dyn EntitlementExtractor,
+    root: Option<&Path>,
+) {
+    let mut unscanned_hosts: HashMap<PathBuf, Option<Vec<String>>> = HashMap::new();
+
+    for result in results.iter_mut() {
+        let path = PathBuf::from(&result.path);
+        result.parent_bundle = parent_bundle(&path).map(|bundle| bundle.to_string_lossy().to_string());
+
+        let Some(host) = host_executable(&path, &|candidate| held.contains_key(candidate), root) else {
+            continue;
+        };
+        let host_keys = match held.get(&host) {
+            Some(keys) => Some(keys),
+            None => unscanned_hosts
+                .entry(host.clone())
+                .or_insert_with(|| extractor.extract(&host).ok().map(|report| held_entitlements(&report.entitlements)))
+                .as_ref(),
+        };
+        let Some(host_keys) = host_keys else {
+            continue;
+        };
+        let own_keys = held.get(&path).cloned().unwrap_or_else(|| held_entitlements(&result.entitlements));
+        result.entitlements_beyond_host = own_keys.into_iter().filter(|key| !host_keys.contains(key)).collect();
+    }
+}
+
+/// Order results so each bundle's own binaries precede its nested bundles
+pub fn tree_order(results: &mut [BinaryResult]) {
+    results.sort_by_cached_key(|result| {
+        let path = PathBuf::from(&result.path);
+        let mut key: Vec<(bool, PathBuf)> = containing_bundles(&path).into_iter().map(|bundle| (true, bundle)).collect();
+        key.push((false, path));
+        key
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use anyhow::{Result, anyhow};
+    use serde_json::json;
+    use crate::models::SignatureReport;
+
+    /// An app with a login item, an XPC service inside a framework, and a bare helper tool
+    fn app() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let app = dir.path().join("Example.app/Contents");
+        for executable in [
+            "MacOS/Example",
+            "MacOS/helper",
+            "Library/LoginItems/Launcher.app/Contents/MacOS/Launcher",
+            "Frameworks/Kit.framework/Versions/A/Kit",
+            "Frameworks/Kit.framework/Versions/A/XPCServices/Fetch.xpc/Contents/MacOS/Fetch",
+        ] {
+            let path = app.join(executable);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, b"binary").unwrap();
+        }
+        std::os::unix::fs::symlink("A", app.join("Frameworks/Kit.framework/Versions/Current")).unwrap();
+        dir
+    }
+
+    fn result(path: &Path, keys: &[&str]) -> BinaryResult {
+        serde_json::from_value(json!({
+            "path": path.to_str().unwrap(),
+            "entitlements": keys.iter().map(|key| (key.to_string(), json!(true))).collect::<serde_json::Map<_, _>>(),
+            "entitlement_count": keys.len(),
+        }))
+        .unwrap()
+    }
+
+    struct NoExtractor;
+
+    impl EntitlementExtractor for NoExtractor {
+        fn extract(&self, binary_path: &Path) -> Result<SignatureReport> {
+            Err(anyhow!("not scanned: {}", binary_path.display()))
+        }
+    }
+
+    #[test]
+    fn test_bundle_kinds() {
+        assert_eq!(BundleKind::of(Path::new("/Applications/Example.app")), Some(BundleKind::App));
+        assert_eq!(
+            BundleKind::of(Path::new("/Applications/Example.app/Contents/Library/LoginItems/Launcher.app")),
+            Some(BundleKind::LoginItem)
+        );
+        assert_eq!(BundleKind::of(Path::new("/Library/SystemExtensions/x/net.systemextension")), Some(BundleKind::SystemExtension));
+        assert_eq!(BundleKind::of(Path::new("/usr/bin/tool")), None);
+    }
+
+    #[test]
+    fn test_containing_bundles() {
+        let path = Path::new("/A.app/Contents/PlugIns/Share.appex/Contents/MacOS/Share");
+        assert_eq!(
+            containing_bundles(path),
+            vec![PathBuf::from("/A.app"), PathBuf::from("/A.app/Contents/PlugIns/Share.appex")]
+        );
+        assert_eq!(parent_bundle(Path::new("/usr/bin/tool")), None);
+    }
+
+    #[test]
+    fn test_hosts() {
+        let dir = app();
+        let app = dir.path().join("Example.app");
+        let main = app.join("Contents/MacOS/Example");
+        let kit = app.join("Contents/Frameworks/Kit.framework");
+
+        let host = |binary: &Path| host_executable(binary, &|_| false, None);
+
+        assert!(is_main_executable(&main, &app, None));
+        assert!(is_main_executable(&kit.join("Versions/A/Kit"), &kit, None));
+        assert_eq!(host(&main), None);
+        assert_eq!(host(&app.join("Contents/MacOS/helper")), Some(main.clone()));
+        assert_eq!(host(&app.join("Contents/Library/LoginItems/Launcher.app/Contents/MacOS/Launcher")), Some(main.clone()));
+        assert_eq!(host(&kit.join("Versions/A/Kit")), Some(main.clone()));
+        assert_eq!(host(&kit.join("Versions/A/XPCServices/Fetch.xpc/Contents/MacOS/Fetch")), Some(main));
+    }
+
+    #[test]
+    fn test_loading_executables() {
+        let dir = app();
+        let app = dir.path().join("Example.app");
+        let main = app.join("Contents/MacOS/Example");
+        let kit = app.join("Contents/Frameworks/Kit.framework");
+        let fetch = kit.join("Versions/A/XPCServices/Fetch.xpc/Contents/MacOS/Fetch");
+        let loader = |binary: &Path| loading_executable(binary, None);
+
+        assert_eq!(loader(&main), Some(main.clone()));
+        assert_eq!(loader(&app.join("Contents/MacOS/helper")), Some(app.join("Contents/MacOS/helper")));
+        assert_eq!(loader(&fetch), Some(fetch.clone()));
+        assert_eq!(loader(&kit.join("Versions/A/Kit")), Some(main.clone()));
+        assert_eq!(loader(&app.join("Contents/PlugIns/libplugin.dylib")), Some(main));
+        assert_eq!(loader(Path::new("/usr/local/bin/tool")), Some(PathBuf::from("/usr/local/bin/tool")));
+    }
+
+    #[test]
+    fn test_declared_executable_name() {
+        let dir = app();
+        let app = dir.path().join("Example.app");
+        fs::write(
+            app.join("Contents/Info.plist"),
+            b"<plist><dict><key>CFBundleExecutable</key><string>helper</string></dict></plist>",
+        )
+        .unwrap();
+        assert_eq!(main_executable(&app, &|_| false, None), Some(app.join("Contents/MacOS/helper")));
+        assert!(!is_main_executable(&app.join("Contents/MacOS/Example"), &app, None));
+    }
+
+    #[test]
+    fn test_annotate_flags_entitlements_beyond_host() {
+        let dir = app();
+        let app = dir.path().join("Example.app");
+        let main = app.join("Contents/MacOS/Example");
+        let launcher = app.join("Contents/Library/LoginItems/Launcher.app/Contents/MacOS/Launcher");
+        let held = HashMap::from([
+            (main.clone(), vec!["com.apple.security.app-sandbox".to_string()]),
+            (launcher.clone(), vec!["com.apple.security.app-sandbox".to_string(), "com.apple.private.tcc.allow".to_string()]),
+        ]);
+        let mut results = vec![result(&main, &["com.apple.security.app-sandbox"]), result(&launcher, &["com.apple.private.tcc.allow"])];
+
+        annotate(&mut results, &held, &NoExtractor, None);
+
+        assert_eq!(results[0].parent_bundle.as_deref(), app.to_str());
+        assert!(results[0].entitlements_beyond_host.is_empty());
+        assert_eq!(results[1].parent_bundle.as_deref(), launcher.ancestors().nth(3).unwrap().to_str());
+        assert_eq!(results[1].entitlements_beyond_host, vec!["com.apple.private.tcc.allow"]);
+    }
+
+    #[test]
+    fn test_unreadable_host_is_not_compared() {
+        let dir = app();
+        let helper = dir.path().join("Example.app/Contents/MacOS/helper");
+        let mut results = vec![result(&helper, &["com.apple.private.tcc.allow"])];
+
+        annotate(&mut results, &HashMap::new(), &NoExtractor, None);
+        assert!(results[0].entitlements_beyond_host.is_empty());
+    }
+
+    #[test]
+    fn test_hosts_inside_archives_are_found_among_scanned_binaries() {
+        let app = Path::new("/tmp/App.ipa!/Payload/Foo.app");
+        let main = app.join("Foo");
+        let share = app.join("PlugIns/Share.appex/Share");
+        let held = HashMap::from([
+            (main.clone(), vec!["get-task-allow".to_string()]),
+            (share.clone(), vec!["get-task-allow".to_string(), "com.apple.private.tcc.allow".to_string()]),
+        ]);
+        let mut results = vec![result(&share, &["get-task-allow", "com.apple.private.tcc.allow"])];
+
+        annotate(&mut results, &held, &NoExtractor, None);
+        assert_eq!(results[0].entitlements_beyond_host, vec!["com.apple.private.tcc.allow"]);
+    }
+
+    #[test]
+    fn test_hosts_under_a_root_resolve_inside_the_image() {
+        let dir = app();
+        let image = dir.path();
+        let versions = image.join("Example.app/Contents/Frameworks/Kit.framework/Versions");
+        let kit = versions.parent().unwrap();
+        // Absolute links point back into the image, not at the host
+        fs::remove_file(versions.join("Current")).unwrap();
+        std::os::unix::fs::symlink(Path::new("/").join(versions.join("A").strip_prefix(image).unwrap()), versions.join("Current")).unwrap();
+
+        assert_eq!(main_executable(kit, &|_| false, None), None);
+        assert_eq!(main_executable(kit, &|_| false, Some(image)), Some(versions.join("Current/Kit")));
+    }
+
+    #[test]
+    fn test_tree_order_lists_bundle_binaries_before_nested_bundles() {
+        let paths = [
+            "/A.app/Contents/Library/LoginItems/L.app/Contents/MacOS/L",
+            "/A.app/Contents/MacOS/A",
+            "/usr/bin/tool",
+        ];
+        let mut results: Vec<BinaryResult> = paths.iter().map(|path| result(Path::new(path), &[])).collect();
+        tree_order(&mut results);
+        let ordered: Vec<&str> = results.iter().map(|result| result.path.as_str()).collect();
+        assert_eq!(ordered, vec!["/usr/bin/tool", "/A.app/Contents/MacOS/A", paths[0]]);
+    }
+}
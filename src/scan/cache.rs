@@ -0,0 +1,403 @@
+//! Persistent extraction cache
+//!
+//! With `--cache`, signature reports are kept under `~/.cache/listent` between
+//! scans, keyed on the device, inode, size, mtime and ctime of the file read,
+//! so unchanged binaries are not parsed again. Each combination of backend,
+//! read options and listent version gets its own cache file, since they
+//! change the report.
+//!
+//! Reports also read the enclosing bundle's Info.plist, provisioning profile
+//! and CodeResources, so those files are part of the key. What depends on the
+//! clock or on which libraries exist (profile expiry, hijackable library
+//! loads) is worked out again on every scan, and with `--verify-resources`
+//! binaries under a resource seal are always read afresh, as the seal covers
+//! every file of the bundle.
+//!
+//! Entries for paths that no longer exist are dropped when the cache is
+//! saved; `--rebuild-cache` starts from an empty cache. Archive members have
+//! no file identity of their own and are not cached.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::entitlements::native::{ReadOptions, dylibs, profile, seal};
+use crate::models::{ExtractorBackend, SignatureReport};
+use super::bundles;
+
+/// Cache directory under the home directory
+const CACHE_DIR: &str = ".cache/listent";
+
+/// Identity of a file's contents as far as the filesystem can tell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FileKey {
+    pub dev: u64,
+    pub inode: u64,
+    pub size: u64,
+    /// Seconds and nanoseconds
+    pub mtime: (i64, i64),
+    pub ctime: (i64, i64),
+}
+
+impl FileKey {
+    /// Key of the file at `path`, following symlinks
+    pub fn of(path: &Path) -> std::io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        Ok(Self {
+            dev: metadata.dev(),
+            inode: metadata.ino(),
+            size: metadata.size(),
+            mtime: (metadata.mtime(), metadata.mtime_nsec()),
+            ctime: (metadata.ctime(), metadata.ctime_nsec()),
+        })
+    }
+}
+
+/// Files the native reader also reads, relative to the binary's directory: the
+/// Info.plist, provisioning profile and CodeResources of a deep bundle (the
+/// binary sits in `Contents/MacOS`), then of a shallow one
+const BUNDLE_FILES: [&str; 5] = [
+    "../Info.plist",
+    "../embedded.provisionprofile",
+    "../_CodeSignature/CodeResources",
+    "Info.plist",
+    "_CodeSignature/CodeResources",
+];
+
+/// Keys of the bundle files a report of `path` depends on; `None` where absent
+fn dependency_keys(path: &Path) -> Vec<Option<FileKey>> {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    BUNDLE_FILES.iter().map(|file| FileKey::of(&dir.join(file)).ok()).collect()
+}
+
+/// On-disk layout of a cache file
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    /// Backend and options the reports were read with
+    options: String,
+    entries: Vec<CacheEntry>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    path: PathBuf,
+    #[serde(flatten)]
+    key: FileKey,
+    /// Keys of the bundle files the report was read with
+    dependencies: Vec<Option<FileKey>>,
+    report: SignatureReport,
+}
+
+/// Reports from earlier scans, plus those extracted during this one
+pub struct ExtractionCache {
+    path: PathBuf,
+    options: String,
+    /// Whether resource seals are verified, so sealed bundles must be read afresh
+    verify_resources: bool,
+    /// Foreign filesystem root, for looking up libraries
+    root: Option<PathBuf>,
+    entries: HashMap<PathBuf, CacheEntry>,
+    /// Whether the cache file is being replaced by an empty one
+    rebuild: bool,
+    added: Mutex<HashMap<PathBuf, CacheEntry>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl ExtractionCache {
+    /// `~/.cache/listent`
+    pub fn default_dir() -> Result<PathBuf> {
+        let home = dirs::home_dir().ok_or_else(|| anyhow!("Could not determine home directory"))?;
+        Ok(home.join(CACHE_DIR))
+    }
+
+    /// Description of what the reports depend on besides the file itself
+    fn options_key(backend: &ExtractorBackend, options: &ReadOptions) -> String {
+        format!(
+            "listent {} backend={} verify={} hardening={} verify-resources={} root={}",
+            env!("CARGO_PKG_VERSION"),
+            backend,
+            options.verify,
+            options.hardening,
+            options.verify_resources,
+            options.root.as_ref().map(|root| root.display().to_string()).unwrap_or_default(),
+        )
+    }
+
+    /// Open the cache of reports read by `backend` with `options` in `dir`; a
+    /// missing, unreadable or stale cache file (or `rebuild`) starts an empty cache
+    pub fn open(dir: &Path, backend: &ExtractorBackend, options: &ReadOptions, rebuild: bool) -> Self {
+        let key = Self::options_key(backend, options);
+        let digest = Sha256::digest(key.as_bytes());
+        let name: String = digest[..8].iter().map(|byte| format!("{:02x}", byte)).collect();
+        let path = dir.join(format!("reports-{}.json", name));
+
+        let entries = if rebuild { HashMap::new() } else { Self::load(&path, &key).unwrap_or_default() };
+        Self {
+            path,
+            options: key,
+            verify_resources: options.verify_resources,
+            root: options.root.clone(),
+            entries,
+            rebuild,
+            added: Mutex::new(HashMap::new()),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    fn load(path: &Path, options: &str) -> Result<HashMap<PathBuf, CacheEntry>> {
+        let file: CacheFile = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        if file.options != options {
+            return Ok(HashMap::new());
+        }
+        Ok(file.entries.into_iter().map(|entry| (entry.path.clone(), entry)).collect())
+    }
+
+    /// The cached report of the file at `path`, or the result of `extract`,
+    /// which is cached when it succeeds
+    pub fn get_or_extract(&self, path: &Path, extract: impl FnOnce() -> Result<SignatureReport>) -> Result<SignatureReport> {
+        let key = match FileKey::of(path) {
+            Ok(key) => key,
+            Err(_) => return extract(),
+        };
+        if self.verify_resources && seal::find_seal(path, self.root.as_deref()).is_some() {
+            return extract();
+        }
+        let dependencies = dependency_keys(path);
+        if let Some(entry) = self.entries.get(path).filter(|entry| entry.key == key && entry.dependencies == dependencies) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            let mut report = entry.report.clone();
+            self.refresh(path, &mut report);
+            return Ok(report);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let report = extract()?;
+        let entry = CacheEntry { path: path.to_path_buf(), key, dependencies, report: report.clone() };
+        self.added.lock().unwrap().insert(path.to_path_buf(), entry);
+        Ok(report)
+    }
+
+    /// Work out again what a cached report got from the clock and from
+    /// libraries beside the binary
+    fn refresh(&self, path: &Path, report: &mut SignatureReport) {
+        if let Some(profile) = &mut report.provisioning_profile {
+            profile.expired = profile.expiration_date.as_deref().is_some_and(profile::has_expired);
+        }
+        let root = self.root.as_deref();
+        let executable = bundles::loading_executable(path, root).unwrap_or_else(|| path.to_path_buf());
+        report.dylib_hijack_risks = dylibs::hijack_risks(path, &executable, &report.entitlements, &report.dylibs, &report.rpaths, root);
+    }
+
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Write the cache back if anything changed, without the entries of paths
+    /// that no longer exist, replacing the file atomically
+    pub fn save(self) -> Result<()> {
+        let added = self.added.into_inner().unwrap();
+        let mut entries = self.entries;
+        let cached = entries.len();
+        entries.retain(|path, _| path.exists());
+        if added.is_empty() && entries.len() == cached && !self.rebuild && self.path.exists() {
+            return Ok(());
+        }
+        entries.extend(added);
+
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create cache directory: {}", dir.display()))?;
+        let file = CacheFile {
+            options: self.options,
+            entries: entries.into_values().collect(),
+        };
+        // Concurrent scans each write their own file; the last to finish wins
+        let mut writer = BufWriter::new(tempfile::NamedTempFile::new_in(dir)?);
+        serde_json::to_writer(&mut writer, &file)?;
+        let temp = writer.into_inner().map_err(|e| e.into_error())?;
+        temp.as_file().sync_all()?;
+        temp.persist(&self.path).with_context(|| format!("Failed to write cache: {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::models::ProvisioningProfile;
+
+    fn open(dir: &Path, options: &ReadOptions, rebuild: bool) -> ExtractionCache {
+        ExtractionCache::open(dir, &ExtractorBackend::Native, options, rebuild)
+    }
+
+    fn report() -> SignatureReport {
+        SignatureReport {
+            entitlements: HashMap::from([("com.apple.security.get-task-allow".to_string(), json!(true))]),
+            warnings: vec!["Could not read bundle Info.plist: truncated".to_string()],
+            ..SignatureReport::default()
+        }
+    }
+
+    #[test]
+    fn test_reports_are_reused_across_scans() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("tool");
+        fs::write(&binary, b"binary").unwrap();
+        let cache_dir = dir.path().join("cache");
+
+        let cache = open(&cache_dir, &ReadOptions::default(), false);
+        let first = cache.get_or_extract(&binary, || Ok(report())).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (0, 1));
+        cache.save().unwrap();
+
+        let cache = open(&cache_dir, &ReadOptions::default(), false);
+        let second = cache.get_or_extract(&binary, || panic!("should not extract again")).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (1, 0));
+        assert_eq!(second.entitlements, first.entitlements);
+        assert_eq!(second.warnings, first.warnings);
+    }
+
+    #[test]
+    fn test_changed_files_options_and_rebuilds_miss() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("tool");
+        fs::write(&binary, b"binary").unwrap();
+        let cache_dir = dir.path().join("cache");
+
+        let cache = open(&cache_dir, &ReadOptions::default(), false);
+        cache.get_or_extract(&binary, || Ok(report())).unwrap();
+        cache.save().unwrap();
+
+        for cache in [
+            open(&cache_dir, &ReadOptions { verify: true, ..Default::default() }, false),
+            open(&cache_dir, &ReadOptions::default(), true),
+        ] {
+            cache.get_or_extract(&binary, || Ok(report())).unwrap();
+            assert_eq!((cache.hits(), cache.misses()), (0, 1));
+        }
+
+        fs::write(&binary, b"changed binary").unwrap();
+        let cache = open(&cache_dir, &ReadOptions::default(), false);
+        cache.get_or_extract(&binary, || Ok(report())).unwrap();
+        assert_eq!(cache.misses(), 1);
+    }
+
+    #[test]
+    fn test_failed_extractions_are_not_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("tool");
+        fs::write(&binary, b"binary").unwrap();
+
+        let cache = open(dir.path(), &ReadOptions::default(), false);
+        assert!(cache.get_or_extract(&binary, || Err(anyhow!("unreadable"))).is_err());
+        assert!(cache.get_or_extract(&binary, || Ok(report())).is_ok());
+        assert_eq!(cache.misses(), 2);
+    }
+
+    #[test]
+    fn test_corrupt_cache_starts_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = open(dir.path(), &ReadOptions::default(), false);
+        fs::write(&cache.path, b"{ not json").unwrap();
+
+        let cache = open(dir.path(), &ReadOptions::default(), false);
+        assert!(cache.entries.is_empty());
+    }
+
+    #[test]
+    fn test_changed_bundle_files_miss() {
+        let dir = tempfile::tempdir().unwrap();
+        let contents = dir.path().join("Example.app/Contents");
+        let binary = contents.join("MacOS/Example");
+        fs::create_dir_all(binary.parent().unwrap()).unwrap();
+        fs::write(&binary, b"binary").unwrap();
+        let cache_dir = dir.path().join("cache");
+
+        for info_plist in [None, Some(&b"<plist/>"[..]), Some(&b"<plist><dict/></plist>"[..])] {
+            if let Some(info_plist) = info_plist {
+                fs::write(contents.join("Info.plist"), info_plist).unwrap();
+            }
+            let cache = open(&cache_dir, &ReadOptions::default(), false);
+            cache.get_or_extract(&binary, || Ok(report())).unwrap();
+            assert_eq!(cache.misses(), 1);
+            cache.save().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_profile_expiry_is_checked_on_every_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("tool");
+        fs::write(&binary, b"binary").unwrap();
+        let expiring = SignatureReport {
+            provisioning_profile: Some(ProvisioningProfile {
+                name: None,
+                team_identifier: None,
+                team_name: None,
+                expiration_date: Some("2001-01-01T00:00:00Z".to_string()),
+                expired: false,
+                entitlements: HashMap::new(),
+                unauthorized_entitlements: Vec::new(),
+            }),
+            ..report()
+        };
+
+        let cache = open(dir.path(), &ReadOptions::default(), false);
+        cache.get_or_extract(&binary, || Ok(expiring)).unwrap();
+        cache.save().unwrap();
+
+        let cache = open(dir.path(), &ReadOptions::default(), false);
+        let cached = cache.get_or_extract(&binary, || panic!("should not extract again")).unwrap();
+        assert!(cached.provisioning_profile.unwrap().expired);
+    }
+
+    #[test]
+    fn test_sealed_bundles_are_read_afresh_when_verifying_resources() {
+        let dir = tempfile::tempdir().unwrap();
+        let contents = dir.path().join("Example.app/Contents");
+        let binary = contents.join("MacOS/Example");
+        fs::create_dir_all(binary.parent().unwrap()).unwrap();
+        fs::create_dir_all(contents.join("_CodeSignature")).unwrap();
+        fs::write(&binary, b"binary").unwrap();
+        fs::write(contents.join(seal::CODE_RESOURCES_PATH), b"<plist/>").unwrap();
+        let options = ReadOptions { verify_resources: true, ..Default::default() };
+
+        let cache = open(dir.path(), &options, false);
+        cache.get_or_extract(&binary, || Ok(report())).unwrap();
+        cache.save().unwrap();
+
+        let cache = open(dir.path(), &options, false);
+        cache.get_or_extract(&binary, || Ok(report())).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (0, 0));
+    }
+
+    #[test]
+    fn test_entries_of_removed_files_are_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = dir.path().join("cache");
+        let [kept, removed] = ["kept", "removed"].map(|name| dir.path().join(name));
+        let cache = open(&cache_dir, &ReadOptions::default(), false);
+        for binary in [&kept, &removed] {
+            fs::write(binary, b"binary").unwrap();
+            cache.get_or_extract(binary, || Ok(report())).unwrap();
+        }
+        cache.save().unwrap();
+
+        fs::remove_file(&removed).unwrap();
+        open(&cache_dir, &ReadOptions::default(), false).save().unwrap();
+
+        let cache = open(&cache_dir, &ReadOptions::default(), false);
+        assert_eq!(cache.entries.keys().collect::<Vec<_>>(), vec![&kept]);
+    }
+}
@@ -0,0 +1,243 @@
+//! Path filters with gitignore syntax
+//!
+//! Used for `--exclude`/`--include`, `.listentignore` files and the monitor
+//! and daemon path filters, so exclusion lists read the same everywhere:
+//! - `name` or `*.dylib` (no slash) matches that name at any depth
+//! - `/Applications` or `Contents/Frameworks` (a leading or inner slash) is
+//!   anchored: to `/` on the command line and in monitor or daemon filters,
+//!   to the directory holding the `.listentignore` file otherwise
+//! - `*` and `?` stay within a path component; `**` spans any number of them
+//! - A trailing `/` only matches directories
+//! - A leading `!` negates; the last matching pattern wins
+//! - Blank lines and lines starting with `#` are ignored
+//!
+//! A pattern matching a directory applies to everything below it. Invalid
+//! patterns fall back to matching literally, as entitlement filters do.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Result, anyhow};
+use glob::{MatchOptions, Pattern};
+
+/// Name of the per-directory ignore file
+pub const IGNORE_FILE_NAME: &str = ".listentignore";
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// One line of a filter
+#[derive(Debug, Clone)]
+struct PathRule {
+    pattern: Pattern,
+    negated: bool,
+    directory_only: bool,
+}
+
+impl PathRule {
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, text) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let directory_only = text.len() > 1 && text.ends_with('/');
+        let text = if directory_only { &text[..text.len() - 1] } else { text };
+        let anchored = text.contains('/');
+        let text = text.trim_start_matches('/');
+        if text.is_empty() {
+            return None;
+        }
+
+        let glob = if anchored { text.to_string() } else { format!("**/{}", text) };
+        let pattern = Pattern::new(&glob).unwrap_or_else(|_| {
+            let literal = Pattern::escape(text);
+            Pattern::new(&if anchored { literal.clone() } else { format!("**/{}", literal) }).expect("escaped pattern")
+        });
+        Some(Self { pattern, negated, directory_only })
+    }
+
+    fn matches(&self, relative: &str, is_dir: bool) -> bool {
+        (is_dir || !self.directory_only) && self.pattern.matches_with(relative, MATCH_OPTIONS)
+    }
+}
+
+/// Ordered patterns relative to a base directory
+#[derive(Debug, Clone, Default)]
+pub struct PathFilter {
+    base: PathBuf,
+    rules: Vec<PathRule>,
+}
+
+impl PathFilter {
+    /// Patterns anchored at `base`
+    pub fn new<S: AsRef<str>>(patterns: &[S], base: &Path) -> Self {
+        Self {
+            base: base.to_path_buf(),
+            rules: patterns.iter().filter_map(|pattern| PathRule::parse(pattern.as_ref())).collect(),
+        }
+    }
+
+    /// Command-line, monitor or daemon patterns, anchored at `/`
+    pub fn from_patterns<S: AsRef<str>>(patterns: &[S]) -> Self {
+        Self::new(patterns, Path::new("/"))
+    }
+
+    /// The ignore file at `path`, anchored at its directory
+    pub fn from_ignore_file(path: &Path, base: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let lines: Vec<&str> = contents.lines().collect();
+        Ok(Self::new(&lines, base))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether the last pattern matching `path` itself selects it (`Some(true)`)
+    /// or negates it (`Some(false)`); `None` when no pattern matches
+    pub fn matched(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let relative = path.strip_prefix(&self.base).ok()?.to_str()?;
+        if relative.is_empty() {
+            return None;
+        }
+        self.rules.iter().rev().find(|rule| rule.matches(relative, is_dir)).map(|rule| !rule.negated)
+    }
+
+    /// Like [`matched`](Self::matched), falling back to the nearest ancestor directory a pattern matches
+    pub fn decide(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let mut is_dir = is_dir;
+        for candidate in path.ancestors() {
+            if let Some(selected) = self.matched(candidate, is_dir) {
+                return Some(selected);
+            }
+            is_dir = true;
+        }
+        None
+    }
+
+    /// Whether a file is selected: matched by a pattern, or by nothing when
+    /// every pattern is a negation
+    pub fn selects(&self, path: &Path) -> bool {
+        self.decide(path, false).unwrap_or_else(|| self.rules.iter().all(|rule| rule.negated))
+    }
+}
+
+/// Check command-line and configuration patterns before use; `/` alone matches everything
+pub fn validate_path_patterns<S: AsRef<str>>(patterns: &[S]) -> Result<()> {
+    for pattern in patterns {
+        let pattern = pattern.as_ref();
+        let text = pattern.strip_prefix('!').unwrap_or(pattern);
+        Pattern::new(text).map_err(|e| anyhow!("Invalid path pattern '{}': {}", pattern, e))?;
+    }
+    Ok(())
+}
+
+/// A monitor or daemon path filter as a pattern: an absolute path that exists
+/// is matched literally, even with glob characters in it (`Foo [Beta].app`)
+pub fn filter_pattern(filter: &str) -> String {
+    let path = Path::new(filter);
+    if path.is_absolute() && path.exists() {
+        Pattern::escape(filter)
+    } else {
+        filter.to_string()
+    }
+}
+
+/// Whether a pattern is a plain path rather than a glob or a negation
+pub fn is_plain_path(pattern: &str) -> bool {
+    !pattern.starts_with('!') && !crate::entitlements::pattern_matcher::is_glob_pattern(pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn excluded(filter: &PathFilter, path: &str, is_dir: bool) -> bool {
+        filter.decide(Path::new(path), is_dir) == Some(true)
+    }
+
+    #[test]
+    fn test_names_match_at_any_depth() {
+        let filter = PathFilter::from_patterns(&["node_modules", "*.dylib"]);
+        assert!(excluded(&filter, "/src/app/node_modules", true));
+        assert!(excluded(&filter, "/src/app/node_modules/bin/tool", false));
+        assert!(excluded(&filter, "/usr/lib/libfoo.dylib", false));
+        assert!(!excluded(&filter, "/usr/bin/node", false));
+    }
+
+    #[test]
+    fn test_slashes_anchor_patterns() {
+        let filter = PathFilter::from_patterns(&["/Applications/Xcode.app", "/System/**/Frameworks/*.framework"]);
+        assert!(excluded(&filter, "/Applications/Xcode.app/Contents/MacOS/Xcode", false));
+        assert!(!excluded(&filter, "/Users/me/Applications/Xcode.app", true));
+        assert!(excluded(&filter, "/System/Library/Frameworks/AppKit.framework", true));
+        assert!(excluded(&filter, "/System/Library/PrivateFrameworks/X/Frameworks/Y.framework", true));
+        // `*` does not cross directories
+        assert_eq!(PathFilter::from_patterns(&["/usr/*/tool"]).decide(Path::new("/usr/a/b/tool"), false), None);
+    }
+
+    #[test]
+    fn test_directory_only_and_negated_patterns() {
+        let filter = PathFilter::from_patterns(&["build/", "*.app", "!Keep.app"]);
+        assert!(excluded(&filter, "/x/build", true));
+        assert!(!excluded(&filter, "/x/build", false));
+        assert!(excluded(&filter, "/Applications/Other.app", true));
+        assert_eq!(filter.decide(Path::new("/Applications/Keep.app/Contents/MacOS/Keep"), false), Some(false));
+    }
+
+    #[test]
+    fn test_ignore_files_are_relative_to_their_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let ignore = dir.path().join(IGNORE_FILE_NAME);
+        fs::write(&ignore, "# vendored SDKs\n\n/sdk\n.git/\n").unwrap();
+        let filter = PathFilter::from_ignore_file(&ignore, Path::new("/project")).unwrap();
+
+        assert!(excluded(&filter, "/project/sdk", true));
+        assert!(!excluded(&filter, "/project/app/sdk", true));
+        assert!(excluded(&filter, "/project/app/.git", true));
+        assert_eq!(filter.decide(Path::new("/elsewhere/sdk"), true), None);
+    }
+
+    #[test]
+    fn test_selects_like_monitor_path_filters() {
+        let filter = PathFilter::from_patterns(&["/Applications", "/usr/bin"]);
+        assert!(filter.selects(Path::new("/Applications/Safari.app/Contents/MacOS/Safari")));
+        assert!(filter.selects(Path::new("/usr/bin/ls")));
+        assert!(!filter.selects(Path::new("/usr/sbin/sshd")));
+        assert!(!filter.selects(Path::new("/ApplicationsOld/tool")));
+
+        let negations_only = PathFilter::from_patterns(&["!/System"]);
+        assert!(negations_only.selects(Path::new("/Applications/Foo.app/Contents/MacOS/Foo")));
+        assert!(!negations_only.selects(Path::new("/System/Library/CoreServices/Finder")));
+    }
+
+    #[test]
+    fn test_existing_paths_with_glob_characters_match_literally() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = dir.path().join("Foo [Beta].app");
+        std::fs::create_dir(&app).unwrap();
+
+        let pattern = filter_pattern(app.to_str().unwrap());
+        let filter = PathFilter::from_patterns(&[&pattern]);
+        assert!(filter.selects(&app.join("Contents/MacOS/Foo")));
+        assert!(!filter.selects(&dir.path().join("Foo B.app/Contents/MacOS/Foo")));
+
+        // Paths that do not exist stay patterns
+        assert_eq!(filter_pattern("/nonexistent/[ab].app"), "/nonexistent/[ab].app");
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(validate_path_patterns(&["/Applications", "!*.app/", "**/node_modules"]).is_ok());
+        assert!(validate_path_patterns(&["[unclosed"]).is_err());
+        assert!(is_plain_path("/Applications"));
+        assert!(!is_plain_path("/Applications/*.app"));
+        assert!(!is_plain_path("!/System"));
+    }
+}
@@ -0,0 +1,403 @@
+//! Installer packages
+//!
+//! Opens `.pkg` flat packages (xar archives) without installing or unpacking
+//! them: the package signature, each component's `PackageInfo`, the Mach-O
+//! files of its `Payload` keyed by install path, and its `Scripts`. Payloads
+//! are gzip or pbzip2 compressed cpio streams, read entry by entry, so vendor
+//! installers can be reviewed from a Linux sandbox.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek};
+use std::path::Path;
+use anyhow::{Context, Result, anyhow, bail};
+use flate2::read::MultiGzDecoder;
+use bzip2::read::MultiBzDecoder;
+use crate::entitlements::native::cms;
+use crate::models::{PackageComponent, PackageReport, PackageScript, SignerInfo, SignerKind};
+use super::archive::{member_path, read_mach_o, MAX_READ_SIZE};
+use super::{is_mach_o_magic, DiscoveredBinary};
+use xar::XarArchive;
+
+pub mod cpio;
+pub mod xar;
+
+#[cfg(test)]
+pub(crate) mod fixtures;
+
+/// Extension of flat installer packages
+pub const PACKAGE_EXTENSION: &str = "pkg";
+
+/// Where `PackageInfo` places the payload when it names no install location
+const DEFAULT_INSTALL_LOCATION: &str = "/";
+
+/// Largest PackageInfo or script read
+const MAX_METADATA_SIZE: u64 = 1 << 20;
+
+/// Whether `path` names a flat installer package
+pub fn is_package(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case(PACKAGE_EXTENSION))
+}
+
+/// Read a package's signature, components and scripts, handing each Mach-O
+/// file it would install to `found` as it is read.
+///
+/// Binaries are reported as `Foo.pkg!/Applications/Foo.app/Contents/MacOS/Foo`.
+pub fn read_package(path: &Path, found: &mut dyn FnMut(DiscoveredBinary)) -> Result<PackageReport> {
+    let file = File::open(path).with_context(|| format!("Failed to open package: {}", path.display()))?;
+    read_package_from(path, BufReader::new(file), found).with_context(|| format!("Failed to read package: {}", path.display()))
+}
+
+fn read_package_from<R: Read + Seek>(path: &Path, reader: R, found: &mut dyn FnMut(DiscoveredBinary)) -> Result<PackageReport> {
+    let mut xar = XarArchive::open(reader)?;
+    let mut report = PackageReport {
+        path: path.to_string_lossy().to_string(),
+        signer: SignerInfo::without_certificate(SignerKind::Unsigned),
+        components: Vec::new(),
+        scripts: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    let checksum_matches = match xar.toc_checksum_matches() {
+        Ok(Some(false)) => {
+            report.warnings.push("Table of contents does not match its checksum".to_string());
+            false
+        }
+        Ok(Some(true)) => true,
+        // Without a checksum the signatures have nothing to sign
+        Ok(None) => false,
+        Err(e) => {
+            report.warnings.push(format!("Could not check table of contents checksum: {}", e));
+            false
+        }
+    };
+    match package_signer(&mut xar, checksum_matches) {
+        Ok(signer) => report.signer = signer,
+        Err(e) => report.warnings.push(format!("Could not read package signature: {}", e)),
+    }
+
+    // Payload members of all components share one read budget
+    let mut budget = MAX_READ_SIZE;
+    for prefix in component_prefixes(&xar) {
+        let component = match read_package_info(&mut xar, &prefix) {
+            Ok(component) => component,
+            Err(e) => {
+                report.warnings.push(format!("Could not read {}PackageInfo: {}", prefix, e));
+                continue;
+            }
+        };
+        if let Err(e) = read_payload(&mut xar, &prefix, &component, path, &mut budget, found) {
+            report.warnings.push(format!("Could not read {}Payload: {}", prefix, e));
+        }
+        if let Err(e) = read_scripts(&mut xar, &prefix, &component, path, &mut report.scripts, found) {
+            report.warnings.push(format!("Could not read {}Scripts: {}", prefix, e));
+        }
+        report.components.push(component);
+    }
+    Ok(report)
+}
+
+/// The certificates of a package signature, leaf first, and whether the
+/// leaf's signature over the TOC checksum verified
+struct PackageSignature {
+    certificates: Vec<Vec<u8>>,
+    signed: bool,
+}
+
+/// Signer of the package
+fn package_signer<R: Read + Seek>(xar: &mut XarArchive<R>, checksum_matches: bool) -> Result<SignerInfo> {
+    let Some(signature) = package_signature(xar, checksum_matches)? else {
+        return Ok(SignerInfo::without_certificate(SignerKind::Unsigned));
+    };
+    let certificates = signature
+        .certificates
+        .iter()
+        .map(|der| cms::parse_certificate_der(der))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(cms::installer_signer_info(&certificates, signature.signed))
+}
+
+/// The package signature; a CMS signature wins over the classic RSA one
+///
+/// Both sign the TOC checksum, so they only vouch for the package when that
+/// checksum matches the table of contents.
+fn package_signature<R: Read + Seek>(xar: &mut XarArchive<R>, checksum_matches: bool) -> Result<Option<PackageSignature>> {
+    let checksum = if checksum_matches { xar.toc_checksum()? } else { None };
+
+    if let Some(signature) = xar.signatures.iter().find(|signature| signature.style == "CMS").cloned() {
+        let data = xar.read_heap(signature.offset, signature.size)?;
+        let certificates = cms::parse_certificates(&data)?;
+        let signed = match (&checksum, cms::certificate_chain(&certificates).first()) {
+            (Some((_, checksum)), Some(leaf)) => cms::signed_by_leaf(&data, checksum, leaf).unwrap_or(false),
+            _ => false,
+        };
+        let certificates = certificates.iter().map(|certificate| certificate.der.to_vec()).collect();
+        return Ok(Some(PackageSignature { certificates, signed }));
+    }
+
+    let Some(signature) = xar.signatures.first().cloned() else {
+        return Ok(None);
+    };
+    let certificates = signature
+        .certificates
+        .iter()
+        .map(|der| cms::parse_certificate_der(der))
+        .collect::<Result<Vec<_>>>()?;
+    // The classic signature is a PKCS #1 signature of the checksum as a digest
+    let signed = match (&checksum, cms::certificate_chain(&certificates).first()) {
+        (Some((hash, checksum)), Some(leaf)) => leaf.verifies(*hash, checksum, &xar.read_heap(signature.offset, signature.size)?),
+        _ => false,
+    };
+    Ok(Some(PackageSignature { certificates: signature.certificates, signed }))
+}
+
+/// Directories holding a component: the root of a component package, or
+/// each `Foo.pkg/` of a product archive
+fn component_prefixes<R>(xar: &XarArchive<R>) -> Vec<String> {
+    xar.files
+        .iter()
+        .filter(|file| file.is_file)
+        .filter_map(|file| file.path.strip_suffix("PackageInfo"))
+        .filter(|prefix| prefix.is_empty() || (prefix.ends_with(".pkg/") && !prefix[..prefix.len() - 1].contains('/')))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Read a file of the archive whole, up to `limit` bytes
+fn read_file<R: Read + Seek>(xar: &mut XarArchive<R>, path: &str, limit: u64) -> Result<Option<Vec<u8>>> {
+    let Some(data) = xar.find(path).and_then(|file| file.data.clone()) else {
+        return Ok(None);
+    };
+    if data.size > limit {
+        bail!("{} is too large ({} bytes)", path, data.size);
+    }
+    let mut contents = Vec::new();
+    xar.open_data(&data)?.take(limit).read_to_end(&mut contents)?;
+    Ok(Some(contents))
+}
+
+/// Identifier, version and install location from a component's PackageInfo
+fn read_package_info<R: Read + Seek>(xar: &mut XarArchive<R>, prefix: &str) -> Result<PackageComponent> {
+    let data = read_file(xar, &format!("{}PackageInfo", prefix), MAX_METADATA_SIZE)?.unwrap_or_default();
+    let xml = String::from_utf8(data)?;
+    let document = roxmltree::Document::parse(&xml)?;
+    let info = document.root_element();
+    if !info.has_tag_name("pkg-info") {
+        bail!("Expected <pkg-info>, found <{}>", info.tag_name().name());
+    }
+    Ok(PackageComponent {
+        identifier: info.attribute("identifier").ok_or_else(|| anyhow!("<pkg-info> has no identifier"))?.to_string(),
+        version: info.attribute("version").map(str::to_string),
+        install_location: info.attribute("install-location").unwrap_or(DEFAULT_INSTALL_LOCATION).to_string(),
+    })
+}
+
+/// Decompress a gzip or pbzip2 stream; anything else is passed through as plain cpio
+fn decompress<'a>(reader: impl Read + 'a) -> Result<Box<dyn Read + 'a>> {
+    let mut reader = BufReader::new(reader);
+    let magic = reader.fill_buf()?;
+    Ok(if magic.starts_with(&[0x1f, 0x8b]) {
+        Box::new(MultiGzDecoder::new(reader))
+    } else if magic.starts_with(b"BZh") {
+        Box::new(MultiBzDecoder::new(reader))
+    } else if magic.starts_with(b"pbzx") {
+        bail!("pbzx (LZMA) compressed archives are not supported");
+    } else {
+        Box::new(reader)
+    })
+}
+
+/// Absolute install path of a payload entry such as `./Foo.app/Contents/MacOS/Foo`
+fn install_path(install_location: &str, entry: &str) -> String {
+    let relative = entry.trim_start_matches('.').trim_start_matches('/');
+    format!("/{}/{}", install_location.trim_matches('/'), relative).replace("//", "/")
+}
+
+/// Hand the Mach-O files of a component's Payload to `found`, reading them within `budget`
+fn read_payload<R: Read + Seek>(
+    xar: &mut XarArchive<R>,
+    prefix: &str,
+    component: &PackageComponent,
+    package_path: &Path,
+    budget: &mut u64,
+    found: &mut dyn FnMut(DiscoveredBinary),
+) -> Result<()> {
+    let Some(data) = xar.find(&format!("{}Payload", prefix)).and_then(|file| file.data.clone()) else {
+        return Ok(());
+    };
+    cpio::read_entries(decompress(xar.open_data(&data)?)?, |entry, contents| {
+        if entry.is_file() {
+            if let Some(contents) = read_mach_o(contents, entry.size, budget)? {
+                let path = member_path(package_path, &install_path(&component.install_location, &entry.name));
+                found(DiscoveredBinary { path, contents: Some(contents), source: None });
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Collect a component's installer scripts; Mach-O tools among them are scanned as binaries
+fn read_scripts<R: Read + Seek>(
+    xar: &mut XarArchive<R>,
+    prefix: &str,
+    component: &PackageComponent,
+    package_path: &Path,
+    scripts: &mut Vec<PackageScript>,
+    found: &mut dyn FnMut(DiscoveredBinary),
+) -> Result<()> {
+    let Some(data) = xar.find(&format!("{}Scripts", prefix)).and_then(|file| file.data.clone()) else {
+        return Ok(());
+    };
+    cpio::read_entries(decompress(xar.open_data(&data)?)?, |entry, contents| {
+        if !entry.is_file() {
+            return Ok(());
+        }
+        let name = entry.name.trim_start_matches("./").to_string();
+        if entry.size > MAX_METADATA_SIZE {
+            bail!("script {} is too large ({} bytes)", name, entry.size);
+        }
+        let mut data = Vec::new();
+        contents.read_to_end(&mut data)?;
+        if data.first_chunk::<4>().is_some_and(is_mach_o_magic) {
+            let path = member_path(package_path, &format!("{}Scripts/{}", prefix, name));
+            found(DiscoveredBinary { path, contents: Some(data), source: None });
+        } else {
+            scripts.push(PackageScript {
+                component: Some(component.identifier.clone()),
+                name,
+                contents: String::from_utf8_lossy(&data).to_string(),
+            });
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use crate::entitlements::native::fixtures::{cms as cms_fixtures, MachOBuilder};
+    use crate::entitlements::native::macho::CPU_TYPE_ARM64;
+    use fixtures::XarBuilder;
+
+    const PACKAGE_INFO: &[u8] = br#"<?xml version="1.0"?><pkg-info identifier="com.example.app.pkg" version="1.2" install-location="/Applications"/>"#;
+
+    fn binary() -> Vec<u8> {
+        MachOBuilder::new(CPU_TYPE_ARM64, 0).build()
+    }
+
+    /// Read a package image, collecting the binaries handed out
+    fn read(path: &str, image: Vec<u8>) -> (PackageReport, Vec<DiscoveredBinary>) {
+        let mut binaries = Vec::new();
+        let report = read_package_from(Path::new(path), Cursor::new(image), &mut |binary| binaries.push(binary)).unwrap();
+        (report, binaries)
+    }
+
+    /// Whether the leaf's signature over the TOC checksum verifies
+    fn signed(image: Vec<u8>) -> bool {
+        let mut xar = XarArchive::open(Cursor::new(image)).unwrap();
+        let checksum_matches = xar.toc_checksum_matches().unwrap() == Some(true);
+        package_signature(&mut xar, checksum_matches).unwrap().unwrap().signed
+    }
+
+    fn paths(binaries: &[DiscoveredBinary]) -> Vec<String> {
+        binaries.iter().map(|binary| binary.path.to_string_lossy().to_string()).collect()
+    }
+
+    #[test]
+    fn test_component_package() {
+        let payload = fixtures::cpio(&[
+            (".", 0o040755, b""),
+            ("./Example.app/Contents/MacOS/Example", 0o100755, &binary()),
+            ("./Example.app/Contents/Info.plist", 0o100644, b"<plist/>"),
+        ]);
+        let scripts = fixtures::cpio(&[("./postinstall", 0o100755, b"#!/bin/sh\nlaunchctl load /Library/LaunchDaemons/x.plist\n")]);
+        let certificates = cms_fixtures::chain("Developer ID Installer: Example Corp (TEAM123456)", "Developer ID Certification Authority", "Apple Root CA");
+        let image = XarBuilder::new()
+            .file("PackageInfo", PACKAGE_INFO)
+            .stored("Payload", &fixtures::gzip(&payload))
+            .stored("Scripts", &fixtures::gzip(&scripts))
+            .certificates(certificates)
+            .build();
+
+        let (report, binaries) = read("/tmp/Example.pkg", image);
+
+        assert_eq!(paths(&binaries), vec!["/tmp/Example.pkg!/Applications/Example.app/Contents/MacOS/Example"]);
+        assert_eq!(binaries[0].contents, Some(binary()));
+        // The fixture root only carries Apple's root name, not its certificate
+        assert_eq!(report.signer.kind, SignerKind::Other);
+        assert_eq!(report.signer.common_name.as_deref(), Some("Developer ID Installer: Example Corp (TEAM123456)"));
+        assert_eq!(report.components.len(), 1);
+        assert_eq!(report.components[0].summary(), "com.example.app.pkg 1.2 -> /Applications");
+        assert_eq!(report.scripts.len(), 1);
+        assert_eq!(report.scripts[0].summary(), "com.example.app.pkg/postinstall (2 lines)");
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+    }
+
+    #[test]
+    fn test_product_archive_with_pbzip2_payload() {
+        let payload = fixtures::cpio(&[("./usr/local/bin/tool", 0o100755, &binary())]);
+        // pbzip2 writes independent bzip2 streams back to back
+        let half = payload.len() / 2;
+        let compressed = [fixtures::bzip2(&payload[..half]), fixtures::bzip2(&payload[half..])].concat();
+        let info = br#"<pkg-info identifier="com.example.tool.pkg"/>"#;
+        let image = XarBuilder::new()
+            .file("Distribution", b"<installer-gui-script/>")
+            .file("tool.pkg/PackageInfo", info)
+            .stored("tool.pkg/Payload", &compressed)
+            .build();
+
+        let (report, binaries) = read("Tool.pkg", image);
+
+        assert_eq!(paths(&binaries), vec!["Tool.pkg!/usr/local/bin/tool"]);
+        assert_eq!(report.signer.kind, SignerKind::Unsigned);
+        assert_eq!(report.components[0].install_location, "/");
+    }
+
+    #[test]
+    fn test_problems_are_warnings() {
+        let image = XarBuilder::new()
+            .file("PackageInfo", PACKAGE_INFO)
+            .stored("Payload", b"pbzx\0\0\0\0")
+            .corrupt_checksum()
+            .build();
+
+        let (report, binaries) = read("Broken.pkg", image);
+
+        assert!(binaries.is_empty());
+        assert_eq!(report.components.len(), 1);
+        assert_eq!(report.warnings.len(), 2);
+        assert!(report.warnings[0].contains("checksum"));
+        assert!(report.warnings[1].contains("pbzx"));
+    }
+
+    #[test]
+    fn test_signature_must_sign_the_matching_checksum() {
+        let certificates = cms_fixtures::chain("Developer ID Installer: Example Corp (TEAM123456)", "Developer ID Certification Authority", "Apple Root CA");
+        let package = || XarBuilder::new().file("PackageInfo", PACKAGE_INFO).certificates(certificates.clone());
+
+        assert!(signed(package().build()));
+        assert!(signed(package().cms().build()));
+        // A signature of a checksum that does not match the TOC vouches for nothing
+        assert!(!signed(package().corrupt_checksum().build()));
+        assert!(!signed(package().cms().corrupt_checksum().build()));
+
+        // Nor does an altered signature, which follows the 20-byte checksum in the heap
+        let mut image = package().build();
+        let heap = 28 + u64::from_be_bytes(image[8..16].try_into().unwrap()) as usize;
+        image[heap + 20] ^= 1;
+        assert!(!signed(image));
+
+        let (report, _) = read("Example.pkg", package().cms().build());
+        assert_eq!(report.signer.common_name.as_deref(), Some("Developer ID Installer: Example Corp (TEAM123456)"));
+        assert_eq!(report.signer.chain.len(), 3);
+    }
+
+    #[test]
+    fn test_install_paths() {
+        assert_eq!(install_path("/", "./usr/bin/tool"), "/usr/bin/tool");
+        assert_eq!(install_path("/Applications/", "./Foo.app/Foo"), "/Applications/Foo.app/Foo");
+        assert_eq!(install_path("Library", "Foo"), "/Library/Foo");
+    }
+}
@@ -0,0 +1,183 @@
+//! cpio archives
+//!
+//! Package payloads and script archives are cpio streams, usually in the
+//! portable ASCII (`070707`) format written by `pkgbuild`, occasionally in
+//! the `newc` (`070701`) format. Entries are visited in order without
+//! buffering the archive.
+
+use std::io::{self, Read};
+use anyhow::{Result, anyhow, bail};
+
+const ODC_MAGIC: &[u8; 6] = b"070707";
+const NEWC_MAGIC: &[u8; 6] = b"070701";
+const NEWC_CRC_MAGIC: &[u8; 6] = b"070702";
+const TRAILER: &str = "TRAILER!!!";
+
+const ODC_HEADER_LEN: usize = 76;
+const NEWC_HEADER_LEN: usize = 110;
+
+/// File type bits of `mode`
+const S_IFMT: u32 = 0o170000;
+const S_IFREG: u32 = 0o100000;
+
+/// Longest entry name accepted
+const MAX_NAME_LEN: u64 = 4096;
+
+/// One entry of a cpio archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpioEntry {
+    /// Path as stored, usually starting with `./`
+    pub name: String,
+    pub mode: u32,
+    pub size: u64,
+}
+
+impl CpioEntry {
+    pub fn is_file(&self) -> bool {
+        self.mode & S_IFMT == S_IFREG
+    }
+}
+
+/// Call `visit` with every entry and a reader over its contents.
+///
+/// Contents the visitor leaves unread are skipped.
+pub fn read_entries<R: Read>(mut reader: R, mut visit: impl FnMut(&CpioEntry, &mut dyn Read) -> Result<()>) -> Result<()> {
+    loop {
+        let mut magic = [0u8; 6];
+        reader.read_exact(&mut magic).map_err(|e| anyhow!("Truncated cpio archive: {}", e))?;
+        let newc = match &magic {
+            ODC_MAGIC => false,
+            NEWC_MAGIC | NEWC_CRC_MAGIC => true,
+            _ => bail!("Unsupported cpio header {:?}", String::from_utf8_lossy(&magic)),
+        };
+
+        let (mode, name_size, size) = if newc {
+            let mut header = [0u8; NEWC_HEADER_LEN - 6];
+            reader.read_exact(&mut header)?;
+            (field(&header[8..16], 16)? as u32, field(&header[88..96], 16)?, field(&header[48..56], 16)?)
+        } else {
+            let mut header = [0u8; ODC_HEADER_LEN - 6];
+            reader.read_exact(&mut header)?;
+            (field(&header[12..18], 8)? as u32, field(&header[53..59], 8)?, field(&header[59..70], 8)?)
+        };
+        if name_size == 0 || name_size > MAX_NAME_LEN {
+            bail!("Invalid cpio name length {}", name_size);
+        }
+
+        let mut name = vec![0u8; name_size as usize];
+        reader.read_exact(&mut name)?;
+        name.pop(); // NUL terminator
+        let name = String::from_utf8_lossy(&name).to_string();
+        if newc {
+            skip(&mut reader, padding(NEWC_HEADER_LEN as u64 + name_size))?;
+        }
+        if name == TRAILER {
+            return Ok(());
+        }
+
+        let entry = CpioEntry { name, mode, size };
+        let mut contents = (&mut reader).take(size);
+        visit(&entry, &mut contents)?;
+        io::copy(&mut contents, &mut io::sink())?;
+        if contents.limit() > 0 {
+            bail!("Truncated cpio entry {}", entry.name);
+        }
+        if newc {
+            skip(&mut reader, padding(size))?;
+        }
+    }
+}
+
+/// Parse a fixed-width octal or hex header field
+fn field(bytes: &[u8], radix: u32) -> Result<u64> {
+    let text = std::str::from_utf8(bytes)?;
+    u64::from_str_radix(text, radix).map_err(|_| anyhow!("Invalid cpio header field {:?}", text))
+}
+
+/// `newc` aligns names and contents to four bytes
+fn padding(length: u64) -> u64 {
+    (4 - length % 4) % 4
+}
+
+fn skip<R: Read>(reader: &mut R, count: u64) -> Result<()> {
+    let skipped = io::copy(&mut reader.take(count), &mut io::sink())?;
+    if skipped < count {
+        bail!("Truncated cpio archive");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::fixtures;
+
+    fn entries(archive: &[u8]) -> Vec<(String, bool, Vec<u8>)> {
+        let mut found = Vec::new();
+        read_entries(archive, |entry, contents| {
+            let mut data = Vec::new();
+            contents.read_to_end(&mut data)?;
+            found.push((entry.name.clone(), entry.is_file(), data));
+            Ok(())
+        })
+        .unwrap();
+        found
+    }
+
+    /// A `newc` archive with a four-byte-aligned layout
+    fn newc(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        for (name, contents) in files.iter().copied().chain([(TRAILER, &b""[..])]) {
+            archive.extend_from_slice(NEWC_MAGIC);
+            let fields = [0, 0o100644, 0, 0, 1, 0, contents.len(), 0, 0, 0, 0, name.len() + 1, 0];
+            for value in fields {
+                archive.extend_from_slice(format!("{:08x}", value).as_bytes());
+            }
+            archive.extend_from_slice(name.as_bytes());
+            archive.push(0);
+            archive.resize(archive.len() + padding(NEWC_HEADER_LEN as u64 + name.len() as u64 + 1) as usize, 0);
+            archive.extend_from_slice(contents);
+            archive.resize(archive.len() + padding(contents.len() as u64) as usize, 0);
+        }
+        archive
+    }
+
+    #[test]
+    fn test_reads_odc_entries() {
+        let archive = fixtures::cpio(&[(".", 0o040755, b""), ("./bin/tool", 0o100755, b"binary"), ("./README", 0o100644, b"text")]);
+        assert_eq!(
+            entries(&archive),
+            vec![
+                (".".to_string(), false, Vec::new()),
+                ("./bin/tool".to_string(), true, b"binary".to_vec()),
+                ("./README".to_string(), true, b"text".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reads_newc_entries() {
+        let archive = newc(&[("a", b"12345"), ("bb", b"")]);
+        let names: Vec<(String, Vec<u8>)> = entries(&archive).into_iter().map(|(name, _, data)| (name, data)).collect();
+        assert_eq!(names, vec![("a".to_string(), b"12345".to_vec()), ("bb".to_string(), Vec::new())]);
+    }
+
+    #[test]
+    fn test_unread_contents_are_skipped() {
+        let archive = fixtures::cpio(&[("./one", 0o100644, b"first"), ("./two", 0o100644, b"second")]);
+        let mut names = Vec::new();
+        read_entries(archive.as_slice(), |entry, _| {
+            names.push(entry.name.clone());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(names, vec!["./one", "./two"]);
+    }
+
+    #[test]
+    fn test_truncated_archive_is_error() {
+        let mut archive = fixtures::cpio(&[("./tool", 0o100755, b"binary contents")]);
+        archive.truncate(ODC_HEADER_LEN + 10);
+        assert!(read_entries(archive.as_slice(), |_, _| Ok(())).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! Builders for synthetic installer packages used by the package tests
+
+use std::io::Write;
+use base64::Engine;
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use sha1::{Digest, Sha1};
+use crate::entitlements::native::fixtures::{cms, rsa::KEY};
+use crate::entitlements::native::rsa::HashAlgorithm;
+use super::xar::XAR_MAGIC;
+
+/// Length of a SHA-1 TOC checksum
+const CHECKSUM_SIZE: usize = 20;
+
+/// A portable ASCII (`070707`) cpio archive of `(name, mode, contents)` entries
+pub fn cpio(entries: &[(&str, u32, &[u8])]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    for (name, mode, contents) in entries.iter().copied().chain([("TRAILER!!!", 0, &b""[..])]) {
+        let header = format!(
+            "070707{:06o}{:06o}{:06o}{:06o}{:06o}{:06o}{:06o}{:011o}{:06o}{:011o}",
+            0, 0, mode, 0, 0, 1, 0, 0, name.len() + 1, contents.len()
+        );
+        archive.extend_from_slice(header.as_bytes());
+        archive.extend_from_slice(name.as_bytes());
+        archive.push(0);
+        archive.extend_from_slice(contents);
+    }
+    archive
+}
+
+pub fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+pub fn bzip2(data: &[u8]) -> Vec<u8> {
+    let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Builds a xar archive with a sha1 TOC checksum and an optional RSA or CMS
+/// signature of that checksum, made with the fixture key
+#[derive(Default)]
+pub struct XarBuilder {
+    /// Path, contents, and whether to zlib-encode them
+    files: Vec<(String, Vec<u8>, bool)>,
+    certificates: Vec<Vec<u8>>,
+    cms: bool,
+    corrupt_checksum: bool,
+}
+
+impl XarBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a zlib-encoded (`application/x-gzip`) file; parent directories are implied
+    pub fn file(mut self, path: &str, contents: &[u8]) -> Self {
+        self.files.push((path.to_string(), contents.to_vec(), true));
+        self
+    }
+
+    /// Add a file stored as `application/octet-stream`
+    pub fn stored(mut self, path: &str, contents: &[u8]) -> Self {
+        self.files.push((path.to_string(), contents.to_vec(), false));
+        self
+    }
+
+    /// Sign with an RSA signature carrying these DER certificates
+    pub fn certificates(mut self, certificates: Vec<Vec<u8>>) -> Self {
+        self.certificates = certificates;
+        self
+    }
+
+    /// Sign with a CMS (`x-signature`) signature instead of the RSA one
+    pub fn cms(mut self) -> Self {
+        self.cms = true;
+        self
+    }
+
+    /// Store a checksum that does not match the TOC (the signature still signs it)
+    pub fn corrupt_checksum(mut self) -> Self {
+        self.corrupt_checksum = true;
+        self
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        let sign = |checksum: &[u8]| {
+            if self.cms {
+                cms::signed_detached(checksum, &self.certificates, false)
+            } else {
+                KEY.sign(HashAlgorithm::Sha1, checksum)
+            }
+        };
+
+        let mut heap = vec![0u8; CHECKSUM_SIZE];
+        let mut toc = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<xar><toc>");
+        toc.push_str(&format!("<checksum style=\"sha1\"><offset>0</offset><size>{}</size></checksum>", CHECKSUM_SIZE));
+        if !self.certificates.is_empty() {
+            // Signatures of any checksum have the same length
+            let size = sign(&[0; CHECKSUM_SIZE]).len();
+            let (element, style) = if self.cms { ("x-signature", "CMS") } else { ("signature", "RSA") };
+            toc.push_str(&format!("<{} style=\"{}\"><offset>{}</offset><size>{}</size>", element, style, heap.len(), size));
+            toc.push_str("<KeyInfo xmlns=\"http://www.w3.org/2000/09/xmldsig#\"><X509Data>");
+            for certificate in &self.certificates {
+                let encoded = base64::engine::general_purpose::STANDARD.encode(certificate);
+                toc.push_str(&format!("<X509Certificate>{}</X509Certificate>", encoded));
+            }
+            toc.push_str(&format!("</X509Data></KeyInfo></{}>", element));
+            heap.resize(heap.len() + size, 0);
+        }
+
+        let mut id = 0;
+        let files: Vec<(Vec<&str>, &[u8], bool)> = self
+            .files
+            .iter()
+            .map(|(path, contents, encode)| (path.split('/').collect(), contents.as_slice(), *encode))
+            .collect();
+        render_files(&files, &mut toc, &mut heap, &mut id);
+        toc.push_str("</toc></xar>");
+
+        let compressed_toc = zlib(toc.as_bytes());
+        let mut checksum = Sha1::digest(&compressed_toc).to_vec();
+        if self.corrupt_checksum {
+            checksum[0] ^= 0xff;
+        }
+        heap[..CHECKSUM_SIZE].copy_from_slice(&checksum);
+        if !self.certificates.is_empty() {
+            let signature = sign(&checksum);
+            heap[CHECKSUM_SIZE..CHECKSUM_SIZE + signature.len()].copy_from_slice(&signature);
+        }
+
+        let mut image = Vec::new();
+        image.extend_from_slice(&XAR_MAGIC.to_be_bytes());
+        image.extend_from_slice(&28u16.to_be_bytes());
+        image.extend_from_slice(&1u16.to_be_bytes());
+        image.extend_from_slice(&(compressed_toc.len() as u64).to_be_bytes());
+        image.extend_from_slice(&(toc.len() as u64).to_be_bytes());
+        image.extend_from_slice(&1u32.to_be_bytes());
+        image.extend_from_slice(&compressed_toc);
+        image.extend_from_slice(&heap);
+        image
+    }
+}
+
+/// Emit `<file>` elements for paths sharing a parent, appending their data to the heap
+fn render_files(files: &[(Vec<&str>, &[u8], bool)], toc: &mut String, heap: &mut Vec<u8>, id: &mut usize) {
+    let mut names: Vec<&str> = Vec::new();
+    for (components, _, _) in files {
+        if !names.contains(&components[0]) {
+            names.push(components[0]);
+        }
+    }
+    for name in names {
+        *id += 1;
+        toc.push_str(&format!("<file id=\"{}\"><name>{}</name>", id, name));
+        let children: Vec<(Vec<&str>, &[u8], bool)> = files
+            .iter()
+            .filter(|(components, _, _)| components[0] == name && components.len() > 1)
+            .map(|(components, contents, encode)| (components[1..].to_vec(), *contents, *encode))
+            .collect();
+        match files.iter().find(|(components, _, _)| components == &[name]) {
+            Some((_, contents, encode)) => {
+                let stored = if *encode { zlib(contents) } else { contents.to_vec() };
+                let style = if *encode { "application/x-gzip" } else { "application/octet-stream" };
+                toc.push_str(&format!(
+                    "<type>file</type><data><length>{}</length><offset>{}</offset><size>{}</size><encoding style=\"{}\"/></data>",
+                    stored.len(), heap.len(), contents.len(), style
+                ));
+                heap.extend_from_slice(&stored);
+            }
+            None => {
+                toc.push_str("<type>directory</type>");
+                render_files(&children, toc, heap, id);
+            }
+        }
+        toc.push_str("</file>");
+    }
+}
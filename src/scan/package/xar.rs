@@ -0,0 +1,299 @@
+//! xar archives
+//!
+//! Flat installer packages are xar archives: a big-endian header, a
+//! zlib-compressed XML table of contents, and a heap holding the file data,
+//! the TOC checksum and the signature over that checksum. File data is
+//! streamed out of the heap and decompressed according to its encoding.
+
+use std::io::{Read, Seek, SeekFrom};
+use anyhow::{Context, Result, anyhow, bail};
+use base64::Engine;
+use bzip2::read::BzDecoder;
+use flate2::read::ZlibDecoder;
+use roxmltree::Node;
+use crate::entitlements::native::rsa::HashAlgorithm;
+
+/// "xar!"
+pub const XAR_MAGIC: u32 = 0x7861_7221;
+
+/// Size of the fixed header fields; `header_size` may announce more
+const HEADER_LEN: usize = 28;
+
+/// Largest table of contents read, compressed or not
+const MAX_TOC_SIZE: u64 = 64 << 20;
+
+/// A file or directory in the table of contents
+#[derive(Debug, Clone, PartialEq)]
+pub struct XarFile {
+    /// Path from the archive root, `/`-separated
+    pub path: String,
+    pub is_file: bool,
+    /// Location of the contents in the heap; absent for directories
+    pub data: Option<XarData>,
+}
+
+/// Where a file's contents sit in the heap
+#[derive(Debug, Clone, PartialEq)]
+pub struct XarData {
+    /// Offset from the start of the heap
+    pub offset: u64,
+    /// Stored (encoded) length
+    pub length: u64,
+    /// Length once decoded
+    pub size: u64,
+    /// MIME type of the encoding, e.g. `application/x-gzip`
+    pub encoding: String,
+}
+
+/// A signature of the table of contents checksum
+#[derive(Debug, Clone, PartialEq)]
+pub struct XarSignature {
+    /// `RSA` for the classic signature, `CMS` for `x-signature`
+    pub style: String,
+    pub offset: u64,
+    pub size: u64,
+    /// DER certificates listed in the TOC, leaf first
+    pub certificates: Vec<Vec<u8>>,
+}
+
+/// Heap location and algorithm of the TOC checksum
+#[derive(Debug, Clone, PartialEq)]
+struct XarChecksum {
+    style: String,
+    offset: u64,
+    size: u64,
+}
+
+/// An open xar archive
+pub struct XarArchive<R> {
+    reader: R,
+    heap_offset: u64,
+    compressed_toc: Vec<u8>,
+    checksum: Option<XarChecksum>,
+    pub files: Vec<XarFile>,
+    pub signatures: Vec<XarSignature>,
+}
+
+impl<R: Read + Seek> XarArchive<R> {
+    /// Read the header and table of contents
+    pub fn open(mut reader: R) -> Result<Self> {
+        let mut header = [0u8; HEADER_LEN];
+        reader.read_exact(&mut header).context("Truncated xar header")?;
+        let magic = u32::from_be_bytes(header[0..4].try_into()?);
+        if magic != XAR_MAGIC {
+            bail!("Not a xar archive (magic {:#010x})", magic);
+        }
+        let header_size = u16::from_be_bytes(header[4..6].try_into()?) as u64;
+        let toc_length = u64::from_be_bytes(header[8..16].try_into()?);
+        if header_size < HEADER_LEN as u64 {
+            bail!("Invalid xar header size {}", header_size);
+        }
+        if toc_length > MAX_TOC_SIZE {
+            bail!("xar table of contents too large ({} bytes)", toc_length);
+        }
+
+        reader.seek(SeekFrom::Start(header_size))?;
+        let mut compressed_toc = vec![0u8; toc_length as usize];
+        reader.read_exact(&mut compressed_toc).context("Truncated xar table of contents")?;
+        let mut xml = String::new();
+        ZlibDecoder::new(compressed_toc.as_slice())
+            .take(MAX_TOC_SIZE)
+            .read_to_string(&mut xml)
+            .context("Failed to decompress xar table of contents")?;
+        let (files, signatures, checksum) = parse_toc(&xml)?;
+
+        Ok(Self {
+            reader,
+            heap_offset: header_size + toc_length,
+            compressed_toc,
+            checksum,
+            files,
+            signatures,
+        })
+    }
+
+    /// The file at `path`, if the archive has one
+    pub fn find(&self, path: &str) -> Option<&XarFile> {
+        self.files.iter().find(|file| file.is_file && file.path == path)
+    }
+
+    /// The TOC checksum stored in the heap, which the signatures sign, and its
+    /// algorithm; `None` without a checksum or with an unsupported algorithm
+    pub fn toc_checksum(&mut self) -> Result<Option<(HashAlgorithm, Vec<u8>)>> {
+        let Some(checksum) = self.checksum.clone() else {
+            return Ok(None);
+        };
+        let hash = match checksum.style.as_str() {
+            "sha1" => HashAlgorithm::Sha1,
+            "sha256" => HashAlgorithm::Sha256,
+            _ => return Ok(None),
+        };
+        Ok(Some((hash, self.read_heap(checksum.offset, checksum.size)?)))
+    }
+
+    /// Whether the checksum stored in the heap matches the table of contents;
+    /// `None` without a checksum or with an unsupported algorithm
+    pub fn toc_checksum_matches(&mut self) -> Result<Option<bool>> {
+        Ok(self.toc_checksum()?.map(|(hash, stored)| stored == hash.digest(&self.compressed_toc)))
+    }
+
+    /// Raw bytes from the heap
+    pub fn read_heap(&mut self, offset: u64, size: u64) -> Result<Vec<u8>> {
+        if size > MAX_TOC_SIZE {
+            bail!("xar heap entry too large ({} bytes)", size);
+        }
+        self.reader.seek(SeekFrom::Start(self.heap_offset + offset))?;
+        let mut data = vec![0u8; size as usize];
+        self.reader.read_exact(&mut data).context("Truncated xar heap")?;
+        Ok(data)
+    }
+
+    /// Stream the decoded contents of a file
+    pub fn open_data(&mut self, data: &XarData) -> Result<Box<dyn Read + '_>> {
+        self.reader.seek(SeekFrom::Start(self.heap_offset + data.offset))?;
+        let stored = (&mut self.reader).take(data.length);
+        Ok(match data.encoding.as_str() {
+            "" | "application/octet-stream" => Box::new(stored),
+            "application/x-gzip" => Box::new(ZlibDecoder::new(stored)),
+            "application/x-bzip2" => Box::new(BzDecoder::new(stored)),
+            other => bail!("Unsupported xar encoding {}", other),
+        })
+    }
+}
+
+type Toc = (Vec<XarFile>, Vec<XarSignature>, Option<XarChecksum>);
+
+/// Parse the files, signatures and checksum out of the TOC XML
+fn parse_toc(xml: &str) -> Result<Toc> {
+    let document = roxmltree::Document::parse(xml).context("Invalid xar table of contents")?;
+    let toc = document
+        .root_element()
+        .children()
+        .find(|node| node.has_tag_name("toc"))
+        .ok_or_else(|| anyhow!("xar table of contents has no <toc>"))?;
+
+    let mut files = Vec::new();
+    let mut signatures = Vec::new();
+    let mut checksum = None;
+    for node in toc.children().filter(Node::is_element) {
+        match node.tag_name().name() {
+            "file" => collect_files(node, "", &mut files)?,
+            "signature" | "x-signature" => signatures.push(XarSignature {
+                style: node.attribute("style").unwrap_or_default().to_string(),
+                offset: child_number(node, "offset")?,
+                size: child_number(node, "size")?,
+                certificates: certificates(node)?,
+            }),
+            "checksum" => {
+                checksum = Some(XarChecksum {
+                    style: node.attribute("style").unwrap_or_default().to_ascii_lowercase(),
+                    offset: child_number(node, "offset")?,
+                    size: child_number(node, "size")?,
+                })
+            }
+            _ => {}
+        }
+    }
+    Ok((files, signatures, checksum))
+}
+
+/// Add a `<file>` element and its children to `files`
+fn collect_files(node: Node, parent: &str, files: &mut Vec<XarFile>) -> Result<()> {
+    let name = child_text(node, "name").ok_or_else(|| anyhow!("xar file without a name"))?;
+    if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+        bail!("Invalid xar file name {:?}", name);
+    }
+    let path = if parent.is_empty() { name.to_string() } else { format!("{}/{}", parent, name) };
+    let data = match node.children().find(|child| child.has_tag_name("data")) {
+        Some(data) => Some(XarData {
+            offset: child_number(data, "offset")?,
+            length: child_number(data, "length")?,
+            size: child_number(data, "size")?,
+            encoding: data
+                .children()
+                .find(|child| child.has_tag_name("encoding"))
+                .and_then(|encoding| encoding.attribute("style"))
+                .unwrap_or_default()
+                .to_string(),
+        }),
+        None => None,
+    };
+    files.push(XarFile { is_file: child_text(node, "type").unwrap_or("file") == "file", data, path: path.clone() });
+
+    for child in node.children().filter(|child| child.has_tag_name("file")) {
+        collect_files(child, &path, files)?;
+    }
+    Ok(())
+}
+
+/// Base64 `X509Certificate` elements anywhere under a signature
+fn certificates(signature: Node) -> Result<Vec<Vec<u8>>> {
+    signature
+        .descendants()
+        .filter(|node| node.has_tag_name("X509Certificate"))
+        .map(|node| {
+            let text: String = node.text().unwrap_or_default().split_whitespace().collect();
+            base64::engine::general_purpose::STANDARD
+                .decode(text)
+                .map_err(|e| anyhow!("Invalid certificate in xar signature: {}", e))
+        })
+        .collect()
+}
+
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children().find(|child| child.has_tag_name(name))?.text()
+}
+
+fn child_number(node: Node, name: &str) -> Result<u64> {
+    let text = child_text(node, name).ok_or_else(|| anyhow!("xar <{}> without <{}>", node.tag_name().name(), name))?;
+    text.trim().parse().map_err(|_| anyhow!("Invalid xar <{}> value {:?}", name, text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use super::super::fixtures::XarBuilder;
+
+    #[test]
+    fn test_lists_nested_files() {
+        let image = XarBuilder::new().file("Distribution", b"<installer-gui-script/>").file("Tool.pkg/Payload", b"payload").build();
+        let archive = XarArchive::open(Cursor::new(image)).unwrap();
+
+        let paths: Vec<(&str, bool)> = archive.files.iter().map(|file| (file.path.as_str(), file.is_file)).collect();
+        assert_eq!(paths, vec![("Distribution", true), ("Tool.pkg", false), ("Tool.pkg/Payload", true)]);
+        assert!(archive.find("Tool.pkg").is_none());
+    }
+
+    #[test]
+    fn test_reads_encoded_data() {
+        let image = XarBuilder::new().file("PackageInfo", b"<pkg-info/>").stored("Bom", b"raw").build();
+        let mut archive = XarArchive::open(Cursor::new(image)).unwrap();
+
+        for (path, expected) in [("PackageInfo", &b"<pkg-info/>"[..]), ("Bom", b"raw")] {
+            let data = archive.find(path).unwrap().data.clone().unwrap();
+            let mut contents = Vec::new();
+            archive.open_data(&data).unwrap().read_to_end(&mut contents).unwrap();
+            assert_eq!(contents, expected);
+        }
+        assert_eq!(archive.toc_checksum_matches().unwrap(), Some(true));
+    }
+
+    #[test]
+    fn test_detects_toc_checksum_mismatch() {
+        let image = XarBuilder::new().file("PackageInfo", b"<pkg-info/>").corrupt_checksum().build();
+        let mut archive = XarArchive::open(Cursor::new(image)).unwrap();
+        assert_eq!(archive.toc_checksum_matches().unwrap(), Some(false));
+    }
+
+    #[test]
+    fn test_rejects_escaping_names() {
+        let xml = "<xar><toc><file id=\"1\"><name>../etc</name><type>file</type></file></toc></xar>";
+        assert!(parse_toc(xml).is_err());
+    }
+
+    #[test]
+    fn test_rejects_other_formats() {
+        assert!(XarArchive::open(Cursor::new(b"PK\x03\x04 not a xar archive at all".to_vec())).is_err());
+    }
+}
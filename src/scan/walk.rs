@@ -0,0 +1,594 @@
+//! Parallel single-pass traversal
+//!
+//! Walks the scan paths once on the rayon pool: every directory is listed in
+//! its own task, and each binary found is handed to the visitor in a task of
+//! its own, so extraction starts while the rest of the tree is still being
+//! walked. The number of files grows as directories are listed, which is
+//! what the progress total is built from.
+//!
+//! Directories and files are identified by device and inode:
+//! - A directory is listed once, however many symlinks, firmlinks or
+//!   overlapping scan paths lead to it, so symlink loops end
+//! - A file reached by several paths (hardlinks, firmlinks such as
+//!   `/System/Volumes/Data`, followed symlinks) is read once; the other paths
+//!   are returned as its aliases, including those through directories that
+//!   were reached again at another path
+//! - With `one_file_system`, directories on another device than their scan
+//!   path are not entered
+//!
+//! Archives given as scan paths are read member by member; those found while
+//! walking only with `expand_archives`. Installer packages are always opened.
+//!
+//! Excluded directories, and those matched by a `.listentignore` file in them
+//! or above them, are pruned while walking and never listed.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, DirEntry, Metadata};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use crate::entitlements::native::root;
+use crate::models::PackageReport;
+use super::filter::{PathFilter, IGNORE_FILE_NAME};
+use super::{archive, check_single_file, package, DiscoveredBinary};
+
+/// How to walk the scan paths
+#[derive(Debug, Clone)]
+pub struct WalkOptions {
+    /// Foreign filesystem root: symlinks are resolved inside the image
+    pub root: Option<PathBuf>,
+    /// Follow symlinks found while walking; scan paths themselves are always followed
+    pub follow_symlinks: bool,
+    /// Do not enter directories on another device than their scan path
+    pub one_file_system: bool,
+    /// Files and directories not to walk (`--exclude`)
+    pub exclude: PathFilter,
+    /// When not empty, only files these select are checked (`--include`)
+    pub include: PathFilter,
+    /// Read inside archives found while walking, not only those given as scan paths
+    pub expand_archives: bool,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        Self {
+            root: None,
+            follow_symlinks: true,
+            one_file_system: false,
+            expand_archives: false,
+            exclude: PathFilter::default(),
+            include: PathFilter::default(),
+        }
+    }
+}
+
+/// Receives what the walk finds; called from many threads at once
+pub trait WalkVisitor: Sync {
+    /// A top-level scan path is about to be walked
+    fn entering(&self, path: &Path);
+    /// A directory listing turned up `count` more files
+    fn files_found(&self, count: usize);
+    /// A binary to extract, in its own task on the rayon pool
+    fn binary(&self, binary: DiscoveredBinary);
+    /// An installer package whose binaries are handed over separately
+    fn package(&self, package: PackageReport);
+    /// A file was checked; `found` when it was, or held, at least one binary.
+    /// Files already reached by another path count as not found.
+    fn file_checked(&self, found: bool);
+    /// An archive or installer package could not be read, or only in part
+    fn unreadable(&self, path: &Path);
+}
+
+/// Device and inode
+type FileId = (u64, u64);
+
+fn file_id(metadata: &Metadata) -> FileId {
+    (metadata.dev(), metadata.ino())
+}
+
+/// `.listentignore` files of a directory and its ancestors, innermost first
+struct Ignores {
+    filter: PathFilter,
+    parent: Option<Arc<Ignores>>,
+}
+
+impl Ignores {
+    /// The innermost ignore file's verdict on a path
+    fn matched(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        self.filter.matched(path, is_dir).or_else(|| self.parent.as_ref()?.matched(path, is_dir))
+    }
+}
+
+/// Directories and files seen so far, shared by every task of a walk
+#[derive(Default)]
+struct Seen {
+    /// Path each directory was listed at
+    directories: Mutex<HashMap<FileId, PathBuf>>,
+    /// Later paths to directories already listed, with the path they were listed at
+    directory_aliases: Mutex<Vec<(PathBuf, PathBuf)>>,
+    /// First path each file was reached by
+    files: Mutex<HashMap<FileId, PathBuf>>,
+    /// Later paths to the same file, by first path
+    aliases: Mutex<HashMap<PathBuf, Vec<PathBuf>>>,
+    /// Symlinks followed, at the path they were walked at
+    links: Mutex<HashSet<PathBuf>>,
+}
+
+impl Seen {
+    /// Every path of the files reached more than once, keyed by the path the
+    /// file was visited at; paths not through a followed symlink come first
+    fn into_aliases(self) -> HashMap<PathBuf, Vec<PathBuf>> {
+        let mut groups: HashMap<PathBuf, Vec<PathBuf>> = self
+            .aliases
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|(first, others)| (first.clone(), std::iter::once(first).chain(others).collect()))
+            .collect();
+
+        // Files under a directory reached again are also at the other paths,
+        // found by looking each ancestor up among the directories listed
+        let mut directory_aliases: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+        for (listed, other) in self.directory_aliases.into_inner().unwrap() {
+            directory_aliases.entry(listed).or_default().push(other);
+        }
+        if !directory_aliases.is_empty() {
+            let moved_paths = |path: &Path| -> Vec<PathBuf> {
+                path.ancestors()
+                    .skip(1)
+                    .filter_map(|dir| Some((path.strip_prefix(dir).ok()?, directory_aliases.get(dir)?)))
+                    .flat_map(|(relative, others)| others.iter().map(move |other| other.join(relative)))
+                    .collect()
+            };
+            for first in self.files.into_inner().unwrap().into_values() {
+                let paths = groups.entry(first.clone()).or_insert_with(|| vec![first]);
+                let moved: Vec<PathBuf> = paths.iter().flat_map(|path| moved_paths(path)).collect();
+                paths.extend(moved);
+            }
+        }
+
+        let links = self.links.into_inner().unwrap();
+        let through_link = |path: &Path| path.ancestors().any(|ancestor| links.contains(ancestor));
+        groups
+            .into_iter()
+            .filter_map(|(visited, mut paths)| {
+                paths.sort_by_cached_key(|path| (through_link(path), path.clone()));
+                paths.dedup();
+                (paths.len() > 1).then_some((visited, paths))
+            })
+            .collect()
+    }
+}
+
+/// Walk the scan paths, returning once every directory has been listed and
+/// every binary found has been visited.
+///
+/// Binaries are reported at the path they were found at. Returns every path
+/// of the files reached more than once, keyed by the path the file was
+/// visited at, with the path to report the file at first: one that does not
+/// go through a followed symlink, where there is one.
+pub fn walk<V: WalkVisitor>(
+    scan_paths: &[String],
+    options: &WalkOptions,
+    interrupted: &AtomicBool,
+    visitor: &V,
+) -> HashMap<PathBuf, Vec<PathBuf>> {
+    let seen = Seen::default();
+    let walker = Walker { options, interrupted, visitor, seen: &seen };
+    rayon::scope(|scope| {
+        for path_str in scan_paths {
+            if interrupted.load(Ordering::Relaxed) {
+                break;
+            }
+            let path = Path::new(path_str);
+            // Under a foreign root, scan paths may be symlinks into the image (e.g. /etc)
+            let source = match &options.root {
+                Some(root) => root::resolve(root, path).unwrap_or_else(|_| path.to_path_buf()),
+                None => path.to_path_buf(),
+            };
+            let Ok(metadata) = fs::metadata(&source) else {
+                continue;
+            };
+
+            visitor.entering(path);
+            if metadata.is_file() {
+                visitor.files_found(1);
+                walker.file(scope, path, &source, file_id(&metadata), true);
+            } else if metadata.is_dir() {
+                let path = path.to_path_buf();
+                scope.spawn(move |scope| walker.directory(scope, &path, &source, metadata.dev(), None));
+            }
+        }
+    });
+    seen.into_aliases()
+}
+
+struct Walker<'a, V> {
+    options: &'a WalkOptions,
+    interrupted: &'a AtomicBool,
+    visitor: &'a V,
+    seen: &'a Seen,
+}
+
+// Only references, whatever the visitor
+impl<V> Clone for Walker<'_, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V> Copy for Walker<'_, V> {}
+
+impl<'a, V: WalkVisitor> Walker<'a, V> {
+    /// List a directory, spawning a task per subdirectory
+    ///
+    /// `dir_path` is the path reported for the directory and `source_dir`
+    /// where it is read; they differ once a symlink has been resolved inside a
+    /// foreign root. `device` is the device of the scan path it was reached
+    /// from, and `ignores` the ignore files of the directories above it.
+    fn directory(self, scope: &rayon::Scope<'a>, dir_path: &Path, source_dir: &Path, device: u64, ignores: Option<Arc<Ignores>>) {
+        let Ok(metadata) = fs::metadata(source_dir) else {
+            return;
+        };
+        if self.options.one_file_system && metadata.dev() != device {
+            return;
+        }
+        // Already listed through another path, or a symlink loop
+        match self.seen.directories.lock().unwrap().entry(file_id(&metadata)) {
+            Entry::Occupied(listed) => {
+                let listed = listed.get();
+                if !dir_path.starts_with(listed) && !listed.starts_with(dir_path) {
+                    self.seen.directory_aliases.lock().unwrap().push((listed.clone(), dir_path.to_path_buf()));
+                }
+                return;
+            }
+            Entry::Vacant(slot) => {
+                slot.insert(dir_path.to_path_buf());
+            }
+        }
+
+        let entries = match fs::read_dir(source_dir) {
+            Ok(entries) => entries,
+            Err(_) => return, // Skip unreadable directories silently
+        };
+        let ignores = match PathFilter::from_ignore_file(&source_dir.join(IGNORE_FILE_NAME), &self.match_path(dir_path)) {
+            Ok(filter) if !filter.is_empty() => Some(Arc::new(Ignores { filter, parent: ignores })),
+            _ => ignores,
+        };
+
+        let mut files = Vec::new();
+        // Skip unreadable entries
+        for entry in entries.flatten() {
+            if self.interrupted.load(Ordering::Relaxed) {
+                return;
+            }
+            let path = dir_path.join(entry.file_name());
+            let Some(source) = self.entry_source(&entry) else {
+                continue;
+            };
+            let Ok(metadata) = fs::metadata(&source) else {
+                continue;
+            };
+            if self.excluded(&path, metadata.is_dir(), ignores.as_deref()) {
+                continue;
+            }
+            if entry.file_type().is_ok_and(|file_type| file_type.is_symlink()) {
+                self.seen.links.lock().unwrap().insert(path.clone());
+            }
+
+            if metadata.is_file() {
+                files.push((path, source, file_id(&metadata)));
+            } else if metadata.is_dir() {
+                let ignores = ignores.clone();
+                scope.spawn(move |scope| self.directory(scope, &path, &source, device, ignores));
+            }
+        }
+
+        self.visitor.files_found(files.len());
+        for (path, source, id) in files {
+            if self.interrupted.load(Ordering::Relaxed) {
+                return;
+            }
+            self.file(scope, &path, &source, id, self.options.expand_archives);
+        }
+    }
+
+    /// Path patterns are matched against: the in-image path under a foreign root
+    fn match_path(&self, path: &Path) -> PathBuf {
+        match &self.options.root {
+            Some(root) => root::image_path(root, path),
+            None => path.to_path_buf(),
+        }
+    }
+
+    /// Whether to skip a directory entry: excluded on the command line or by
+    /// an ignore file, or a file `--include` does not select
+    fn excluded(&self, path: &Path, is_dir: bool, ignores: Option<&Ignores>) -> bool {
+        let path = self.match_path(path);
+        self.options.exclude.decide(&path, is_dir) == Some(true)
+            || ignores.and_then(|ignores| ignores.matched(&path, is_dir)) == Some(true)
+            || (!is_dir && !self.options.include.selects(&path))
+    }
+
+    /// Where to read a directory entry; `None` for symlinks that are not
+    /// followed, or dangle or loop inside the image
+    fn entry_source(&self, entry: &DirEntry) -> Option<PathBuf> {
+        let is_symlink = entry.file_type().is_ok_and(|file_type| file_type.is_symlink());
+        if !is_symlink {
+            return Some(entry.path());
+        }
+        if !self.options.follow_symlinks {
+            return None;
+        }
+        match &self.options.root {
+            // Follow symlinks inside the image rather than on the host
+            Some(root) => root::resolve(root, &entry.path()).ok(),
+            None => Some(entry.path()),
+        }
+    }
+
+    /// Check a single file, expanding installer packages (and archives, with
+    /// `expand_archives`) into their Mach-O members, each handed to the
+    /// visitor as soon as it is read
+    ///
+    /// The file is read at `source` and reported at `path`; a file already
+    /// reached by another path is only recorded as an alias.
+    fn file(self, scope: &rayon::Scope<'a>, path: &Path, source: &Path, id: FileId, expand_archives: bool) {
+        match self.seen.files.lock().unwrap().entry(id) {
+            Entry::Occupied(first) => {
+                self.seen.aliases.lock().unwrap().entry(first.get().clone()).or_default().push(path.to_path_buf());
+                self.visitor.file_checked(false);
+                return;
+            }
+            Entry::Vacant(slot) => {
+                slot.insert(path.to_path_buf());
+            }
+        }
+
+        let visitor = self.visitor;
+        let mut spawn = |binary: DiscoveredBinary| scope.spawn(move |_| visitor.binary(binary));
+        let read = if package::is_package(source) {
+            package::read_package(source, &mut spawn).map(|package| visitor.package(package))
+        } else if expand_archives && archive::is_archive(source) {
+            archive::read_archive(source, &mut spawn)
+        } else {
+            let binary = check_single_file(source).map(|mut binary| {
+                if source != path {
+                    binary.path = path.to_path_buf();
+                    binary.source = Some(source.to_path_buf());
+                }
+                binary
+            });
+            visitor.file_checked(binary.is_some());
+            binary.into_iter().for_each(spawn);
+            return;
+        };
+
+        // Unreadable archives and packages are counted like unreadable binaries
+        if read.is_err() {
+            visitor.unreadable(path);
+        }
+        visitor.file_checked(read.is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder {
+        entered: Mutex<Vec<PathBuf>>,
+        files: AtomicUsize,
+        binaries: Mutex<Vec<PathBuf>>,
+        checked: AtomicUsize,
+        unreadable: Mutex<Vec<PathBuf>>,
+    }
+
+    impl WalkVisitor for Recorder {
+        fn entering(&self, path: &Path) {
+            self.entered.lock().unwrap().push(path.to_path_buf());
+        }
+        fn files_found(&self, count: usize) {
+            self.files.fetch_add(count, Ordering::Relaxed);
+        }
+        fn binary(&self, binary: DiscoveredBinary) {
+            self.binaries.lock().unwrap().push(binary.path);
+        }
+        fn package(&self, _package: PackageReport) {}
+        fn file_checked(&self, _found: bool) {
+            self.checked.fetch_add(1, Ordering::Relaxed);
+        }
+        fn unreadable(&self, path: &Path) {
+            self.unreadable.lock().unwrap().push(path.to_path_buf());
+        }
+    }
+
+    fn executable(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, b"#!/bin/sh\n").unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    fn sorted(paths: &Mutex<Vec<PathBuf>>) -> Vec<PathBuf> {
+        let mut paths = paths.lock().unwrap().clone();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_walks_nested_directories_once() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["bin/tool", "lib/a/b/helper", "lib/a/c/other"] {
+            executable(&dir.path().join(name));
+        }
+        fs::write(dir.path().join("lib/a/README"), b"text").unwrap();
+
+        let recorder = Recorder::default();
+        let scan_paths = vec![dir.path().display().to_string(), dir.path().join("missing").display().to_string()];
+        walk(&scan_paths, &WalkOptions::default(), &AtomicBool::new(false), &recorder);
+
+        assert_eq!(
+            sorted(&recorder.binaries),
+            vec![dir.path().join("bin/tool"), dir.path().join("lib/a/b/helper"), dir.path().join("lib/a/c/other")]
+        );
+        assert_eq!(recorder.files.load(Ordering::Relaxed), 4);
+        assert_eq!(recorder.checked.load(Ordering::Relaxed), 4);
+        assert_eq!(*recorder.entered.lock().unwrap(), vec![dir.path().to_path_buf()]);
+    }
+
+    #[test]
+    fn test_symlinks_resolve_inside_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        executable(&dir.path().join("usr/libexec/real"));
+        fs::create_dir_all(dir.path().join("usr/bin")).unwrap();
+        std::os::unix::fs::symlink("/usr/libexec/real", dir.path().join("usr/bin/alias")).unwrap();
+
+        let recorder = Recorder::default();
+        let scan_paths = vec![dir.path().join("usr/bin").display().to_string()];
+        let options = WalkOptions { root: Some(dir.path().to_path_buf()), ..WalkOptions::default() };
+        walk(&scan_paths, &options, &AtomicBool::new(false), &recorder);
+
+        assert_eq!(sorted(&recorder.binaries), vec![dir.path().join("usr/bin/alias")]);
+    }
+
+    #[test]
+    fn test_archives_found_while_walking_are_expanded_on_request() {
+        use std::io::Write;
+        use crate::entitlements::native::fixtures::MachOBuilder;
+        use crate::entitlements::native::macho::CPU_TYPE_ARM64;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = zip::ZipWriter::new(fs::File::create(dir.path().join("App.ipa")).unwrap());
+        writer.start_file("Payload/Foo.app/Foo", zip::write::SimpleFileOptions::default()).unwrap();
+        writer.write_all(&MachOBuilder::new(CPU_TYPE_ARM64, 0).build()).unwrap();
+        writer.finish().unwrap();
+        fs::write(dir.path().join("Broken.zip"), b"not a zip").unwrap();
+        let scan_paths = vec![dir.path().display().to_string()];
+
+        let recorder = Recorder::default();
+        walk(&scan_paths, &WalkOptions::default(), &AtomicBool::new(false), &recorder);
+        assert!(recorder.binaries.lock().unwrap().is_empty());
+        assert!(recorder.unreadable.lock().unwrap().is_empty());
+
+        let recorder = Recorder::default();
+        let options = WalkOptions { expand_archives: true, ..WalkOptions::default() };
+        walk(&scan_paths, &options, &AtomicBool::new(false), &recorder);
+        assert_eq!(sorted(&recorder.binaries), vec![PathBuf::from(format!("{}!/Payload/Foo.app/Foo", dir.path().join("App.ipa").display()))]);
+        assert_eq!(*recorder.unreadable.lock().unwrap(), vec![dir.path().join("Broken.zip")]);
+
+        // Given as a scan path, an archive is always read
+        let recorder = Recorder::default();
+        walk(&[dir.path().join("App.ipa").display().to_string()], &WalkOptions::default(), &AtomicBool::new(false), &recorder);
+        assert_eq!(recorder.binaries.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_interrupted_walk_visits_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        executable(&dir.path().join("tool"));
+
+        let recorder = Recorder::default();
+        walk(&[dir.path().display().to_string()], &WalkOptions::default(), &AtomicBool::new(true), &recorder);
+        assert!(recorder.binaries.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_symlink_loops_end() {
+        let dir = tempfile::tempdir().unwrap();
+        executable(&dir.path().join("a/tool"));
+        std::os::unix::fs::symlink("..", dir.path().join("a/up")).unwrap();
+        std::os::unix::fs::symlink(".", dir.path().join("a/self")).unwrap();
+
+        let recorder = Recorder::default();
+        walk(&[dir.path().display().to_string()], &WalkOptions::default(), &AtomicBool::new(false), &recorder);
+        assert_eq!(sorted(&recorder.binaries), vec![dir.path().join("a/tool")]);
+    }
+
+    #[test]
+    fn test_hardlinks_and_symlinks_are_aliases() {
+        let dir = tempfile::tempdir().unwrap();
+        executable(&dir.path().join("bin/tool"));
+        fs::hard_link(dir.path().join("bin/tool"), dir.path().join("bin/hardlink")).unwrap();
+        std::os::unix::fs::symlink("tool", dir.path().join("bin/symlink")).unwrap();
+
+        let recorder = Recorder::default();
+        let aliases = walk(&[dir.path().display().to_string()], &WalkOptions::default(), &AtomicBool::new(false), &recorder);
+
+        let binaries = sorted(&recorder.binaries);
+        assert_eq!(binaries.len(), 1);
+        assert_eq!(aliases[&binaries[0]], ["bin/hardlink", "bin/tool", "bin/symlink"].map(|name| dir.path().join(name)));
+    }
+
+    #[test]
+    fn test_files_in_symlinked_directories_are_aliases() {
+        let dir = tempfile::tempdir().unwrap();
+        executable(&dir.path().join("real/tool"));
+        executable(&dir.path().join("real/lib/helper"));
+        std::os::unix::fs::symlink("real", dir.path().join("link")).unwrap();
+
+        let recorder = Recorder::default();
+        let aliases = walk(&[dir.path().display().to_string()], &WalkOptions::default(), &AtomicBool::new(false), &recorder);
+
+        let binaries = sorted(&recorder.binaries);
+        assert_eq!(binaries.len(), 2);
+        for name in ["tool", "lib/helper"] {
+            // Whichever path was listed first, the real one is reported
+            let visited = binaries.iter().find(|binary| binary.ends_with(name)).unwrap();
+            assert_eq!(aliases[visited], vec![dir.path().join("real").join(name), dir.path().join("link").join(name)]);
+        }
+    }
+
+    #[test]
+    fn test_symlinks_are_skipped_when_not_followed() {
+        let dir = tempfile::tempdir().unwrap();
+        executable(&dir.path().join("real/tool"));
+        fs::create_dir(dir.path().join("bin")).unwrap();
+        std::os::unix::fs::symlink("../real/tool", dir.path().join("bin/link")).unwrap();
+        std::os::unix::fs::symlink("../real", dir.path().join("bin/dir")).unwrap();
+
+        let recorder = Recorder::default();
+        let options = WalkOptions { follow_symlinks: false, ..WalkOptions::default() };
+        let scan_paths = vec![dir.path().join("bin").display().to_string()];
+        let aliases = walk(&scan_paths, &options, &AtomicBool::new(false), &recorder);
+        assert!(recorder.binaries.lock().unwrap().is_empty());
+        assert!(aliases.is_empty());
+    }
+
+    #[test]
+    fn test_excluded_and_ignored_directories_are_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["app/tool", "app/node_modules/dep/bin", "app/sdk/clang", "app/vendor/sdk/clang", "other/tool"] {
+            executable(&dir.path().join(name));
+        }
+        fs::write(dir.path().join("app").join(IGNORE_FILE_NAME), "/sdk\n").unwrap();
+        let recorder = Recorder::default();
+        let options = WalkOptions {
+            exclude: PathFilter::from_patterns(&["node_modules/", "/**/other"]),
+            ..WalkOptions::default()
+        };
+        walk(&[dir.path().display().to_string()], &options, &AtomicBool::new(false), &recorder);
+
+        assert_eq!(sorted(&recorder.binaries), vec![dir.path().join("app/tool"), dir.path().join("app/vendor/sdk/clang")]);
+        // Pruned directories are never listed
+        assert_eq!(recorder.files.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_include_selects_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["Foo.app/Contents/MacOS/Foo", "Foo.app/Contents/Helpers/helper", "bin/tool"] {
+            executable(&dir.path().join(name));
+        }
+        let recorder = Recorder::default();
+        let options = WalkOptions { include: PathFilter::from_patterns(&["**/*.app/Contents/MacOS/"]), ..WalkOptions::default() };
+        walk(&[dir.path().display().to_string()], &options, &AtomicBool::new(false), &recorder);
+
+        assert_eq!(sorted(&recorder.binaries), vec![dir.path().join("Foo.app/Contents/MacOS/Foo")]);
+    }
+}
@@ -35,4 +35,59 @@ fn test_help_describes_entitlement_filter() {
         .success()
         .stdout(predicate::str::contains("entitlement"))
         .stdout(predicate::str::contains("Filter"));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_help_describes_signer_filter() {
+    let mut cmd = assert_cmd::cargo_bin_cmd!("listent");
+    cmd.arg("--help");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("--signer"))
+        .stdout(predicate::str::contains("developer-id"));
+}
+
+#[test]
+fn test_help_describes_verify() {
+    let mut cmd = assert_cmd::cargo_bin_cmd!("listent");
+    cmd.arg("--help");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("--verify"));
+}
+
+#[test]
+fn test_hardening_needs_native_backend() {
+    let fixture = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(fixture.path(), "{}").unwrap();
+    let mut cmd = assert_cmd::cargo_bin_cmd!("listent");
+    cmd.arg("--hardening")
+        .arg("--backend").arg(format!("fixture:{}", fixture.path().display()))
+        .arg(fixture.path());
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--hardening requires the native or auto backend"));
+}
+
+#[test]
+fn test_unknown_backend_is_rejected() {
+    let mut cmd = assert_cmd::cargo_bin_cmd!("listent");
+    cmd.arg("--backend").arg("objdump");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("fixture:FILE"));
+}
+
+#[test]
+fn test_unknown_signer_is_rejected() {
+    let mut cmd = assert_cmd::cargo_bin_cmd!("listent");
+    cmd.args(["--signer", "apple"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown signer 'apple'"));
+}
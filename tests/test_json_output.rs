@@ -1,7 +1,45 @@
+use std::path::{Path, PathBuf};
 use predicates::prelude::*;
 use tempfile::TempDir;
 use serde_json::Value;
 
+/// Write an executable shell script at `path`, creating its directories
+fn write_executable(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, b"#!/bin/sh\n").unwrap();
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+/// Record signature reports, keyed by path, for `--backend fixture:FILE`
+fn write_fixtures(dir: &Path, reports: Value) -> PathBuf {
+    let fixtures = dir.join("fixtures.json");
+    std::fs::write(&fixtures, reports.to_string()).unwrap();
+    fixtures
+}
+
+/// The same report for each of `paths`
+fn reports_for(paths: &[&Path], report: Value) -> Value {
+    Value::Object(paths.iter().map(|path| (path.display().to_string(), report.clone())).collect())
+}
+
+/// Run a quiet scan replaying `fixtures` with these arguments and return its output
+fn run_fixture_scan<S: AsRef<std::ffi::OsStr>>(fixtures: &Path, args: &[S]) -> String {
+    let mut cmd = assert_cmd::cargo_bin_cmd!("listent");
+    cmd.arg("--quiet")
+       .arg("--backend").arg(format!("fixture:{}", fixtures.display()))
+       .args(args);
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+/// [`run_fixture_scan`] with `--json`, parsed
+fn json_fixture_scan<S: AsRef<std::ffi::OsStr>>(fixtures: &Path, args: &[S]) -> Value {
+    let mut cmd_args: Vec<std::ffi::OsString> = vec!["--json".into()];
+    cmd_args.extend(args.iter().map(|arg| arg.as_ref().to_owned()));
+    serde_json::from_str(&run_fixture_scan(fixtures, &cmd_args)).unwrap()
+}
+
 #[test]
 fn test_json_output_flag() {
     let temp = TempDir::new().unwrap();
@@ -97,4 +135,311 @@ fn test_json_vs_human_output_different() {
     
     assert!(human_str.contains("Scan Summary:"), "Human output should have summary separator");
     assert!(!json_str.contains("Scan Summary:"), "JSON output should not have summary separator");
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_fixture_backend_replays_recorded_signatures() {
+    let temp = TempDir::new().unwrap();
+    let tool = temp.path().join("tool");
+    write_executable(&tool);
+    let fixtures = write_fixtures(temp.path(), serde_json::json!({
+        tool.to_str().unwrap(): { "entitlements": { "com.apple.security.app-sandbox": true } }
+    }));
+
+    let json = json_fixture_scan(&fixtures, &[&tool]);
+    assert_eq!(json["results"][0]["entitlements"]["com.apple.security.app-sandbox"], Value::Bool(true));
+}
+
+#[test]
+fn test_platform_and_sdk_filters() {
+    let temp = TempDir::new().unwrap();
+    let mut recorded = serde_json::Map::new();
+    for (name, platform, sdk) in [("driver", "driverkit", "21.4"), ("app", "macos", "14.2")] {
+        let tool = temp.path().join(name);
+        write_executable(&tool);
+        recorded.insert(tool.to_str().unwrap().to_string(), serde_json::json!({
+            "entitlements": { "com.apple.developer.driverkit": true },
+            "build_versions": [{ "platform": platform, "minimum_os": "12.0", "sdk": sdk }]
+        }));
+    }
+    let fixtures = write_fixtures(temp.path(), Value::Object(recorded));
+
+    let scan = |filter: &[&str]| {
+        let mut args: Vec<PathBuf> = filter.iter().map(PathBuf::from).collect();
+        args.extend([temp.path().join("driver"), temp.path().join("app")]);
+        let json = json_fixture_scan(&fixtures, &args);
+        json["results"].as_array().unwrap().iter()
+            .map(|result| result["build_versions"][0]["platform"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(scan(&["--platform", "driverkit"]), vec!["driverkit"]);
+    assert_eq!(scan(&["--sdk-before", "14.2"]), Vec::<String>::new());
+    assert_eq!(scan(&["--sdk-before", "15.0"]), vec!["macos"]);
+    assert_eq!(scan(&["--sdk-before", "30"]).len(), 2);
+}
+
+#[test]
+fn test_nested_bundles_are_compared_with_their_host() {
+    let temp = TempDir::new().unwrap();
+    let app = temp.path().join("Example.app");
+    let main = app.join("Contents/MacOS/Example");
+    let login_item = app.join("Contents/Library/LoginItems/Launcher.app/Contents/MacOS/Launcher");
+    let mut recorded = serde_json::Map::new();
+    for (binary, entitlements) in [
+        (&main, serde_json::json!({ "com.apple.security.app-sandbox": true })),
+        (&login_item, serde_json::json!({ "com.apple.security.app-sandbox": true, "com.apple.private.tcc.allow": ["kTCCServiceAccessibility"] })),
+    ] {
+        write_executable(binary);
+        recorded.insert(binary.to_str().unwrap().to_string(), serde_json::json!({ "entitlements": entitlements }));
+    }
+    let fixtures = write_fixtures(temp.path(), Value::Object(recorded));
+
+    let json = json_fixture_scan(&fixtures, &[&app]);
+    let results = json["results"].as_array().unwrap();
+    assert_eq!(results[1]["path"], main.to_str().unwrap());
+    assert_eq!(results[1]["parent_bundle"], app.to_str().unwrap());
+    assert!(results[1].get("entitlements_beyond_host").is_none());
+    assert_eq!(results[0]["parent_bundle"], app.join("Contents/Library/LoginItems/Launcher.app").to_str().unwrap());
+    assert_eq!(results[0]["entitlements_beyond_host"], serde_json::json!(["com.apple.private.tcc.allow"]));
+
+    let tree = run_fixture_scan(&fixtures, &[Path::new("--bundles"), &app]);
+    let lines: Vec<&str> = tree.lines().collect();
+    let app_line = lines.iter().position(|line| line.ends_with("Example.app (app)")).unwrap();
+    assert_eq!(lines[app_line + 1], "  Contents/MacOS/Example [1 entitlement]");
+    assert!(lines.contains(&"  Contents/Library/LoginItems/Launcher.app (login item)"));
+    assert!(lines.contains(&"      Warning: entitlements beyond host app: com.apple.private.tcc.allow"));
+}
+
+#[test]
+fn test_archive_members_are_scanned_in_place() {
+    use std::io::Write;
+
+    let temp = TempDir::new().unwrap();
+    let ipa = temp.path().join("App.ipa");
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(&ipa).unwrap());
+    let options = zip::write::SimpleFileOptions::default();
+    for executable in ["Payload/Foo.app/Foo", "Payload/Foo.app/PlugIns/Share.appex/Share"] {
+        writer.start_file(executable, options).unwrap();
+        writer.write_all(&[0xcf, 0xfa, 0xed, 0xfe, 0, 0, 0, 0]).unwrap();
+    }
+    writer.start_file("Payload/Foo.app/Info.plist", options).unwrap();
+    writer.write_all(b"<plist/>").unwrap();
+    writer.finish().unwrap();
+
+    let member = format!("{}!/Payload/Foo.app/Foo", ipa.display());
+    let extension = format!("{}!/Payload/Foo.app/PlugIns/Share.appex/Share", ipa.display());
+    let fixtures = write_fixtures(temp.path(), serde_json::json!({
+        &member: { "entitlements": { "get-task-allow": true } },
+        &extension: { "entitlements": { "get-task-allow": true, "com.apple.private.tcc.allow": true } },
+    }));
+
+    let json = json_fixture_scan(&fixtures, &[&ipa]);
+    assert_eq!(json["results"][0]["path"], member.as_str());
+    assert_eq!(json["results"][0]["parent_bundle"], format!("{}!/Payload/Foo.app", ipa.display()));
+    assert!(json["results"][0].get("entitlements_beyond_host").is_none());
+    // The host app is found among the other members, not on disk
+    assert_eq!(json["results"][1]["path"], extension.as_str());
+    assert_eq!(json["results"][1]["entitlements_beyond_host"], serde_json::json!(["com.apple.private.tcc.allow"]));
+    assert_eq!(json["summary"]["scanned"], 2);
+}
+
+#[test]
+fn test_root_resolves_paths_inside_the_image() {
+    let temp = TempDir::new().unwrap();
+    let image = temp.path().join("image");
+    std::fs::create_dir_all(image.join("usr/bin")).unwrap();
+    for tool in ["usr/bin/tool", "usr/libexec/real"] {
+        write_executable(&image.join(tool));
+    }
+    // Dangles on the host, resolves to /usr/libexec/real inside the image
+    std::os::unix::fs::symlink("/usr/libexec/real", image.join("usr/bin/alias")).unwrap();
+
+    let fixtures = write_fixtures(temp.path(), serde_json::json!({
+        image.join("usr/bin/tool").to_str().unwrap(): { "entitlements": { "com.apple.private.tcc.allow": true } },
+        image.join("usr/libexec/real").to_str().unwrap(): { "entitlements": { "get-task-allow": true } },
+    }));
+
+    // No paths: the default scan paths are looked up inside the image
+    let json = json_fixture_scan(&fixtures, &[Path::new("--root"), &image]);
+    assert_eq!(json["results"][0]["path"], "/usr/bin/alias");
+    assert_eq!(json["results"][0]["entitlements"]["get-task-allow"], Value::Bool(true));
+    assert_eq!(json["results"][1]["path"], "/usr/bin/tool");
+    assert_eq!(json["summary"]["scanned"], 2);
+
+    let mut cmd = assert_cmd::cargo_bin_cmd!("listent");
+    cmd.arg("--root").arg(&image).arg("/Applications");
+    cmd.assert().failure().stderr(predicate::str::contains("Path does not exist in"));
+}
+
+#[test]
+fn test_extraction_cache_is_reused_between_scans() {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path().join("home");
+    let bin = temp.path().join("bin");
+    for tool in ["one", "two"] {
+        write_executable(&bin.join(tool));
+    }
+
+    let scan = |extra: &[&str]| -> Value {
+        let mut cmd = assert_cmd::cargo_bin_cmd!("listent");
+        cmd.env("HOME", &home)
+           .arg("--json")
+           .arg("--quiet")
+           .arg("--backend").arg("native")
+           .args(extra)
+           .arg(&bin);
+        serde_json::from_slice(&cmd.assert().success().get_output().stdout).unwrap()
+    };
+
+    let counts = |json: &Value| (json["summary"]["cache_hits"].clone(), json["summary"]["cache_misses"].clone());
+    let uncached = scan(&[]);
+    assert!(uncached["summary"].get("cache_hits").is_none());
+    assert!(!home.exists());
+
+    assert_eq!(counts(&scan(&["--cache"])), (Value::from(0), Value::from(2)));
+    assert_eq!(counts(&scan(&["--cache"])), (Value::from(2), Value::from(0)));
+    assert_eq!(counts(&scan(&["--rebuild-cache"])), (Value::from(0), Value::from(2)));
+}
+
+#[test]
+fn test_no_cache_overrides_cache_flags() {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path().join("home");
+    let bin = temp.path().join("bin");
+    write_executable(&bin.join("tool"));
+
+    for flags in [["--cache", "--no-cache"], ["--no-cache", "--rebuild-cache"]] {
+        let mut cmd = assert_cmd::cargo_bin_cmd!("listent");
+        cmd.env("HOME", &home)
+           .arg("--json")
+           .arg("--quiet")
+           .arg("--backend").arg("native")
+           .args(flags)
+           .arg(&bin);
+        let json: Value = serde_json::from_slice(&cmd.assert().success().get_output().stdout).unwrap();
+        assert!(json["summary"].get("cache_hits").is_none());
+    }
+    assert!(!home.exists());
+}
+
+#[test]
+fn test_hardlinks_are_reported_once_with_aliases() {
+    let temp = TempDir::new().unwrap();
+    let bin = temp.path().join("bin");
+    write_executable(&bin.join("tool"));
+    std::fs::hard_link(bin.join("tool"), bin.join("alias")).unwrap();
+    std::os::unix::fs::symlink("tool", bin.join("link")).unwrap();
+
+    // Any of the paths may be read first, so each is recorded. The one reported is
+    // the first in sort order not through a symlink (`alias`); the others are aliases
+    let paths = ["tool", "alias", "link"].map(|name| bin.join(name));
+    let fixtures = write_fixtures(
+        temp.path(),
+        reports_for(&paths.iter().map(PathBuf::as_path).collect::<Vec<_>>(), serde_json::json!({ "entitlements": { "get-task-allow": true } })),
+    );
+
+    let json = json_fixture_scan(&fixtures, &[&bin]);
+    assert_eq!(json["results"].as_array().unwrap().len(), 1);
+    assert_eq!(json["results"][0]["path"], bin.join("alias").display().to_string());
+    assert_eq!(
+        json["results"][0]["aliases"],
+        serde_json::json!([bin.join("link").display().to_string(), bin.join("tool").display().to_string()])
+    );
+    assert_eq!(json["summary"]["scanned"], 1);
+
+    let json = json_fixture_scan(&fixtures, &[Path::new("--no-follow-symlinks"), &bin]);
+    assert_eq!(json["results"][0]["aliases"], serde_json::json!([bin.join("tool").display().to_string()]));
+}
+
+#[test]
+fn test_exclude_and_ignore_files_prune_the_walk() {
+    let temp = TempDir::new().unwrap();
+    let project = temp.path().join("project");
+    let paths = ["bin/tool", "node_modules/dep/bin/dep", "sdk/bin/clang", "build/tool"].map(|name| project.join(name));
+    for path in &paths {
+        write_executable(path);
+    }
+    std::fs::write(project.join(".listentignore"), "# vendored\n/sdk\n").unwrap();
+    let fixtures = write_fixtures(
+        temp.path(),
+        reports_for(&paths.iter().map(PathBuf::as_path).collect::<Vec<_>>(), serde_json::json!({ "entitlements": { "get-task-allow": true } })),
+    );
+
+    let json = json_fixture_scan(&fixtures, &[Path::new("--exclude"), Path::new("node_modules,build/"), &project]);
+    let paths: Vec<&str> = json["results"].as_array().unwrap().iter().map(|r| r["path"].as_str().unwrap()).collect();
+    assert_eq!(paths, vec![project.join("bin/tool").display().to_string()]);
+}
+
+#[test]
+fn test_invalid_exclude_pattern_is_rejected() {
+    let mut cmd = assert_cmd::cargo_bin_cmd!("listent");
+    cmd.arg("--exclude").arg("[unclosed").arg("/tmp");
+    cmd.assert().failure().stderr(predicate::str::contains("Invalid --exclude pattern"));
+}
+
+#[test]
+fn test_ndjson_streams_results_then_a_summary() {
+    let temp = TempDir::new().unwrap();
+    let bin = temp.path().join("bin");
+    let paths = ["a", "b", "c"].map(|name| bin.join(name));
+    for path in &paths {
+        write_executable(path);
+    }
+    let fixtures = write_fixtures(
+        temp.path(),
+        reports_for(&paths.iter().map(PathBuf::as_path).collect::<Vec<_>>(), serde_json::json!({ "entitlements": { "get-task-allow": true } })),
+    );
+
+    let stdout = run_fixture_scan(&fixtures, &[Path::new("--format"), Path::new("ndjson"), &bin]);
+    let records: Vec<Value> = stdout.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(records.len(), 4);
+    let (summary, results) = records.split_last().unwrap();
+    assert_eq!(summary["type"], "summary");
+    assert_eq!(summary["scanned"], 3);
+    assert_eq!(summary["matched"], 3);
+
+    // Results arrive in completion order
+    let mut reported: Vec<&str> = results.iter().map(|r| {
+        assert_eq!(r["type"], "result");
+        assert_eq!(r["entitlements"]["get-task-allow"], true);
+        r["path"].as_str().unwrap()
+    }).collect();
+    reported.sort();
+    assert_eq!(reported, paths.map(|path| path.display().to_string()));
+}
+
+#[test]
+fn test_ndjson_writes_aliases_before_the_summary() {
+    let temp = TempDir::new().unwrap();
+    let bin = temp.path().join("bin");
+    write_executable(&bin.join("tool"));
+    std::fs::hard_link(bin.join("tool"), bin.join("alias")).unwrap();
+    let paths = ["tool", "alias"].map(|name| bin.join(name));
+    let fixtures = write_fixtures(
+        temp.path(),
+        reports_for(&paths.iter().map(PathBuf::as_path).collect::<Vec<_>>(), serde_json::json!({ "entitlements": { "get-task-allow": true } })),
+    );
+
+    let stdout = run_fixture_scan(&fixtures, &[Path::new("--format"), Path::new("ndjson"), &bin]);
+    let records: Vec<Value> = stdout.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    let types: Vec<&str> = records.iter().map(|record| record["type"].as_str().unwrap()).collect();
+    assert_eq!(types, ["result", "aliases", "summary"]);
+
+    // Whichever path was written, the aliases record names the other
+    let written = records[0]["path"].as_str().unwrap();
+    assert_eq!(records[1]["path"], written);
+    let other = paths.iter().map(|path| path.display().to_string()).find(|path| path != written).unwrap();
+    assert_eq!(records[1]["aliases"], serde_json::json!([other]));
+}
+
+#[test]
+fn test_ndjson_rejects_bundle_tree_and_json() {
+    let mut cmd = assert_cmd::cargo_bin_cmd!("listent");
+    cmd.arg("--format").arg("ndjson").arg("--bundles").arg("/tmp");
+    cmd.assert().failure().stderr(predicate::str::contains("--bundles cannot be used with --format ndjson"));
+
+    let mut cmd = assert_cmd::cargo_bin_cmd!("listent");
+    cmd.arg("--format").arg("ndjson").arg("--json").arg("/tmp");
+    cmd.assert().failure();
+}
@@ -56,6 +56,13 @@ fn test_monitor_with_invalid_arguments() {
         .failure()
         .stderr(predicate::str::contains("does not exist"));
 
+    // Relative paths would match at any depth, so they must be written as patterns
+    let mut cmd = assert_cmd::cargo_bin_cmd!("listent");
+    cmd.args(&["monitor", "Applications"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("must be absolute or a pattern (e.g. **/Applications)"));
+
     // Test --interval without monitor subcommand
     let mut cmd = assert_cmd::cargo_bin_cmd!("listent");
     cmd.args(&["--interval", "5.0"])
@@ -29,6 +29,7 @@ fn test_binary_result_entitlement_count_consistency() {
         path: "/Applications/Safari.app/Contents/MacOS/Safari".to_string(),
         entitlement_count: entitlements.len(),
         entitlements,
+        ..Default::default()
     };
 
     assert_eq!(result.entitlement_count, result.entitlements.len(),
@@ -41,6 +42,7 @@ fn test_binary_result_empty_entitlements() {
         path: "/usr/bin/ls".to_string(),
         entitlement_count: 0,
         entitlements: HashMap::new(),
+        ..Default::default()
     };
 
     assert_eq!(result.entitlement_count, 0);
@@ -56,12 +58,249 @@ fn test_binary_result_serialization() {
         path: "/Applications/Test.app".to_string(),
         entitlement_count: 1,
         entitlements,
+        ..Default::default()
     };
 
     let json = serde_json::to_string(&result).expect("Should serialize");
     assert!(json.contains("path"));
     assert!(json.contains("entitlements"));
     assert!(json.contains("entitlement_count"));
+    assert!(!json.contains("warnings"), "warnings should be omitted when empty");
+    assert!(!json.contains("slices"), "slice breakdown should be omitted for thin binaries");
+}
+
+#[test]
+fn test_binary_result_serializes_warnings() {
+    let result = BinaryResult {
+        path: "/usr/bin/test".to_string(),
+        entitlement_count: 0,
+        entitlements: HashMap::new(),
+        warnings: vec!["XML and DER entitlements disagree (only in DER: a)".to_string()],
+        ..Default::default()
+    };
+
+    let json: serde_json::Value = serde_json::to_value(&result).expect("Should serialize");
+    assert_eq!(json["warnings"][0], "XML and DER entitlements disagree (only in DER: a)");
+}
+
+#[test]
+fn test_binary_result_serializes_slice_breakdown() {
+    let slice = |arch: &str, keys: &[&str]| SliceEntitlements {
+        arch: arch.to_string(),
+        entitlement_count: keys.len(),
+        entitlements: ents(keys),
+        warnings: Vec::new(),
+        code_directory: None,
+        verification: None,
+    };
+    let result = BinaryResult {
+        path: "/usr/local/bin/tool".to_string(),
+        entitlement_count: 0,
+        entitlements: HashMap::new(),
+        slices: vec![
+            slice("x86_64", &[]),
+            slice("arm64", &["com.apple.security.get-task-allow"]),
+        ],
+        slices_disagree: Some(true),
+        ..Default::default()
+    };
+
+    let json: serde_json::Value = serde_json::to_value(&result).expect("Should serialize");
+    assert_eq!(json["slices_disagree"], true);
+    assert_eq!(json["slices"][1]["arch"], "arm64");
+    assert_eq!(json["slices"][1]["entitlement_count"], 1);
+}
+
+#[test]
+fn test_binary_result_serializes_code_directory() {
+    let mut result = BinaryResult {
+        path: "/usr/local/bin/tool".to_string(),
+        entitlement_count: 0,
+        entitlements: HashMap::new(),
+        ..Default::default()
+    };
+    let json = serde_json::to_string(&result).expect("Should serialize");
+    assert!(!json.contains("code_directory"), "unsigned binaries have no code directory");
+
+    result.code_directory = Some(CodeDirectoryInfo {
+        identifier: "com.example.tool".to_string(),
+        team_id: Some("ABCDE12345".to_string()),
+        hash_type: "sha256".to_string(),
+        cdhash: "00".repeat(20),
+        flags: 0x10000,
+        flag_names: vec!["runtime".to_string()],
+        platform_binary: false,
+    });
+    let json: serde_json::Value = serde_json::to_value(&result).expect("Should serialize");
+    assert_eq!(json["code_directory"]["identifier"], "com.example.tool");
+    assert_eq!(json["code_directory"]["team_id"], "ABCDE12345");
+    assert_eq!(json["code_directory"]["flag_names"][0], "runtime");
+    assert!(json.get("designated_requirement").is_none());
+
+    result.designated_requirement = Some("identifier \"com.example.tool\" and anchor apple generic".to_string());
+    let json: serde_json::Value = serde_json::to_value(&result).expect("Should serialize");
+    assert_eq!(json["designated_requirement"], "identifier \"com.example.tool\" and anchor apple generic");
+}
+
+#[test]
+fn test_code_directory_summary() {
+    let info = CodeDirectoryInfo {
+        identifier: "com.apple.ls".to_string(),
+        team_id: None,
+        hash_type: "sha256".to_string(),
+        cdhash: "ab".repeat(20),
+        flags: 0x2,
+        flag_names: vec!["adhoc".to_string()],
+        platform_binary: true,
+    };
+    assert_eq!(
+        info.summary(),
+        format!("com.apple.ls (team: none, platform binary, flags: 0x2(adhoc), cdhash: {})", "ab".repeat(20))
+    );
+}
+
+// ==================== Signer Tests ====================
+
+#[test]
+fn test_signer_kind_round_trips_through_names() {
+    for kind in SignerKind::ALL {
+        assert_eq!(kind.as_str().parse::<SignerKind>(), Ok(kind));
+        assert_eq!(serde_json::to_value(kind).unwrap(), kind.as_str());
+    }
+    let error = "apple".parse::<SignerKind>().unwrap_err();
+    assert!(error.contains("developer-id"), "error should list valid kinds: {}", error);
+}
+
+#[test]
+fn test_scan_filters_match_signer() {
+    let developer_id = SignerInfo {
+        kind: SignerKind::DeveloperId,
+        common_name: Some("Developer ID Application: Example (ABCDE12345)".to_string()),
+        organization: Some("Example".to_string()),
+        chain: vec!["Developer ID Application: Example (ABCDE12345)".to_string(), "Apple Root CA".to_string()],
+    };
+
+    let any = ScanFilters::default();
+    assert!(any.matches_signer(Some(&developer_id)));
+    assert!(any.matches_signer(None));
+
+    let third_party = ScanFilters { signers: vec![SignerKind::DeveloperId, SignerKind::AdHoc], ..ScanFilters::default() };
+    assert!(third_party.matches_signer(Some(&developer_id)));
+    assert!(!third_party.matches_signer(Some(&SignerInfo::without_certificate(SignerKind::ApplePlatform))));
+    assert!(!third_party.matches_signer(None), "unknown signers never pass an explicit filter");
+}
+
+#[test]
+fn test_signer_summary() {
+    assert_eq!(SignerInfo::without_certificate(SignerKind::Unsigned).summary(), "unsigned");
+    let signer = SignerInfo {
+        kind: SignerKind::ApplePlatform,
+        common_name: Some("Software Signing".to_string()),
+        organization: Some("Apple Inc.".to_string()),
+        chain: vec!["Software Signing".to_string(), "Apple Root CA".to_string()],
+    };
+    assert_eq!(signer.summary(), "apple-platform (Software Signing -> Apple Root CA)");
+}
+
+// ==================== Build Version Tests ====================
+
+#[test]
+fn test_os_version_parsing_and_order() {
+    let version: OsVersion = "13.0".parse().unwrap();
+    assert_eq!(version, OsVersion { major: 13, minor: 0, patch: 0 });
+    assert_eq!("14".parse::<OsVersion>().unwrap().to_string(), "14.0");
+    assert_eq!(OsVersion::from_packed(0x000c_0301).to_string(), "12.3.1");
+    assert!(OsVersion::from_packed(0x000c_0301) < version);
+    assert!("13.x".parse::<OsVersion>().is_err());
+    assert!("1.2.3.4".parse::<OsVersion>().is_err());
+    assert_eq!(serde_json::to_value(version).unwrap(), serde_json::json!("13.0"));
+}
+
+#[test]
+fn test_build_version_filters() {
+    let build = BuildVersion {
+        platform: Platform::Driverkit,
+        minimum_os: "19.0".parse().unwrap(),
+        sdk: Some("21.4".parse().unwrap()),
+    };
+    assert_eq!(build.summary(), "DriverKit 19.0+ (SDK 21.4)");
+    assert_eq!("mac-catalyst".parse::<Platform>().unwrap(), Platform::MacCatalyst);
+    assert!("linux".parse::<Platform>().is_err());
+
+    assert!(ScanFilters::default().matches_build(&[]), "filters are off by default");
+
+    let platform = ScanFilters { platforms: vec![Platform::Driverkit], ..ScanFilters::default() };
+    assert!(platform.matches_build(&[build]));
+    assert!(!platform.matches_build(&[]));
+
+    let old_sdk = ScanFilters { sdk_before: Some("22.0".parse().unwrap()), ..ScanFilters::default() };
+    assert!(old_sdk.matches_build(&[build]));
+    let older_sdk = ScanFilters { sdk_before: Some("21.4".parse().unwrap()), ..ScanFilters::default() };
+    assert!(!older_sdk.matches_build(&[build]), "the limit itself is not older");
+    assert!(!old_sdk.matches_build(&[BuildVersion { sdk: None, ..build }]));
+
+    let old_os = ScanFilters { min_os_before: Some("20.0".parse().unwrap()), platforms: vec![Platform::Macos], ..ScanFilters::default() };
+    assert!(!old_os.matches_build(&[build]), "every filter must match");
+}
+
+// ==================== Constraint Tests ====================
+
+#[test]
+fn test_unconstrained_private_filter() {
+    let filters = ScanFilters { unconstrained_private: true, ..ScanFilters::default() };
+    let mut report = SignatureReport { entitlements: ents(&["com.apple.private.tcc.allow"]), ..SignatureReport::default() };
+    assert!(filters.matches_constraints(&report), "private entitlements without constraints should match");
+
+    report.constraints = Some(SignatureConstraints {
+        library: Some(serde_json::json!({"signing-identifier": "com.apple.foo"})),
+        ..SignatureConstraints::default()
+    });
+    assert!(filters.matches_constraints(&report), "library constraints alone do not constrain launch");
+
+    report.constraints = Some(SignatureConstraints {
+        launch_parent: Some(serde_json::json!({"reqs": {"is-init-proc": true}})),
+        ..SignatureConstraints::default()
+    });
+    assert!(!filters.matches_constraints(&report));
+
+    let public = SignatureReport { entitlements: ents(&["com.apple.security.app-sandbox"]), ..SignatureReport::default() };
+    assert!(!filters.matches_constraints(&public));
+    assert!(ScanFilters::default().matches_constraints(&public), "filter is off by default");
+}
+
+#[test]
+fn test_constraints_serialization_omits_absent_slots() {
+    let constraints = SignatureConstraints {
+        launch_self: Some(serde_json::json!({"ccat": 1})),
+        ..SignatureConstraints::default()
+    };
+    let json = serde_json::to_value(&constraints).unwrap();
+    assert_eq!(json, serde_json::json!({"launch_self": {"ccat": 1}}));
+    assert_eq!(constraints.labeled().len(), 1);
+}
+
+// ==================== SignatureReport Tests ====================
+
+#[test]
+fn test_signature_report_slices_disagree() {
+    let slice = |arch: &str, keys: &[&str]| SliceEntitlements {
+        arch: arch.to_string(),
+        entitlement_count: keys.len(),
+        entitlements: ents(keys),
+        warnings: Vec::new(),
+        code_directory: None,
+        verification: None,
+    };
+
+    let mut report = SignatureReport::default();
+    assert_eq!(report.slices_disagree(), None, "thin binaries have nothing to compare");
+
+    report.slices = vec![slice("x86_64", &["a"]), slice("arm64", &["a"])];
+    assert_eq!(report.slices_disagree(), Some(false));
+
+    report.slices = vec![slice("x86_64", &["a"]), slice("arm64", &["a", "b"])];
+    assert_eq!(report.slices_disagree(), Some(true));
+    assert_eq!(report.all_entitlement_keys(), vec!["a".to_string(), "b".to_string()]);
 }
 
 // ==================== ScanSummary Tests ====================
@@ -74,6 +313,8 @@ fn test_scan_summary_interrupted_omitted_when_none() {
         skipped_unreadable: 5,
         duration_ms: 1500,
         interrupted: None,
+        cache_hits: None,
+        cache_misses: None,
     };
 
     let json = serde_json::to_string(&summary).expect("Should serialize");
@@ -88,6 +329,8 @@ fn test_scan_summary_interrupted_present_when_true() {
         skipped_unreadable: 2,
         duration_ms: 800,
         interrupted: Some(true),
+        cache_hits: None,
+        cache_misses: None,
     };
 
     let json = serde_json::to_string(&summary).expect("Should serialize");
@@ -102,6 +345,8 @@ fn test_scan_summary_statistics_consistency() {
         skipped_unreadable: 10,
         duration_ms: 2000,
         interrupted: None,
+        cache_hits: None,
+        cache_misses: None,
     };
 
     // Matched + skipped should not exceed scanned (not required but logical)
@@ -117,12 +362,59 @@ fn test_scan_summary_duration_non_negative() {
         skipped_unreadable: 0,
         duration_ms: 0, // Edge case: instant scan
         interrupted: None,
+        cache_hits: None,
+        cache_misses: None,
     };
 
     // duration_ms is u64, so it's always non-negative
     assert!(summary.duration_ms == 0 || summary.duration_ms > 0);
 }
 
+#[test]
+fn test_scan_summary_cache_counts_present_when_cache_used() {
+    let mut summary = ScanSummary {
+        scanned: 10,
+        matched: 5,
+        skipped_unreadable: 0,
+        duration_ms: 40,
+        interrupted: None,
+        cache_hits: None,
+        cache_misses: None,
+    };
+    let json = serde_json::to_string(&summary).expect("Should serialize");
+    assert!(!json.contains("cache_"), "cache counts should be omitted without a cache");
+
+    summary.cache_hits = Some(9);
+    summary.cache_misses = Some(1);
+    let json = serde_json::to_string(&summary).expect("Should serialize");
+    assert!(json.contains("\"cache_hits\":9") && json.contains("\"cache_misses\":1"));
+}
+
+#[test]
+fn test_scan_records_are_tagged_with_their_type() {
+    let summary = ScanSummary {
+        scanned: 2,
+        matched: 1,
+        skipped_unreadable: 1,
+        duration_ms: 5,
+        interrupted: None,
+        cache_hits: None,
+        cache_misses: None,
+    };
+    let json: serde_json::Value = serde_json::to_value(ScanRecord::Summary(&summary)).expect("Should serialize");
+    assert_eq!(json["type"], "summary");
+    assert_eq!(json["scanned"], 2);
+    assert_eq!(json["skipped_unreadable"], 1);
+}
+
+#[test]
+fn test_output_format_parses_its_names() {
+    for format in [OutputFormat::Human, OutputFormat::Json, OutputFormat::Ndjson] {
+        assert_eq!(format.to_string().parse::<OutputFormat>(), Ok(format));
+    }
+    assert!("yaml".parse::<OutputFormat>().unwrap_err().contains("ndjson"));
+}
+
 // ==================== MonitoredProcess Tests ====================
 
 #[test]
@@ -133,6 +425,8 @@ fn test_monitored_process_creation() {
         name: "Safari".to_string(),
         executable_path: PathBuf::from("/Applications/Safari.app/Contents/MacOS/Safari"),
         entitlements: ents(&["com.apple.security.network.client"]),
+        code_directory: None,
+        bundle_info: None,
         discovery_timestamp: SystemTime::now(),
     };
 
@@ -149,6 +443,8 @@ fn test_monitored_process_with_no_entitlements() {
         name: "unsigned".to_string(),
         executable_path: PathBuf::from("/tmp/unsigned"),
         entitlements: HashMap::new(),
+        code_directory: None,
+        bundle_info: None,
         discovery_timestamp: SystemTime::now(),
     };
 
@@ -167,6 +463,8 @@ fn test_monitored_process_with_multiple_entitlements() {
             "com.apple.security.network.client",
             "com.apple.security.files.user-selected.read-write",
         ]),
+        code_directory: None,
+        bundle_info: None,
         discovery_timestamp: SystemTime::now(),
     };
 
@@ -181,6 +479,8 @@ fn test_monitored_process_serialization() {
         name: "test".to_string(),
         executable_path: PathBuf::from("/test"),
         entitlements: ents(&["entitlement"]),
+        code_directory: None,
+        bundle_info: None,
         discovery_timestamp: SystemTime::UNIX_EPOCH,
     };
 
@@ -206,6 +506,8 @@ fn test_process_snapshot_new_processes_empty_previous() {
         name: "new".to_string(),
         executable_path: PathBuf::from("/new"),
         entitlements: HashMap::new(),
+        code_directory: None,
+        bundle_info: None,
         discovery_timestamp: SystemTime::now(),
     });
 
@@ -229,6 +531,8 @@ fn test_process_snapshot_new_processes_same_snapshot() {
         name: "existing".to_string(),
         executable_path: PathBuf::from("/existing"),
         entitlements: HashMap::new(),
+        code_directory: None,
+        bundle_info: None,
         discovery_timestamp: SystemTime::now(),
     });
 
@@ -252,6 +556,8 @@ fn test_process_snapshot_detects_multiple_new_processes() {
         name: "old".to_string(),
         executable_path: PathBuf::from("/old"),
         entitlements: HashMap::new(),
+        code_directory: None,
+        bundle_info: None,
         discovery_timestamp: SystemTime::now(),
     });
 
@@ -268,6 +574,8 @@ fn test_process_snapshot_detects_multiple_new_processes() {
         name: "old".to_string(),
         executable_path: PathBuf::from("/old"),
         entitlements: HashMap::new(),
+        code_directory: None,
+        bundle_info: None,
         discovery_timestamp: SystemTime::now(),
     });
     new_processes.insert((101, 0), MonitoredProcess {
@@ -276,6 +584,8 @@ fn test_process_snapshot_detects_multiple_new_processes() {
         name: "new1".to_string(),
         executable_path: PathBuf::from("/new1"),
         entitlements: HashMap::new(),
+        code_directory: None,
+        bundle_info: None,
         discovery_timestamp: SystemTime::now(),
     });
     new_processes.insert((102, 0), MonitoredProcess {
@@ -284,6 +594,8 @@ fn test_process_snapshot_detects_multiple_new_processes() {
         name: "new2".to_string(),
         executable_path: PathBuf::from("/new2"),
         entitlements: HashMap::new(),
+        code_directory: None,
+        bundle_info: None,
         discovery_timestamp: SystemTime::now(),
     });
 
@@ -310,6 +622,8 @@ fn test_process_snapshot_terminated_process_not_detected() {
         name: "terminated".to_string(),
         executable_path: PathBuf::from("/terminated"),
         entitlements: HashMap::new(),
+        code_directory: None,
+        bundle_info: None,
         discovery_timestamp: SystemTime::now(),
     });
     old_processes.insert((101, 0), MonitoredProcess {
@@ -318,6 +632,8 @@ fn test_process_snapshot_terminated_process_not_detected() {
         name: "remaining".to_string(),
         executable_path: PathBuf::from("/remaining"),
         entitlements: HashMap::new(),
+        code_directory: None,
+        bundle_info: None,
         discovery_timestamp: SystemTime::now(),
     });
 
@@ -335,6 +651,8 @@ fn test_process_snapshot_terminated_process_not_detected() {
         name: "remaining".to_string(),
         executable_path: PathBuf::from("/remaining"),
         entitlements: HashMap::new(),
+        code_directory: None,
+        bundle_info: None,
         discovery_timestamp: SystemTime::now(),
     });
 
@@ -358,6 +676,7 @@ fn test_polling_configuration_default_values() {
         entitlement_filters: vec![],
         output_json: false,
         quiet_mode: false,
+        backend: ExtractorBackend::Auto,
     };
 
     assert_eq!(config.interval, Duration::from_secs(1));
@@ -369,10 +688,11 @@ fn test_polling_configuration_default_values() {
 fn test_polling_configuration_with_filters() {
     let config = PollingConfiguration {
         interval: Duration::from_millis(500),
-        path_filters: vec![PathBuf::from("/Applications")],
+        path_filters: vec!["/Applications".to_string()],
         entitlement_filters: vec!["com.apple.security.*".to_string()],
         output_json: true,
         quiet_mode: true,
+        backend: ExtractorBackend::Auto,
     };
 
     assert_eq!(config.path_filters.len(), 1);
@@ -390,6 +710,7 @@ fn test_polling_configuration_minimum_interval() {
         entitlement_filters: vec![],
         output_json: false,
         quiet_mode: false,
+        backend: ExtractorBackend::Auto,
     };
 
     assert_eq!(config.interval.as_millis(), 100);
@@ -404,6 +725,7 @@ fn test_polling_configuration_maximum_interval() {
         entitlement_filters: vec![],
         output_json: false,
         quiet_mode: false,
+        backend: ExtractorBackend::Auto,
     };
 
     assert_eq!(config.interval.as_secs(), 300);
@@ -429,9 +751,27 @@ fn test_scan_config_creation() {
         scan_paths: vec!["/Applications".to_string()],
         filters: ScanFilters {
             entitlements: vec!["com.apple.security.network.*".to_string()],
+            signers: Vec::new(),
+            unconstrained_private: false,
+            platforms: Vec::new(),
+            sdk_before: None,
+            min_os_before: None,
         },
-        json_output: true,
+        output_format: OutputFormat::Json,
         quiet_mode: false,
+        verify_signatures: false,
+        report_hardening: false,
+        verify_resources: false,
+        group_bundles: false,
+        root: None,
+        follow_symlinks: true,
+        one_file_system: false,
+        expand_archives: false,
+        exclude: Vec::new(),
+        include: Vec::new(),
+        use_cache: false,
+        rebuild_cache: false,
+        backend: ExtractorBackend::Auto,
     };
 
     assert_eq!(config.scan_paths.len(), 1);
@@ -446,6 +786,122 @@ fn test_scan_filters_default() {
 
 // ==================== EntitlementScanOutput Tests ====================
 
+#[test]
+fn test_binary_result_verification() {
+    let mut result = BinaryResult {
+        path: "/usr/local/bin/tool".to_string(),
+        entitlement_count: 0,
+        entitlements: HashMap::new(),
+        ..Default::default()
+    };
+    assert_eq!(result.verification_summary(), None);
+    let json = serde_json::to_value(&result).unwrap();
+    assert!(json.get("signature_valid").is_none(), "Unverified results omit the field");
+
+    result.signature_valid = Some(true);
+    assert_eq!(result.verification_summary().as_deref(), Some("valid"));
+
+    result.signature_valid = Some(false);
+    result.first_invalid_page = Some(3);
+    result.invalid_special_slots = vec!["entitlements".to_string()];
+    assert_eq!(result.verification_summary().as_deref(), Some("modified (page 3, entitlements)"));
+    let json = serde_json::to_value(&result).unwrap();
+    assert_eq!(json["signature_valid"], serde_json::json!(false));
+    assert_eq!(json["first_invalid_page"], serde_json::json!(3));
+    assert_eq!(json["invalid_special_slots"], serde_json::json!(["entitlements"]));
+}
+
+#[test]
+fn test_bundle_info_summary_and_serialization() {
+    let mut info = BundleInfo {
+        source: InfoPlistSource::Embedded,
+        identifier: Some("com.example.tool".to_string()),
+        name: None,
+        short_version: Some("1.2".to_string()),
+        version: Some("34".to_string()),
+        minimum_system_version: Some("12.0".to_string()),
+    };
+    assert_eq!(info.summary(), "com.example.tool 1.2 (34), macOS 12.0+");
+
+    info.version = Some("1.2".to_string());
+    info.minimum_system_version = None;
+    assert_eq!(info.summary(), "com.example.tool 1.2");
+
+    let json = serde_json::to_value(&info).unwrap();
+    assert_eq!(json["source"], serde_json::json!("embedded"));
+    assert!(json.get("name").is_none());
+}
+
+#[test]
+fn test_extractor_backend_round_trip() {
+    for spelling in ["auto", "native", "codesign", "fixture:/tmp/recorded.json"] {
+        let backend: ExtractorBackend = spelling.parse().unwrap();
+        assert_eq!(backend.to_string(), spelling);
+    }
+    assert_eq!("fixture:/tmp/r.json".parse::<ExtractorBackend>().unwrap(), ExtractorBackend::Fixture(PathBuf::from("/tmp/r.json")));
+    assert!("fixture:".parse::<ExtractorBackend>().is_err());
+    assert!("otool".parse::<ExtractorBackend>().is_err());
+
+    let json = serde_json::to_string(&ExtractorBackend::Native).unwrap();
+    assert_eq!(json, "\"native\"");
+    assert_eq!(serde_json::from_str::<ExtractorBackend>(&json).unwrap(), ExtractorBackend::Native);
+    assert_eq!(ExtractorBackend::default(), ExtractorBackend::Auto);
+}
+
+#[test]
+fn test_provisioning_profile_summary() {
+    let mut profile = ProvisioningProfile {
+        name: Some("Example App Development".to_string()),
+        team_identifier: Some("ABCDE12345".to_string()),
+        team_name: None,
+        expiration_date: Some("2030-01-01T00:00:00Z".to_string()),
+        expired: false,
+        entitlements: HashMap::new(),
+        unauthorized_entitlements: Vec::new(),
+    };
+    assert_eq!(profile.summary(), "Example App Development (team ABCDE12345, expires 2030-01-01T00:00:00Z)");
+
+    profile.expired = true;
+    profile.team_identifier = None;
+    assert_eq!(profile.summary(), "Example App Development (expired 2030-01-01T00:00:00Z)");
+
+    let json = serde_json::to_value(&profile).unwrap();
+    assert!(json.get("unauthorized_entitlements").is_none());
+}
+
+#[test]
+fn test_hardening_summary() {
+    let mut hardening = HardeningInfo { pie: true, stack_canary: true, hardened_runtime: true, ..HardeningInfo::default() };
+    assert_eq!(hardening.summary(), "PIE, stack canary, hardened runtime; missing: __RESTRICT, PAC, library validation");
+
+    // ARC is only listed for Objective-C code
+    hardening.objc = true;
+    assert!(hardening.summary().contains("missing: __RESTRICT, ARC, PAC"));
+    assert_eq!(HardeningInfo::default().summary(), "missing: PIE, __RESTRICT, stack canary, PAC, hardened runtime, library validation");
+}
+
+#[test]
+fn test_resource_seal_summary() {
+    let mut seal = ResourceSealVerification::default();
+    assert!(seal.is_intact());
+    assert_eq!(seal.summary(), "intact");
+
+    seal.modified = vec!["Resources/a.png".to_string(), "Resources/b.png".to_string()];
+    seal.removed = vec!["Frameworks/Kit.framework".to_string()];
+    assert!(!seal.is_intact());
+    assert_eq!(seal.summary(), "2 modified, 1 removed");
+}
+
+#[test]
+fn test_dylib_serialization() {
+    let dylib = DylibDependency { path: "@rpath/Foo.dylib".to_string(), kind: DylibKind::Weak };
+    assert_eq!(serde_json::to_value(&dylib).unwrap(), serde_json::json!({"path": "@rpath/Foo.dylib", "kind": "weak"}));
+    assert_eq!(DylibKind::Reexport.to_string(), "reexport");
+
+    let risk = DylibHijackRisk { path: dylib.path, reasons: vec!["@rpath".to_string(), "weak".to_string()] };
+    assert_eq!(risk.summary(), "@rpath/Foo.dylib (@rpath, weak)");
+}
+
 #[test]
 fn test_entitlement_scan_output_serialization() {
     let output = EntitlementScanOutput {
@@ -458,14 +914,18 @@ fn test_entitlement_scan_output_serialization() {
                     m.insert("test".to_string(), serde_json::json!(true));
                     m
                 },
+                ..Default::default()
             },
         ],
+        packages: Vec::new(),
         summary: ScanSummary {
             scanned: 1,
             matched: 1,
             skipped_unreadable: 0,
             duration_ms: 100,
             interrupted: None,
+            cache_hits: None,
+            cache_misses: None,
         },
     };
 
@@ -479,15 +939,43 @@ fn test_entitlement_scan_output_serialization() {
 fn test_entitlement_scan_output_empty_results() {
     let output = EntitlementScanOutput {
         results: vec![],
+        packages: Vec::new(),
         summary: ScanSummary {
             scanned: 100,
             matched: 0,
             skipped_unreadable: 5,
             duration_ms: 500,
             interrupted: None,
+            cache_hits: None,
+            cache_misses: None,
         },
     };
 
     assert!(output.results.is_empty());
     assert_eq!(output.summary.matched, 0);
-}
\ No newline at end of file
+}
+#[test]
+fn test_package_report_serialization() {
+    let package = PackageReport {
+        path: "/tmp/Example.pkg".to_string(),
+        signer: SignerInfo::without_certificate(SignerKind::Unsigned),
+        components: vec![PackageComponent {
+            identifier: "com.example.app.pkg".to_string(),
+            version: None,
+            install_location: "/Applications".to_string(),
+        }],
+        scripts: vec![PackageScript {
+            component: None,
+            name: "preinstall".to_string(),
+            contents: "#!/bin/sh\nexit 0\n".to_string(),
+        }],
+        warnings: Vec::new(),
+    };
+
+    assert_eq!(package.components[0].summary(), "com.example.app.pkg -> /Applications");
+    assert_eq!(package.scripts[0].summary(), "preinstall (2 lines)");
+    let json = serde_json::to_value(&package).unwrap();
+    assert_eq!(json["signer"]["kind"], "unsigned");
+    assert!(json.get("warnings").is_none());
+    assert_eq!(serde_json::from_value::<PackageReport>(json).unwrap(), package);
+}